@@ -0,0 +1,43 @@
+// build.rs
+use std::process::Command;
+
+/// 运行命令并返回去掉首尾空白的标准输出，失败时返回 None
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn main() {
+    // 不在git仓库中构建(例如源码包)时提交号为 unknown
+    let commit = output("git", &["rev-parse", "--short=12", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+
+    // 设置了 SOURCE_DATE_EPOCH 时使用该时间，便于可重现构建
+    let date = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0))
+        .unwrap_or_else(chrono::Utc::now)
+        .format("%Y-%m-%dT%H:%M:%SZ");
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc = output(&rustc, &["-V"]).unwrap_or_else(|| "unknown".to_string());
+
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(|f| f.to_lowercase().replace('_', "-")))
+        .collect();
+    features.sort();
+
+    println!("cargo:rustc-env=SYSMON_COMMIT={}", commit);
+    println!("cargo:rustc-env=SYSMON_BUILD_DATE={}", date);
+    println!("cargo:rustc-env=SYSMON_RUSTC={}", rustc);
+    println!("cargo:rustc-env=SYSMON_FEATURES={}", features.join(","));
+    println!("cargo:rustc-env=SYSMON_TARGET={}", std::env::var("TARGET").unwrap_or_default());
+    println!("cargo:rustc-env=SYSMON_PROFILE={}", std::env::var("PROFILE").unwrap_or_default());
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
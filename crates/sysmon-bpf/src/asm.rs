@@ -0,0 +1,245 @@
+// crates/sysmon-bpf/src/asm.rs
+//! eBPF指令的汇编，只包含程序用到的指令
+
+use std::os::fd::{AsRawFd, OwnedFd};
+
+/// 一条指令，与内核的 `struct bpf_insn` 布局相同
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Insn {
+    pub code: u8,
+    /// 低4位是目标寄存器，高4位是源寄存器
+    pub regs: u8,
+    pub off: i16,
+    pub imm: i32,
+}
+
+pub const R0: u8 = 0;
+pub const R1: u8 = 1;
+pub const R2: u8 = 2;
+pub const R3: u8 = 3;
+pub const R4: u8 = 4;
+pub const R6: u8 = 6;
+pub const R7: u8 = 7;
+pub const R10: u8 = 10;
+
+/// 读写的宽度
+pub const DW: u8 = 0x18;
+pub const W: u8 = 0x00;
+pub const H: u8 = 0x08;
+
+/// `ld_imm64` 的源寄存器为1时立即数是表的文件描述符
+const PSEUDO_MAP_FD: u8 = 1;
+
+/// 带跳转标签的指令序列
+#[derive(Default)]
+pub struct Asm {
+    insns: Vec<Insn>,
+    /// 跳转指令的位置和目标标签
+    jumps: Vec<(usize, &'static str)>,
+    labels: Vec<(&'static str, usize)>,
+}
+
+impl Asm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&mut self, code: u8, dst: u8, src: u8, off: i16, imm: i32) {
+        self.insns.push(Insn { code, regs: dst | (src << 4), off, imm });
+    }
+
+    pub fn mov(&mut self, dst: u8, src: u8) {
+        self.push(0xbf, dst, src, 0, 0);
+    }
+
+    pub fn mov_imm(&mut self, dst: u8, imm: i32) {
+        self.push(0xb7, dst, 0, 0, imm);
+    }
+
+    pub fn add_imm(&mut self, dst: u8, imm: i32) {
+        self.push(0x07, dst, 0, 0, imm);
+    }
+
+    pub fn rsh_imm(&mut self, dst: u8, imm: i32) {
+        self.push(0x77, dst, 0, 0, imm);
+    }
+
+    /// 把低16位从网络字节序转为本机字节序
+    pub fn be16(&mut self, dst: u8) {
+        self.push(0xdc, dst, 0, 0, 16);
+    }
+
+    pub fn load(&mut self, size: u8, dst: u8, src: u8, off: i16) {
+        self.push(0x61 | size, dst, src, off, 0);
+    }
+
+    pub fn store(&mut self, size: u8, dst: u8, off: i16, src: u8) {
+        self.push(0x63 | size, dst, src, off, 0);
+    }
+
+    pub fn store_imm(&mut self, size: u8, dst: u8, off: i16, imm: i32) {
+        self.push(0x62 | size, dst, 0, off, imm);
+    }
+
+    /// `*(u64 *)(dst + off) += src`，原子操作
+    pub fn atomic_add(&mut self, dst: u8, off: i16, src: u8) {
+        self.push(0xdb, dst, src, off, 0);
+    }
+
+    /// 表的文件描述符，占两条指令
+    pub fn load_map(&mut self, dst: u8, map: &OwnedFd) {
+        self.load_map_fd(dst, map.as_raw_fd());
+    }
+
+    fn load_map_fd(&mut self, dst: u8, fd: i32) {
+        self.push(0x18, dst, PSEUDO_MAP_FD, 0, fd);
+        self.push(0, 0, 0, 0, 0);
+    }
+
+    pub fn call(&mut self, helper: i32) {
+        self.push(0x85, 0, 0, 0, helper);
+    }
+
+    pub fn jump_if_nonzero(&mut self, dst: u8, label: &'static str) {
+        self.jumps.push((self.insns.len(), label));
+        self.push(0x55, dst, 0, 0, 0);
+    }
+
+    pub fn jump(&mut self, label: &'static str) {
+        self.jumps.push((self.insns.len(), label));
+        self.push(0x05, 0, 0, 0, 0);
+    }
+
+    pub fn label(&mut self, label: &'static str) {
+        self.labels.push((label, self.insns.len()));
+    }
+
+    pub fn exit(&mut self) {
+        self.push(0x95, 0, 0, 0, 0);
+    }
+
+    /// 填上跳转的偏移；标签没有定义时 panic，属于程序的错误
+    pub fn finish(mut self) -> Vec<Insn> {
+        for (at, label) in &self.jumps {
+            let target = self.labels.iter().find(|(name, _)| name == label).map(|(_, at)| *at);
+            let target = target.unwrap_or_else(|| panic!("undefined label {}", label));
+            self.insns[*at].off = (target as isize - *at as isize - 1) as i16;
+        }
+        self.insns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按内核读取的方式展开为字节
+    fn bytes(insns: &[Insn]) -> Vec<u8> {
+        assert_eq!(std::mem::size_of::<Insn>(), 8);
+        insns
+            .iter()
+            .flat_map(|insn| {
+                let mut bytes = vec![insn.code, insn.regs];
+                bytes.extend_from_slice(&insn.off.to_ne_bytes());
+                bytes.extend_from_slice(&insn.imm.to_ne_bytes());
+                bytes
+            })
+            .collect()
+    }
+
+    fn insn(code: u8, regs: u8, off: i16, imm: i32) -> Insn {
+        Insn { code, regs, off, imm }
+    }
+
+    #[test]
+    fn encodes_alu_instructions() {
+        let mut asm = Asm::new();
+        asm.mov(R6, R1);
+        asm.mov_imm(R0, 1);
+        asm.add_imm(R2, -8);
+        asm.rsh_imm(R0, 32);
+        asm.be16(R1);
+        assert_eq!(
+            asm.finish(),
+            [
+                insn(0xbf, 0x16, 0, 0),
+                insn(0xb7, 0x00, 0, 1),
+                insn(0x07, 0x02, 0, -8),
+                insn(0x77, 0x00, 0, 32),
+                insn(0xdc, 0x01, 0, 16),
+            ]
+        );
+    }
+
+    #[test]
+    fn encodes_memory_instructions() {
+        let mut asm = Asm::new();
+        asm.load(W, R1, R6, 132);
+        asm.load(H, R1, R7, 42);
+        asm.store(DW, R10, -8, R0);
+        asm.store(W, R7, 4, R0);
+        asm.store(H, R7, 40, R1);
+        asm.store_imm(DW, R10, -16, 0);
+        asm.atomic_add(R7, 8, R1);
+        assert_eq!(
+            asm.finish(),
+            [
+                insn(0x61, 0x61, 132, 0),
+                insn(0x69, 0x71, 42, 0),
+                insn(0x7b, 0x0a, -8, 0),
+                insn(0x63, 0x07, 4, 0),
+                insn(0x6b, 0x17, 40, 0),
+                insn(0x7a, 0x0a, -16, 0),
+                insn(0xdb, 0x17, 8, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn encodes_calls_and_map_loads() {
+        let mut asm = Asm::new();
+        asm.load_map_fd(R1, 7);
+        asm.call(1);
+        asm.mov_imm(R0, 1);
+        asm.exit();
+        assert_eq!(
+            bytes(&asm.finish()),
+            [
+                [0x18, 0x11, 0, 0].as_slice(),
+                &7i32.to_ne_bytes(),
+                &[0; 8],
+                &[0x85, 0, 0, 0],
+                &1i32.to_ne_bytes(),
+                &[0xb7, 0, 0, 0],
+                &1i32.to_ne_bytes(),
+                &[0x95, 0, 0, 0, 0, 0, 0, 0],
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn resolves_jumps_forward_and_backward() {
+        let mut asm = Asm::new();
+        asm.label("start");
+        asm.jump_if_nonzero(R0, "out");
+        asm.mov_imm(R0, 0);
+        asm.jump("start");
+        asm.jump("out");
+        asm.label("out");
+        asm.exit();
+        let insns = asm.finish();
+        assert_eq!(insns[0], insn(0x55, 0x00, 3, 0));
+        assert_eq!(insns[2], insn(0x05, 0x00, -3, 0));
+        assert_eq!(insns[3], insn(0x05, 0x00, 0, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "undefined label missing")]
+    fn rejects_undefined_labels() {
+        let mut asm = Asm::new();
+        asm.jump("missing");
+        asm.finish();
+    }
+}
@@ -0,0 +1,337 @@
+// crates/sysmon-bpf/src/lib.rs
+//! `system_monitor --net-talkers` 使用的eBPF程序：在cgroup v2的根上挂载，按套接字cookie记录收发的字节数和创建套接字的进程。
+//! 程序手工汇编(`asm`)，直接调用 `bpf()`，不依赖libbpf和clang；只在Linux上可用，需要root或 `CAP_BPF` 和 `CAP_NET_ADMIN`
+#![cfg(target_os = "linux")]
+
+mod asm;
+mod program;
+
+use program::{COMM, LOCAL_PORT, PID, PORT, RX, TX, UID, VALUE_SIZE};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+const BPF_MAP_CREATE: libc::c_long = 0;
+const BPF_MAP_LOOKUP_ELEM: libc::c_long = 1;
+const BPF_MAP_GET_NEXT_KEY: libc::c_long = 4;
+const BPF_PROG_LOAD: libc::c_long = 5;
+const BPF_LINK_CREATE: libc::c_long = 28;
+
+const BPF_MAP_TYPE_LRU_HASH: u32 = 9;
+const BPF_PROG_TYPE_CGROUP_SKB: u32 = 8;
+const BPF_PROG_TYPE_CGROUP_SOCK: u32 = 9;
+const BPF_CGROUP_INET_INGRESS: u32 = 0;
+const BPF_CGROUP_INET_EGRESS: u32 = 1;
+const BPF_CGROUP_INET_SOCK_CREATE: u32 = 2;
+
+/// 跟踪的套接字数，超过时淘汰最久没有流量的
+const MAX_SOCKETS: u32 = 16384;
+
+#[repr(C)]
+#[derive(Default)]
+struct MapCreate {
+    map_type: u32,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+    map_flags: u32,
+    inner_map_fd: u32,
+    numa_node: u32,
+    map_name: [u8; 16],
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct MapElem {
+    map_fd: u32,
+    pad: u32,
+    key: u64,
+    /// 查询时为值，`BPF_MAP_GET_NEXT_KEY` 时为下一个键
+    value: u64,
+    flags: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct ProgLoad {
+    prog_type: u32,
+    insn_cnt: u32,
+    insns: u64,
+    license: u64,
+    log_level: u32,
+    log_size: u32,
+    log_buf: u64,
+    kern_version: u32,
+    prog_flags: u32,
+    prog_name: [u8; 16],
+    prog_ifindex: u32,
+    expected_attach_type: u32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct LinkCreate {
+    prog_fd: u32,
+    target_fd: u32,
+    attach_type: u32,
+    flags: u32,
+}
+
+/// 无法挂载的原因
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// 校验器拒绝了程序，`reason` 是校验日志的最后一行
+    Rejected { program: &'static str, error: io::Error, reason: String },
+    /// 没有挂载cgroup v2
+    NoCgroup2,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(error) => error.fmt(f),
+            Error::Rejected { program, error, reason } => write!(f, "eBPF program {} rejected: {} {}", program, error, reason),
+            Error::NoCgroup2 => f.write_str("cgroup v2 is not mounted"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+/// 一个套接字累计的流量
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Socket {
+    /// 创建套接字的进程，接受的连接和挂载之前创建的套接字为0
+    pub pid: u32,
+    pub uid: u32,
+    pub comm: String,
+    /// 对端端口，没有连接的套接字(监听的套接字等)为0
+    pub port: u16,
+    pub local_port: u16,
+    pub rx: u64,
+    pub tx: u64,
+}
+
+fn bpf<T>(cmd: libc::c_long, attr: &mut T) -> io::Result<i32> {
+    let result = unsafe { libc::syscall(libc::SYS_bpf, cmd, attr as *mut T, std::mem::size_of::<T>()) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(result as i32)
+}
+
+fn name(name: &str) -> [u8; 16] {
+    let mut buf = [0u8; 16];
+    buf[..name.len().min(15)].copy_from_slice(&name.as_bytes()[..name.len().min(15)]);
+    buf
+}
+
+fn create_map() -> io::Result<OwnedFd> {
+    let mut attr = MapCreate {
+        map_type: BPF_MAP_TYPE_LRU_HASH,
+        key_size: 8,
+        value_size: VALUE_SIZE as u32,
+        max_entries: MAX_SOCKETS,
+        map_name: name("sysmon_talkers"),
+        ..Default::default()
+    };
+    Ok(unsafe { OwnedFd::from_raw_fd(bpf(BPF_MAP_CREATE, &mut attr)?) })
+}
+
+/// 三个程序：(程序类型, 挂载点, 名称, 指令)
+fn programs(map: &OwnedFd) -> [(u32, u32, &'static str, Vec<asm::Insn>); 3] {
+    [
+        (BPF_PROG_TYPE_CGROUP_SOCK, BPF_CGROUP_INET_SOCK_CREATE, "sysmon_create", program::create(map)),
+        (BPF_PROG_TYPE_CGROUP_SKB, BPF_CGROUP_INET_EGRESS, "sysmon_egress", program::packet(map, true)),
+        (BPF_PROG_TYPE_CGROUP_SKB, BPF_CGROUP_INET_INGRESS, "sysmon_ingress", program::packet(map, false)),
+    ]
+}
+
+fn load(prog_type: u32, attach_type: u32, program_name: &'static str, insns: &[asm::Insn]) -> Result<OwnedFd, Error> {
+    let license = b"GPL\0";
+    let mut attr = ProgLoad {
+        prog_type,
+        insn_cnt: insns.len() as u32,
+        insns: insns.as_ptr() as u64,
+        license: license.as_ptr() as u64,
+        prog_name: name(program_name),
+        expected_attach_type: attach_type,
+        ..Default::default()
+    };
+    match bpf(BPF_PROG_LOAD, &mut attr) {
+        Ok(fd) => Ok(unsafe { OwnedFd::from_raw_fd(fd) }),
+        Err(error) if error.raw_os_error() == Some(libc::EACCES) || error.raw_os_error() == Some(libc::EINVAL) => {
+            // 校验器拒绝时带上日志重新加载一次，取得拒绝的原因
+            let mut log = vec![0u8; 64 * 1024];
+            attr.log_level = 1;
+            attr.log_size = log.len() as u32;
+            attr.log_buf = log.as_mut_ptr() as u64;
+            let _ = bpf(BPF_PROG_LOAD, &mut attr);
+            let log = String::from_utf8_lossy(&log[..log.iter().position(|&b| b == 0).unwrap_or(0)]).into_owned();
+            let reason = log.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default().to_string();
+            Err(Error::Rejected { program: program_name, error, reason })
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// cgroup v2 的挂载点
+fn cgroup_root() -> Result<String, Error> {
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")?;
+    mountinfo
+        .lines()
+        .find_map(|line| {
+            let (mount, fs) = line.split_once(" - ")?;
+            (fs.split_whitespace().next() == Some("cgroup2")).then(|| mount.split_whitespace().nth(4).map(str::to_string))?
+        })
+        .ok_or(Error::NoCgroup2)
+}
+
+/// 挂载的程序和记录流量的表；释放时关闭链接，内核随之卸载程序
+pub struct Probe {
+    map: OwnedFd,
+    cgroup: String,
+    _links: Vec<OwnedFd>,
+}
+
+impl Probe {
+    pub fn attach() -> Result<Self, Error> {
+        let map = create_map()?;
+        let root = cgroup_root()?;
+        let cgroup = std::fs::File::open(&root)?;
+        let mut links = Vec::new();
+        for (prog_type, attach_type, program_name, insns) in programs(&map) {
+            let program = load(prog_type, attach_type, program_name, &insns)?;
+            // 通过链接挂载，与其他程序挂载的程序共存，本程序退出时自动卸载
+            let mut attr = LinkCreate {
+                prog_fd: program.as_raw_fd() as u32,
+                target_fd: cgroup.as_raw_fd() as u32,
+                attach_type,
+                flags: 0,
+            };
+            links.push(unsafe { OwnedFd::from_raw_fd(bpf(BPF_LINK_CREATE, &mut attr)?) });
+        }
+        Ok(Self { map, cgroup: root, _links: links })
+    }
+
+    /// 挂载程序的cgroup
+    pub fn cgroup(&self) -> &str {
+        &self.cgroup
+    }
+
+    /// 表中的全部套接字，以cookie为键；遍历期间被淘汰的套接字跳过
+    pub fn sockets(&self) -> HashMap<u64, Socket> {
+        let mut sockets = HashMap::new();
+        let mut key: Option<u64> = None;
+        // 遍历期间有套接字被淘汰时内核从头开始，最多遍历表的容量次
+        for _ in 0..MAX_SOCKETS {
+            let mut next = 0u64;
+            let mut attr = MapElem {
+                map_fd: self.map.as_raw_fd() as u32,
+                key: key.as_ref().map_or(0, |key| key as *const u64 as u64),
+                value: &mut next as *mut u64 as u64,
+                ..Default::default()
+            };
+            if bpf(BPF_MAP_GET_NEXT_KEY, &mut attr).is_err() {
+                break;
+            }
+            key = Some(next);
+            let mut value = [0u8; VALUE_SIZE];
+            let mut attr = MapElem {
+                map_fd: self.map.as_raw_fd() as u32,
+                key: &next as *const u64 as u64,
+                value: value.as_mut_ptr() as u64,
+                ..Default::default()
+            };
+            if sockets.contains_key(&next) || bpf(BPF_MAP_LOOKUP_ELEM, &mut attr).is_err() {
+                continue;
+            }
+            sockets.insert(next, decode(&value));
+        }
+        sockets
+    }
+}
+
+fn decode(value: &[u8; VALUE_SIZE]) -> Socket {
+    let u32_at = |off: i16| u32::from_ne_bytes(value[off as usize..off as usize + 4].try_into().unwrap());
+    let u64_at = |off: i16| u64::from_ne_bytes(value[off as usize..off as usize + 8].try_into().unwrap());
+    let comm = &value[COMM as usize..COMM as usize + 16];
+    Socket {
+        pid: u32_at(PID),
+        uid: u32_at(UID),
+        comm: String::from_utf8_lossy(&comm[..comm.iter().position(|&b| b == 0).unwrap_or(16)]).into_owned(),
+        port: u16::from_ne_bytes([value[PORT as usize], value[PORT as usize + 1]]),
+        local_port: u16::from_ne_bytes([value[LOCAL_PORT as usize], value[LOCAL_PORT as usize + 1]]),
+        rx: u64_at(RX),
+        tx: u64_at(TX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 没有权限(不是root，或者容器中不允许 `bpf()`)时跳过
+    fn permitted<T>(result: io::Result<T>) -> Option<T> {
+        match result {
+            Err(error) if matches!(error.raw_os_error(), Some(libc::EPERM) | Some(libc::ENOSYS)) => {
+                eprintln!("skipped: {}", error);
+                None
+            }
+            result => Some(result.unwrap()),
+        }
+    }
+
+    #[test]
+    fn verifier_accepts_the_programs() {
+        let Some(map) = permitted(create_map()) else { return };
+        for (prog_type, attach_type, program_name, insns) in programs(&map) {
+            if let Err(error) = load(prog_type, attach_type, program_name, &insns) {
+                panic!("{}", error);
+            }
+        }
+    }
+
+    #[test]
+    fn verifier_rejects_programs_that_drop_the_return_value() {
+        let Some(map) = permitted(create_map()) else { return };
+        let mut insns = program::create(&map);
+        // 去掉 `r0 = 1`，返回值未初始化
+        insns.remove(insns.len() - 2);
+        match load(BPF_PROG_TYPE_CGROUP_SOCK, BPF_CGROUP_INET_SOCK_CREATE, "sysmon_test", &insns) {
+            Err(Error::Rejected { program, reason, .. }) => {
+                assert_eq!(program, "sysmon_test");
+                assert!(!reason.is_empty());
+            }
+            Err(error) => panic!("{}", error),
+            Ok(_) => panic!("loaded"),
+        }
+    }
+
+    #[test]
+    fn decodes_values() {
+        let mut value = [0u8; VALUE_SIZE];
+        value[PID as usize..][..4].copy_from_slice(&42u32.to_ne_bytes());
+        value[UID as usize..][..4].copy_from_slice(&1000u32.to_ne_bytes());
+        value[TX as usize..][..8].copy_from_slice(&7u64.to_ne_bytes());
+        value[RX as usize..][..8].copy_from_slice(&(1u64 << 40).to_ne_bytes());
+        value[COMM as usize..][..5].copy_from_slice(b"nginx");
+        value[PORT as usize..][..2].copy_from_slice(&443u16.to_ne_bytes());
+        value[LOCAL_PORT as usize..][..2].copy_from_slice(&51000u16.to_ne_bytes());
+        assert_eq!(
+            decode(&value),
+            Socket { pid: 42, uid: 1000, comm: "nginx".to_string(), port: 443, local_port: 51000, rx: 1 << 40, tx: 7 }
+        );
+
+        value[COMM as usize..][..16].copy_from_slice(b"0123456789abcdef");
+        assert_eq!(decode(&value).comm, "0123456789abcdef");
+    }
+}
@@ -0,0 +1,128 @@
+// crates/sysmon-bpf/src/program.rs
+//! 挂载在cgroup上的三个程序和表中值的布局
+
+use crate::asm::{Asm, Insn, DW, H, R0, R1, R2, R3, R4, R6, R7, R10, W};
+use std::os::fd::OwnedFd;
+
+const FN_MAP_LOOKUP_ELEM: i32 = 1;
+const FN_MAP_UPDATE_ELEM: i32 = 2;
+const FN_GET_CURRENT_PID_TGID: i32 = 14;
+const FN_GET_CURRENT_UID_GID: i32 = 15;
+const FN_GET_CURRENT_COMM: i32 = 16;
+const FN_GET_SOCKET_COOKIE: i32 = 46;
+const BPF_ANY: i32 = 0;
+const BPF_NOEXIST: i32 = 1;
+
+/// 表中的值：pid(u32)、uid(u32)、发送字节(u64)、接收字节(u64)、程序名([u8; 16])、对端端口(u16)、本地端口(u16)
+pub const VALUE_SIZE: usize = 48;
+pub const PID: i16 = 0;
+pub const UID: i16 = 4;
+pub const TX: i16 = 8;
+pub const RX: i16 = 16;
+pub const COMM: i16 = 24;
+pub const PORT: i16 = 40;
+pub const LOCAL_PORT: i16 = 42;
+
+/// `struct __sk_buff` 中的字段
+const SKB_LEN: i16 = 0;
+const SKB_REMOTE_PORT: i16 = 132;
+const SKB_LOCAL_PORT: i16 = 136;
+
+/// 栈上的键(cookie)和值
+const KEY: i16 = -8;
+const VALUE: i16 = KEY - VALUE_SIZE as i16;
+
+/// 放行数据包或套接字：cgroup程序返回0会丢弃数据包、拒绝创建套接字
+fn allow(asm: &mut Asm) {
+    asm.mov_imm(R0, 1);
+    asm.exit();
+}
+
+/// 当前进程的pid、uid和程序名写入 `dst + off` 处的值
+fn owner(asm: &mut Asm, dst: u8, off: i16) {
+    asm.call(FN_GET_CURRENT_PID_TGID);
+    asm.rsh_imm(R0, 32);
+    asm.store(W, dst, off + PID, R0);
+    asm.call(FN_GET_CURRENT_UID_GID);
+    asm.store(W, dst, off + UID, R0);
+    asm.mov(R1, dst);
+    asm.add_imm(R1, (off + COMM) as i32);
+    asm.mov_imm(R2, 16);
+    asm.call(FN_GET_CURRENT_COMM);
+}
+
+/// 数据包的对端端口和本地端口写入 `dst + off` 处的值
+fn ports(asm: &mut Asm, dst: u8, off: i16) {
+    asm.load(W, R1, R6, SKB_REMOTE_PORT);
+    // 对端端口为网络字节序，小端机器上在高16位；本地端口为本机字节序
+    if cfg!(target_endian = "little") {
+        asm.rsh_imm(R1, 16);
+    }
+    asm.be16(R1);
+    asm.store(H, dst, off + PORT, R1);
+    asm.load(W, R1, R6, SKB_LOCAL_PORT);
+    asm.store(H, dst, off + LOCAL_PORT, R1);
+}
+
+/// 栈上的键设为 `R6` 的套接字cookie，值清零
+fn key(asm: &mut Asm) {
+    asm.mov(R6, R1);
+    asm.call(FN_GET_SOCKET_COOKIE);
+    asm.store(DW, R10, KEY, R0);
+    for i in 0..VALUE_SIZE as i16 / 8 {
+        asm.store_imm(DW, R10, VALUE + i * 8, 0);
+    }
+}
+
+fn update(asm: &mut Asm, map: &OwnedFd, flags: i32) {
+    asm.load_map(R1, map);
+    asm.mov(R2, R10);
+    asm.add_imm(R2, KEY as i32);
+    asm.mov(R3, R10);
+    asm.add_imm(R3, VALUE as i32);
+    asm.mov_imm(R4, flags);
+    asm.call(FN_MAP_UPDATE_ELEM);
+}
+
+/// 创建套接字时记下创建它的进程
+pub fn create(map: &OwnedFd) -> Vec<Insn> {
+    let mut asm = Asm::new();
+    key(&mut asm);
+    asm.mov(R7, R10);
+    owner(&mut asm, R7, VALUE);
+    update(&mut asm, map, BPF_ANY);
+    allow(&mut asm);
+    asm.finish()
+}
+
+/// 累加数据包的字节数；还没有记录的套接字(接受的连接和挂载之前创建的套接字)不知道属于哪个进程，由采集时查询。
+/// 数据包可能在软中断中收发，这时的当前进程与套接字无关，不能据此归属
+pub fn packet(map: &OwnedFd, egress: bool) -> Vec<Insn> {
+    let bytes = if egress { TX } else { RX };
+    let mut asm = Asm::new();
+    key(&mut asm);
+    asm.load_map(R1, map);
+    asm.mov(R2, R10);
+    asm.add_imm(R2, KEY as i32);
+    asm.call(FN_MAP_LOOKUP_ELEM);
+    asm.jump_if_nonzero(R0, "found");
+
+    asm.load(W, R1, R6, SKB_LEN);
+    asm.store(DW, R10, VALUE + bytes, R1);
+    asm.mov(R7, R10);
+    ports(&mut asm, R7, VALUE);
+    update(&mut asm, map, BPF_NOEXIST);
+    asm.jump("out");
+
+    asm.label("found");
+    asm.mov(R7, R0);
+    asm.load(W, R1, R6, SKB_LEN);
+    asm.atomic_add(R7, bytes, R1);
+    // 创建套接字时还没有绑定和连接，第一个数据包才有端口
+    asm.load(H, R1, R7, LOCAL_PORT);
+    asm.jump_if_nonzero(R1, "out");
+    ports(&mut asm, R7, 0);
+    asm.label("out");
+    allow(&mut asm);
+    asm.finish()
+}
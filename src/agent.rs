@@ -0,0 +1,166 @@
+// src/agent.rs
+use crate::breaker::SinkHealth;
+use crate::environment::{self, Environment};
+use crate::histogram::{Histogram, Percentiles};
+use crate::{bench, collector, version};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// 本程序自身的资源占用和发送情况，用来确认监控程序本身没有拖慢主机
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentStats {
+    /// 程序版本，便于排查不同主机上的版本差异
+    #[serde(default)]
+    pub version: String,
+    /// 构建时的git提交号
+    #[serde(default)]
+    pub commit: String,
+    /// 运行的平台：`linux`、`macos`、`windows`、`freebsd` 等
+    #[serde(default)]
+    pub platform: String,
+    /// 这个平台上无法采集、样本中总是为0的字段，例如Windows上的 `proc.running`；
+    /// 以及本次读不到数据、从样本中省略的采集项，例如 /proc 不可读时的 `proc`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unavailable: Vec<String>,
+    /// 运行环境：物理机、虚拟机、WSL或容器
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,
+    /// 累计使用的CPU时间(毫秒，用户态+内核态)
+    pub cpu_time_ms: u64,
+    /// 与上一个样本之间的CPU占用率(%)
+    pub cpu: f32,
+    /// 常驻内存(字节)
+    pub rss: u64,
+    /// 本次采集的耗时(毫秒)
+    pub collect_ms: f64,
+    /// 本次完成采集的各采集项的耗时(毫秒)，各采集项同时采集，`collect_ms` 约等于其中最长的一个
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub collectors_ms: BTreeMap<String, f64>,
+    /// 启动以来各次采集耗时的分布，偶尔一次的长时间停顿在 `collect_ms` 和平均值中都很难看出
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collect_percentiles: Option<Percentiles>,
+    /// 上一次发送的耗时(毫秒)，还没有发送过时没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_ms: Option<f64>,
+    /// 启动以来各次发送耗时的分布，包括失败的发送
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_percentiles: Option<Percentiles>,
+    /// 累计发送失败的次数，断路器断开时跳过的发送不计入
+    pub send_errors: u64,
+    /// 磁盘缓存中等待发送的字节数
+    pub spool_bytes: u64,
+    /// 端点(`endpoint`)、各导出插件(`exporter:名称`)和模板输出(`template:名称`)的断路器状态，还没有发送过时没有
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sinks: BTreeMap<String, SinkHealth>,
+    /// 过载时采集间隔放慢的倍数，没有放慢时没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slowdown: Option<u32>,
+    /// 过载时暂停的采集项
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shed: Vec<String>,
+    /// 启动以来各采集项panic后重新初始化的次数，没有panic过的采集项不列出
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub restarts: BTreeMap<String, u32>,
+}
+
+/// 在样本之间累积自身的统计
+#[derive(Default)]
+pub struct Agent {
+    last: Option<(Instant, Duration)>,
+    send_ms: Option<f64>,
+    send_errors: u64,
+    spool_bytes: u64,
+    sinks: BTreeMap<String, SinkHealth>,
+    collect_histogram: Histogram,
+    send_histogram: Histogram,
+    restarts: BTreeMap<String, u32>,
+}
+
+impl Agent {
+    /// 记录一次发送的耗时和结果，以及发送之后磁盘缓存的大小和各发送目标的状态；
+    /// 断路器断开、没有连接端点时没有耗时
+    pub fn sent(&mut self, elapsed: Option<Duration>, ok: bool, spool_bytes: u64, sinks: BTreeMap<String, SinkHealth>) {
+        if let Some(elapsed) = elapsed {
+            self.send_ms = Some(millis(elapsed));
+            self.send_histogram.record(elapsed);
+            if !ok {
+                self.send_errors += 1;
+            }
+        }
+        self.spool_bytes = spool_bytes;
+        self.sinks = sinks;
+    }
+
+    /// 记录采集项 `name` 的一次panic，返回启动以来的次数
+    pub fn restarted(&mut self, name: &str) -> u32 {
+        let restarts = self.restarts.entry(name.to_string()).or_default();
+        *restarts += 1;
+        *restarts
+    }
+
+    /// 生成本次样本的统计，`collect` 为本次采集的耗时
+    pub fn sample(&mut self, collect: Duration) -> AgentStats {
+        let now = Instant::now();
+        let cpu_time = bench::cpu_time();
+        let cpu = match self.last {
+            Some((at, used)) if now > at => {
+                (cpu_time.saturating_sub(used).as_secs_f64() / (now - at).as_secs_f64() * 100.0) as f32
+            }
+            _ => 0.0,
+        };
+        self.last = Some((now, cpu_time));
+        self.collect_histogram.record(collect);
+
+        AgentStats {
+            version: version::VERSION.to_string(),
+            commit: version::COMMIT.to_string(),
+            platform: std::env::consts::OS.to_string(),
+            unavailable: collector::UNAVAILABLE.iter().map(|field| field.to_string()).collect(),
+            environment: Some(environment::current().clone()),
+            cpu_time_ms: cpu_time.as_millis() as u64,
+            cpu,
+            rss: rss(),
+            collect_ms: millis(collect),
+            collectors_ms: BTreeMap::new(),
+            collect_percentiles: self.collect_histogram.percentiles(),
+            send_ms: self.send_ms,
+            send_percentiles: self.send_histogram.percentiles(),
+            send_errors: self.send_errors,
+            spool_bytes: self.spool_bytes,
+            sinks: self.sinks.clone(),
+            slowdown: None,
+            shed: Vec::new(),
+            restarts: self.restarts.clone(),
+        }
+    }
+}
+
+/// 精确到微秒的毫秒数
+fn millis(duration: Duration) -> f64 {
+    duration.as_micros() as f64 / 1000.0
+}
+
+/// 当前的常驻内存(字节)，从 /proc/self/statm 读取
+#[cfg(target_os = "linux")]
+fn rss() -> u64 {
+    let pages = std::fs::read_to_string("/proc/self/statm")
+        .ok()
+        .and_then(|statm| statm.split_whitespace().nth(1)?.parse::<u64>().ok())
+        .unwrap_or(0);
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    pages * page_size.max(0) as u64
+}
+
+/// 当前的常驻内存(字节)，没有 /proc 的平台上通过sysinfo读取(macOS为 proc_pidinfo，FreeBSD为sysctl的 kern.proc)
+#[cfg(not(target_os = "linux"))]
+fn rss() -> u64 {
+    use sysinfo::{ProcessExt, ProcessRefreshKind, SystemExt};
+
+    let Ok(pid) = sysinfo::get_current_pid() else {
+        return 0;
+    };
+    let mut system = sysinfo::System::new();
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+    system.process(pid).map_or(0, |process| process.memory())
+}
@@ -0,0 +1,238 @@
+// src/aggregate.rs
+use crate::alert::Metric;
+use crate::churn::ProcessChurn;
+use crate::log_watch::LogMatches;
+use crate::probe::ProbeStats;
+use crate::talkers::NetTalkers;
+use crate::{CpuWindow, MemoryStats, NetworkStats, SystemStats};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// 未指定 `--aggregate-metric` 时汇总的指标
+pub const DEFAULT_METRICS: [&str; 9] = [
+    "cpu",
+    "cpu.max_percent",
+    "mem.used_percent",
+    "swap.used_percent",
+    "disk.used_percent",
+    "net.rx",
+    "net.tx",
+    "agent.collect_ms",
+    "agent.send_ms",
+];
+
+/// 一个汇总窗口的统计，附在汇总后的样本中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Aggregate {
+    /// 窗口中第一个样本的时间，样本的 `timestamp` 为最后一个样本的时间
+    pub since: DateTime<Utc>,
+    /// 窗口中的样本数
+    pub samples: usize,
+    /// 每个指标在窗口内的分布
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metrics: Vec<Summary>,
+    /// 按网卡名，窗口内收发的总字节数
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub bytes: HashMap<String, Traffic>,
+}
+
+/// 一个指标在一个实例上的最小值、最大值、均值和95分位数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    /// 汇总的指标，例如 `net.tx`
+    pub metric: String,
+    /// 挂载点或网卡名，其余指标没有
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub instance: String,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub p95: f64,
+}
+
+/// 收发的字节数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Traffic {
+    pub rx: u64,
+    pub tx: u64,
+}
+
+/// 把一个窗口内的样本合成一个样本：CPU和网速取均值，内存和swap取占用最高的一次，其余字段取最后一个样本
+pub struct Aggregator {
+    window: Duration,
+    metrics: Vec<(String, Metric)>,
+    pending: Vec<SystemStats>,
+    bytes: HashMap<String, Traffic>,
+    /// 上一个样本的时间，用来把网速换算成字节数；跨窗口保留
+    last: Option<DateTime<Utc>>,
+}
+
+impl Aggregator {
+    pub fn new(window: Duration, metrics: Vec<(String, Metric)>) -> Self {
+        Self { window, metrics, pending: Vec::new(), bytes: HashMap::new(), last: None }
+    }
+
+    /// 重新加载配置时更新设置，当前窗口中的样本保留
+    pub fn configure(&mut self, window: Duration, metrics: Vec<(String, Metric)>) {
+        self.window = window;
+        self.metrics = metrics;
+    }
+
+    /// 窗口为零时不汇总，每个样本原样发送
+    pub fn enabled(&self) -> bool {
+        !self.window.is_zero()
+    }
+
+    /// 加入一个样本，窗口结束时返回汇总后的样本
+    pub fn push(&mut self, stats: SystemStats) -> Option<SystemStats> {
+        // 重新加载配置关闭汇总时，窗口中剩下的样本和这个样本一起汇总发出
+        if !self.enabled() && self.pending.is_empty() {
+            self.last = Some(stats.timestamp);
+            return Some(stats);
+        }
+        if let Some(last) = self.last {
+            let elapsed = (stats.timestamp - last).to_std().unwrap_or_default().as_secs_f64();
+            for (iface, net) in &stats.net {
+                let traffic = self.bytes.entry(iface.clone()).or_default();
+                traffic.rx += (net.rx.unwrap_or(0) as f64 * elapsed) as u64;
+                traffic.tx += (net.tx.unwrap_or(0) as f64 * elapsed) as u64;
+            }
+        }
+        self.last = Some(stats.timestamp);
+        self.pending.push(stats);
+
+        let first = self.pending[0].timestamp;
+        let last = self.pending[self.pending.len() - 1].timestamp;
+        match (last - first).to_std() {
+            // 下一个样本会超出窗口时就结束，窗口长度是间隔的整数倍时每个窗口的样本数相同
+            Ok(span) if span + self.interval() >= self.window => self.take(),
+            _ => None,
+        }
+    }
+
+    /// 立即汇总当前窗口中的样本，退出前调用；没有样本时返回 None
+    pub fn take(&mut self) -> Option<SystemStats> {
+        let samples = std::mem::take(&mut self.pending);
+        let bytes = std::mem::take(&mut self.bytes);
+        let mut stats = samples.last()?.clone();
+
+        let count = samples.len();
+        let cores = stats.cpu.len();
+        if samples.iter().all(|s| s.cpu.len() == cores) {
+            stats.cpu = (0..cores)
+                .map(|i| samples.iter().map(|s| s.cpu[i]).sum::<f32>() / count as f32)
+                .collect();
+        }
+        // 测量窗口首尾相接，合起来是整个汇总窗口
+        let windows: Vec<&CpuWindow> = samples.iter().filter_map(|s| s.cpu_window.as_ref()).collect();
+        if !windows.is_empty() {
+            stats.cpu_window = Some(CpuWindow {
+                ms: windows.iter().map(|w| w.ms).sum(),
+                samples: windows.iter().map(|w| w.samples).sum(),
+                peak: (0..cores)
+                    .map(|i| samples.iter().map(|s| cpu_peak(s, i)).fold(0.0, f32::max))
+                    .collect(),
+            });
+        }
+        stats.mem = peak(samples.iter().filter_map(|s| s.mem.as_ref()));
+        stats.swap = peak(samples.iter().filter_map(|s| s.swap.as_ref()));
+        for (iface, net) in &mut stats.net {
+            let rates: Vec<&NetworkStats> = samples.iter().filter_map(|s| s.net.get(iface)).collect();
+            net.rx = average(rates.iter().filter_map(|r| r.rx));
+            net.tx = average(rates.iter().filter_map(|r| r.tx));
+        }
+        // 温度和内存一样取窗口中的最高值
+        for (sensor, celsius) in &mut stats.temp {
+            *celsius = samples.iter().filter_map(|s| s.temp.get(sensor)).fold(*celsius, |a, b| a.max(*b));
+        }
+        // 往返时间和抖动取窗口中的平均值，丢包率按窗口中全部探测计算
+        for (target, probe) in &mut stats.probes {
+            let results: Vec<&ProbeStats> = samples.iter().filter_map(|s| s.probes.get(target)).collect();
+            let mean = |values: Vec<f64>| (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+            probe.rtt_ms = mean(results.iter().filter_map(|r| r.rtt_ms).collect());
+            probe.jitter_ms = mean(results.iter().filter_map(|r| r.jitter_ms).collect());
+            probe.sent = results.iter().map(|r| r.sent).sum();
+            probe.received = results.iter().map(|r| r.received).sum();
+            probe.loss_percent = probe.sent.saturating_sub(probe.received) as f64 / probe.sent.max(1) as f64 * 100.0;
+        }
+        // 日志的匹配行数是每次采集新增的，窗口中合计
+        for (pattern, logs) in &mut stats.logs {
+            let windows: Vec<&LogMatches> = samples.iter().filter_map(|s| s.logs.get(pattern)).collect();
+            logs.matches = windows.iter().map(|l| l.matches).sum();
+            logs.per_second = windows.iter().map(|l| l.per_second).sum::<f64>() / windows.len() as f64;
+        }
+        // 进程的启动和退出只出现在采集到它们的样本中，窗口中的都保留
+        stats.lifecycle = samples.iter().flat_map(|s| s.lifecycle.iter().cloned()).collect();
+        // 进程的创建和退出同样是每次采集新增的，窗口中合计
+        stats.churn = ProcessChurn::merge(&samples.iter().filter_map(|s| s.churn.as_ref()).collect::<Vec<_>>());
+        // 各进程和端口的流量是速率，窗口中取平均值
+        stats.talkers = NetTalkers::merge(&samples.iter().filter_map(|s| s.talkers.as_ref()).collect::<Vec<_>>());
+
+        stats.aggregate = Some(Aggregate {
+            since: samples[0].timestamp,
+            samples: count,
+            metrics: self.summarize(&samples),
+            bytes,
+        });
+        Some(stats)
+    }
+
+    fn summarize(&self, samples: &[SystemStats]) -> Vec<Summary> {
+        let mut summaries = Vec::new();
+        for (name, metric) in &self.metrics {
+            let mut values: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+            for stats in samples {
+                for (instance, value) in metric.values(stats) {
+                    values.entry(instance).or_default().push(value);
+                }
+            }
+            for (instance, mut values) in values {
+                values.sort_by(f64::total_cmp);
+                summaries.push(Summary {
+                    metric: name.clone(),
+                    instance,
+                    min: values[0],
+                    max: values[values.len() - 1],
+                    avg: values.iter().sum::<f64>() / values.len() as f64,
+                    p95: percentile(&values, 0.95),
+                });
+            }
+        }
+        summaries
+    }
+
+    /// 窗口中相邻样本的平均间隔，只有一个样本时为零
+    fn interval(&self) -> Duration {
+        let n = self.pending.len();
+        if n < 2 {
+            return Duration::ZERO;
+        }
+        let span = self.pending[n - 1].timestamp - self.pending[0].timestamp;
+        span.to_std().unwrap_or_default() / (n - 1) as u32
+    }
+}
+
+/// 有速率的样本的均值，都没有速率时为 None
+fn average(rates: impl Iterator<Item = u64>) -> Option<u64> {
+    let (sum, count) = rates.fold((0, 0), |(sum, count), rate| (sum + rate, count + 1));
+    sum.checked_div(count)
+}
+
+/// 一个样本中第 `core` 个核心的最高占用率，只刷新过一次时就是它的占用率
+fn cpu_peak(stats: &SystemStats, core: usize) -> f32 {
+    let peak = stats.cpu_window.as_ref().and_then(|w| w.peak.get(core));
+    peak.or(stats.cpu.get(core)).copied().unwrap_or(0.0)
+}
+
+/// 占用最高的一次
+fn peak<'a>(values: impl Iterator<Item = &'a MemoryStats>) -> Option<MemoryStats> {
+    values.max_by_key(|m| m.used).cloned()
+}
+
+/// 最近秩法的分位数，`sorted` 已按升序排列且不为空
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
@@ -0,0 +1,295 @@
+// src/aggregator.rs
+use crate::crypto::{self, SIGNATURE_HEADER};
+use crate::delta::{Decoded, Decoder};
+use crate::i18n::tr;
+use crate::prometheus::Metrics;
+use crate::serve::{authorized, header, read_request, respond};
+use crate::SystemStats;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
+
+/// 读取请求的超时时间和一次推送的最大长度
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// 一个代理的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum State {
+    /// 在 `--stale-after` 内推送过样本
+    Up,
+    /// 超过 `--stale-after` 没有推送
+    Stale,
+    /// 发送了 `stopping` 事件，主动停止
+    Stopped,
+}
+
+/// 一个代理最近一次推送的情况
+#[derive(Debug, Serialize)]
+pub struct Host {
+    pub state: State,
+    /// 最近一次推送的来源地址，`remote` 轮询的主机为SSH的目标地址
+    pub address: String,
+    pub last_seen: DateTime<Utc>,
+    /// 最新的样本，只收到过事件时没有
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<SystemStats>,
+    #[serde(skip)]
+    seen: Instant,
+}
+
+/// 推送的请求体中的事件，只关心 `stopping`
+#[derive(Deserialize)]
+struct Event {
+    event: String,
+    #[serde(default)]
+    instance_id: Option<String>,
+    #[serde(default)]
+    tags: BTreeMap<String, String>,
+}
+
+enum Item {
+    Stats(Box<SystemStats>),
+    /// 代理使用 `--delta` 时的样本，按主机还原后才能解析
+    Delta(Value),
+    Event(Event),
+}
+
+/// 接收多个代理推送的样本，按主机保留最新的样本和状态；主机以样本的 `host` 标签区分，没有时用实例ID，都没有时用来源地址
+pub struct Aggregator {
+    hosts: RwLock<BTreeMap<String, Host>>,
+    stale_after: Duration,
+    token: Option<String>,
+    /// 设置了时推送需要带上用这个密钥计算的 `X-Sysmon-Signature`
+    signing_key: Option<String>,
+    deltas: Mutex<Decoder>,
+    received: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl Aggregator {
+    /// 设置了 `token` 时除 `/healthz` 外都需要 `Authorization: Bearer <token>`，代理用 `--token` 设置；
+    /// 设置了 `signing_key` 时推送需要带上正确的签名，代理用 `--signing-key` 设置
+    pub fn new(stale_after: Duration, token: Option<String>, signing_key: Option<String>) -> Arc<Self> {
+        Arc::new(Self {
+            hosts: RwLock::new(BTreeMap::new()),
+            stale_after,
+            token,
+            signing_key,
+            deltas: Mutex::new(Decoder::default()),
+            received: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        })
+    }
+
+    /// 处理一次推送：单个样本、样本数组(`--batch`)或事件，返回其中最大的样本序号，只有事件时为 `None`
+    pub fn ingest(&self, body: &[u8], address: IpAddr) -> Result<Option<u64>, String> {
+        let items = match serde_json::from_slice::<Value>(body).map_err(|e| e.to_string())? {
+            Value::Array(items) => items,
+            item => vec![item],
+        };
+        // 先全部解析，有一个无效时整个请求都不接受，代理会重发
+        let items = items
+            .into_iter()
+            .map(|item| match (item.get("event"), item.get("delta")) {
+                (Some(_), _) => serde_json::from_value(item).map(Item::Event),
+                (None, Some(_)) => Ok(Item::Delta(item)),
+                (None, None) => serde_json::from_value(item).map(|stats| Item::Stats(Box::new(stats))),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        let mut ack = None;
+        let mut hosts = self.hosts.write().unwrap();
+        for item in items {
+            match item {
+                Item::Stats(stats) => {
+                    ack = ack.max(Some(stats.seq));
+                    let name = host_name(&stats.tags, stats.instance_id.as_deref(), address);
+                    self.update(&mut hosts, name, address.to_string(), *stats);
+                }
+                Item::Delta(item) => {
+                    ack = ack.max(item.get("seq").and_then(Value::as_u64));
+                    let tags = item.get("tags").and_then(|tags| serde_json::from_value(tags.clone()).ok()).unwrap_or_default();
+                    let name = host_name(&tags, item.get("instance_id").and_then(Value::as_str), address);
+                    let decoded = self.deltas.lock().unwrap().decode(&name, item);
+                    match decoded {
+                        Decoded::Sample(sample) => match serde_json::from_value(sample) {
+                            Ok(stats) => self.update(&mut hosts, name, address.to_string(), stats),
+                            Err(error) => tracing::warn!(host = %name, %error, "{}", tr("无法还原增量样本")),
+                        },
+                        Decoded::Missing { prev } => {
+                            tracing::debug!(host = %name, prev, "{}", tr("缺少前一个样本，等待下一个完整样本"));
+                        }
+                    }
+                }
+                Item::Event(event) if event.event == "stopping" => {
+                    let name = host_name(&event.tags, event.instance_id.as_deref(), address);
+                    if let Some(host) = hosts.get_mut(&name) {
+                        tracing::info!(host = %name, "{}", tr("主机已停止"));
+                        host.state = State::Stopped;
+                        host.last_seen = Utc::now();
+                    }
+                }
+                Item::Event(_) => {}
+            }
+        }
+        Ok(ack)
+    }
+
+    /// 记录一个主机的样本，`remote` 轮询到样本时直接调用
+    pub fn record(&self, name: String, address: String, stats: SystemStats) {
+        self.update(&mut self.hosts.write().unwrap(), name, address, stats);
+    }
+
+    fn update(&self, hosts: &mut BTreeMap<String, Host>, name: String, address: String, stats: SystemStats) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+        let host = hosts.entry(name.clone()).or_insert_with(|| {
+            tracing::info!(host = %name, %address, "{}", tr("发现新的主机"));
+            Host { state: State::Up, address: address.clone(), last_seen: Utc::now(), stats: None, seen: Instant::now() }
+        });
+        if host.state != State::Up {
+            tracing::info!(host = %name, %address, "{}", tr("主机恢复推送"));
+        }
+        host.state = State::Up;
+        host.address = address;
+        host.last_seen = Utc::now();
+        host.seen = Instant::now();
+        // 代理重发缓存中的旧样本时不覆盖更新的样本
+        if host.stats.as_ref().is_none_or(|latest| latest.timestamp <= stats.timestamp) {
+            host.stats = Some(stats);
+        }
+    }
+
+    /// 没有设置签名密钥，或者请求带有正确的签名
+    fn signed(&self, request: &str, body: &[u8]) -> bool {
+        let Some(key) = &self.signing_key else { return true };
+        header(request, SIGNATURE_HEADER).is_some_and(|signature| crypto::verify(key, body, signature))
+    }
+
+    /// 把超过 `--stale-after` 没有推送的主机标记为失联，定期调用
+    pub fn check(&self) {
+        for (name, host) in self.hosts.write().unwrap().iter_mut() {
+            if host.state == State::Up && host.seen.elapsed() > self.stale_after {
+                tracing::warn!(host = %name, last_seen = %host.last_seen, "{}", tr("主机失联"));
+                host.state = State::Stale;
+            }
+        }
+    }
+
+    /// 所有主机的状态和最新样本
+    fn hosts(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&*self.hosts.read().unwrap())
+    }
+
+    fn host(&self, name: &str) -> Option<serde_json::Result<String>> {
+        self.hosts.read().unwrap().get(name).map(serde_json::to_string)
+    }
+
+    /// 本服务自身的指标、每个主机的状态，以及在线主机的最新样本(带 `host` 标签)
+    fn metrics(&self) -> String {
+        let hosts = self.hosts.read().unwrap();
+        let mut out = Metrics::default();
+
+        out.family("sysmon_aggregator_hosts", "gauge", "Hosts by state");
+        for (label, state) in [("up", State::Up), ("stale", State::Stale), ("stopped", State::Stopped)] {
+            let count = hosts.values().filter(|host| host.state == state).count();
+            out.sample("sysmon_aggregator_hosts", &[("state", label)], count as f64);
+        }
+        out.family("sysmon_aggregator_samples_received_total", "counter", "Samples received from agents");
+        out.sample("sysmon_aggregator_samples_received_total", &[], self.received.load(Ordering::Relaxed) as f64);
+        out.family("sysmon_aggregator_requests_rejected_total", "counter", "Pushes rejected as unauthorized or invalid");
+        out.sample("sysmon_aggregator_requests_rejected_total", &[], self.rejected.load(Ordering::Relaxed) as f64);
+
+        if !hosts.is_empty() {
+            out.family("sysmon_host_up", "gauge", "Whether the host pushed a sample within --stale-after");
+            out.family("sysmon_host_last_seen_timestamp_seconds", "gauge", "Time of the last push from the host");
+        }
+        for (name, host) in hosts.iter() {
+            let labels = [("host", name.as_str())];
+            out.sample("sysmon_host_up", &labels, if host.state == State::Up { 1.0 } else { 0.0 });
+            out.sample("sysmon_host_last_seen_timestamp_seconds", &labels, host.last_seen.timestamp_millis() as f64 / 1000.0);
+        }
+        // 失联或停止的主机不再输出样本，以免Prometheus一直看到旧的值
+        for (name, host) in hosts.iter().filter(|(_, host)| host.state == State::Up) {
+            if let Some(stats) = &host.stats {
+                out.stats(stats, &[("host", name)]);
+            }
+        }
+        out.finish()
+    }
+}
+
+fn host_name(tags: &BTreeMap<String, String>, instance_id: Option<&str>, address: IpAddr) -> String {
+    tags.get("host").map(String::as_str).or(instance_id).map_or_else(|| address.to_string(), str::to_string)
+}
+
+/// 汇总服务：`POST /` 接收代理推送的样本并返回 `{"ack": N}`，`GET /hosts` 返回所有主机的状态和最新样本，
+/// `GET /hosts/NAME` 返回一个主机的，`GET /metrics` 按Prometheus文本格式返回，`GET /healthz` 在服务运行时返回 `ok`
+pub async fn run(listener: TcpListener, aggregator: Arc<Aggregator>) {
+    loop {
+        let Ok((stream, peer)) = listener.accept().await else {
+            continue;
+        };
+        let aggregator = aggregator.clone();
+        tokio::spawn(async move {
+            let _ = handle(stream, peer.ip(), aggregator).await;
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream, address: IpAddr, aggregator: Arc<Aggregator>) -> std::io::Result<()> {
+    let Ok(request) = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream, MAX_BODY_BYTES)).await else {
+        return Ok(());
+    };
+    let (request, body) = request?;
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+
+    let denied = path != "/healthz" && aggregator.token.as_deref().is_some_and(|token| !authorized(&request, query, token));
+    let (status, body) = match (method, path, body) {
+        _ if denied => ("401 Unauthorized", String::new()),
+        ("POST", "/", None) => ("413 Payload Too Large", String::new()),
+        ("POST", "/", Some(body)) if !aggregator.signed(&request, &body) => {
+            tracing::warn!(%address, "{}", tr("推送的签名不符，已拒绝"));
+            ("401 Unauthorized", String::new())
+        }
+        ("POST", "/", Some(body)) => match aggregator.ingest(&body, address) {
+            Ok(ack) => ("200 OK", serde_json::json!({ "ack": ack }).to_string()),
+            Err(error) => {
+                tracing::warn!(%address, %error, "{}", tr("无法解析推送的样本"));
+                ("400 Bad Request", String::new())
+            }
+        },
+        ("GET", "/hosts", _) => match aggregator.hosts() {
+            Ok(body) => ("200 OK", body),
+            Err(_) => ("500 Internal Server Error", String::new()),
+        },
+        ("GET", "/metrics", _) => ("200 OK", aggregator.metrics()),
+        ("GET", "/healthz", _) => ("200 OK", "ok\n".to_string()),
+        ("GET", path, _) => match path.strip_prefix("/hosts/").and_then(|name| aggregator.host(name)) {
+            Some(Ok(body)) => ("200 OK", body),
+            Some(Err(_)) => ("500 Internal Server Error", String::new()),
+            None => ("404 Not Found", String::new()),
+        },
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+    if method == "POST" && !status.starts_with('2') {
+        aggregator.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let content_type = match path {
+        "/metrics" => "text/plain; version=0.0.4",
+        "/healthz" => "text/plain",
+        _ => "application/json",
+    };
+    respond(stream, status, content_type, &body, denied).await
+}
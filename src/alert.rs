@@ -0,0 +1,792 @@
+// src/alert.rs
+use crate::display::percent;
+use crate::forecast::Trend;
+use crate::i18n::tr;
+use crate::SystemStats;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// 比较运算符
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn parse(op: &str) -> Option<Self> {
+        Some(match op {
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            "==" => Op::Eq,
+            "!=" => Op::Ne,
+            _ => return None,
+        })
+    }
+
+    fn test(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Op::Gt => value > threshold,
+            Op::Ge => value >= threshold,
+            Op::Lt => value < threshold,
+            Op::Le => value <= threshold,
+            Op::Eq => value == threshold,
+            Op::Ne => value != threshold,
+        }
+    }
+}
+
+/// 规则引用的指标：`section["key"].field`，省略 `["key"]` 时对每个挂载点/网卡分别判断
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    section: String,
+    key: Option<String>,
+    field: String,
+}
+
+/// 有多个实例、可以用 `["key"]` 按名称选择的部分
+const KEYED: [&str; 9] = ["disk", "net", "temp", "probes", "http", "dns", "tls", "logs", "derived"];
+
+/// 各部分可用的字段，第一个是省略字段时的默认值
+const FIELDS: [(&str, &[&str]); 15] = [
+    ("cpu", &["used_percent", "max_percent"]),
+    ("mem", &["used_percent", "free_percent", "used", "free", "total", "growth_mb_per_hour", "hours_until_full"]),
+    ("swap", &["used_percent", "free_percent", "used", "free", "total"]),
+    ("disk", &["used_percent", "free_percent", "used", "free", "total", "growth_mb_per_hour", "hours_until_full"]),
+    ("net", &["rx", "tx"]),
+    ("proc", &["total", "running", "sleeping", "zombie"]),
+    ("churn", &["short_lived", "exits", "forks", "cpu", "cpu_s"]),
+    ("temp", &["celsius"]),
+    ("probes", &["rtt_ms", "jitter_ms", "loss_percent"]),
+    ("http", &["ok", "response_ms", "status"]),
+    ("dns", &["ok", "lookup_ms"]),
+    ("tls", &["days_left"]),
+    ("logs", &["per_second", "matches"]),
+    ("derived", &["value"]),
+    ("agent", &["cpu", "rss", "collect_ms", "send_ms", "send_errors", "spool_bytes", "collect_p99", "send_p99"]),
+];
+
+impl Metric {
+    /// 解析单独的指标，例如 `net.tx`、`disk["/var"].used`
+    pub fn parse(text: &str) -> Option<Self> {
+        static RE: OnceLock<Regex> = OnceLock::new();
+        let re = RE.get_or_init(|| Regex::new(r#"^\s*([a-z]+)(?:\[\s*"([^"]*)"\s*\])?(?:\.([a-z_][a-z0-9_]*))?\s*$"#).unwrap());
+        let caps = re.captures(text)?;
+        Self::new(&caps[1], caps.get(2).map(|m| m.as_str()), caps.get(3).map(|m| m.as_str()))
+    }
+
+    fn new(section: &str, key: Option<&str>, field: Option<&str>) -> Option<Self> {
+        let (_, fields) = FIELDS.iter().find(|(name, _)| *name == section)?;
+        let field = match field {
+            Some(field) => fields.iter().find(|f| **f == field)?,
+            None => fields.first()?,
+        };
+        // 只有磁盘、网卡、温度传感器、探测目标、HTTP、DNS、TLS检查、日志模式和派生指标可以按名称选择
+        if key.is_some() && !KEYED.contains(&section) {
+            return None;
+        }
+        Some(Self {
+            section: section.to_string(),
+            key: key.map(str::to_string),
+            field: field.to_string(),
+        })
+    }
+
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// 是否只会取出一个值：没有多个实例的部分，或者用 `["key"]` 选择了实例
+    pub fn single(&self) -> bool {
+        self.key.is_some() || !KEYED.contains(&self.section.as_str())
+    }
+
+    /// 从样本中取出指标的值：(实例, 值)，实例是挂载点、网卡名、传感器名、探测目标、HTTP、DNS、TLS检查、日志模式或派生指标的名称，其余指标为空字符串
+    pub fn values(&self, stats: &SystemStats) -> Vec<(String, f64)> {
+        // 总量为0(例如没有配置swap)时没有百分比，不参与判断
+        let usage = |used: u64, total: u64| match self.field.as_str() {
+            "used_percent" | "free_percent" if total == 0 => None,
+            "used_percent" => Some(percent(used, total)),
+            "free_percent" => Some(100.0 - percent(used, total)),
+            "used" => Some(used as f64),
+            "free" => Some(total.saturating_sub(used) as f64),
+            _ => Some(total as f64),
+        };
+        let selected = |name: &str| self.key.as_deref().is_none_or(|key| key == name);
+
+        match self.section.as_str() {
+            "cpu" if !stats.cpu.is_empty() => {
+                let value = match self.field.as_str() {
+                    "max_percent" => stats.cpu.iter().copied().fold(0.0, f32::max) as f64,
+                    _ => stats.cpu.iter().sum::<f32>() as f64 / stats.cpu.len() as f64,
+                };
+                vec![(String::new(), value)]
+            }
+            // 使用 `--forecast` 且数据足够时才有趋势，用量没有增长时没有 `hours_until_full`
+            "mem" | "disk" if matches!(self.field.as_str(), "growth_mb_per_hour" | "hours_until_full") => {
+                let Some(forecast) = &stats.forecast else { return Vec::new() };
+                let value = |trend: &Trend| match self.field.as_str() {
+                    "hours_until_full" => trend.hours_until_full,
+                    _ => Some(trend.growth_mb_per_hour),
+                };
+                match self.section.as_str() {
+                    "mem" => forecast.mem.iter().filter_map(|trend| Some((String::new(), value(trend)?))).collect(),
+                    _ => forecast.disk
+                        .iter()
+                        .filter(|(mount, _)| selected(mount))
+                        .filter_map(|(mount, trend)| Some((mount.clone(), value(trend)?)))
+                        .collect(),
+                }
+            }
+            "mem" => stats.mem.iter().filter_map(|m| Some((String::new(), usage(m.used, m.total)?))).collect(),
+            "swap" => stats.swap.iter().filter_map(|m| Some((String::new(), usage(m.used, m.total)?))).collect(),
+            "disk" => stats.disk
+                .iter()
+                .filter(|(mount, _)| selected(mount))
+                .filter_map(|(mount, disk)| Some((mount.clone(), usage(disk.used, disk.total)?)))
+                .collect(),
+            "net" => stats.net
+                .iter()
+                .filter(|(iface, _)| selected(iface))
+                .filter_map(|(iface, net)| Some((iface.clone(), if self.field == "rx" { net.rx } else { net.tx }? as f64)))
+                .collect(),
+            "proc" => stats.proc
+                .iter()
+                .map(|proc| {
+                    let value = match self.field.as_str() {
+                        "running" => proc.running,
+                        "sleeping" => proc.sleeping,
+                        "zombie" => proc.zombie,
+                        _ => proc.total,
+                    };
+                    (String::new(), value as f64)
+                })
+                .collect(),
+            "churn" => stats.churn
+                .iter()
+                .map(|churn| {
+                    let value = match self.field.as_str() {
+                        "exits" => churn.exits as f64,
+                        "forks" => churn.forks as f64,
+                        "cpu" => churn.cpu,
+                        "cpu_s" => churn.cpu_s,
+                        _ => churn.short_lived as f64,
+                    };
+                    (String::new(), value)
+                })
+                .collect(),
+            "temp" => stats.temp
+                .iter()
+                .filter(|(sensor, _)| selected(sensor))
+                .map(|(sensor, celsius)| (sensor.clone(), *celsius as f64))
+                .collect(),
+            // 全部丢失时没有往返时间，只有丢包率参与判断
+            "probes" => stats.probes
+                .iter()
+                .filter(|(target, _)| selected(target))
+                .filter_map(|(target, probe)| {
+                    let value = match self.field.as_str() {
+                        "jitter_ms" => probe.jitter_ms?,
+                        "loss_percent" => probe.loss_percent,
+                        _ => probe.rtt_ms?,
+                    };
+                    Some((target.clone(), value))
+                })
+                .collect(),
+            // `ok` 通过时为1、没有通过时为0；没有收到响应时没有响应时间和状态码
+            "http" => stats.http
+                .iter()
+                .filter(|(name, _)| selected(name))
+                .filter_map(|(name, check)| {
+                    let value = match self.field.as_str() {
+                        "response_ms" => check.response_ms?,
+                        "status" => check.status? as f64,
+                        _ => if check.ok { 1.0 } else { 0.0 },
+                    };
+                    Some((name.clone(), value))
+                })
+                .collect(),
+            // 超时时没有解析用时
+            "dns" => stats.dns
+                .iter()
+                .filter(|(name, _)| selected(name))
+                .filter_map(|(name, check)| {
+                    let value = match self.field.as_str() {
+                        "lookup_ms" => check.lookup_ms?,
+                        _ => if check.ok { 1.0 } else { 0.0 },
+                    };
+                    Some((name.clone(), value))
+                })
+                .collect(),
+            // 连接或握手失败时没有有效期，不参与判断
+            "tls" => stats.tls
+                .iter()
+                .filter(|(endpoint, _)| selected(endpoint))
+                .filter_map(|(endpoint, check)| Some((endpoint.clone(), check.days_left?)))
+                .collect(),
+            "logs" => stats.logs
+                .iter()
+                .filter(|(pattern, _)| selected(pattern))
+                .map(|(pattern, logs)| {
+                    let value = if self.field == "matches" { logs.matches as f64 } else { logs.per_second };
+                    (pattern.clone(), value)
+                })
+                .collect(),
+            "derived" => stats.derived
+                .iter()
+                .filter(|(name, _)| selected(name))
+                .map(|(name, value)| (name.clone(), *value))
+                .collect(),
+            // 还没有发送过时没有 `send_ms` 和 `send_p99`
+            "agent" if self.field == "send_ms" => stats.agent
+                .iter()
+                .filter_map(|agent| Some((String::new(), agent.send_ms?)))
+                .collect(),
+            "agent" if self.field == "send_p99" => stats.agent
+                .iter()
+                .filter_map(|agent| Some((String::new(), agent.send_percentiles.as_ref()?.p99)))
+                .collect(),
+            "agent" if self.field == "collect_p99" => stats.agent
+                .iter()
+                .filter_map(|agent| Some((String::new(), agent.collect_percentiles.as_ref()?.p99)))
+                .collect(),
+            "agent" => stats.agent
+                .iter()
+                .map(|agent| {
+                    let value = match self.field.as_str() {
+                        "cpu" => agent.cpu as f64,
+                        "rss" => agent.rss as f64,
+                        "collect_ms" => agent.collect_ms,
+                        "send_errors" => agent.send_errors as f64,
+                        _ => agent.spool_bytes as f64,
+                    };
+                    (String::new(), value)
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// 阈值的单位：KB/MB/GB/TB 按1000进位，KiB/MiB/GiB/TiB 按1024进位，`%` 和 `B` 不换算
+fn unit_factor(unit: &str) -> Option<f64> {
+    Some(match unit {
+        "" | "%" | "B" => 1.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    })
+}
+
+/// 一条告警规则，例如 `mem.used_percent > 90 for 5m clear 80 every 1h`、`disk["/var"].free < 5GB`
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    /// 规则的原始表达式
+    pub expr: String,
+    metric: Metric,
+    op: Op,
+    threshold: f64,
+    /// 条件需要持续满足多久才触发
+    duration: Duration,
+    /// 触发后以这个阈值判断是否解除，避免指标在阈值附近来回波动时反复触发
+    clear: Option<f64>,
+    /// 持续触发时每隔这段时间再通知一次
+    every: Option<Duration>,
+}
+
+fn rule_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r#"^\s*([a-z]+)(?:\[\s*"([^"]*)"\s*\])?(?:\.([a-z_][a-z0-9_]*))?\s*(>=|<=|==|!=|>|<)\s*(-?[0-9]+(?:\.[0-9]+)?)\s*([A-Za-z%]*)(?:\s+for\s+(\S+))?(?:\s+clear\s+(-?[0-9]+(?:\.[0-9]+)?)\s*([A-Za-z%]*))?(?:\s+every\s+(\S+))?\s*$"#,
+        )
+        .unwrap()
+    })
+}
+
+impl Rule {
+    /// 解析 `NAME=EXPR` 形式的规则
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("{}: {}", tr("无效的告警规则"), value);
+        let (name, expr) = value.split_once('=').ok_or_else(invalid)?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid());
+        }
+
+        let caps = rule_regex().captures(expr).ok_or_else(invalid)?;
+        let group = |i: usize| caps.get(i).map(|m| m.as_str());
+        let metric = Metric::new(&caps[1], group(2), group(3)).ok_or_else(invalid)?;
+        let op = Op::parse(&caps[4]).ok_or_else(invalid)?;
+        let factor = unit_factor(&caps[6]).ok_or_else(invalid)?;
+        let threshold = caps[5].parse::<f64>().map_err(|_| invalid())? * factor;
+        let duration = match group(7) {
+            Some(duration) => crate::parse_duration(duration).ok_or_else(invalid)?,
+            None => Duration::ZERO,
+        };
+        let clear = match group(8) {
+            Some(clear) => {
+                let factor = unit_factor(group(9).unwrap_or_default()).ok_or_else(invalid)?;
+                Some(clear.parse::<f64>().map_err(|_| invalid())? * factor)
+            }
+            None => None,
+        };
+        // 解除阈值必须在触发条件不满足的一侧，`==` 和 `!=` 不能设置
+        let valid_clear = clear.is_none_or(|clear| match op {
+            Op::Gt | Op::Ge => clear <= threshold,
+            Op::Lt | Op::Le => clear >= threshold,
+            Op::Eq | Op::Ne => false,
+        });
+        if !valid_clear {
+            return Err(invalid());
+        }
+        let every = match group(10) {
+            Some(every) => Some(crate::parse_duration(every).filter(|d| !d.is_zero()).ok_or_else(invalid)?),
+            None => None,
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            expr: expr.trim().to_string(),
+            metric,
+            op,
+            threshold,
+            duration,
+            clear,
+            every,
+        })
+    }
+}
+
+/// `--alert NAME=EXPR` 的校验
+pub fn rule_arg(value: &str) -> Result<String, String> {
+    Rule::parse(value).map(|_| value.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum State {
+    Firing,
+    Resolved,
+}
+
+/// 告警状态的变化，也用于通知和上报
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub rule: String,
+    /// 挂载点或网卡名，其余指标没有
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub instance: String,
+    pub state: State,
+    pub expr: String,
+    pub value: f64,
+    pub threshold: f64,
+    /// 开始触发的时间
+    pub since: DateTime<Utc>,
+    pub timestamp: DateTime<Utc>,
+    /// 持续触发时按 `every` 重复的通知
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub repeat: bool,
+}
+
+/// 正在触发的告警，随样本一起上报
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Active {
+    pub rule: String,
+    /// 挂载点或网卡名，其余指标没有
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub instance: String,
+    pub expr: String,
+    /// 最近一个样本的值
+    pub value: f64,
+    pub threshold: f64,
+    /// 开始触发的时间
+    pub since: DateTime<Utc>,
+    /// 已经持续触发的时间(毫秒)
+    pub duration_ms: u64,
+}
+
+/// 一条规则在一个实例上的状态
+#[derive(Debug, Default)]
+struct Tracked {
+    /// 条件开始满足的时间
+    pending: Option<DateTime<Utc>>,
+    /// 开始触发的时间
+    firing: Option<DateTime<Utc>>,
+    /// 上一次通知的时间
+    notified: Option<DateTime<Utc>>,
+    value: f64,
+}
+
+/// 按样本评估全部规则，记录每条规则在每个实例上的触发/解除状态
+pub struct Engine {
+    rules: Vec<Rule>,
+    states: BTreeMap<(String, String), Tracked>,
+}
+
+impl Engine {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self { rules, states: BTreeMap::new() }
+    }
+
+    /// 重新加载配置时更新规则，名称和表达式都没变的规则保留原来的状态
+    pub fn configure(&mut self, rules: Vec<Rule>) {
+        let unchanged = |name: &str| {
+            let old = self.rules.iter().find(|r| r.name == name);
+            let new = rules.iter().find(|r| r.name == name);
+            matches!((old, new), (Some(old), Some(new)) if old.expr == new.expr)
+        };
+        let keep: Vec<String> = self.rules.iter().map(|r| r.name.clone()).filter(|name| unchanged(name)).collect();
+        self.states.retain(|(rule, _), _| keep.contains(rule));
+        self.rules = rules;
+    }
+
+    /// 用一个样本评估全部规则，返回状态发生变化的告警；时间取样本的时间戳，重放时同样适用
+    pub fn evaluate(&mut self, stats: &SystemStats) -> Vec<Event> {
+        let now = stats.timestamp;
+        let mut events = Vec::new();
+
+        for rule in &self.rules {
+            let values = rule.metric.values(stats);
+            let event = |instance: &str, state, value, since| Event {
+                rule: rule.name.clone(),
+                instance: instance.to_string(),
+                state,
+                expr: rule.expr.clone(),
+                value,
+                threshold: rule.threshold,
+                since,
+                timestamp: now,
+                repeat: false,
+            };
+            let elapsed = |since: DateTime<Utc>| (now - since).to_std().unwrap_or_default();
+
+            for (instance, value) in &values {
+                let tracked = self.states.entry((rule.name.clone(), instance.clone())).or_default();
+                tracked.value = *value;
+                // 触发之后改用解除阈值判断
+                let threshold = match tracked.firing {
+                    Some(_) => rule.clear.unwrap_or(rule.threshold),
+                    None => rule.threshold,
+                };
+                if rule.op.test(*value, threshold) {
+                    let pending = *tracked.pending.get_or_insert(now);
+                    match tracked.firing {
+                        None if elapsed(pending) >= rule.duration => {
+                            tracked.firing = Some(now);
+                            tracked.notified = Some(now);
+                            events.push(event(instance, State::Firing, *value, now));
+                        }
+                        Some(since) if rule.every.is_some_and(|every| tracked.notified.is_none_or(|n| elapsed(n) >= every)) => {
+                            tracked.notified = Some(now);
+                            events.push(Event { repeat: true, ..event(instance, State::Firing, *value, since) });
+                        }
+                        _ => {}
+                    }
+                } else {
+                    tracked.pending = None;
+                    if let Some(since) = tracked.firing.take() {
+                        events.push(event(instance, State::Resolved, *value, since));
+                    }
+                }
+            }
+
+            // 挂载点或网卡消失时解除其上的告警
+            let gone: Vec<String> = self.states
+                .keys()
+                .filter(|(name, instance)| *name == rule.name && !values.iter().any(|(i, _)| i == instance))
+                .map(|(_, instance)| instance.clone())
+                .collect();
+            for instance in gone {
+                if let Some(tracked) = self.states.remove(&(rule.name.clone(), instance.clone())) {
+                    if let Some(since) = tracked.firing {
+                        events.push(event(&instance, State::Resolved, tracked.value, since));
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    /// 当前正在触发的告警，`now` 用于计算持续时间
+    pub fn active(&self, now: DateTime<Utc>) -> Vec<Active> {
+        self.states
+            .iter()
+            .filter_map(|((name, instance), tracked)| {
+                let since = tracked.firing?;
+                let rule = self.rules.iter().find(|r| r.name == *name)?;
+                Some(Active {
+                    rule: name.clone(),
+                    instance: instance.clone(),
+                    expr: rule.expr.clone(),
+                    value: tracked.value,
+                    threshold: rule.threshold,
+                    since,
+                    duration_ms: (now - since).num_milliseconds().max(0) as u64,
+                })
+            })
+            .collect()
+    }
+}
+
+/// 发出的通知：告警事件加上主机名和标签
+#[derive(Debug, Serialize)]
+pub struct Notification<'a> {
+    pub host: &'a str,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: &'a BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub event: &'a Event,
+}
+
+impl Notification<'_> {
+    /// 一行文字的摘要，例如 `web-01 mem: mem.used_percent > 90 (93.5)`
+    pub fn summary(&self) -> String {
+        let event = self.event;
+        let instance = if event.instance.is_empty() { String::new() } else { format!(" [{}]", event.instance) };
+        format!("{} {}{}: {} ({})", self.host, event.rule, instance, event.expr, format_value(event.value))
+    }
+
+    /// 同一主机上同一条规则同一个实例的告警使用相同的键，接收端据此合并触发和解除
+    pub fn key(&self) -> String {
+        let event = self.event;
+        // 挂载点 `/` 也是实例，不能按结尾的 `/` 去掉
+        match event.instance.as_str() {
+            "" => format!("{}/{}", self.host, event.rule),
+            instance => format!("{}/{}/{}", self.host, event.rule, instance),
+        }
+    }
+}
+
+/// 最多保留两位小数
+fn format_value(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample(seconds: i64, fields: serde_json::Value) -> SystemStats {
+        let mut sample = json!({ "version": 1, "seq": 0, "timestamp": DateTime::from_timestamp(seconds, 0).unwrap() });
+        sample.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(sample).unwrap()
+    }
+
+    fn rule(value: &str) -> Rule {
+        Rule::parse(value).unwrap()
+    }
+
+    #[test]
+    fn parses_threshold_with_defaults() {
+        let rule = rule(" high mem = mem.used_percent > 90 ");
+        assert_eq!(rule.name, "high mem");
+        assert_eq!(rule.expr, "mem.used_percent > 90");
+        assert_eq!(rule.metric, Metric { section: "mem".into(), key: None, field: "used_percent".into() });
+        assert_eq!(rule.op, Op::Gt);
+        assert_eq!(rule.threshold, 90.0);
+        assert_eq!(rule.duration, Duration::ZERO);
+        assert_eq!(rule.clear, None);
+        assert_eq!(rule.every, None);
+    }
+
+    #[test]
+    fn parses_operators_and_default_fields() {
+        for (expr, op) in [(">", Op::Gt), (">=", Op::Ge), ("<", Op::Lt), ("<=", Op::Le), ("==", Op::Eq), ("!=", Op::Ne)] {
+            assert_eq!(rule(&format!("r=cpu {} 1", expr)).op, op);
+        }
+        assert_eq!(rule("r=disk>1").metric.field, "used_percent");
+        assert_eq!(rule("r=probes>1").metric.field, "rtt_ms");
+        assert_eq!(rule("r=temp < -5.5").threshold, -5.5);
+    }
+
+    #[test]
+    fn parses_keys_and_units() {
+        let free = rule(r#"r=disk["/var lib"].free < 5GB"#);
+        assert_eq!(free.metric.key(), Some("/var lib"));
+        assert_eq!(free.threshold, 5e9);
+        assert_eq!(rule("r=net.tx > 10MiB").threshold, 10.0 * 1024.0 * 1024.0);
+        assert_eq!(rule("r=net.tx > 1.5KB").threshold, 1500.0);
+        assert_eq!(rule("r=mem.used_percent > 90%").threshold, 90.0);
+        assert_eq!(rule("r=agent.rss > 1TiB").threshold, 1024f64.powi(4));
+    }
+
+    #[test]
+    fn parses_for_clear_and_every() {
+        let rule = rule("r=cpu > 90 for 5m clear 80 every 1h");
+        assert_eq!(rule.duration, Duration::from_secs(300));
+        assert_eq!(rule.clear, Some(80.0));
+        assert_eq!(rule.every, Some(Duration::from_secs(3600)));
+
+        assert_eq!(self::rule("r=cpu > 90 for 30").duration, Duration::from_secs(30));
+        assert_eq!(self::rule("r=net.rx > 10MB clear 8MB").clear, Some(8e6));
+        assert_eq!(self::rule("r=disk.free < 5GB clear 6GB").clear, Some(6e9));
+        assert_eq!(self::rule("r=cpu >= 90 clear 90").clear, Some(90.0));
+        assert_eq!(self::rule("r=cpu > 1 every 10m").every, Some(Duration::from_secs(600)));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for value in [
+            "cpu > 90",
+            "=cpu > 90",
+            "r=",
+            "r=cpu",
+            "r=cpu 90",
+            "r=cpu => 90",
+            "r=gpu > 90",
+            "r=cpu.idle > 90",
+            r#"r=cpu["0"] > 90"#,
+            "r=cpu > 90 XB",
+            "r=cpu > 90 for",
+            "r=cpu > 90 for soon",
+            "r=cpu > 90 clear 95",
+            "r=cpu < 10 clear 5",
+            "r=cpu == 90 clear 80",
+            "r=cpu > 90 every 0",
+            "r=cpu > 90 clear 80 for 5m",
+            "r=cpu > 90 and mem > 90",
+        ] {
+            assert!(Rule::parse(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn parses_metrics() {
+        assert_eq!(Metric::parse("net.tx"), Some(Metric { section: "net".into(), key: None, field: "tx".into() }));
+        assert_eq!(Metric::parse(r#" disk["/"] "#).map(|m| (m.key, m.field)), Some((Some("/".into()), "used_percent".into())));
+        assert!(Metric::parse("net.tx > 1").is_none());
+        assert!(Metric::parse("proc.threads").is_none());
+        assert!(Metric::parse("mem").unwrap().single());
+        assert!(!Metric::parse("disk").unwrap().single());
+    }
+
+    #[test]
+    fn reads_values_per_instance() {
+        let stats = sample(0, json!({
+            "cpu": [10.0, 30.0],
+            "mem": { "total": 200, "used": 50 },
+            "swap": { "total": 0, "used": 0 },
+            "disk": { "/": { "total": 100, "used": 90 }, "/var": { "total": 100, "used": 10 } },
+        }));
+        assert_eq!(Metric::parse("cpu").unwrap().values(&stats), vec![(String::new(), 20.0)]);
+        assert_eq!(Metric::parse("cpu.max_percent").unwrap().values(&stats), vec![(String::new(), 30.0)]);
+        assert_eq!(Metric::parse("mem.free_percent").unwrap().values(&stats), vec![(String::new(), 75.0)]);
+        // 没有配置swap时百分比不参与判断
+        assert!(Metric::parse("swap").unwrap().values(&stats).is_empty());
+        assert_eq!(Metric::parse("disk.free").unwrap().values(&stats), vec![("/".into(), 10.0), ("/var".into(), 90.0)]);
+        assert_eq!(Metric::parse(r#"disk["/var"]"#).unwrap().values(&stats), vec![("/var".into(), 10.0)]);
+    }
+
+    #[test]
+    fn fires_after_duration_and_resolves() {
+        let mut engine = Engine::new(vec![rule("hot=cpu > 90 for 10s")]);
+        let cpu = |seconds, value: f64| sample(seconds, json!({ "cpu": [value] }));
+
+        assert!(engine.evaluate(&cpu(0, 95.0)).is_empty());
+        assert!(engine.evaluate(&cpu(5, 95.0)).is_empty());
+        let events = engine.evaluate(&cpu(10, 95.0));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].state, State::Firing);
+        assert_eq!(engine.active(DateTime::from_timestamp(15, 0).unwrap())[0].duration_ms, 5000);
+
+        let events = engine.evaluate(&cpu(20, 50.0));
+        assert_eq!((events[0].state, events[0].since.timestamp()), (State::Resolved, 10));
+        assert!(engine.active(DateTime::from_timestamp(20, 0).unwrap()).is_empty());
+
+        // 条件中断后重新计时
+        assert!(engine.evaluate(&cpu(30, 95.0)).is_empty());
+        assert!(engine.evaluate(&cpu(35, 50.0)).is_empty());
+        assert!(engine.evaluate(&cpu(40, 95.0)).is_empty());
+    }
+
+    #[test]
+    fn clears_at_the_clear_threshold() {
+        let mut engine = Engine::new(vec![rule("hot=cpu > 90 clear 80")]);
+        let cpu = |seconds, value: f64| sample(seconds, json!({ "cpu": [value] }));
+
+        assert_eq!(engine.evaluate(&cpu(0, 95.0))[0].state, State::Firing);
+        assert!(engine.evaluate(&cpu(1, 85.0)).is_empty());
+        assert!(engine.evaluate(&cpu(2, 92.0)).is_empty());
+        assert_eq!(engine.evaluate(&cpu(3, 80.0))[0].state, State::Resolved);
+        assert!(engine.evaluate(&cpu(4, 85.0)).is_empty());
+    }
+
+    #[test]
+    fn repeats_every_interval_while_firing() {
+        let mut engine = Engine::new(vec![rule("hot=cpu > 90 every 1m")]);
+        let cpu = |seconds| sample(seconds, json!({ "cpu": [95.0] }));
+
+        assert!(!engine.evaluate(&cpu(0))[0].repeat);
+        assert!(engine.evaluate(&cpu(30)).is_empty());
+        let events = engine.evaluate(&cpu(60));
+        assert!(events[0].repeat);
+        assert_eq!(events[0].since.timestamp(), 0);
+        assert!(engine.evaluate(&cpu(90)).is_empty());
+        assert!(engine.evaluate(&cpu(120))[0].repeat);
+    }
+
+    #[test]
+    fn resolves_instances_that_disappear() {
+        let mut engine = Engine::new(vec![rule("full=disk > 80")]);
+        let disk = json!({ "/": { "total": 100, "used": 50 }, "/mnt": { "total": 100, "used": 95 } });
+        let events = engine.evaluate(&sample(0, json!({ "disk": disk })));
+        assert_eq!((events.len(), events[0].instance.as_str()), (1, "/mnt"));
+
+        let events = engine.evaluate(&sample(1, json!({ "disk": { "/": { "total": 100, "used": 50 } } })));
+        assert_eq!((events[0].state, events[0].instance.as_str(), events[0].value), (State::Resolved, "/mnt", 95.0));
+    }
+
+    #[test]
+    fn keeps_state_of_unchanged_rules_on_reload() {
+        let mut engine = Engine::new(vec![rule("a=cpu > 90"), rule("b=cpu > 50")]);
+        engine.evaluate(&sample(0, json!({ "cpu": [95.0] })));
+        engine.configure(vec![rule("a=cpu > 90"), rule("b=cpu > 60")]);
+        let active = engine.active(DateTime::from_timestamp(0, 0).unwrap());
+        assert_eq!(active.iter().map(|a| a.rule.as_str()).collect::<Vec<_>>(), ["a"]);
+        // 表达式改变的规则重新开始，再次触发
+        let events = engine.evaluate(&sample(1, json!({ "cpu": [95.0] })));
+        assert_eq!(events.iter().map(|e| e.rule.as_str()).collect::<Vec<_>>(), ["b"]);
+    }
+
+    #[test]
+    fn formats_notifications() {
+        let mut engine = Engine::new(vec![rule("full=disk > 80")]);
+        let events = engine.evaluate(&sample(0, json!({ "disk": { "/": { "total": 3, "used": 3 } } })));
+        let tags = BTreeMap::new();
+        let notification = Notification { host: "web-01", tags: &tags, event: &events[0] };
+        assert_eq!(notification.summary(), "web-01 full [/]: disk > 80 (100)");
+        assert_eq!(notification.key(), "web-01/full//");
+        let event = Event { instance: String::new(), ..events[0].clone() };
+        assert_eq!(Notification { host: "web-01", tags: &tags, event: &event }.key(), "web-01/full");
+        assert_eq!(format_value(93.456), "93.46");
+        assert_eq!(format_value(93.5), "93.5");
+    }
+}
@@ -0,0 +1,135 @@
+// src/anomaly.rs
+use crate::alert::Metric;
+use crate::i18n::tr;
+use crate::SystemStats;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+/// 基线至少包含这么多样本才开始检测，刚启动时方差还不可靠
+const WARMUP_SAMPLES: u64 = 30;
+
+/// 标准差的下限(相对均值)，避免几乎不变的指标因为很小的波动被当成异常
+const MIN_RELATIVE_STDDEV: f64 = 0.01;
+
+/// 偏离基线的指标值，随样本一起上报
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anomaly {
+    /// 检测的指标，例如 `net.tx`
+    pub metric: String,
+    /// 挂载点或网卡名，其余指标没有
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub instance: String,
+    pub value: f64,
+    /// 基线的均值
+    pub mean: f64,
+    /// 基线的标准差
+    pub stddev: f64,
+    /// 偏离均值的标准差倍数，低于基线时为负数
+    pub z: f64,
+    /// 这次异常开始的时间
+    pub since: DateTime<Utc>,
+}
+
+/// 一个指标在一个实例上的基线：按时间指数加权的均值和方差
+#[derive(Debug)]
+struct Baseline {
+    mean: f64,
+    variance: f64,
+    samples: u64,
+    updated: DateTime<Utc>,
+    /// 正在持续的异常开始的时间
+    since: Option<DateTime<Utc>>,
+}
+
+/// `--anomaly METRIC` 的校验
+pub fn metric_arg(value: &str) -> Result<String, String> {
+    match Metric::parse(value) {
+        Some(_) => Ok(value.to_string()),
+        None => Err(format!("{}: {}", tr("无效的指标"), value)),
+    }
+}
+
+/// `--anomaly-threshold` 的校验，必须是正数
+pub fn threshold_arg(value: &str) -> Result<String, String> {
+    match value.parse::<f64>() {
+        Ok(threshold) if threshold > 0.0 && threshold.is_finite() => Ok(value.to_string()),
+        _ => Err(format!("{}: {}", tr("无效的异常阈值"), value)),
+    }
+}
+
+/// 用EWMA维护每个指标的基线，值偏离均值超过 `threshold` 个标准差时报告异常
+pub struct Detector {
+    metrics: Vec<(String, Metric)>,
+    /// 基线的时间常数，越长基线变化越慢
+    window: Duration,
+    threshold: f64,
+    baselines: BTreeMap<(String, String), Baseline>,
+}
+
+impl Detector {
+    pub fn new(metrics: Vec<(String, Metric)>, window: Duration, threshold: f64) -> Self {
+        Self { metrics, window, threshold, baselines: BTreeMap::new() }
+    }
+
+    /// 重新加载配置时更新设置，仍然检测的指标保留已有的基线
+    pub fn configure(&mut self, metrics: Vec<(String, Metric)>, window: Duration, threshold: f64) {
+        self.baselines.retain(|(name, _), _| metrics.iter().any(|(n, _)| n == name));
+        self.metrics = metrics;
+        self.window = window;
+        self.threshold = threshold;
+    }
+
+    /// 用一个样本更新基线，返回偏离基线的指标；时间取样本的时间戳，重放时同样适用
+    pub fn evaluate(&mut self, stats: &SystemStats) -> Vec<Anomaly> {
+        let now = stats.timestamp;
+        let mut anomalies = Vec::new();
+        let mut seen = BTreeSet::new();
+
+        for (name, metric) in &self.metrics {
+            for (instance, value) in metric.values(stats) {
+                let key = (name.clone(), instance);
+                let Some(baseline) = self.baselines.get_mut(&key) else {
+                    self.baselines.insert(key.clone(), Baseline { mean: value, variance: 0.0, samples: 1, updated: now, since: None });
+                    seen.insert(key);
+                    continue;
+                };
+
+                // 先与更新前的基线比较，异常值本身不影响这次判断
+                let stddev = baseline.variance.sqrt();
+                let scale = stddev.max(baseline.mean.abs() * MIN_RELATIVE_STDDEV);
+                let deviation = value - baseline.mean;
+                let z = if scale > 0.0 { deviation / scale } else { 0.0 };
+                if baseline.samples >= WARMUP_SAMPLES && z.abs() >= self.threshold {
+                    let since = *baseline.since.get_or_insert(now);
+                    anomalies.push(Anomaly {
+                        metric: name.clone(),
+                        instance: key.1.clone(),
+                        value,
+                        mean: baseline.mean,
+                        stddev,
+                        z,
+                        since,
+                    });
+                } else {
+                    baseline.since = None;
+                }
+
+                // 按距离上个样本的时间衰减，采集间隔不固定时基线的时间尺度不变
+                let elapsed = (now - baseline.updated).to_std().unwrap_or_default();
+                let alpha = 1.0 - (-elapsed.as_secs_f64() / self.window.as_secs_f64()).exp();
+                let increment = alpha * deviation;
+                baseline.mean += increment;
+                baseline.variance = (1.0 - alpha) * (baseline.variance + deviation * increment);
+                baseline.samples += 1;
+                baseline.updated = now;
+                seen.insert(key);
+            }
+        }
+
+        // 挂载点或网卡消失后丢弃它们的基线
+        self.baselines.retain(|key, _| seen.contains(key));
+        anomalies
+    }
+}
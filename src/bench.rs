@@ -0,0 +1,117 @@
+// src/bench.rs
+use std::time::Duration;
+
+/// 是否统计内存分配：只有以 `--features bench` 编译时才安装 `CountingAlloc`
+pub const COUNTING: bool = cfg!(feature = "bench");
+
+#[cfg(feature = "bench")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    thread_local! {
+        /// 本线程的(分配次数, 分配字节数)，只在本线程内读写，不需要原子操作
+        static COUNTS: Cell<(u64, u64)> = const { Cell::new((0, 0)) };
+    }
+
+    fn count(bytes: usize) {
+        // 线程退出时已经销毁的计数器不再统计
+        let _ = COUNTS.try_with(|counts| {
+            let (allocations, total) = counts.get();
+            counts.set((allocations + 1, total + bytes as u64));
+        });
+    }
+
+    /// 按线程统计分配次数和字节数的全局分配器，供 `bench` 计算每个采集项每次采集的分配量
+    pub struct CountingAlloc;
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            count(layout.size());
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            count(new_size);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    pub fn allocations() -> (u64, u64) {
+        COUNTS.try_with(Cell::get).unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "bench")]
+pub use counting::CountingAlloc;
+
+/// 当前线程到目前为止的(分配次数, 分配字节数)，没有启用 `bench` 特性时总是为0
+pub fn allocations() -> (u64, u64) {
+    #[cfg(feature = "bench")]
+    return counting::allocations();
+    #[cfg(not(feature = "bench"))]
+    (0, 0)
+}
+
+/// 本进程已使用的CPU时间(用户态+内核态)
+#[cfg(unix)]
+pub fn cpu_time() -> Duration {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return Duration::ZERO;
+    }
+    let time = |t: libc::timeval| Duration::new(t.tv_sec as u64, t.tv_usec as u32 * 1000);
+    time(usage.ru_utime) + time(usage.ru_stime)
+}
+
+/// 本进程已使用的CPU时间(用户态+内核态)
+#[cfg(windows)]
+pub fn cpu_time() -> Duration {
+    use windows_sys::Win32::Foundation::FILETIME;
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, GetProcessTimes};
+
+    let mut times: [FILETIME; 4] = unsafe { std::mem::zeroed() };
+    let [creation, exit, kernel, user] = &mut times;
+    if unsafe { GetProcessTimes(GetCurrentProcess(), creation, exit, kernel, user) } == 0 {
+        return Duration::ZERO;
+    }
+    // FILETIME 以100纳秒为单位
+    let time = |t: &FILETIME| Duration::from_nanos(((t.dwHighDateTime as u64) << 32 | t.dwLowDateTime as u64) * 100);
+    time(kernel) + time(user)
+}
+
+/// 一个采集项多次运行的统计
+#[derive(Default)]
+pub struct Measurement {
+    pub runs: u32,
+    pub total: Duration,
+    pub max: Duration,
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+impl Measurement {
+    pub fn record(&mut self, elapsed: Duration, allocations: u64, bytes: u64) {
+        self.runs += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        self.allocations += allocations;
+        self.bytes += bytes;
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.total / self.runs.max(1)
+    }
+
+    pub fn allocations_per_run(&self) -> f64 {
+        self.allocations as f64 / self.runs.max(1) as f64
+    }
+
+    pub fn bytes_per_run(&self) -> u64 {
+        self.bytes / self.runs.max(1) as u64
+    }
+}
@@ -0,0 +1,132 @@
+// src/breaker.rs
+use crate::i18n::tr;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// 默认连续失败多少次后断开
+pub const DEFAULT_THRESHOLD: u32 = 5;
+
+/// 默认断开后等待多久再探测一次
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CircuitState {
+    /// 正常发送
+    Closed,
+    /// 连续失败达到阈值，暂停发送，冷却时间过后探测一次
+    Open,
+}
+
+/// 一个发送目标(端点或导出插件)的状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SinkHealth {
+    pub state: CircuitState,
+    /// 连续失败的次数，成功一次后归零
+    pub consecutive_failures: u32,
+    /// 启动以来失败的次数
+    pub failures: u64,
+    /// 断开期间跳过的发送次数
+    pub skipped: u64,
+    /// 最近一次失败的原因，成功后清除
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// 断路器：连续失败 `threshold` 次后断开，之后的发送直接跳过，不再每次都等满超时；
+/// 每过 `cooldown` 放行一次作为探测，成功则恢复，失败则继续断开。`threshold` 为0时从不断开
+#[derive(Debug)]
+pub struct Breaker {
+    sink: String,
+    threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: u32,
+    failures: u64,
+    skipped: u64,
+    last_error: Option<String>,
+    /// 断开时下一次允许探测的时间
+    retry_at: Option<Instant>,
+}
+
+impl Breaker {
+    pub fn new(sink: impl Into<String>, threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            sink: sink.into(),
+            threshold,
+            cooldown,
+            consecutive_failures: 0,
+            failures: 0,
+            skipped: 0,
+            last_error: None,
+            retry_at: None,
+        }
+    }
+
+    /// 重新加载配置时更新阈值和冷却时间，当前状态保留
+    pub fn configure(&mut self, threshold: u32, cooldown: Duration) {
+        self.threshold = threshold;
+        self.cooldown = cooldown;
+        if threshold == 0 {
+            self.retry_at = None;
+        }
+    }
+
+    /// 换了发送目标后从头计数
+    pub fn reset(&mut self) {
+        self.consecutive_failures = 0;
+        self.last_error = None;
+        self.retry_at = None;
+    }
+
+    /// 这次是否应该发送：闭合时总是发送；断开时只在冷却时间过后放行一次探测，其余计为跳过
+    pub fn allow(&mut self) -> bool {
+        match self.retry_at {
+            Some(retry_at) if Instant::now() < retry_at => {
+                self.skipped += 1;
+                false
+            }
+            _ => true,
+        }
+    }
+
+    pub fn success(&mut self) {
+        if self.retry_at.take().is_some() {
+            tracing::info!(sink = %self.sink, skipped = self.skipped, "{}", tr("发送目标已恢复，断路器闭合"));
+        }
+        self.consecutive_failures = 0;
+        self.last_error = None;
+    }
+
+    pub fn failure(&mut self, error: String) {
+        self.consecutive_failures += 1;
+        self.failures += 1;
+        self.last_error = Some(error);
+        if self.threshold == 0 || self.consecutive_failures < self.threshold {
+            return;
+        }
+        if self.retry_at.is_none() {
+            tracing::warn!(
+                sink = %self.sink,
+                failures = self.consecutive_failures,
+                cooldown_s = self.cooldown.as_secs(),
+                "{}",
+                tr("发送目标连续失败，断路器断开，暂停发送")
+            );
+        }
+        self.retry_at = Some(Instant::now() + self.cooldown);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.retry_at.is_some()
+    }
+
+    pub fn health(&self) -> SinkHealth {
+        SinkHealth {
+            state: if self.is_open() { CircuitState::Open } else { CircuitState::Closed },
+            consecutive_failures: self.consecutive_failures,
+            failures: self.failures,
+            skipped: self.skipped,
+            last_error: self.last_error.clone(),
+        }
+    }
+}
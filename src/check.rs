@@ -0,0 +1,64 @@
+// src/check.rs
+use crate::i18n::tr;
+use crate::display::percent;
+use crate::SystemStats;
+
+/// 可以设置阈值的指标，均为使用率(%)
+pub const METRICS: [&str; 4] = ["cpu", "mem", "swap", "disk"];
+
+/// 一项检查的结果，`disk` 按挂载点分别检查
+pub struct Check {
+    pub name: String,
+    pub value: f64,
+    pub max: f64,
+}
+
+impl Check {
+    pub fn ok(&self) -> bool {
+        self.value <= self.max
+    }
+}
+
+/// `NAME=PERCENT` 形式阈值的校验
+pub fn threshold_arg(value: &str) -> Result<String, String> {
+    parse_threshold(value)
+        .map(|_| value.to_string())
+        .ok_or_else(|| format!("{}: {}", tr("无效的阈值"), value))
+}
+
+pub fn parse_threshold(value: &str) -> Option<(String, f64)> {
+    let (name, max) = value.split_once('=')?;
+    let max: f64 = max.trim().trim_end_matches('%').parse().ok()?;
+    (METRICS.contains(&name.trim()) && max.is_finite()).then(|| (name.trim().to_string(), max))
+}
+
+/// 用样本逐项比较阈值，样本中没有的指标(采集项已关闭)不检查
+pub fn evaluate(stats: &SystemStats, thresholds: &[(String, f64)]) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for (metric, max) in thresholds {
+        let mut check = |name: String, value: f64| checks.push(Check { name, value, max: *max });
+        match metric.as_str() {
+            "cpu" if !stats.cpu.is_empty() => {
+                check(metric.clone(), stats.cpu.iter().sum::<f32>() as f64 / stats.cpu.len() as f64)
+            }
+            // 总量为0(例如没有配置swap)时没有百分比，不检查
+            "mem" => {
+                if let Some(mem) = stats.mem.as_ref().filter(|mem| mem.total > 0) {
+                    check(metric.clone(), percent(mem.used, mem.total));
+                }
+            }
+            "swap" => {
+                if let Some(swap) = stats.swap.as_ref().filter(|swap| swap.total > 0) {
+                    check(metric.clone(), percent(swap.used, swap.total));
+                }
+            }
+            "disk" => {
+                for (mount, disk) in stats.disk.iter().filter(|(_, disk)| disk.total > 0) {
+                    check(format!("disk {}", mount), percent(disk.used, disk.total));
+                }
+            }
+            _ => {}
+        }
+    }
+    checks
+}
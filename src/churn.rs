@@ -0,0 +1,434 @@
+// src/churn.rs
+use crate::collector::{Collector, Context, Section};
+use crate::i18n::tr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use sysinfo::{System, SystemExt, Uid, UserExt};
+
+/// 采集项的名称
+pub const NAME: &str = "churn";
+
+/// `top` 中最多列出的进程名
+const MAX_TOP: usize = 10;
+
+/// 上次采集以来进程的创建和退出，包括两次采集之间启动又退出、进程表的快照中看不到的短命进程
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessChurn {
+    /// 创建的进程和线程数(`/proc/stat` 的 `processes`)，不按进程过滤条件过滤
+    pub forks: u64,
+    /// 退出的进程数
+    pub exits: u64,
+    /// 其中上次采集之后才启动的进程数
+    pub short_lived: u64,
+    /// 短命进程用掉的CPU时间(秒)
+    pub cpu_s: f64,
+    /// `cpu_s` 占两次采集间隔的百分比，一个核心跑满为100
+    pub cpu: f64,
+    /// 按进程名和用户合计的短命进程，CPU时间最多的在前
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top: Vec<ShortLived>,
+    /// 退出的进程太多，内核丢弃了一部分通知，计数偏小
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub overflow: bool,
+}
+
+impl ProcessChurn {
+    /// 合计汇总窗口中的各次统计，`cpu` 取平均值
+    pub fn merge(samples: &[&ProcessChurn]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut merged = Self::default();
+        let mut top: HashMap<(&str, &str), (u64, f64)> = HashMap::new();
+        for churn in samples {
+            merged.forks += churn.forks;
+            merged.exits += churn.exits;
+            merged.short_lived += churn.short_lived;
+            merged.cpu_s += churn.cpu_s;
+            merged.cpu += churn.cpu / samples.len() as f64;
+            merged.overflow |= churn.overflow;
+            for process in &churn.top {
+                let (count, cpu_s) = top.entry((&process.name, &process.user)).or_default();
+                *count += process.count;
+                *cpu_s += process.cpu_s;
+            }
+        }
+        merged.cpu_s = (merged.cpu_s * 1000.0).round() / 1000.0;
+        merged.cpu = (merged.cpu * 100.0).round() / 100.0;
+        merged.top = top
+            .into_iter()
+            .map(|((name, user), (count, cpu_s))| ShortLived {
+                name: name.to_string(),
+                user: user.to_string(),
+                count,
+                cpu_s: (cpu_s * 1000.0).round() / 1000.0,
+            })
+            .collect();
+        sort(&mut merged.top);
+        Some(merged)
+    }
+}
+
+/// CPU时间最多的在前，最多保留 [`MAX_TOP`] 个
+fn sort(top: &mut Vec<ShortLived>) {
+    top.sort_by(|a, b| b.cpu_s.total_cmp(&a.cpu_s).then(b.count.cmp(&a.count)).then(a.name.cmp(&b.name)));
+    top.truncate(MAX_TOP);
+}
+
+/// 同名同用户的短命进程
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortLived {
+    pub name: String,
+    /// 用户名，无法解析时为UID
+    pub user: String,
+    pub count: u64,
+    pub cpu_s: f64,
+}
+
+/// 一个进程名和UID退出的进程
+#[derive(Default)]
+struct Exits {
+    exits: u64,
+    short_lived: u64,
+    /// 短命进程的CPU时间(微秒)
+    cpu_us: u64,
+}
+
+/// 监听线程累积的退出，直到下一次采集取走
+struct Tally {
+    since: Instant,
+    exits: HashMap<(String, u32), Exits>,
+    /// 按进程ID累积短命线程的CPU时间，进程退出时计入进程
+    threads: HashMap<u32, u64>,
+    overflow: bool,
+    /// 监听线程因错误退出
+    closed: bool,
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+impl Tally {
+    fn new() -> Self {
+        Self { since: Instant::now(), exits: HashMap::new(), threads: HashMap::new(), overflow: false, closed: false }
+    }
+
+    fn add(&mut self, exit: Exit) {
+        // 运行时间不到距上次采集的时间，说明是上次采集之后启动的
+        let short = exit.elapsed_us < self.since.elapsed().as_micros() as u64;
+        if exit.pid != exit.tgid {
+            if short {
+                *self.threads.entry(exit.tgid).or_default() += exit.cpu_us;
+            }
+            return;
+        }
+        let threads = self.threads.remove(&exit.pid).unwrap_or_default();
+        let exits = self.exits.entry((exit.comm, exit.uid)).or_default();
+        exits.exits += 1;
+        if short {
+            exits.short_lived += 1;
+            exits.cpu_us += exit.cpu_us + threads;
+        }
+    }
+}
+
+/// 一个任务(进程或线程)退出时内核给出的统计
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct Exit {
+    pid: u32,
+    /// 所属进程的ID，与 `pid` 相同时是进程(主线程)
+    tgid: u32,
+    uid: u32,
+    comm: String,
+    /// 从启动到退出的时间(微秒)
+    elapsed_us: u64,
+    /// 用户态和内核态的CPU时间(微秒)
+    cpu_us: u64,
+}
+
+/// 通过taskstats(generic netlink)接收每个退出的进程的CPU时间，统计两次采集之间的进程创建和退出。
+/// 只在Linux上可用，需要root或 `CAP_NET_ADMIN`；无法监听时记录一条警告，样本中没有 `churn`
+pub struct Churn {
+    tally: Option<Arc<Mutex<Tally>>>,
+    /// 上次采集时 `/proc/stat` 的 `processes`
+    forks: Option<u64>,
+}
+
+impl Churn {
+    pub fn new() -> Self {
+        Self { tally: None, forks: None }
+    }
+}
+
+impl Default for Churn {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collector for Churn {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn init(&mut self, system: &mut System) {
+        system.refresh_users_list();
+        self.forks = forks();
+        match listen() {
+            Ok(tally) => self.tally = Some(tally),
+            Err(error) => tracing::warn!(%error, "{}", tr("无法监听进程退出，不统计短命进程")),
+        }
+    }
+
+    fn collect(&mut self, ctx: &mut Context) -> Section {
+        let Some(tally) = &self.tally else { return Section::Unavailable };
+        let (window, exits, overflow) = {
+            let mut tally = tally.lock().unwrap();
+            if tally.closed {
+                return Section::Unavailable;
+            }
+            tally.threads.clear();
+            let window = std::mem::replace(&mut tally.since, Instant::now()).elapsed();
+            (window, std::mem::take(&mut tally.exits), std::mem::take(&mut tally.overflow))
+        };
+        let total = forks();
+        let mut churn = ProcessChurn {
+            forks: total.zip(self.forks).map_or(0, |(now, last)| now.saturating_sub(last)),
+            overflow,
+            ..ProcessChurn::default()
+        };
+        self.forks = total.or(self.forks);
+
+        let mut cpu_us = 0;
+        for ((name, uid), exits) in exits {
+            let user = uid.to_string().parse::<Uid>().ok();
+            if !ctx.process_filter.name.matches(&name)
+                || (!ctx.process_uids.is_empty() && !user.as_ref().is_some_and(|uid| ctx.process_uids.contains(uid)))
+            {
+                continue;
+            }
+            churn.exits += exits.exits;
+            churn.short_lived += exits.short_lived;
+            cpu_us += exits.cpu_us;
+            if exits.short_lived > 0 {
+                let user = match user.as_ref().and_then(|uid| ctx.system.get_user_by_id(uid)) {
+                    Some(user) => user.name().to_string(),
+                    None => uid.to_string(),
+                };
+                churn.top.push(ShortLived { name, user, count: exits.short_lived, cpu_s: seconds(exits.cpu_us) });
+            }
+        }
+        churn.cpu_s = seconds(cpu_us);
+        let percent = cpu_us as f64 / 1e6 / window.as_secs_f64().max(1e-3) * 100.0;
+        churn.cpu = (percent * 100.0).round() / 100.0;
+        sort(&mut churn.top);
+        Section::Churn(churn)
+    }
+}
+
+/// 微秒换算为秒，保留三位小数
+fn seconds(us: u64) -> f64 {
+    (us as f64 / 1000.0).round() / 1000.0
+}
+
+/// 开机以来创建的进程和线程数
+fn forks() -> Option<u64> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    stat.lines().find_map(|line| line.strip_prefix("processes "))?.trim().parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn listen() -> std::io::Result<Arc<Mutex<Tally>>> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, tr("只在Linux上可用")))
+}
+
+/// 向taskstats注册所有CPU，在后台线程中接收退出通知；采集项释放后线程随之退出
+#[cfg(target_os = "linux")]
+fn listen() -> std::io::Result<Arc<Mutex<Tally>>> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::from(libc::AF_NETLINK), Type::RAW, Some(Protocol::from(libc::NETLINK_GENERIC)))?;
+    // 退出的进程多时通知来得很快，缓冲区不够时内核丢弃通知
+    let _ = socket.set_recv_buffer_size(4 << 20);
+    socket.set_read_timeout(Some(std::time::Duration::from_secs(1)))?;
+    let family = netlink::family(&socket)?;
+    let cpus = std::fs::read_to_string("/sys/devices/system/cpu/possible")?;
+    netlink::register(&socket, family, cpus.trim())?;
+
+    let tally = Arc::new(Mutex::new(Tally::new()));
+    let weak = Arc::downgrade(&tally);
+    std::thread::Builder::new().name("churn".to_string()).spawn(move || netlink::receive(socket, family, weak))?;
+    Ok(tally)
+}
+
+/// generic netlink的报文：nlmsghdr、genlmsghdr和属性；`talkers` 查询套接字时也用到报文的解析
+#[cfg(target_os = "linux")]
+pub(crate) mod netlink {
+    use super::{Exit, Tally};
+    use crate::i18n::tr;
+    use socket2::Socket;
+    use std::io::{self, Read};
+    use std::sync::{Mutex, Weak};
+
+    const GENL_ID_CTRL: u16 = 0x10;
+    const CTRL_CMD_GETFAMILY: u8 = 3;
+    const CTRL_ATTR_FAMILY_ID: u16 = 1;
+    const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+    const TASKSTATS_CMD_GET: u8 = 1;
+    const TASKSTATS_CMD_NEW: u8 = 2;
+    const TASKSTATS_TYPE_STATS: u16 = 3;
+    const TASKSTATS_TYPE_AGGR_PID: u16 = 4;
+    const TASKSTATS_CMD_ATTR_REGISTER_CPUMASK: u16 = 3;
+    const NLM_F_REQUEST: u16 = 1;
+    const NLM_F_ACK: u16 = 4;
+    const NLMSG_ERROR: u16 = 2;
+    const HEADER: usize = 16;
+    const GENL_HEADER: usize = 4;
+
+    /// 查询taskstats的family ID
+    pub(super) fn family(socket: &Socket) -> io::Result<u16> {
+        socket.send(&request(GENL_ID_CTRL, NLM_F_REQUEST, CTRL_CMD_GETFAMILY, CTRL_ATTR_FAMILY_NAME, b"TASKSTATS\0"))?;
+        let mut buf = vec![0u8; 8192];
+        loop {
+            let n = (&*socket).read(&mut buf)?;
+            for (kind, payload) in messages(&buf[..n]) {
+                error(kind, payload)?;
+                if kind != GENL_ID_CTRL {
+                    continue;
+                }
+                let id = attrs(payload.get(GENL_HEADER..).unwrap_or_default())
+                    .find(|(attr, value)| *attr == CTRL_ATTR_FAMILY_ID && value.len() >= 2)
+                    .map(|(_, value)| u16::from_ne_bytes([value[0], value[1]]));
+                if let Some(id) = id {
+                    return Ok(id);
+                }
+            }
+        }
+    }
+
+    /// 注册接收这些CPU上的退出通知，`cpus` 的格式如 `0-7`
+    pub(super) fn register(socket: &Socket, family: u16, cpus: &str) -> io::Result<()> {
+        let mask = format!("{cpus}\0");
+        socket.send(&request(family, NLM_F_REQUEST | NLM_F_ACK, TASKSTATS_CMD_GET, TASKSTATS_CMD_ATTR_REGISTER_CPUMASK, mask.as_bytes()))?;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = (&*socket).read(&mut buf)?;
+            for (kind, payload) in messages(&buf[..n]) {
+                if kind == NLMSG_ERROR {
+                    return error(kind, payload);
+                }
+            }
+        }
+    }
+
+    pub(super) fn receive(socket: Socket, family: u16, tally: Weak<Mutex<Tally>>) {
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let result = (&socket).read(&mut buf);
+            let Some(tally) = tally.upgrade() else { return };
+            let mut tally = tally.lock().unwrap();
+            match result {
+                Ok(n) => {
+                    for (kind, payload) in messages(&buf[..n]) {
+                        if kind == family && payload.first() == Some(&TASKSTATS_CMD_NEW) {
+                            exits(payload.get(GENL_HEADER..).unwrap_or_default()).for_each(|exit| tally.add(exit));
+                        }
+                    }
+                }
+                Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => tally.overflow = true,
+                Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted) => {}
+                Err(error) => {
+                    tracing::warn!(%error, "{}", tr("无法监听进程退出，不统计短命进程"));
+                    tally.closed = true;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 只有一个属性的请求
+    fn request(kind: u16, flags: u16, cmd: u8, attr: u16, value: &[u8]) -> Vec<u8> {
+        let attr_len = 4 + value.len();
+        let len = HEADER + GENL_HEADER + align(attr_len);
+        let mut message = Vec::with_capacity(len);
+        message.extend_from_slice(&(len as u32).to_ne_bytes());
+        message.extend_from_slice(&kind.to_ne_bytes());
+        message.extend_from_slice(&flags.to_ne_bytes());
+        message.extend_from_slice(&1u32.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(&[cmd, 1, 0, 0]);
+        message.extend_from_slice(&(attr_len as u16).to_ne_bytes());
+        message.extend_from_slice(&attr.to_ne_bytes());
+        message.extend_from_slice(value);
+        message.resize(len, 0);
+        message
+    }
+
+    /// 内核返回的错误，错误码为0是确认
+    pub(crate) fn error(kind: u16, payload: &[u8]) -> io::Result<()> {
+        match payload.get(..4).filter(|_| kind == NLMSG_ERROR) {
+            Some(code) if i32::from_ne_bytes([code[0], code[1], code[2], code[3]]) != 0 => {
+                Err(io::Error::from_raw_os_error(-i32::from_ne_bytes([code[0], code[1], code[2], code[3]])))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn align(len: usize) -> usize {
+        (len + 3) & !3
+    }
+
+    /// 一次读到的各条报文的类型和内容(去掉nlmsghdr)
+    pub(crate) fn messages(mut buf: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+        std::iter::from_fn(move || {
+            let len = u32::from_ne_bytes(buf.get(..4)?.try_into().ok()?) as usize;
+            if len < HEADER || len > buf.len() {
+                return None;
+            }
+            let kind = u16::from_ne_bytes([buf[4], buf[5]]);
+            let payload = &buf[HEADER..len];
+            buf = buf.get(align(len)..).unwrap_or_default();
+            Some((kind, payload))
+        })
+    }
+
+    /// 各个属性的类型和值
+    fn attrs(mut buf: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+        std::iter::from_fn(move || {
+            let len = u16::from_ne_bytes(buf.get(..2)?.try_into().ok()?) as usize;
+            if len < 4 || len > buf.len() {
+                return None;
+            }
+            // 去掉 NLA_F_NESTED 等标志位
+            let kind = u16::from_ne_bytes([buf[2], buf[3]]) & 0x3fff;
+            let value = &buf[4..len];
+            buf = buf.get(align(len)..).unwrap_or_default();
+            Some((kind, value))
+        })
+    }
+
+    /// 通知中每个退出的任务；整个进程退出时另有按进程合计的统计，其中没有CPU时间，不使用
+    fn exits(payload: &[u8]) -> impl Iterator<Item = Exit> + '_ {
+        attrs(payload)
+            .filter(|(kind, _)| *kind == TASKSTATS_TYPE_AGGR_PID)
+            .filter_map(|(_, nested)| attrs(nested).find(|(kind, _)| *kind == TASKSTATS_TYPE_STATS))
+            .filter_map(|(_, stats)| parse(stats))
+    }
+
+    /// `struct taskstats` 中用到的字段，偏移见 linux/taskstats.h；`ac_tgid` 在第12版加入，更早的内核把每个任务都当作进程
+    fn parse(stats: &[u8]) -> Option<Exit> {
+        let u32_at = |offset: usize| Some(u32::from_ne_bytes(stats.get(offset..offset + 4)?.try_into().ok()?));
+        let u64_at = |offset: usize| Some(u64::from_ne_bytes(stats.get(offset..offset + 8)?.try_into().ok()?));
+        let comm = stats.get(80..112)?;
+        let comm = &comm[..comm.iter().position(|b| *b == 0).unwrap_or(comm.len())];
+        let pid = u32_at(128)?;
+        Some(Exit {
+            pid,
+            tgid: u32_at(368).filter(|tgid| *tgid != 0).unwrap_or(pid),
+            uid: u32_at(120)?,
+            comm: String::from_utf8_lossy(comm).into_owned(),
+            elapsed_us: u64_at(144)?,
+            cpu_us: u64_at(152)? + u64_at(160)?,
+        })
+    }
+}
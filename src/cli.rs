@@ -0,0 +1,1393 @@
+// src/cli.rs
+use crate::exec::exec_arg;
+use crate::i18n::tr;
+use crate::identity::identity_arg;
+use crate::template_sink::template_sink_arg;
+use crate::{
+    alert, anomaly, check, config, derived, dns_check, events, filter, hook, http_check, log_watch, maintenance, mock, probe, remote, throttle,
+    tls_check, token, units, version, webhook, parse_duration, Collectors,
+};
+use clap::{Arg, ArgAction, Command};
+
+/// 重放速度的校验，必须是正数
+fn speed_arg(value: &str) -> Result<String, String> {
+    match value.parse::<f64>() {
+        Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(value.to_string()),
+        _ => Err(format!("{}: {}", tr("无效的重放速度"), value)),
+    }
+}
+
+/// 端点的校验：http(s):// 开头的URL或 unix:// 开头的套接字路径(只在Unix平台可用)
+fn endpoint_arg(value: &str) -> Result<String, String> {
+    let valid = match value.strip_prefix("unix://") {
+        Some(_) if !cfg!(unix) => return Err(format!("{}: {}", tr("Unix套接字端点只能在Unix平台使用"), value)),
+        Some(path) => !path.is_empty(),
+        None => reqwest::Url::parse(value).is_ok_and(|url| matches!(url.scheme(), "http" | "https")),
+    };
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!("{}: {}", tr("无效的端点"), value))
+    }
+}
+
+/// `--crash-webhook` 的校验：http(s):// 开头的URL
+fn crash_webhook_arg(value: &str) -> Result<String, String> {
+    match reqwest::Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(value.to_string()),
+        _ => Err(format!("{}: {}", tr("无效的webhook"), value)),
+    }
+}
+
+/// 时长选项的校验，错误在解析命令行和配置文件时就报告
+fn duration_arg(value: &str) -> Result<String, String> {
+    parse_duration(value)
+        .map(|_| value.to_string())
+        .ok_or_else(|| format!("{}: {}", tr("无效的时长"), value))
+}
+
+/// `record` 和 `vacuum` 共用的分级保留选项
+fn compaction_args() -> [Arg; 3] {
+    [
+        Arg::new("raw-for")
+            .long("raw-for")
+            .value_name("DURATION")
+            .value_parser(duration_arg)
+            .default_value("24h")
+            .help(tr("原样保留样本的时间，更早的按分钟汇总")),
+        Arg::new("minute-for")
+            .long("minute-for")
+            .value_name("DURATION")
+            .value_parser(duration_arg)
+            .default_value("30d")
+            .help(tr("按分钟汇总的样本保留的时间，更早的按小时汇总")),
+        Arg::new("retention")
+            .long("retention")
+            .value_name("DURATION")
+            .value_parser(duration_arg)
+            .default_value("0")
+            .help(tr("样本保留的时间，更早的删除；为0时不删除")),
+    ]
+}
+
+/// `--alert-mail-to RULE=ADDR[,ADDR]` 的校验
+fn rule_recipients_arg(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((rule, to)) if !rule.trim().is_empty() && to.split(',').all(|a| !a.trim().is_empty()) => Ok(value.to_string()),
+        _ => Err(format!("{}: {}", tr("无效的收件人"), value)),
+    }
+}
+
+fn collector_interval_arg(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((name, duration)) if !name.trim().is_empty() && parse_duration(duration).is_some() => {
+            Ok(value.to_string())
+        }
+        _ => Err(format!("{}: {}", tr("无效的采集间隔"), value)),
+    }
+}
+
+pub fn command() -> Command {
+    Command::new("System Monitor")
+        .version(version::VERSION)
+        .disable_version_flag(true)
+        .author("Your Name")
+        .about(tr("监控Linux系统资源使用情况并发送JSON数据"))
+        .arg(
+            Arg::new("version")
+                .short('V')
+                .long("version")
+                .action(ArgAction::SetTrue)
+                .help(tr("显示版本后退出，与 --verbose 一起使用时显示提交号、构建时间、编译器和特性"))
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .value_name("FILE")
+                .global(true)
+                .help(tr("TOML或YAML配置文件，命令行参数优先于文件中的设置"))
+        )
+        .arg(
+            Arg::new("lang")
+                .long("lang")
+                .value_name("LANG")
+                .value_parser(["zh", "en"])
+                .help(tr("界面语言(zh 或 en)，默认根据 LANG 环境变量判断"))
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .action(ArgAction::Count)
+                .help(tr("输出更详细的日志，可重复使用(-vv)"))
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .action(ArgAction::Count)
+                .help(tr("只输出警告和错误日志，重复使用(-qq)时只输出错误"))
+        )
+        .arg(
+            Arg::new("log-format")
+                .long("log-format")
+                .value_name("FORMAT")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .help(tr("日志格式，json 时每条日志为一行JSON"))
+        )
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .value_name("PATH")
+                .help(tr("把日志追加到文件而不是标准错误"))
+        )
+        .arg(
+            Arg::new("log-max-size")
+                .long("log-max-size")
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help(tr("日志文件超过该大小(字节)时轮转"))
+        )
+        .arg(
+            Arg::new("log-rotate")
+                .long("log-rotate")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("每隔该时长轮转一次日志文件(例如 1d，按UTC零点对齐)"))
+        )
+        .arg(
+            Arg::new("log-keep")
+                .long("log-keep")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("5")
+                .help(tr("轮转后保留的旧日志文件数"))
+        )
+        .arg(
+            Arg::new("daemon")
+                .long("daemon")
+                .action(ArgAction::SetTrue)
+                .help(tr("转入后台运行，不显示任何内容；日志只写到 --log-file 指定的文件"))
+        )
+        .arg(
+            Arg::new("embedded")
+                .long("embedded")
+                .action(ArgAction::SetTrue)
+                .help(tr("低占用模式，用于路由器、网关等小型设备：不显示，只用一个线程，各采集项依次采集，不需要进程列表时不建立进程表"))
+        )
+        .arg(
+            Arg::new("pid-file")
+                .long("pid-file")
+                .value_name("PATH")
+                .help(tr("把进程号写入该文件，退出时删除；文件中的进程仍在运行时拒绝启动"))
+        )
+        .arg(
+            Arg::new("generate-unit")
+                .long("generate-unit")
+                .action(ArgAction::SetTrue)
+                .help(tr("输出以 Type=notify 和看门狗运行的加固systemd服务文件后退出"))
+        )
+        .arg(
+            Arg::new("interval")
+                .short('i')
+                .long("interval")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("刷新间隔，可以是秒数(如 0.5)或带单位的时长(如 250ms、1m)"))
+                .default_value("1")
+        )
+        .arg(
+            Arg::new("endpoint")
+                .short('e')
+                .long("endpoint")
+                .value_name("URL")
+                .help(tr("接收数据的端点URL，也可以是 unix:///path/to.sock 形式的Unix套接字"))
+                .value_parser(endpoint_arg)
+                .default_value("http://localhost:25800")
+        )
+        .arg(
+            Arg::new("no-display")
+                .long("no-display")
+                .action(ArgAction::SetTrue)
+                .help(tr("不显示监控信息，只发送数据"))
+        )
+        .arg(
+            Arg::new("compact")
+                .long("compact")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("no-display")
+                .help(tr("每个间隔输出一行摘要，不刷新整个屏幕"))
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .value_parser(["text", "json"])
+                .conflicts_with_all(["no-display", "compact"])
+                .help(tr("输出格式，json 时每个间隔向标准输出写一行JSON样本"))
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("PROFILE")
+                .value_parser(config::PROFILES.map(|(name, _, _)| name))
+                .help(tr("采集档案：minimal 每10秒采集CPU和内存，standard 每5秒采集除进程外的全部，full 每秒采集全部；单独指定的选项优先"))
+        )
+        .arg(
+            Arg::new("collectors")
+                .long("collectors")
+                .value_name("LIST")
+                .value_delimiter(',')
+                .value_parser(Collectors::NAMES)
+                .help(tr("只启用这些采集项(逗号分隔)：cpu、mem、disk、net、proc、temp，默认全部启用(容器和WSL中不启用temp)"))
+        )
+        .arg(
+            Arg::new("source")
+                .long("source")
+                .value_name("SOURCE")
+                .value_parser(mock::source_arg)
+                .help(tr("数据来源：system 为本机，mock:FILE 按场景文件生成模拟数据代替内置采集项"))
+                .default_value("system")
+        )
+        .arg(
+            Arg::new("disable")
+                .long("disable")
+                .value_name("LIST")
+                .value_delimiter(',')
+                .value_parser(Collectors::switchable())
+                .help(tr("关闭这些采集项(逗号分隔)"))
+        )
+        .arg(
+            Arg::new("collector-interval")
+                .long("collector-interval")
+                .value_name("NAME=DURATION")
+                .action(ArgAction::Append)
+                .value_parser(collector_interval_arg)
+                .help(tr("单独设置某个采集项的采集间隔，例如 proc=10s、disk=1m，可重复使用"))
+        )
+        .arg(
+            Arg::new("exporter")
+                .long("exporter")
+                .value_name("PATH[=CONFIG]")
+                .action(ArgAction::Append)
+                .help(tr("加载WASM导出插件，发往端点的每批样本也交给插件导出，CONFIG传给插件的 init，可重复使用"))
+        )
+        .arg(
+            Arg::new("template-sink")
+                .long("template-sink")
+                .value_name("TEMPLATE=TARGET")
+                .action(ArgAction::Append)
+                .value_parser(template_sink_arg)
+                .help(tr("发往端点的每个样本再按模板(Handlebars语法)渲染成文本，发送到 http(s)://URL、file:PATH、tcp://HOST:PORT 或 udp://HOST:PORT，可重复使用"))
+        )
+        .arg(
+            Arg::new("exec")
+                .long("exec")
+                .value_name("NAME=COMMAND")
+                .action(ArgAction::Append)
+                .value_parser(exec_arg)
+                .help(tr("定期运行命令，把输出(JSON或 key=value 行)以NAME为键加入样本，可重复使用"))
+        )
+        .arg(
+            Arg::new("exec-timeout")
+                .long("exec-timeout")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .default_value("10s")
+                .help(tr("--exec 命令的超时时间，超时的命令会被杀掉"))
+        )
+        .arg(
+            Arg::new("probe")
+                .long("probe")
+                .value_name("TARGET")
+                .action(ArgAction::Append)
+                .value_parser(probe::probe_arg)
+                .help(tr("每次采集时探测的目标：icmp://HOST 或 HOST 发送ICMP回显，tcp://HOST:PORT 或 HOST:PORT 建立TCP连接，可重复使用"))
+        )
+        .arg(
+            Arg::new("probe-count")
+                .long("probe-count")
+                .value_name("N")
+                .default_value("3")
+                .value_parser(clap::value_parser!(u32).range(1..=100))
+                .help(tr("每次采集向每个目标发送的探测数"))
+        )
+        .arg(
+            Arg::new("probe-timeout")
+                .long("probe-timeout")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("等待探测回应的时间，超时的探测计为丢失，默认与 --collect-timeout 相同"))
+        )
+        .arg(
+            Arg::new("http-check")
+                .long("http-check")
+                .value_name("[NAME=]URL [status=CODE] [contains=TEXT]")
+                .action(ArgAction::Append)
+                .value_parser(http_check::http_check_arg)
+                .help(tr("每次采集时请求的URL，检查状态码(默认2xx)和响应中的文本，insecure 不验证证书，可重复使用"))
+        )
+        .arg(
+            Arg::new("http-check-timeout")
+                .long("http-check-timeout")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("HTTP检查的超时时间，默认与 --collect-timeout 相同"))
+        )
+        .arg(
+            Arg::new("dns-check")
+                .long("dns-check")
+                .value_name("NAME[@SERVER]")
+                .action(ArgAction::Append)
+                .value_parser(dns_check::dns_check_arg)
+                .help(tr("每次采集时解析的域名，默认通过系统的解析器，@SERVER 直接查询这个DNS服务器(IP地址)，可重复使用"))
+        )
+        .arg(
+            Arg::new("dns-check-timeout")
+                .long("dns-check-timeout")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("DNS检查的超时时间，默认与 --collect-timeout 相同"))
+        )
+        .arg(
+            Arg::new("tls-check")
+                .long("tls-check")
+                .value_name("HOST[:PORT]")
+                .action(ArgAction::Append)
+                .value_parser(tls_check::tls_check_arg)
+                .help(tr("定期连接的TLS端点(端口默认443)，给出证书链中最先过期的证书距离过期的天数，默认每小时检查一次，可重复使用"))
+        )
+        .arg(
+            Arg::new("tls-check-timeout")
+                .long("tls-check-timeout")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("TLS检查的超时时间，默认与 --collect-timeout 相同"))
+        )
+        .arg(
+            Arg::new("watch-log")
+                .long("watch-log")
+                .value_name("PATH")
+                .action(ArgAction::Append)
+                .requires("watch-pattern")
+                .help(tr("跟踪的日志文件，统计新写入的行中匹配 --watch-pattern 的行数，文件轮转后继续跟踪，可重复使用"))
+        )
+        .arg(
+            Arg::new("watch-pattern")
+                .long("watch-pattern")
+                .value_name("NAME=REGEX")
+                .action(ArgAction::Append)
+                .requires("watch-log")
+                .value_parser(log_watch::log_pattern_arg)
+                .help(tr("在 --watch-log 的文件中统计的正则表达式，样本中以NAME为键，可重复使用"))
+        )
+        .arg(
+            Arg::new("derive")
+                .long("derive")
+                .value_name("NAME=EXPR")
+                .action(ArgAction::Append)
+                .value_parser(derived::derive_arg)
+                .help(tr("每次采集后由样本中的值计算的派生指标，例如 'mem_used_pct=mem.used / mem.total * 100'、'net_rx=sum(net.*.rx)'，样本中以NAME为键，可重复使用"))
+        )
+        .arg(
+            Arg::new("forecast")
+                .long("forecast")
+                .value_name("WINDOW")
+                .value_parser(duration_arg)
+                .help(tr("按最近这段时间(例如 6h)内存和各挂载点用量的线性趋势，给出每小时的增长和预计用满的小时数，告警规则可以引用"))
+        )
+        .arg(
+            Arg::new("script")
+                .long("script")
+                .value_name("FILE")
+                .action(ArgAction::Append)
+                .help(tr("每次采集后运行的Lua脚本，脚本中的 collect(stats) 返回的值以文件名为键加入样本，可重复使用"))
+        )
+        .arg(
+            Arg::new("cpu-sample-interval")
+                .long("cpu-sample-interval")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .default_value("1s")
+                .help(tr("在后台刷新CPU的间隔，样本中的占用率是两次采集之间各次刷新的平均值，并给出各核心的最高值；为0时只在采集时刷新"))
+        )
+        .arg(
+            Arg::new("collect-timeout")
+                .long("collect-timeout")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("一次采集最多等待的时间，超时的采集项沿用上次的结果，默认为刷新间隔的一半"))
+        )
+        .arg(
+            Arg::new("max-agent-cpu")
+                .long("max-agent-cpu")
+                .value_name("PERCENT")
+                .value_parser(throttle::limit_arg)
+                .help(tr("本程序自身的CPU占用率(单核的百分比)超过这个值时放慢采集"))
+        )
+        .arg(
+            Arg::new("max-rss")
+                .long("max-rss")
+                .value_name("BYTES")
+                .value_parser(throttle::limit_arg)
+                .help(tr("本程序自身的常驻内存(字节)超过这个值时放慢采集"))
+        )
+        .arg(
+            Arg::new("max-load")
+                .long("max-load")
+                .value_name("LOAD")
+                .value_parser(throttle::limit_arg)
+                .help(tr("主机1分钟平均负载除以CPU核数超过这个值时放慢采集"))
+        )
+        .arg(
+            Arg::new("max-slowdown")
+                .long("max-slowdown")
+                .value_name("N")
+                .default_value("8")
+                .value_parser(clap::value_parser!(u32).range(1..))
+                .help(tr("过载时采集间隔最多放慢的倍数"))
+        )
+        .arg(
+            Arg::new("overload-disable")
+                .long("overload-disable")
+                .value_name("LIST")
+                .value_delimiter(',')
+                .value_parser(Collectors::switchable())
+                .help(tr("放慢到最大倍数后仍然过载时暂停这些采集项(逗号分隔)"))
+        )
+        .arg(
+            Arg::new("net-include")
+                .long("net-include")
+                .value_name("REGEX")
+                .action(ArgAction::Append)
+                .help(tr("只采集名称匹配正则表达式的网卡，可重复使用"))
+        )
+        .arg(
+            Arg::new("net-exclude")
+                .long("net-exclude")
+                .value_name("REGEX")
+                .action(ArgAction::Append)
+                .help(tr("不采集名称匹配正则表达式的网卡，可重复使用"))
+        )
+        .arg(
+            Arg::new("proc-name")
+                .long("proc-name")
+                .value_name("REGEX")
+                .action(ArgAction::Append)
+                .help(tr("只统计名称匹配正则表达式的进程，可重复使用"))
+        )
+        .arg(
+            Arg::new("proc-user")
+                .long("proc-user")
+                .value_name("USER")
+                .action(ArgAction::Append)
+                .help(tr("只统计属于该用户(用户名或UID)的进程，可重复使用"))
+        )
+        .arg(
+            Arg::new("proc-cgroup")
+                .long("proc-cgroup")
+                .value_name("REGEX")
+                .action(ArgAction::Append)
+                .help(tr("只统计cgroup路径匹配正则表达式的进程，可重复使用"))
+        )
+        .arg(
+            Arg::new("proc-events")
+                .long("proc-events")
+                .action(ArgAction::SetTrue)
+                .help(tr("在样本中报告上次采集以来启动和退出的进程，按 --proc-name、--proc-user、--proc-cgroup 过滤"))
+        )
+        .arg(
+            Arg::new("proc-churn")
+                .long("proc-churn")
+                .action(ArgAction::SetTrue)
+                .help(tr("统计上次采集以来进程的创建和退出，以及两次采集之间启动又退出的进程用掉的CPU(只在Linux上可用，需要root)"))
+        )
+        .arg(
+            Arg::new("net-talkers")
+                .long("net-talkers")
+                .action(ArgAction::SetTrue)
+                .help(tr("用eBPF按进程和对端端口统计网络流量，在样本中列出收发最多的几项(需要以 --features ebpf 编译，只在Linux上可用，需要root)"))
+        )
+        .arg(
+            Arg::new("top")
+                .long("top")
+                .value_name("N")
+                .default_value("0")
+                .value_parser(clap::value_parser!(usize))
+                .help(tr("在样本中附带CPU占用最高的N个进程"))
+        )
+        .arg(
+            Arg::new("once")
+                .long("once")
+                .action(ArgAction::SetTrue)
+                .help(tr("等待一个刷新间隔后输出并发送一个样本然后退出，发送失败时退出状态为4"))
+        )
+        .arg(
+            Arg::new("count")
+                .long("count")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .conflicts_with("once")
+                .help(tr("采集N个样本后退出"))
+        )
+        .arg(
+            Arg::new("duration")
+                .long("duration")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .conflicts_with("once")
+                .help(tr("运行指定的时长(如 30s、5m)后退出"))
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .value_name("FILE")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("-")
+                .help(tr("试运行：照常采集和显示，但不发送数据也不写缓存，请求体写到标准错误或追加到指定文件"))
+        )
+        .arg(
+            Arg::new("show-iface")
+                .long("show-iface")
+                .value_name("PATTERN")
+                .action(ArgAction::Append)
+                .help(tr("只显示匹配的网卡(支持 * 和 ? 通配符)，可重复使用"))
+        )
+        .arg(
+            Arg::new("hide-iface")
+                .long("hide-iface")
+                .value_name("PATTERN")
+                .action(ArgAction::Append)
+                .help(tr("不显示匹配的网卡(支持 * 和 ? 通配符)，可重复使用"))
+        )
+        .arg(
+            Arg::new("show-mount")
+                .long("show-mount")
+                .value_name("PATTERN")
+                .action(ArgAction::Append)
+                .help(tr("只显示匹配的挂载点(支持 * 和 ? 通配符)，可重复使用"))
+        )
+        .arg(
+            Arg::new("hide-mount")
+                .long("hide-mount")
+                .value_name("PATTERN")
+                .action(ArgAction::Append)
+                .help(tr("不显示匹配的挂载点(支持 * 和 ? 通配符)，可重复使用"))
+        )
+        .arg(
+            Arg::new("history")
+                .long("history")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help(tr("显示趋势图时保留的样本数"))
+                .default_value("120")
+        )
+        .arg(
+            Arg::new("history-window")
+                .long("history-window")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("在内存中保留最近这段时间的样本(例如 60m)，用于趋势图和 serve 的 GET /history"))
+        )
+        .arg(
+            Arg::new("plain")
+                .long("plain")
+                .action(ArgAction::SetTrue)
+                .help(tr("不输出转义序列，每个间隔输出一个带时间戳的文本块(输出不是终端时自动启用)"))
+        )
+        .arg(
+            Arg::new("display")
+                .long("display")
+                .value_name("SECTIONS")
+                .value_delimiter(',')
+                .value_parser(["cpu", "mem", "disk", "net", "proc"])
+                .help(tr("文本显示中的区块及其顺序(逗号分隔)，默认 cpu,mem,disk,net,proc"))
+        )
+        .arg(
+            Arg::new("cpu-cores")
+                .long("cpu-cores")
+                .value_name("MODE")
+                .value_parser(["auto", "full", "summary"])
+                .default_value("auto")
+                .help(tr("逐个显示每个CPU核心(full)还是只显示汇总(summary)，auto 时超过16个核心显示汇总"))
+        )
+        .arg(
+            Arg::new("units")
+                .long("units")
+                .value_name("UNITS")
+                .value_parser(["si", "iec"])
+                .default_value("iec")
+                .help(tr("显示字节数的单位制：si 为1000进制(kB、MB)，iec 为1024进制(KiB、MiB)"))
+        )
+        .arg(
+            Arg::new("net-bits")
+                .long("net-bits")
+                .action(ArgAction::SetTrue)
+                .help(tr("网络速率以比特/秒显示"))
+        )
+        .arg(
+            Arg::new("braille")
+                .long("braille")
+                .action(ArgAction::SetTrue)
+                .help(tr("终端界面中用盲文点阵绘制高分辨率的CPU和网络历史曲线(也可按 g 切换)"))
+        )
+        .arg(
+            Arg::new("bar-width")
+                .long("bar-width")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help(tr("文本显示中百分比条的宽度，为0时只显示数字"))
+                .default_value("20")
+        )
+        .arg(
+            Arg::new("spool-dir")
+                .long("spool-dir")
+                .value_name("DIR")
+                .help(tr("发送失败时缓存数据的目录"))
+        )
+        .arg(
+            Arg::new("spool-max-size")
+                .long("spool-max-size")
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(u64))
+                .help(tr("缓存目录的最大容量(字节)"))
+                .default_value("67108864")
+        )
+        .arg(
+            Arg::new("spool-key")
+                .long("spool-key")
+                .value_name("KEY")
+                .env("SYSMON_SPOOL_KEY")
+                .hide_env_values(true)
+                .requires("spool-dir")
+                .help(tr("用这个密钥加密缓存目录中的样本，export 读取时也需要"))
+        )
+        .arg(
+            Arg::new("health-listen")
+                .long("health-listen")
+                .value_name("ADDR")
+                .help(tr("在该地址提供 GET /healthz 和 GET /readyz，供负载均衡和Kubernetes探测(serve 在自己的地址上提供)"))
+        )
+        .arg(
+            Arg::new("max-backlog")
+                .long("max-backlog")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("512")
+                .help(tr("发送队列中等待的样本超过N时 /readyz 失败"))
+        )
+        .arg(
+            Arg::new("batch-size")
+                .long("batch-size")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help(tr("每次请求发送的样本数，大于1时以JSON数组发送"))
+                .default_value("1")
+        )
+        .arg(
+            Arg::new("batch-interval")
+                .long("batch-interval")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("批量发送的最长等待时间，秒数或带单位的时长(如 500ms)"))
+        )
+        .arg(
+            Arg::new("fields")
+                .long("fields")
+                .value_name("LIST")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .allow_hyphen_values(true)
+                .value_parser(filter::field_arg)
+                .help(tr("请求体中只保留这些字段(逗号分隔)，以 - 开头的去掉，例如 'cpu,mem,disk.*.used' 或 '-top,-agent.environment'，可重复使用；不影响显示"))
+        )
+        .arg(
+            Arg::new("sink-units")
+                .long("sink-units")
+                .value_name("SINK=SPEC")
+                .action(ArgAction::Append)
+                .value_parser(units::sink_units_arg)
+                .help(tr("发往某个输出(endpoint、exporter:名称、template:名称)的样本换算单位和精度，例如 'template:influx=bytes=KiB,percent=fraction,precision=2'，可重复使用；不影响显示"))
+        )
+        .arg(
+            Arg::new("delta")
+                .long("delta")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("每隔这么久发送一次完整样本，之间只发送变化超过 --delta-epsilon 的字段，接收端需要还原(aggregate 子命令会自动还原)"))
+        )
+        .arg(
+            Arg::new("delta-epsilon")
+                .long("delta-epsilon")
+                .value_name("PERCENT")
+                .value_parser(clap::value_parser!(f64))
+                .default_value("1")
+                .help(tr("使用 --delta 时，数值相对于上次发送的值变化超过这个百分比才发送，为0时任何变化都发送"))
+        )
+        .arg(
+            Arg::new("aggregate")
+                .long("aggregate")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("把这段时间内采集的样本汇总成一个样本再发送，例如每秒采集、每30秒发送"))
+        )
+        .arg(
+            Arg::new("aggregate-metric")
+                .long("aggregate-metric")
+                .value_name("METRIC")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .value_parser(anomaly::metric_arg)
+                .help(tr("汇总时统计最小值、最大值、均值和95分位数的指标，可重复使用或用逗号分隔"))
+        )
+        .arg(
+            Arg::new("token")
+                .long("token")
+                .value_name("TOKEN")
+                .env("SYSMON_TOKEN")
+                .hide_env_values(true)
+                .help(tr("以Bearer方式发送的认证令牌"))
+        )
+        .arg(
+            Arg::new("basic-auth")
+                .long("basic-auth")
+                .value_name("USER:PASSWORD")
+                .env("SYSMON_BASIC_AUTH")
+                .hide_env_values(true)
+                .conflicts_with("token")
+                .help(tr("Basic认证的用户名和密码"))
+        )
+        .arg(
+            Arg::new("token-file")
+                .long("token-file")
+                .value_name("PATH")
+                .env("SYSMON_TOKEN_FILE")
+                .conflicts_with_all(["token", "basic-auth"])
+                .help(tr("从文件读取以Bearer方式发送的令牌，文件被其他程序更新后重新读取"))
+        )
+        .arg(
+            Arg::new("oauth-token-url")
+                .long("oauth-token-url")
+                .value_name("URL")
+                .env("SYSMON_OAUTH_TOKEN_URL")
+                .value_parser(token::token_url_arg)
+                .conflicts_with_all(["token", "basic-auth", "token-file"])
+                .requires("oauth-client-id")
+                .help(tr("OAuth2令牌端点：用客户端凭据获取令牌，过期前刷新"))
+        )
+        .arg(
+            Arg::new("oauth-client-id")
+                .long("oauth-client-id")
+                .value_name("ID")
+                .env("SYSMON_OAUTH_CLIENT_ID")
+                .requires("oauth-token-url")
+                .help(tr("OAuth2客户端ID"))
+        )
+        .arg(
+            Arg::new("oauth-client-secret")
+                .long("oauth-client-secret")
+                .value_name("SECRET")
+                .env("SYSMON_OAUTH_CLIENT_SECRET")
+                .hide_env_values(true)
+                .requires("oauth-token-url")
+                .help(tr("OAuth2客户端密钥"))
+        )
+        .arg(
+            Arg::new("oauth-scope")
+                .long("oauth-scope")
+                .value_name("SCOPE")
+                .requires("oauth-token-url")
+                .help(tr("请求令牌的权限范围，多个用空格分隔"))
+        )
+        .arg(
+            Arg::new("oauth-param")
+                .long("oauth-param")
+                .value_name("NAME=VALUE")
+                .action(ArgAction::Append)
+                .value_parser(token::oauth_param_arg)
+                .requires("oauth-token-url")
+                .help(tr("附加到令牌请求中的参数，例如 audience=https://ingest.example.com，可重复使用"))
+        )
+        .arg(
+            Arg::new("oauth-client-auth")
+                .long("oauth-client-auth")
+                .value_name("METHOD")
+                .value_parser(["basic", "post"])
+                .default_value("basic")
+                .requires("oauth-token-url")
+                .help(tr("客户端凭据的发送方式：basic 为Basic认证头，post 为请求体中的 client_id 和 client_secret"))
+        )
+        .arg(
+            Arg::new("signing-key")
+                .long("signing-key")
+                .value_name("KEY")
+                .env("SYSMON_SIGNING_KEY")
+                .hide_env_values(true)
+                .help(tr("用这个密钥对每个请求体计算HMAC-SHA256签名，放在 X-Sysmon-Signature 请求头中，接收端据此校验来源和完整性"))
+        )
+        .arg(
+            Arg::new("header")
+                .short('H')
+                .long("header")
+                .value_name("NAME: VALUE")
+                .action(ArgAction::Append)
+                .help(tr("附加到每个请求的请求头，可重复使用"))
+        )
+        .arg(
+            Arg::new("ca-cert")
+                .long("ca-cert")
+                .value_name("PATH")
+                .help(tr("用于校验服务器证书的CA证书(PEM)"))
+        )
+        .arg(
+            Arg::new("client-cert")
+                .long("client-cert")
+                .value_name("PATH")
+                .requires("client-key")
+                .help(tr("双向TLS使用的客户端证书(PEM)"))
+        )
+        .arg(
+            Arg::new("client-key")
+                .long("client-key")
+                .value_name("PATH")
+                .requires("client-cert")
+                .help(tr("客户端证书对应的私钥(PKCS#8 PEM)"))
+        )
+        .arg(
+            Arg::new("insecure")
+                .long("insecure")
+                .action(ArgAction::SetTrue)
+                .help(tr("不校验服务器证书(仅用于测试环境)"))
+        )
+        .arg(
+            Arg::new("proxy")
+                .long("proxy")
+                .value_name("URL")
+                .help(tr("代理地址，支持 http://、https://、socks5:// 和 socks5h://，未指定时使用 HTTP_PROXY/HTTPS_PROXY 环境变量"))
+        )
+        .arg(
+            Arg::new("no-proxy")
+                .long("no-proxy")
+                .value_name("HOSTS")
+                .requires("proxy")
+                .help(tr("不经过代理的主机列表(逗号分隔)，默认读取 NO_PROXY 环境变量"))
+        )
+        .arg(
+            Arg::new("connect-timeout")
+                .long("connect-timeout")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("连接端点的超时时间，秒数或带单位的时长(如 1.5s)"))
+                .default_value("5")
+        )
+        .arg(
+            Arg::new("request-timeout")
+                .long("request-timeout")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("单次请求的超时时间，秒数或带单位的时长(如 30s)"))
+                .default_value("10")
+        )
+        .arg(
+            Arg::new("keepalive")
+                .long("keepalive")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("空闲连接的保持时间，秒数或带单位的时长(如 2m)，为0时不复用连接"))
+                .default_value("90")
+        )
+        .arg(
+            Arg::new("jitter")
+                .long("jitter")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("每次发送前随机等待的最长时间，秒数或带单位的时长(如 200ms)"))
+        )
+        .arg(
+            Arg::new("breaker-threshold")
+                .long("breaker-threshold")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u32))
+                .help(tr("端点或导出插件连续失败多少次后暂停发送，为0时不暂停"))
+                .default_value("5")
+        )
+        .arg(
+            Arg::new("breaker-cooldown")
+                .long("breaker-cooldown")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .help(tr("暂停发送后每隔多久探测一次，秒数或带单位的时长(如 1m)"))
+                .default_value("30")
+        )
+        .arg(
+            Arg::new("rate-limit")
+                .long("rate-limit")
+                .value_name("N")
+                .value_parser(clap::value_parser!(f64))
+                .help(tr("每秒最多发送的请求数"))
+        )
+        .arg(
+            Arg::new("tag")
+                .short('t')
+                .long("tag")
+                .value_name("KEY=VALUE")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help(tr("附加到每个样本的标签，可重复使用或用逗号分隔"))
+        )
+        .arg(
+            Arg::new("hostname")
+                .long("hostname")
+                .value_name("NAME")
+                .value_parser(identity_arg)
+                .help(tr("代替系统的主机名，出现在样本、事件、告警通知和终端界面中"))
+        )
+        .arg(
+            Arg::new("instance-id")
+                .long("instance-id")
+                .value_name("ID")
+                .value_parser(identity_arg)
+                .help(tr("附加到每个样本和事件的实例ID，主机名变化时接收端据此区分主机；为 machine-id 时由本机的机器ID派生"))
+        )
+        .arg(
+            Arg::new("cloud-metadata")
+                .long("cloud-metadata")
+                .value_name("PROVIDER")
+                .value_parser(["auto", "aws", "gcp", "azure"])
+                .num_args(0..=1)
+                .default_missing_value("auto")
+                .help(tr("启动时查询云主机元数据(实例ID、规格、区域)并附加到样本"))
+        )
+        .arg(
+            Arg::new("kubernetes")
+                .long("kubernetes")
+                .action(ArgAction::SetTrue)
+                .help(tr("以DaemonSet运行时从downward API的环境变量 NODE_NAME、POD_NAME、POD_NAMESPACE 读取节点、Pod和命名空间，作为 node、pod、namespace 标签"))
+        )
+        .arg(
+            Arg::new("kubernetes-allocatable")
+                .long("kubernetes-allocatable")
+                .action(ArgAction::SetTrue)
+                .requires("kubernetes")
+                .help(tr("启动时从API服务器读取节点的可分配资源，样本中给出可分配和已使用的CPU与内存(服务账号需要 get nodes 权限)"))
+        )
+        .arg(
+            Arg::new("alert")
+                .long("alert")
+                .value_name("NAME=RULE")
+                .action(ArgAction::Append)
+                .value_parser(alert::rule_arg)
+                .help(tr("告警规则，例如 'mem=mem.used_percent > 90 for 5m'，可重复使用"))
+        )
+        .arg(
+            Arg::new("alert-webhook")
+                .long("alert-webhook")
+                .value_name("[FORMAT=]URL")
+                .action(ArgAction::Append)
+                .value_parser(webhook::webhook_arg)
+                .help(tr("告警触发和解除时通知的webhook，FORMAT 为 json(默认)、slack、teams 或 pagerduty(此时URL为路由键)，可重复使用"))
+        )
+        .arg(
+            Arg::new("crash-webhook")
+                .long("crash-webhook")
+                .value_name("URL")
+                .value_parser(crash_webhook_arg)
+                .help(tr("程序崩溃(panic)时把崩溃报告以JSON POST到这个地址，然后以退出状态101退出"))
+        )
+        .arg(
+            Arg::new("smtp-server")
+                .long("smtp-server")
+                .value_name("HOST[:PORT]")
+                .help(tr("发送告警邮件的SMTP服务器"))
+        )
+        .arg(
+            Arg::new("smtp-tls")
+                .long("smtp-tls")
+                .value_name("MODE")
+                .value_parser(["starttls", "tls", "none"])
+                .default_value("starttls")
+                .help(tr("与SMTP服务器之间的加密方式，默认端口分别为587、465、25"))
+        )
+        .arg(
+            Arg::new("smtp-user")
+                .long("smtp-user")
+                .value_name("USER")
+                .requires("smtp-password")
+                .help(tr("SMTP认证的用户名"))
+        )
+        .arg(
+            Arg::new("smtp-password")
+                .long("smtp-password")
+                .value_name("PASSWORD")
+                .requires("smtp-user")
+                .help(tr("SMTP认证的密码"))
+        )
+        .arg(
+            Arg::new("mail-from")
+                .long("mail-from")
+                .value_name("ADDR")
+                .help(tr("告警邮件的发件人，默认为 system_monitor@主机名"))
+        )
+        .arg(
+            Arg::new("mail-to")
+                .long("mail-to")
+                .value_name("ADDR")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help(tr("告警邮件的收件人，可重复使用或用逗号分隔"))
+        )
+        .arg(
+            Arg::new("alert-mail-to")
+                .long("alert-mail-to")
+                .value_name("RULE=ADDR[,ADDR]")
+                .action(ArgAction::Append)
+                .value_parser(rule_recipients_arg)
+                .help(tr("指定规则的告警邮件收件人，代替 --mail-to，可重复使用"))
+        )
+        .arg(
+            Arg::new("desktop-notify")
+                .long("desktop-notify")
+                .action(ArgAction::SetTrue)
+                .help(tr("告警触发和解除时弹出桌面通知(需要以 desktop 特性编译)"))
+        )
+        .arg(
+            Arg::new("alert-exec")
+                .long("alert-exec")
+                .value_name("RULE=COMMAND")
+                .action(ArgAction::Append)
+                .value_parser(hook::hook_arg)
+                .help(tr("告警触发和解除时用 sh -c 运行的命令，RULE 为 * 时对全部规则运行，可重复使用"))
+        )
+        .arg(
+            Arg::new("alert-exec-timeout")
+                .long("alert-exec-timeout")
+                .value_name("DURATION")
+                .default_value("30s")
+                .value_parser(duration_arg)
+                .help(tr("告警命令的超时时间，超时后终止命令及其子进程"))
+        )
+        .arg(
+            Arg::new("alert-exec-concurrency")
+                .long("alert-exec-concurrency")
+                .value_name("N")
+                .default_value("4")
+                .value_parser(clap::value_parser!(usize))
+                .help(tr("同时运行的告警命令数上限，超过时排队等待"))
+        )
+        .arg(
+            Arg::new("alert-events")
+                .long("alert-events")
+                .action(ArgAction::SetTrue)
+                .help(tr("告警触发和解除时另外向端点发送事件"))
+        )
+        .arg(
+            Arg::new("maintenance")
+                .long("maintenance")
+                .value_name("NAME=CRON for DURATION")
+                .action(ArgAction::Append)
+                .value_parser(maintenance::window_arg)
+                .help(tr("维护窗口，例如 'backup=0 2 * * sun for 3h'(本地时间)：窗口内不发送告警通知，暂停向端点和其他输出发送样本，可重复使用"))
+        )
+        .arg(
+            Arg::new("quiet-hours")
+                .long("quiet-hours")
+                .value_name("NAME=CRON for DURATION")
+                .action(ArgAction::Append)
+                .value_parser(maintenance::window_arg)
+                .help(tr("静默时段，例如 'night=0 22 * * * for 9h'(本地时间)：时段内不发送告警通知，样本照常发送，可重复使用"))
+        )
+        .arg(
+            Arg::new("power-events")
+                .long("power-events")
+                .action(ArgAction::SetTrue)
+                .help(tr("通过logind订阅休眠、恢复、合盖和登录会话的变化，向端点发送事件，休眠期间暂停采集(Linux)"))
+        )
+        .arg(
+            Arg::new("event-sink")
+                .long("event-sink")
+                .value_name("SINK")
+                .action(ArgAction::Append)
+                .value_parser(events::event_sink_arg)
+                .help(tr("检测网卡、挂载点、交换空间、进程重启和告警阈值的状态变化，发送到 endpoint、log、file:PATH 或模板输出 TEMPLATE=TARGET，可重复使用"))
+        )
+        .arg(
+            Arg::new("event-retention")
+                .long("event-retention")
+                .value_name("DURATION")
+                .value_parser(duration_arg)
+                .default_value("7d")
+                .help(tr("file: 事件输出中的事件保留的时间，更早的事件定期删除"))
+        )
+        .arg(
+            Arg::new("anomaly")
+                .long("anomaly")
+                .value_name("METRIC")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .value_parser(anomaly::metric_arg)
+                .help(tr("检测指标是否偏离近期的基线，例如 net.tx、disk[\"/var\"].used，可重复使用或用逗号分隔"))
+        )
+        .arg(
+            Arg::new("anomaly-window")
+                .long("anomaly-window")
+                .value_name("DURATION")
+                .default_value("1h")
+                .value_parser(duration_arg)
+                .help(tr("异常检测基线的时间窗口，越长基线变化越慢"))
+        )
+        .arg(
+            Arg::new("anomaly-threshold")
+                .long("anomaly-threshold")
+                .value_name("Z")
+                .default_value("4")
+                .value_parser(anomaly::threshold_arg)
+                .help(tr("偏离基线超过多少个标准差时视为异常"))
+        )
+        // 以上选项在所有子命令中通用
+        .mut_args(|arg| arg.global(true))
+        .subcommand(
+            Command::new("run")
+                .about(tr("持续采集、显示并发送数据(不指定子命令时的默认行为)"))
+        )
+        .subcommand(
+            Command::new("once")
+                .about(tr("等待一个刷新间隔后输出并发送一个样本然后退出，发送失败时退出状态为4"))
+        )
+        .subcommand(
+            Command::new("serve")
+                .about(tr("拉取模式：不主动发送数据，通过HTTP提供最新的样本(GET /stats、GET /metrics)、最近的样本(GET /history?since=...)、样本流(GET /events)和仪表盘(GET /)"))
+                .arg(
+                    Arg::new("listen")
+                        .long("listen")
+                        .value_name("ADDR")
+                        .default_value("127.0.0.1:9200")
+                        .help(tr("HTTP服务的监听地址"))
+                )
+                .arg(
+                    Arg::new("auth-token")
+                        .long("auth-token")
+                        .value_name("TOKEN")
+                        .env("SYSMON_AUTH_TOKEN")
+                        .hide_env_values(true)
+                        .help(tr("要求请求带上 Authorization: Bearer TOKEN 或查询参数 token=TOKEN，/healthz 和 /readyz 除外；设置后才接受 POST /control/... 控制命令"))
+                )
+                .arg(
+                    Arg::new("mdns")
+                        .long("mdns")
+                        .action(ArgAction::SetTrue)
+                        .env("SYSMON_MDNS")
+                        .help(tr("通过mDNS在局域网中广播 _sysmon._tcp 服务，便于仪表盘和汇总服务发现本机(需要以 --features mdns 编译)"))
+                )
+        )
+        .subcommand(
+            Command::new("aggregate")
+                .about(tr("汇总模式：不采集本机，接收多个代理推送的样本，通过HTTP提供每个主机的状态和最新样本(GET /hosts、GET /metrics)"))
+                .arg(
+                    Arg::new("listen")
+                        .long("listen")
+                        .value_name("ADDR")
+                        .default_value("127.0.0.1:25800")
+                        .help(tr("HTTP服务的监听地址，代理的 --endpoint 指向这里"))
+                )
+                .arg(
+                    Arg::new("stale-after")
+                        .long("stale-after")
+                        .value_name("DURATION")
+                        .value_parser(duration_arg)
+                        .default_value("60s")
+                        .help(tr("主机超过这段时间没有推送样本时视为失联"))
+                )
+                .arg(
+                    Arg::new("auth-token")
+                        .long("auth-token")
+                        .value_name("TOKEN")
+                        .env("SYSMON_AUTH_TOKEN")
+                        .hide_env_values(true)
+                        .help(tr("要求请求带上 Authorization: Bearer TOKEN(代理用 --token 设置)，/healthz 除外"))
+                )
+                .arg(
+                    Arg::new("signing-key")
+                        .long("signing-key")
+                        .value_name("KEY")
+                        .env("SYSMON_SIGNING_KEY")
+                        .hide_env_values(true)
+                        .help(tr("只接受用这个密钥签名的推送(代理用 --signing-key 设置)，签名不符时返回401"))
+                )
+        )
+        .subcommand(
+            Command::new("remote")
+                .about(tr("轮询模式：不采集本机，按 --interval 通过SSH读取多台主机的 /proc，合并输出各主机的样本"))
+                .arg(
+                    Arg::new("host")
+                        .long("host")
+                        .value_name("[NAME=][USER@]HOST[:PORT]")
+                        .action(ArgAction::Append)
+                        .value_delimiter(',')
+                        .value_parser(remote::host_arg)
+                        .help(tr("要轮询的主机，可重复使用或用逗号分隔"))
+                )
+                .arg(
+                    Arg::new("hosts-file")
+                        .long("hosts-file")
+                        .value_name("PATH")
+                        .help(tr("从文件读取要轮询的主机，每行一个，# 开头的行为注释"))
+                )
+                .arg(
+                    Arg::new("ssh-option")
+                        .long("ssh-option")
+                        .value_name("OPTION")
+                        .action(ArgAction::Append)
+                        .help(tr("传给 ssh -o 的选项，例如 ControlMaster=auto，可重复使用"))
+                )
+                .arg(
+                    Arg::new("ssh-timeout")
+                        .long("ssh-timeout")
+                        .value_name("DURATION")
+                        .value_parser(duration_arg)
+                        .default_value("10s")
+                        .help(tr("连接并读取一台主机最多等待的时间"))
+                )
+                .arg(
+                    Arg::new("listen")
+                        .long("listen")
+                        .value_name("ADDR")
+                        .help(tr("同时通过HTTP提供各主机的状态和最新样本(GET /hosts、GET /metrics)，与 aggregate 相同"))
+                )
+                .arg(
+                    Arg::new("stale-after")
+                        .long("stale-after")
+                        .value_name("DURATION")
+                        .value_parser(duration_arg)
+                        .default_value("60s")
+                        .help(tr("主机超过这段时间没有轮询成功时视为失联"))
+                )
+                .arg(
+                    Arg::new("auth-token")
+                        .long("auth-token")
+                        .value_name("TOKEN")
+                        .env("SYSMON_AUTH_TOKEN")
+                        .hide_env_values(true)
+                        .help(tr("要求HTTP请求带上 Authorization: Bearer TOKEN，/healthz 除外"))
+                )
+        )
+        .subcommand(
+            Command::new("check")
+                .about(tr("采集一个样本并与阈值比较，有指标超出阈值时退出状态为1"))
+                .arg(
+                    Arg::new("max")
+                        .long("max")
+                        .value_name("METRIC=PERCENT")
+                        .action(ArgAction::Append)
+                        .required(true)
+                        .value_parser(check::threshold_arg)
+                        .help(tr("指标使用率的上限，例如 cpu=90、mem=80、swap=50、disk=85，可重复使用"))
+                )
+        )
+        .subcommand(
+            Command::new("export")
+                .about(tr("把缓存目录中尚未发送的样本逐行输出为JSON"))
+        )
+        .subcommand(
+            Command::new("record")
+                .about(tr("持续采集并把每个样本逐行追加到文件，不发送数据"))
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .value_name("FILE")
+                        .required(true)
+                        .help(tr("保存样本的文件"))
+                )
+                .arg(
+                    Arg::new("no-compact")
+                        .long("no-compact")
+                        .action(ArgAction::SetTrue)
+                        .help(tr("不压缩录制文件，保留全部原始样本"))
+                )
+                .args(compaction_args())
+        )
+        .subcommand(
+            Command::new("vacuum")
+                .about(tr("压缩录制文件：较早的样本按分钟或小时汇总，删除超过保留时间的样本"))
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .value_name("FILE")
+                        .required(true)
+                        .help(tr("录制的样本文件"))
+                )
+                .args(compaction_args())
+        )
+        .subcommand(
+            Command::new("replay")
+                .about(tr("按原来的时间间隔重放录制的样本，照常显示和发送"))
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .value_name("FILE")
+                        .required(true)
+                        .help(tr("录制的样本文件"))
+                )
+                .arg(
+                    Arg::new("speed")
+                        .long("speed")
+                        .value_name("N")
+                        .value_parser(speed_arg)
+                        .default_value("1")
+                        .help(tr("重放速度的倍数，例如 10 表示以10倍速重放"))
+                )
+        )
+        .subcommand(
+            Command::new("diff")
+                .about(tr("比较两个样本快照并列出显著的变化，有变化时退出状态为1"))
+                .arg(Arg::new("a").value_name("A").required(true).help(tr("之前的快照文件(JSON)")))
+                .arg(Arg::new("b").value_name("B").required(true).help(tr("之后的快照文件(JSON)")))
+                .arg(
+                    Arg::new("min-percent")
+                        .long("min-percent")
+                        .value_name("PERCENT")
+                        .value_parser(clap::value_parser!(f64))
+                        .default_value("1")
+                        .help(tr("使用率变化小于该百分点时不列出"))
+                )
+                .arg(
+                    Arg::new("min-count")
+                        .long("min-count")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("1")
+                        .help(tr("进程数量变化小于N时不列出"))
+                )
+        )
+        .subcommand(
+            Command::new("bench")
+                .about(tr("测量每个采集项的耗时和内存分配，以及按刷新间隔运行时本程序的CPU开销"))
+                .arg(
+                    Arg::new("iterations")
+                        .long("iterations")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .default_value("100")
+                        .help(tr("每个采集项运行的次数"))
+                )
+                .arg(
+                    Arg::new("window")
+                        .long("window")
+                        .value_name("DURATION")
+                        .value_parser(duration_arg)
+                        .default_value("10s")
+                        .help(tr("按刷新间隔运行以测量CPU开销的时长"))
+                )
+        )
+        .subcommand(
+            Command::new("config")
+                .about(tr("配置文件相关的命令"))
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("check")
+                        .about(tr("检查配置并输出合并命令行、环境变量和配置文件后的有效配置，有错误时以非零状态退出"))
+                )
+        )
+}
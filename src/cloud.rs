@@ -0,0 +1,129 @@
+// src/cloud.rs
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const METADATA_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudMetadata {
+    pub provider: String,
+    pub instance_id: String,
+    pub instance_type: String,
+    pub region: String,
+    pub zone: String,
+}
+
+/// 依次查询 EC2、GCE 和 Azure 的元数据服务，返回第一个可用的结果
+pub async fn detect(provider: &str) -> Option<CloudMetadata> {
+    let client = reqwest::Client::builder()
+        .timeout(METADATA_TIMEOUT)
+        .no_proxy()
+        .build()
+        .ok()?;
+
+    let (aws, gcp, azure) = tokio::join!(
+        async { if matches!(provider, "auto" | "aws") { aws(&client).await.ok() } else { None } },
+        async { if matches!(provider, "auto" | "gcp") { gcp(&client).await.ok() } else { None } },
+        async { if matches!(provider, "auto" | "azure") { azure(&client).await.ok() } else { None } },
+    );
+
+    aws.or(gcp).or(azure)
+}
+
+async fn aws(client: &reqwest::Client) -> Result<CloudMetadata, Error> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Document {
+        instance_id: String,
+        instance_type: String,
+        region: String,
+        availability_zone: String,
+    }
+
+    let token = client
+        .put("http://169.254.169.254/latest/api/token")
+        .header("X-aws-ec2-metadata-token-ttl-seconds", "60")
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    let document: Document = client
+        .get("http://169.254.169.254/latest/dynamic/instance-identity/document")
+        .header("X-aws-ec2-metadata-token", token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(CloudMetadata {
+        provider: "aws".to_string(),
+        instance_id: document.instance_id,
+        instance_type: document.instance_type,
+        region: document.region,
+        zone: document.availability_zone,
+    })
+}
+
+async fn gcp(client: &reqwest::Client) -> Result<CloudMetadata, Error> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Instance {
+        id: u64,
+        machine_type: String,
+        zone: String,
+    }
+
+    let instance: Instance = client
+        .get("http://metadata.google.internal/computeMetadata/v1/instance/?recursive=true")
+        .header("Metadata-Flavor", "Google")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    // machineType 和 zone 的格式为 projects/<id>/zones/us-central1-a
+    let last = |s: &str| s.rsplit('/').next().unwrap_or(s).to_string();
+    let zone = last(&instance.zone);
+    let region = zone.rsplit_once('-').map_or(zone.clone(), |(region, _)| region.to_string());
+
+    Ok(CloudMetadata {
+        provider: "gcp".to_string(),
+        instance_id: instance.id.to_string(),
+        instance_type: last(&instance.machine_type),
+        region,
+        zone,
+    })
+}
+
+async fn azure(client: &reqwest::Client) -> Result<CloudMetadata, Error> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Compute {
+        vm_id: String,
+        vm_size: String,
+        location: String,
+        zone: String,
+    }
+
+    let compute: Compute = client
+        .get("http://169.254.169.254/metadata/instance/compute?api-version=2021-02-01")
+        .header("Metadata", "true")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(CloudMetadata {
+        provider: "azure".to_string(),
+        instance_id: compute.vm_id,
+        instance_type: compute.vm_size,
+        region: compute.location,
+        zone: compute.zone,
+    })
+}
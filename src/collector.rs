@@ -0,0 +1,631 @@
+// src/collector.rs
+use crate::churn::ProcessChurn;
+use crate::dns_check::DnsCheckStats;
+use crate::filter::{ProcessFilter, RegexFilter};
+use crate::http_check::HttpCheckStats;
+use crate::i18n::tr;
+use crate::lifecycle::ProcessEvent;
+use crate::log_watch::LogMatches;
+use crate::probe::ProbeStats;
+use crate::talkers::NetTalkers;
+use crate::tls_check::TlsCheckStats;
+use crate::{CpuWindow, DiskStats, MemoryStats, NetworkStats, ProcessInfo, ProcessStats, SystemStats};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use sysinfo::{
+    ComponentExt, CpuExt, CpuRefreshKind, DiskExt, NetworkExt, PidExt, Process, ProcessExt, ProcessRefreshKind,
+    ProcessStatus, System, SystemExt, Uid,
+};
+
+/// 当前平台上无法采集的字段，样本的 `agent.unavailable` 中列出，接收端据此区分为0和无法采集
+pub const UNAVAILABLE: &[&str] = if cfg!(windows) { &["proc.running", "proc.sleeping", "proc.zombie"] } else { &[] };
+
+/// 重新读取挂载点、网卡和传感器列表的间隔，其余时候只刷新已知挂载点、网卡和传感器的数据
+const LIST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 一个采集项：内置的 cpu、mem、disk、net、proc、temp 和通过 [`crate::MonitorBuilder::collector`] 注册的采集项
+/// 都实现这个接口，按名称统一启用、关闭和设置采集间隔
+pub trait Collector: Send {
+    /// 采集项的名称，用于 `--collectors`、`--disable`、`--collector-interval` 和样本的 `refreshed`
+    fn name(&self) -> &'static str;
+
+    /// 在采集线程中、第一次采集之前调用一次，预先刷新 `system` 中需要两次刷新才能计算速率或占用率的部分
+    fn init(&mut self, _system: &mut System) {}
+
+    /// 采集一次；需要的话先刷新 `ctx.system` 中对应的部分
+    fn collect(&mut self, ctx: &mut Context) -> Section;
+}
+
+/// 采集时传给采集项的状态
+pub struct Context<'a> {
+    /// 采集项自己的 `System`，各采集项在不同的线程中同时采集，互不共用
+    pub system: &'a mut System,
+    /// 采集时保留的网卡
+    pub net_filter: &'a RegexFilter,
+    pub process_filter: &'a ProcessFilter,
+    /// `process_filter.users` 解析得到的UID
+    pub process_uids: &'a [Uid],
+    /// 样本中附带的CPU占用最高的进程数
+    pub top: usize,
+}
+
+impl Context<'_> {
+    pub fn process_matches(&self, process: &Process) -> bool {
+        process_matches(self.process_filter, self.process_uids, process)
+    }
+}
+
+/// 进程是否符合过滤条件：名称、用户和cgroup
+pub(crate) fn process_matches(filter: &ProcessFilter, uids: &[Uid], process: &Process) -> bool {
+    if !filter.name.matches(process.name()) {
+        return false;
+    }
+    if !uids.is_empty() && !process.user_id().is_some_and(|uid| uids.contains(uid)) {
+        return false;
+    }
+    #[cfg(target_os = "linux")]
+    if !filter.cgroup.is_empty() {
+        let path = format!("/proc/{}/cgroup", process.pid());
+        let cgroups = std::fs::read_to_string(path).unwrap_or_default();
+        return cgroups
+            .lines()
+            .filter_map(|line| line.splitn(3, ':').nth(2))
+            .any(|cgroup| filter.cgroup.matches(cgroup));
+    }
+    true
+}
+
+/// 一个采集项的结果，写入样本中对应的字段；采集项本次未到期时沿用上次的结果
+#[derive(Debug, Clone)]
+pub enum Section {
+    /// 每个核心的CPU占用率和测量窗口
+    Cpu { usage: Vec<f32>, window: CpuWindow },
+    Memory { mem: MemoryStats, swap: MemoryStats },
+    Disk(BTreeMap<String, DiskStats>),
+    Network(HashMap<String, NetworkStats>),
+    Processes { stats: ProcessStats, top: Vec<ProcessInfo> },
+    /// 各传感器的温度
+    Temperature(BTreeMap<String, f32>),
+    /// 各探测目标的往返时间和丢包率
+    Probes(BTreeMap<String, ProbeStats>),
+    /// 各HTTP检查的结果
+    Http(BTreeMap<String, HttpCheckStats>),
+    /// 各DNS检查的结果
+    Dns(BTreeMap<String, DnsCheckStats>),
+    /// 各TLS端点证书的有效期
+    Tls(BTreeMap<String, TlsCheckStats>),
+    /// 各日志模式的匹配行数
+    Logs(BTreeMap<String, LogMatches>),
+    /// 上次采集以来启动和退出的进程
+    Lifecycle(Vec<ProcessEvent>),
+    /// 上次采集以来进程的创建和退出
+    Churn(ProcessChurn),
+    /// 按进程和对端端口的网络流量
+    Talkers(NetTalkers),
+    /// 其他采集项，以采集项的名称为键写入样本
+    Custom(serde_json::Value),
+    /// 模拟数据源生成的样本，代替内置采集项的各部分
+    Mock(Box<SystemStats>),
+    /// 本次读不到有意义的数据，例如开机时还没有CPU、/proc 不可读；样本中省略对应字段，
+    /// 采集项的名称列在 `agent.unavailable` 中
+    Unavailable,
+}
+
+impl Section {
+    pub fn apply(self, name: &str, stats: &mut SystemStats) {
+        match self {
+            Section::Cpu { usage, window } => {
+                stats.cpu = usage;
+                stats.cpu_window = Some(window);
+            }
+            Section::Memory { mem, swap } => {
+                stats.mem = Some(mem);
+                stats.swap = Some(swap);
+            }
+            Section::Disk(disk) => stats.disk = disk,
+            Section::Network(net) => stats.net = net,
+            Section::Processes { stats: proc, top } => {
+                stats.proc = Some(proc);
+                stats.top = top;
+            }
+            Section::Temperature(temp) => stats.temp = temp,
+            Section::Probes(probes) => stats.probes = probes,
+            Section::Http(http) => stats.http = http,
+            Section::Dns(dns) => stats.dns = dns,
+            Section::Tls(tls) => stats.tls = tls,
+            Section::Logs(logs) => stats.logs = logs,
+            Section::Lifecycle(events) => stats.lifecycle = events,
+            Section::Churn(churn) => stats.churn = Some(churn),
+            Section::Talkers(talkers) => stats.talkers = Some(talkers),
+            Section::Custom(value) => {
+                stats.extra.insert(name.to_string(), value);
+            }
+            Section::Mock(sample) => {
+                let SystemStats { cpu, mem, swap, disk, net, proc, top, temp, probes, http, dns, tls, logs, extra, .. } = *sample;
+                stats.cpu = cpu;
+                stats.mem = mem;
+                stats.swap = swap;
+                stats.disk = disk;
+                stats.net = net;
+                stats.proc = proc;
+                stats.top = top;
+                stats.temp = temp;
+                stats.probes = probes;
+                stats.http = http;
+                stats.dns = dns;
+                stats.tls = tls;
+                stats.logs = logs;
+                stats.extra.extend(extra);
+            }
+            Section::Unavailable => {}
+        }
+    }
+
+    /// 采集项报告了错误：其他采集项的结果为 `{"error": ...}`，例如失败的 `--exec` 命令
+    pub fn is_error(&self) -> bool {
+        matches!(self, Section::Custom(value) if value.get("error").is_some())
+    }
+
+    /// 只属于采集到它的那个样本的结果，采集项本次未到期时不沿用
+    pub fn is_event(&self) -> bool {
+        matches!(self, Section::Lifecycle(_) | Section::Churn(_))
+    }
+}
+
+/// 内置的采集项，结果按这个顺序写入样本；`low_footprint` 时进程数不通过sysinfo的进程表统计，CPU也不在后台刷新
+pub(crate) fn builtin(low_footprint: bool, cpu_sample_interval: Duration) -> Vec<Box<dyn Collector>> {
+    let cpu_sample_interval = if low_footprint { Duration::ZERO } else { cpu_sample_interval };
+    vec![
+        Box::new(Cpu::new(cpu_sample_interval)),
+        Box::new(Memory),
+        Box::new(Disk { listed: None }),
+        Box::new(Network::default()),
+        Box::new(Processes { low_footprint }),
+        Box::new(Temperature { listed: None }),
+    ]
+}
+
+/// CPU占用率：sysinfo按两次刷新之间的差值计算，采集时给出上次采集以来各次刷新的时间加权平均，
+/// 刷新间隔比 `--interval` 长时也是整个窗口的平均值，不是采集那一刻的值
+struct Cpu {
+    /// 在后台线程中刷新的间隔，为零时只在采集时刷新
+    sample_interval: Duration,
+    sampler: Arc<Mutex<CpuSampler>>,
+}
+
+impl Cpu {
+    fn new(sample_interval: Duration) -> Self {
+        Self { sample_interval, sampler: Arc::new(Mutex::new(CpuSampler::new())) }
+    }
+}
+
+impl Collector for Cpu {
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
+
+    fn init(&mut self, _system: &mut System) {
+        self.sampler.lock().unwrap().refresh();
+        if self.sample_interval.is_zero() {
+            return;
+        }
+        // 采集项释放后线程随之退出
+        let sampler = Arc::downgrade(&self.sampler);
+        let interval = self.sample_interval;
+        let spawned = std::thread::Builder::new().name("cpu-sampler".to_string()).spawn(move || sample(sampler, interval));
+        if let Err(error) = spawned {
+            tracing::warn!(%error, "{}", tr("无法启动CPU采样线程，只在采集时刷新CPU"));
+        }
+    }
+
+    fn collect(&mut self, _ctx: &mut Context) -> Section {
+        let mut sampler = self.sampler.lock().unwrap();
+        sampler.refresh();
+        match sampler.take() {
+            Some((usage, window)) => Section::Cpu { usage, window },
+            None => Section::Unavailable,
+        }
+    }
+}
+
+/// 每隔 `interval` 刷新一次CPU，刚刚采集过时跳过
+fn sample(sampler: Weak<Mutex<CpuSampler>>, interval: Duration) {
+    loop {
+        std::thread::sleep(interval);
+        let Some(sampler) = sampler.upgrade() else { return };
+        let mut sampler = sampler.lock().unwrap();
+        if sampler.last.is_none_or(|last| last.elapsed() >= interval) {
+            sampler.refresh();
+        }
+    }
+}
+
+/// 累积各次刷新之间的占用率，直到下一次采集取走
+struct CpuSampler {
+    /// 只刷新CPU的 `System`，后台线程和采集线程共用
+    system: System,
+    /// 上一次刷新的时间
+    last: Option<Instant>,
+    /// 每个核心的占用率乘以经过的秒数之和
+    busy: Vec<f64>,
+    secs: f64,
+    samples: u32,
+    peak: Vec<f32>,
+}
+
+impl CpuSampler {
+    fn new() -> Self {
+        Self { system: System::new(), last: None, busy: Vec::new(), secs: 0.0, samples: 0, peak: Vec::new() }
+    }
+
+    fn refresh(&mut self) {
+        // 两次刷新间隔太短时sysinfo算不出有意义的占用率，先等到最短间隔
+        if let Some(last) = self.last {
+            let wait = System::MINIMUM_CPU_UPDATE_INTERVAL.saturating_sub(last.elapsed());
+            if !wait.is_zero() {
+                std::thread::sleep(wait);
+            }
+        }
+        // 样本中只有占用率，不读取频率
+        self.system.refresh_cpu_specifics(CpuRefreshKind::new().with_cpu_usage());
+        let now = Instant::now();
+        let Some(last) = self.last.replace(now) else { return };
+        let usage: Vec<f32> = self.system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
+        // 开机时可能短暂读不到CPU；两次刷新之间没有经过时间时占用率为NaN
+        if usage.is_empty() || usage.iter().any(|usage| !usage.is_finite()) {
+            return;
+        }
+        // 核心数变化(CPU热插拔)时从头累积
+        if self.busy.len() != usage.len() {
+            self.busy = vec![0.0; usage.len()];
+            self.peak = vec![0.0; usage.len()];
+            self.secs = 0.0;
+            self.samples = 0;
+        }
+        let secs = (now - last).as_secs_f64();
+        for (i, usage) in usage.into_iter().enumerate() {
+            let usage = usage.clamp(0.0, 100.0);
+            self.busy[i] += usage as f64 * secs;
+            self.peak[i] = self.peak[i].max(usage);
+        }
+        self.secs += secs;
+        self.samples += 1;
+    }
+
+    /// 取走累积的平均占用率和窗口，窗口中没有有效的刷新时为 None
+    fn take(&mut self) -> Option<(Vec<f32>, CpuWindow)> {
+        if self.samples == 0 || self.secs <= 0.0 {
+            return None;
+        }
+        let usage = self.busy.iter().map(|busy| (busy / self.secs) as f32).collect();
+        let window = CpuWindow {
+            ms: (self.secs * 1000.0).round() as u64,
+            samples: self.samples,
+            peak: if self.samples > 1 { self.peak.clone() } else { Vec::new() },
+        };
+        self.busy.iter_mut().for_each(|busy| *busy = 0.0);
+        self.peak.iter_mut().for_each(|peak| *peak = 0.0);
+        self.secs = 0.0;
+        self.samples = 0;
+        Some((usage, window))
+    }
+}
+
+/// 内存和swap
+struct Memory;
+
+impl Collector for Memory {
+    fn name(&self) -> &'static str {
+        "mem"
+    }
+
+    fn collect(&mut self, ctx: &mut Context) -> Section {
+        let system = &mut *ctx.system;
+        system.refresh_memory();
+        // 读不到 /proc/meminfo 时总量为0，swap也来自同一个文件；没有配置swap时swap的总量为0，照常给出
+        if system.total_memory() == 0 {
+            return Section::Unavailable;
+        }
+        Section::Memory {
+            mem: MemoryStats { total: system.total_memory(), used: system.used_memory() },
+            swap: MemoryStats { total: system.total_swap(), used: system.used_swap() },
+        }
+    }
+}
+
+struct Disk {
+    listed: Option<Instant>,
+}
+
+impl Collector for Disk {
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    fn collect(&mut self, ctx: &mut Context) -> Section {
+        if self.listed.is_none_or(|listed| listed.elapsed() >= LIST_INTERVAL) {
+            ctx.system.refresh_disks_list();
+            self.listed = Some(Instant::now());
+        } else {
+            ctx.system.refresh_disks();
+        }
+        let disk = ctx.system
+            .disks()
+            .iter()
+            .filter(|disk| disk.total_space() > 0 && !system_volume(disk.mount_point()))
+            .map(|disk| {
+                (
+                    disk.mount_point().to_string_lossy().into_owned(),
+                    DiskStats {
+                        total: disk.total_space(),
+                        used: disk.total_space().saturating_sub(disk.available_space()),
+                    },
+                )
+            })
+            .collect();
+        Section::Disk(disk)
+    }
+}
+
+/// macOS中 /System/Volumes 下的系统卷(VM、Preboot、Update等)与数据卷共用同一个APFS容器，容量相同，不单独统计
+fn system_volume(mount: &std::path::Path) -> bool {
+    cfg!(target_os = "macos") && mount.starts_with("/System/Volumes") && mount != std::path::Path::new("/System/Volumes/Data")
+}
+
+/// 网卡流量，按两次采集之间的计数器差值计算速率
+struct Network {
+    last_data: HashMap<String, (u64, u64)>,
+    last_update: Instant,
+    listed: Instant,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self { last_data: HashMap::new(), last_update: Instant::now(), listed: Instant::now() }
+    }
+}
+
+/// 各网卡的累计收发字节数，包括被过滤掉的网卡，过滤条件变化时不必重新计算
+fn network_data(system: &System) -> HashMap<String, (u64, u64)> {
+    let mut net_data = HashMap::new();
+    for (interface_name, data) in system.networks() {
+        net_data.insert(
+            interface_name.clone(),
+            (data.total_received(), data.total_transmitted())
+        );
+    }
+    net_data
+}
+
+impl Collector for Network {
+    fn name(&self) -> &'static str {
+        "net"
+    }
+
+    fn init(&mut self, system: &mut System) {
+        system.refresh_networks_list();
+        self.last_data = network_data(system);
+        self.last_update = Instant::now();
+        self.listed = Instant::now();
+    }
+
+    fn collect(&mut self, ctx: &mut Context) -> Section {
+        // 定期重新读取网卡列表，之后新出现的网卡也能统计
+        if self.listed.elapsed() >= LIST_INTERVAL {
+            ctx.system.refresh_networks_list();
+            self.listed = Instant::now();
+        } else {
+            ctx.system.refresh_networks();
+        }
+        // 速率按两次读取计数器之间的实际时间计算
+        let now = Instant::now();
+        let current_net_data = network_data(ctx.system);
+        let elapsed = (now - self.last_update).as_secs_f64();
+
+        let mut net = HashMap::new();
+        for (interface, &(current_rx, current_tx)) in current_net_data.iter().filter(|(name, _)| ctx.net_filter.matches(name)) {
+            // 新出现的网卡(包括消失后又出现的)也列出，只是还没有速率
+            let (rx, tx) = match self.last_data.get(interface) {
+                Some(&(last_rx, last_tx)) => (rate(last_rx, current_rx, elapsed), rate(last_tx, current_tx, elapsed)),
+                None => (None, None),
+            };
+            if self.last_data.contains_key(interface) && (rx.is_none() || tx.is_none()) {
+                tracing::debug!(interface = %interface, "{}", tr("网卡计数器已重置，本次没有速率"));
+            }
+            net.insert(interface.clone(), NetworkStats { rx, tx, up: link_up(interface) });
+        }
+
+        self.last_data = current_net_data;
+        self.last_update = now;
+        Section::Network(net)
+    }
+}
+
+/// 两次读取之间的速率；计数器变小说明网卡重新启用或驱动重新加载后从零开始计数，这一次的差值无法得知，返回 None
+fn rate(last: u64, current: u64, elapsed: f64) -> Option<u64> {
+    if elapsed <= 0.0 {
+        return None;
+    }
+    current.checked_sub(last).map(|delta| (delta as f64 / elapsed) as u64)
+}
+
+/// 刷新进程时读取的内容：样本和进程列表中没有各进程的磁盘读写，不读取 `/proc/<pid>/io`
+fn process_refresh() -> ProcessRefreshKind {
+    ProcessRefreshKind::new().with_cpu()
+}
+
+/// 进程数量，以及CPU占用最高的进程
+struct Processes {
+    /// 不需要 `--top`、`--proc-user` 和 `--proc-cgroup` 时，在Linux上直接读取 /proc 统计进程数，
+    /// 不建立和保留sysinfo的进程表
+    low_footprint: bool,
+}
+
+impl Processes {
+    fn count_only(&self, ctx: &Context) -> bool {
+        self.low_footprint && ctx.top == 0 && ctx.process_uids.is_empty() && ctx.process_filter.cgroup.is_empty()
+    }
+}
+
+impl Collector for Processes {
+    fn name(&self) -> &'static str {
+        "proc"
+    }
+
+    fn init(&mut self, system: &mut System) {
+        if self.low_footprint {
+            return;
+        }
+        system.refresh_cpu_specifics(CpuRefreshKind::new().with_cpu_usage());
+        system.refresh_processes_specifics(process_refresh());
+    }
+
+    fn collect(&mut self, ctx: &mut Context) -> Section {
+        if self.count_only(ctx) {
+            if let Some(stats) = count_processes(&ctx.process_filter.name).filter(|stats| stats.total > 0) {
+                return Section::Processes { stats, top: Vec::new() };
+            }
+        }
+        ctx.system.refresh_processes_specifics(process_refresh());
+        let mut stats = ProcessStats::default();
+        let mut top = Vec::new();
+
+        for process in ctx.system.processes().values().filter(|p| ctx.process_matches(p)) {
+            stats.total += 1;
+            match process.status() {
+                // sysinfo在Windows上不读取进程状态，所有进程都报告为运行，只计入总数
+                _ if cfg!(windows) => {}
+                ProcessStatus::Run => stats.running += 1,
+                ProcessStatus::Sleep => stats.sleeping += 1,
+                ProcessStatus::Zombie => stats.zombie += 1,
+                // macOS上sysinfo按线程状态细分运行中的进程：不可中断的等待报告为 Dead，挂起报告为 Parked
+                #[cfg(target_os = "macos")]
+                ProcessStatus::Idle | ProcessStatus::Dead | ProcessStatus::Parked => stats.sleeping += 1,
+                // FreeBSD的 SWAIT(中断线程等待)报告为 Dead，SLOCK(等待锁)报告为 LockBlocked
+                #[cfg(target_os = "freebsd")]
+                ProcessStatus::Idle | ProcessStatus::Dead | ProcessStatus::LockBlocked => stats.sleeping += 1,
+                _ => {},
+            }
+            if ctx.top > 0 {
+                top.push(ProcessInfo {
+                    pid: process.pid().as_u32(),
+                    name: process.name().to_string(),
+                    cpu: process.cpu_usage(),
+                    mem: process.memory(),
+                    start_time: process.start_time(),
+                });
+            }
+        }
+
+        // 至少有本程序自己，一个进程都没有说明进程表不可读(没有挂载 /proc 或 hidepid)，
+        // 设置了过滤条件时没有匹配的进程则是正常的
+        if stats.total == 0 && ctx.process_filter.name.is_empty() && ctx.process_uids.is_empty() && ctx.process_filter.cgroup.is_empty() {
+            return Section::Unavailable;
+        }
+        top.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+        top.truncate(ctx.top);
+        Section::Processes { stats, top }
+    }
+}
+
+/// 从 /proc/<pid>/stat 读取进程名和状态统计进程数，所有进程复用同一个缓冲区
+#[cfg(target_os = "linux")]
+fn count_processes(names: &RegexFilter) -> Option<ProcessStats> {
+    use std::fmt::Write;
+    use std::io::Read;
+
+    let mut stats = ProcessStats::default();
+    let mut path = String::new();
+    let mut buf = Vec::new();
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let pid = entry.file_name();
+        let Some(pid) = pid.to_str().filter(|pid| pid.bytes().all(|b| b.is_ascii_digit())) else {
+            continue;
+        };
+        path.clear();
+        let _ = write!(path, "/proc/{}/stat", pid);
+        buf.clear();
+        // 进程可能已经退出
+        let Ok(mut file) = std::fs::File::open(&path) else {
+            continue;
+        };
+        if file.read_to_end(&mut buf).is_err() {
+            continue;
+        }
+        // 格式为 `pid (comm) state ...`，进程名中可能有括号，取第一个左括号和最后一个右括号之间
+        let (Some(open), Some(close)) = (buf.iter().position(|&b| b == b'('), buf.iter().rposition(|&b| b == b')')) else {
+            continue;
+        };
+        if open >= close || !names.matches(&String::from_utf8_lossy(&buf[open + 1..close])) {
+            continue;
+        }
+        stats.total += 1;
+        match buf.get(close + 2) {
+            Some(b'R') => stats.running += 1,
+            Some(b'S') => stats.sleeping += 1,
+            Some(b'Z') => stats.zombie += 1,
+            _ => {}
+        }
+    }
+    Some(stats)
+}
+
+/// 其他平台上仍然通过sysinfo统计
+#[cfg(not(target_os = "linux"))]
+fn count_processes(_names: &RegexFilter) -> Option<ProcessStats> {
+    None
+}
+
+/// 从 /sys/class/net/<网卡>/operstate 读取连接状态，`unknown`、`dormant` 等视为没有状态
+#[cfg(target_os = "linux")]
+fn link_up(interface: &str) -> Option<bool> {
+    match std::fs::read_to_string(format!("/sys/class/net/{}/operstate", interface)).ok()?.trim() {
+        "up" => Some(true),
+        "down" | "lowerlayerdown" | "notpresent" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn link_up(_interface: &str) -> Option<bool> {
+    None
+}
+
+/// 各传感器的温度(°C)，Linux上读取hwmon，macOS上读取SMC(Intel)或HID传感器(Apple芯片)，
+/// FreeBSD上读取 `dev.cpu.N.temperature`(需要加载coretemp或amdtemp)；
+/// 没有传感器或无法读取时为空，样本中省略
+struct Temperature {
+    listed: Option<Instant>,
+}
+
+impl Collector for Temperature {
+    fn name(&self) -> &'static str {
+        "temp"
+    }
+
+    fn collect(&mut self, ctx: &mut Context) -> Section {
+        if self.listed.is_none_or(|listed| listed.elapsed() >= LIST_INTERVAL) {
+            ctx.system.refresh_components_list();
+            self.listed = Some(Instant::now());
+        } else {
+            ctx.system.refresh_components();
+        }
+        let mut temp = BTreeMap::new();
+        for component in ctx.system.components() {
+            let celsius = component.temperature();
+            if !celsius.is_finite() {
+                continue;
+            }
+            // 同名的传感器按出现的顺序编号
+            let mut label = component.label().to_string();
+            let mut index = 1;
+            while temp.contains_key(&label) {
+                index += 1;
+                label = format!("{} #{}", component.label(), index);
+            }
+            temp.insert(label, celsius);
+        }
+        Section::Temperature(temp)
+    }
+}
@@ -0,0 +1,415 @@
+// src/commands.rs
+use crate::aggregator::Aggregator;
+use crate::compact;
+use crate::display::{self, compact_line};
+use crate::error::{Error, EXIT_FAILED};
+use crate::i18n::tr;
+use crate::options::{
+    build_monitor, build_notifiers, build_transport, collect_timeout, compaction_policy, history, interval, parse_collector_intervals,
+    parse_display_filter, parse_net_filter, parse_process_filter, parse_tags, seconds, spool_cipher,
+};
+use crate::remote::{Remote, Target};
+use crate::replay;
+use crate::run::{check_collectors, ticker_for};
+use crate::spool::Spool;
+use crate::{
+    aggregator, bench, check, config, diff, log, mdns, serve, signals, systemd, version, Collectors, MIN_INTERVAL, Monitor,
+    SCHEMA_VERSION, SystemStats,
+};
+use clap::{ArgMatches, Command};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use sysinfo::{System, SystemExt, Uid, UserExt};
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+/// `serve`：按刷新间隔采集，但不主动发送，由HTTP服务提供最新的样本
+pub async fn serve(matches: &ArgMatches, sub: &ArgMatches) -> Result<(), Error> {
+    let mut monitor = build_monitor(matches).await.map_err(Error::config)?;
+    let listen = sub.get_one::<String>("listen").unwrap();
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .map_err(|e| Error::config(format!("{} {}: {}", tr("无法监听"), listen, e)))?;
+    info!(url = %format!("http://{}/", listener.local_addr()?), "{}", tr("正在提供数据"));
+
+    let token = sub.get_one::<String>("auth-token").cloned();
+    // 广播在退出循环时释放，撤销广播
+    let _announcement = match sub.get_flag("mdns") {
+        true => Some(announce(&monitor, listener.local_addr()?, token.is_some())?),
+        false => None,
+    };
+    let (server, mut controls) = serve::Server::new(history(matches, monitor.tick()), token, monitor.host_name());
+    tokio::spawn(serve::run(listener, server.clone()));
+
+    let mut notifier = systemd::Notifier::from_env();
+    let mut sigint = signals::interrupt()?;
+    let mut sigterm = signals::terminate()?;
+    // 与 `run` 一样按固定的节拍采集，采集的耗时不会累积到间隔上；启动时立即采集一次
+    let mut ticker = ticker_for(monitor.tick());
+    ticker.reset_immediately();
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                server.publish(monitor.collect().await)?;
+                server.health().collected(monitor.tick());
+                server.health().check("collectors", check_collectors(&monitor));
+                notifier.collected();
+            }
+            Some((control, reply)) = controls.recv() => {
+                info!(?control, "{}", tr("收到控制命令"));
+                let result = apply_control(&mut monitor, &server, control).await;
+                if let Err(error) = &result {
+                    warn!(%error, "{}", tr("控制命令失败"));
+                }
+                let _ = reply.send(result);
+            }
+            _ = sigint.recv() => return Ok(()),
+            _ = sigterm.recv() => return Ok(()),
+        }
+        // 刷新间隔变化(控制命令修改、过载放慢)时按新的间隔重新对齐
+        if ticker.period() != monitor.tick() {
+            ticker = ticker_for(monitor.tick());
+        }
+    }
+}
+
+/// `serve --mdns`：广播 `_sysmon._tcp` 服务，TXT记录带上版本、样本格式版本、是否需要令牌和 `--tag` 标签
+fn announce(monitor: &Monitor, addr: std::net::SocketAddr, auth: bool) -> Result<mdns::Announcement, Error> {
+    let mut properties = vec![
+        ("version".to_string(), version::VERSION.to_string()),
+        ("schema".to_string(), SCHEMA_VERSION.to_string()),
+        ("auth".to_string(), if auth { "bearer" } else { "none" }.to_string()),
+    ];
+    properties.extend(monitor.tags().iter().map(|(key, value)| (key.clone(), value.clone())));
+    let host = monitor.host_name();
+    let announcement = mdns::Announcement::start(&host, addr, &properties)?;
+    info!(service = mdns::SERVICE_TYPE, instance = %host, port = addr.port(), "{}", tr("已通过mDNS广播服务"));
+    if addr.ip().is_loopback() {
+        warn!(%addr, "{}", tr("监听的是回环地址，局域网中的其他主机无法访问广播的服务"));
+    }
+    Ok(announcement)
+}
+
+/// 执行 `serve` 收到的控制命令，返回响应的JSON
+async fn apply_control(monitor: &mut Monitor, server: &serve::Server, control: serve::Control) -> Result<String, String> {
+    match control {
+        serve::Control::Interval(interval) => {
+            let interval = interval.max(MIN_INTERVAL);
+            monitor.set_interval(interval);
+            Ok(serde_json::json!({ "interval": humantime::format_duration(interval).to_string() }).to_string())
+        }
+        serve::Control::Collectors { enable, disable } => {
+            let names = monitor.collector_names();
+            if let Some(unknown) = enable.iter().chain(&disable).find(|n| n.as_str() != "processes" && !names.contains(&n.as_str())) {
+                return Err(format!("{}: {}", tr("未知的采集项"), unknown));
+            }
+            let mut collectors = monitor.collectors().clone();
+            enable.iter().for_each(|name| collectors.enable(name));
+            disable.iter().for_each(|name| collectors.disable(name));
+            let enabled: Vec<_> = names.into_iter().filter(|name| collectors.enabled(name)).collect();
+            monitor.set_collectors(collectors);
+            Ok(serde_json::json!({ "collectors": enabled }).to_string())
+        }
+        serve::Control::Snapshot => {
+            let stats = monitor.collect().await;
+            let body = serde_json::to_string(&stats).map_err(|e| e.to_string())?;
+            server.publish(stats).map_err(|e| e.to_string())?;
+            Ok(body)
+        }
+        serve::Control::RotateLogs => match log::rotate() {
+            Ok(true) => Ok(serde_json::json!({ "rotated": true }).to_string()),
+            Ok(false) => Err(tr("没有使用 --log-file").to_string()),
+            Err(e) => Err(format!("{}: {}", tr("轮转日志文件失败"), e)),
+        },
+    }
+}
+
+/// `aggregate`：不采集本机，接收其他代理推送的样本，按主机汇总后通过HTTP提供
+pub async fn aggregate(sub: &ArgMatches) -> Result<(), Error> {
+    let listen = sub.get_one::<String>("listen").unwrap();
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .map_err(|e| Error::config(format!("{} {}: {}", tr("无法监听"), listen, e)))?;
+    info!(url = %format!("http://{}/", listener.local_addr()?), "{}", tr("正在接收推送的样本"));
+
+    let stale_after = seconds(sub, "stale-after").unwrap();
+    let token = sub.get_one::<String>("auth-token").cloned();
+    let signing_key = sub.get_one::<String>("signing-key").cloned();
+    let aggregator = Aggregator::new(stale_after, token, signing_key);
+    tokio::spawn(aggregator::run(listener, aggregator.clone()));
+
+    let mut notifier = systemd::Notifier::from_env();
+    let mut sigint = signals::interrupt()?;
+    let mut sigterm = signals::terminate()?;
+    let mut ticker = tokio::time::interval(stale_after.min(Duration::from_secs(1)));
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                aggregator.check();
+                notifier.collected();
+            }
+            _ = sigint.recv() => return Ok(()),
+            _ = sigterm.recv() => return Ok(()),
+        }
+    }
+}
+
+/// `remote --host` 和 `--hosts-file` 中的主机
+fn remote_targets(sub: &ArgMatches) -> Result<Vec<Target>, Error> {
+    let mut values: Vec<String> = sub.get_many::<String>("host").unwrap_or_default().cloned().collect();
+    if let Some(path) = sub.get_one::<String>("hosts-file") {
+        let content = std::fs::read_to_string(path).map_err(|e| Error::config(format!("{} {}: {}", tr("无法读取主机列表"), path, e)))?;
+        values.extend(content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')).map(str::to_string));
+    }
+    let targets = values
+        .iter()
+        .map(|value| Target::parse(value).ok_or_else(|| Error::config(format!("{}: {}", tr("无效的远程主机"), value))))
+        .collect::<Result<Vec<_>, _>>()?;
+    if targets.is_empty() {
+        return Err(Error::config(tr("没有要轮询的主机，使用 --host 或 --hosts-file 指定")));
+    }
+    Ok(targets)
+}
+
+/// `remote`：每台主机在各自的任务中按 `--interval` 轮询，样本逐行输出(JSON或带主机名的单行摘要)，
+/// 设置了 `--listen` 时还像 `aggregate` 一样通过HTTP提供
+pub async fn poll_remote(matches: &ArgMatches, sub: &ArgMatches) -> Result<(), Error> {
+    let targets = remote_targets(sub)?;
+    let interval = interval(matches);
+    let timeout = seconds(sub, "ssh-timeout").unwrap();
+    let options: Vec<String> = sub.get_many::<String>("ssh-option").unwrap_or_default().cloned().collect();
+    let tags = parse_tags(matches).map_err(Error::config)?;
+    let json = matches.get_one::<String>("format").is_some_and(|f| f == "json");
+    let quiet = matches.get_flag("no-display") || matches.get_flag("daemon");
+    let filter = parse_display_filter(matches);
+
+    let stale_after = seconds(sub, "stale-after").unwrap();
+    let aggregator = Aggregator::new(stale_after, sub.get_one::<String>("auth-token").cloned(), None);
+    if let Some(listen) = sub.get_one::<String>("listen") {
+        let listener = tokio::net::TcpListener::bind(listen)
+            .await
+            .map_err(|e| Error::config(format!("{} {}: {}", tr("无法监听"), listen, e)))?;
+        info!(url = %format!("http://{}/hosts", listener.local_addr()?), "{}", tr("正在提供各主机的样本"));
+        tokio::spawn(aggregator::run(listener, aggregator.clone()));
+    }
+
+    let width = targets.iter().map(|target| target.name.chars().count()).max().unwrap_or(0);
+    let (results, mut received) = tokio::sync::mpsc::unbounded_channel();
+    for target in targets {
+        let mut remote = Remote::new(target, options.clone(), timeout, tags.clone());
+        let results = results.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                let result = remote.poll().await;
+                if results.send((remote.target().clone(), result)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    drop(results);
+
+    let mut failing = std::collections::HashSet::new();
+    let mut notifier = systemd::Notifier::from_env();
+    let mut sigint = signals::interrupt()?;
+    let mut sigterm = signals::terminate()?;
+    let mut ticker = tokio::time::interval(stale_after.min(Duration::from_secs(1)));
+    loop {
+        tokio::select! {
+            Some((target, result)) = received.recv() => match result {
+                Ok(stats) => {
+                    if failing.remove(&target.name) {
+                        info!(host = %target.name, "{}", tr("远程主机已恢复"));
+                    }
+                    if json {
+                        println!("{}", serde_json::to_string(&stats)?);
+                    } else if !quiet {
+                        println!("{:<width$} {}", target.name, compact_line(&stats, &filter));
+                    }
+                    aggregator.record(target.name.clone(), target.address(), stats);
+                }
+                Err(error) => {
+                    if failing.insert(target.name.clone()) {
+                        warn!(host = %target.name, address = %target.address(), %error, "{}", tr("无法采集远程主机"));
+                    }
+                }
+            },
+            _ = ticker.tick() => {
+                aggregator.check();
+                notifier.collected();
+            }
+            _ = sigint.recv() => return Ok(()),
+            _ = sigterm.recv() => return Ok(()),
+        }
+    }
+}
+
+/// `check`：等待一个刷新间隔后采集一个样本，逐项与阈值比较
+pub async fn check_thresholds(matches: &ArgMatches, sub: &ArgMatches) -> Result<ExitCode, Error> {
+    let thresholds: Vec<(String, f64)> = sub
+        .get_many::<String>("max")
+        .unwrap_or_default()
+        .filter_map(|t| check::parse_threshold(t))
+        .collect();
+
+    let mut monitor = build_monitor(matches).await.map_err(Error::config)?;
+    tokio::time::sleep(monitor.interval()).await;
+    let stats = monitor.collect().await;
+
+    let checks = check::evaluate(&stats, &thresholds);
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    for check in &checks {
+        let status = if check.ok() { tr("正常") } else { tr("超出阈值") };
+        println!("{:<width$} {:>6.1}% / {:.1}%  {}", check.name, check.value, check.max, status);
+    }
+    if checks.iter().all(check::Check::ok) {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::from(EXIT_FAILED))
+    }
+}
+
+/// `export`：按顺序输出缓存目录中的样本，批量发送的请求体拆成单个样本
+pub fn export(matches: &ArgMatches) -> Result<(), Error> {
+    let dir = matches
+        .get_one::<String>("spool-dir")
+        .ok_or_else(|| Error::config(tr("导出需要指定 --spool-dir")))?;
+    let spool = Spool::open(Path::new(dir), u64::MAX).map_err(Error::config)?.cipher(spool_cipher(matches));
+
+    for body in spool.bodies()? {
+        let samples = if body.starts_with('[') {
+            serde_json::from_str::<Vec<SystemStats>>(&body)?
+        } else {
+            vec![serde_json::from_str::<SystemStats>(&body)?]
+        };
+        for sample in samples {
+            println!("{}", serde_json::to_string(&sample)?);
+        }
+    }
+    Ok(())
+}
+
+/// `bench`：逐个采集项连续运行N次，再按刷新间隔完整运行一段时间，用CPU时间除以经过的时间得到开销
+pub async fn run_bench(matches: &ArgMatches, sub: &ArgMatches) -> Result<(), Error> {
+    let iterations = *sub.get_one::<u32>("iterations").unwrap();
+    let window = seconds(sub, "window").unwrap_or(Duration::from_secs(10));
+    let mut monitor = build_monitor(matches).await.map_err(Error::config)?;
+    let collectors = monitor.collectors().clone();
+    let timeout = collect_timeout(matches, monitor.tick());
+    // 逐个测量时每次都重新采集，并等待采集完成
+    monitor.set_intervals(Duration::ZERO, BTreeMap::new());
+    monitor.set_collect_timeout(None);
+
+    println!(
+        "{:<6} {:>8} {:>12} {:>12} {:>12} {:>14}",
+        tr("采集项"), tr("次数"), tr("平均耗时"), tr("最长耗时"), tr("分配次数"), tr("分配字节")
+    );
+    for name in monitor.collector_names().into_iter().filter(|name| collectors.enabled(name)) {
+        monitor.set_collectors(Collectors::new(&[name.to_string()], &[]));
+        let mut measurement = bench::Measurement::default();
+        for _ in 0..iterations {
+            let started = Instant::now();
+            std::hint::black_box(monitor.collect().await);
+            let elapsed = started.elapsed();
+            // 只统计采集项自己的线程中的分配，不包括CPU采样线程和异步运行时
+            let (allocations, bytes) = monitor.allocations(name);
+            measurement.record(elapsed, allocations, bytes);
+        }
+        let (allocations, bytes) = match bench::COUNTING {
+            true => (format!("{:.1}", measurement.allocations_per_run()), display::format_bytes(measurement.bytes_per_run())),
+            false => ("-".to_string(), "-".to_string()),
+        };
+        println!(
+            "{:<6} {:>8} {:>12} {:>12} {:>12} {:>14}",
+            name,
+            measurement.runs,
+            format!("{:.3} ms", measurement.mean().as_secs_f64() * 1000.0),
+            format!("{:.3} ms", measurement.max.as_secs_f64() * 1000.0),
+            allocations,
+            bytes,
+        );
+    }
+
+    monitor.set_collectors(collectors);
+    monitor.set_intervals(interval(matches), parse_collector_intervals(matches));
+    monitor.set_collect_timeout(Some(timeout));
+    let (started, cpu_started) = (Instant::now(), bench::cpu_time());
+    while started.elapsed() < window {
+        let stats = monitor.collect().await;
+        std::hint::black_box(serde_json::to_string(&stats)?);
+        tokio::time::sleep(monitor.tick()).await;
+    }
+    let cpu = bench::cpu_time().saturating_sub(cpu_started);
+    let elapsed = started.elapsed();
+    println!(
+        "{} {} {}: {:.3}% ({} {:.1} ms / {})",
+        tr("刷新间隔"),
+        humantime::format_duration(monitor.interval()),
+        tr("CPU开销"),
+        cpu.as_secs_f64() / elapsed.as_secs_f64() * 100.0,
+        tr("CPU时间"),
+        cpu.as_secs_f64() * 1000.0,
+        humantime::format_duration(Duration::from_secs(elapsed.as_secs())),
+    );
+    Ok(())
+}
+
+/// `diff`：文件中有多个样本时，比较各自的最后一个
+pub fn diff_snapshots(sub: &ArgMatches) -> Result<ExitCode, Error> {
+    let load = |id: &str| -> Result<SystemStats, Error> {
+        let mut samples = replay::load(Path::new(sub.get_one::<String>(id).unwrap())).map_err(Error::config)?;
+        Ok(samples.pop().unwrap())
+    };
+    let (a, b) = (load("a")?, load("b")?);
+    let thresholds = diff::Thresholds {
+        percent: *sub.get_one::<f64>("min-percent").unwrap(),
+        count: *sub.get_one::<usize>("min-count").unwrap(),
+    };
+
+    let lines = diff::diff(&a, &b, &thresholds);
+    for line in &lines {
+        println!("{}", line);
+    }
+    if lines.is_empty() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::from(EXIT_FAILED))
+    }
+}
+
+/// `vacuum`：按分级保留压缩录制文件
+pub fn vacuum(sub: &ArgMatches) -> Result<(), Error> {
+    let path = Path::new(sub.get_one::<String>("file").unwrap());
+    let size = || std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let bytes = size();
+    let (before, after) = compact::vacuum(path, &compaction_policy(sub))?;
+    println!("{}: {} -> {} {}, {} -> {} {}", path.display(), before, after, tr("个样本"), bytes, size(), tr("字节"));
+    Ok(())
+}
+
+/// `config check`：完成所有不需要网络的校验，并以TOML格式输出有效配置
+pub fn check_config(command: &Command, matches: &ArgMatches) -> Result<(), Error> {
+    build_transport(matches)?;
+    build_notifiers(matches, "localhost")?;
+    parse_tags(matches)?;
+    parse_net_filter(matches)?;
+    let mut system = System::new();
+    let process_filter = parse_process_filter(matches)?;
+    if !process_filter.users.is_empty() {
+        system.refresh_users_list();
+        for user in &process_filter.users {
+            if user.parse::<Uid>().is_err() && !system.users().iter().any(|u| u.name() == user) {
+                return Err(Error::config(format!("{}: {}", tr("未知的用户"), user)));
+            }
+        }
+    }
+
+    print!("{}", config::effective(command, matches)?);
+    eprintln!("{}", tr("配置检查通过"));
+    Ok(())
+}
@@ -0,0 +1,165 @@
+// src/compact.rs
+use crate::aggregate::{self, Aggregator, Traffic};
+use crate::alert::Metric;
+use crate::error::Error;
+use crate::i18n::tr;
+use crate::replay;
+use crate::SystemStats;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 录制时压缩文件的间隔
+const COMPACT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 录制文件的分级保留：最近 `raw` 内的样本原样保留，`minute` 内的按分钟汇总，更早的按小时汇总，超过 `retention` 的删除
+#[derive(Debug, Clone, Copy)]
+pub struct Policy {
+    pub raw: Duration,
+    pub minute: Duration,
+    /// 为零时不删除
+    pub retention: Duration,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self { raw: Duration::from_secs(86400), minute: Duration::from_secs(30 * 86400), retention: Duration::ZERO }
+    }
+}
+
+impl Policy {
+    /// 样本所在的汇总窗口的长度，原样保留时为零，应当删除时为 None
+    fn window(&self, age: Duration) -> Option<Duration> {
+        if !self.retention.is_zero() && age >= self.retention {
+            None
+        } else if age < self.raw {
+            Some(Duration::ZERO)
+        } else if age < self.minute {
+            Some(Duration::from_secs(60))
+        } else {
+            Some(Duration::from_secs(3600))
+        }
+    }
+}
+
+/// 按 `policy` 压缩按时间排列的样本：同一个窗口(按整分钟或整小时对齐)中的样本用与 `--aggregate` 相同的方法合成一个，
+/// 已经汇总过的样本再次汇总时样本数和字节数累加，窗口中只有一个样本时原样保留，所以重复压缩结果不变
+pub fn compact(samples: Vec<SystemStats>, now: DateTime<Utc>, policy: &Policy) -> Vec<SystemStats> {
+    let metrics: Vec<(String, Metric)> = aggregate::DEFAULT_METRICS
+        .iter()
+        .filter_map(|name| Some((name.to_string(), Metric::parse(name)?)))
+        .collect();
+    let mut compacted = Vec::with_capacity(samples.len());
+    let mut bucket: Option<(i64, Vec<SystemStats>)> = None;
+    for stats in samples {
+        let age = (now - stats.timestamp).to_std().unwrap_or_default();
+        let Some(window) = policy.window(age) else { continue };
+        let key = match window.as_secs() as i64 {
+            0 => None,
+            secs => Some(stats.timestamp.timestamp().div_euclid(secs) * secs),
+        };
+        if bucket.as_ref().map(|(start, _)| Some(*start)) != Some(key) {
+            compacted.extend(bucket.take().and_then(|(_, pending)| merge(pending, &metrics)));
+        }
+        match key {
+            Some(start) => bucket.get_or_insert_with(|| (start, Vec::new())).1.push(stats),
+            None => compacted.push(stats),
+        }
+    }
+    compacted.extend(bucket.and_then(|(_, pending)| merge(pending, &metrics)));
+    compacted
+}
+
+/// 把一个窗口中的样本合成一个
+fn merge(mut samples: Vec<SystemStats>, metrics: &[(String, Metric)]) -> Option<SystemStats> {
+    if samples.len() < 2 {
+        return samples.pop();
+    }
+    let since = samples.iter().map(|s| s.aggregate.as_ref().map_or(s.timestamp, |a| a.since)).min()?;
+    let count = samples.iter().map(|s| s.aggregate.as_ref().map_or(1, |a| a.samples)).sum();
+    let mut bytes: HashMap<String, Traffic> = HashMap::new();
+    for traffic in samples.iter().filter_map(|s| s.aggregate.as_ref()).flat_map(|a| &a.bytes) {
+        let total = bytes.entry(traffic.0.clone()).or_default();
+        total.rx += traffic.1.rx;
+        total.tx += traffic.1.tx;
+    }
+
+    let mut aggregator = Aggregator::new(Duration::MAX, metrics.to_vec());
+    for stats in samples {
+        aggregator.push(stats);
+    }
+    let mut stats = aggregator.take()?;
+    if let Some(aggregate) = &mut stats.aggregate {
+        aggregate.since = since;
+        aggregate.samples = count;
+        if !bytes.is_empty() {
+            aggregate.bytes = bytes;
+        }
+    }
+    Some(stats)
+}
+
+/// `vacuum`：压缩录制文件，返回压缩前后的样本数
+pub fn vacuum(path: &Path, policy: &Policy) -> Result<(usize, usize), Error> {
+    let samples = replay::load(path)?;
+    let before = samples.len();
+    let compacted = compact(samples, Utc::now(), policy);
+    let after = compacted.len();
+    rewrite(path, &compacted).map_err(|e| format!("{} {}: {}", tr("无法写入录制文件"), path.display(), e))?;
+    Ok((before, after))
+}
+
+/// 写到临时文件再替换
+fn rewrite(path: &Path, samples: &[SystemStats]) -> std::io::Result<()> {
+    let temp = path.with_extension("tmp");
+    let mut file = std::io::BufWriter::new(File::create(&temp)?);
+    for stats in samples {
+        writeln!(file, "{}", serde_json::to_string(stats)?)?;
+    }
+    file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(&temp, path)
+}
+
+/// `record` 写入的文件：每个样本追加一行，按 `policy` 每小时压缩一次(第一次在开始录制时)
+pub struct Recording {
+    path: PathBuf,
+    file: File,
+    policy: Option<Policy>,
+    compacted: Option<Instant>,
+}
+
+impl Recording {
+    /// `policy` 为 None 时不压缩
+    pub fn open(path: &Path, policy: Option<Policy>) -> Result<Self, String> {
+        Ok(Self { path: path.to_path_buf(), file: open(path)?, policy, compacted: None })
+    }
+
+    pub fn write(&mut self, stats: &SystemStats) -> Result<(), Error> {
+        writeln!(self.file, "{}", serde_json::to_string(stats)?)?;
+        let Some(policy) = &self.policy else { return Ok(()) };
+        if self.compacted.is_none_or(|compacted| compacted.elapsed() >= COMPACT_INTERVAL) {
+            self.compacted = Some(Instant::now());
+            match vacuum(&self.path, policy) {
+                Ok((before, after)) if before != after => {
+                    tracing::info!(path = %self.path.display(), before, after, "{}", tr("录制文件已压缩"));
+                }
+                Ok(_) => {}
+                Err(error) => tracing::warn!(path = %self.path.display(), %error, "{}", tr("无法压缩录制文件")),
+            }
+            // 文件已被替换，之后追加到新文件
+            self.file = open(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+fn open(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("{} {}: {}", tr("无法打开文件"), path.display(), e))
+}
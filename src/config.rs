@@ -0,0 +1,271 @@
+// src/config.rs
+use crate::error::Error;
+use crate::i18n::tr;
+use clap::builder::BoolishValueParser;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde_json::Value;
+use std::ffi::OsString;
+use std::path::Path;
+
+/// 解析命令行，并用 `--config` 指定的配置文件补充命令行和环境变量中没有设置的选项
+///
+/// 每个选项都可以用 `SYSMON_<选项名>` 环境变量设置(例如 `SYSMON_BATCH_SIZE`)，优先级低于命令行、高于配置文件。
+/// 配置文件的键与长选项同名(`-` 也可以写作 `_`)，另外 `tags`、`headers` 和 `collector_intervals` 表
+/// 分别对应 `--tag`、`--header` 和 `--collector-interval`。
+/// `--profile` 选择的档案补充以上都没有设置的采集项和刷新间隔。
+pub fn matches(command: &Command) -> Result<ArgMatches, Error> {
+    merge(command).map_err(|e| match e {
+        Invalid::Args(e) => e.exit(),
+        Invalid::Config(e) => e,
+    })
+}
+
+/// 重新读取配置文件，出错时返回错误而不是退出
+pub fn reload(command: &Command) -> Result<ArgMatches, Error> {
+    merge(command).map_err(|e| match e {
+        Invalid::Args(e) => Error::config(e),
+        Invalid::Config(e) => e,
+    })
+}
+
+/// 命令行的错误由clap输出帮助并退出，配置文件的错误照常返回
+enum Invalid {
+    Args(clap::Error),
+    Config(Error),
+}
+
+impl From<clap::Error> for Invalid {
+    fn from(error: clap::Error) -> Self {
+        Invalid::Args(error)
+    }
+}
+
+impl From<Error> for Invalid {
+    fn from(error: Error) -> Self {
+        Invalid::Config(error)
+    }
+}
+
+/// 采集配置档案：(名称, 采集项, 刷新间隔)，档案中的值优先级最低
+pub const PROFILES: [(&str, &str, &str); 3] = [
+    ("minimal", "cpu,mem", "10"),
+    ("standard", "cpu,mem,disk,net", "5"),
+    ("full", "cpu,mem,disk,net,proc,temp", "1"),
+];
+
+fn merge(command: &Command) -> Result<ArgMatches, Invalid> {
+    let command = &with_env(command.clone());
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let matches = command.clone().try_get_matches_from(&args)?;
+
+    let mut values = Vec::new();
+    if let Some(path) = matches.get_one::<String>("config") {
+        for (key, value) in load(Path::new(path))? {
+            values.push(match (key.replace('_', "-").as_str(), value) {
+                ("tags", Value::Object(tags)) => (
+                    "tag".to_string(),
+                    Value::Array(tags.into_iter().map(|(k, v)| Value::String(format!("{}={}", k, text(&v)))).collect()),
+                ),
+                ("headers", Value::Object(headers)) => (
+                    "header".to_string(),
+                    Value::Array(headers.into_iter().map(|(k, v)| Value::String(format!("{}: {}", k, text(&v)))).collect()),
+                ),
+                ("collector-intervals", Value::Object(intervals)) => (
+                    "collector-interval".to_string(),
+                    Value::Array(intervals.into_iter().map(|(k, v)| Value::String(format!("{}={}", k, text(&v)))).collect()),
+                ),
+                ("alert-mail-to", Value::Object(rules)) => (
+                    "alert-mail-to".to_string(),
+                    Value::Array(rules.into_iter().map(|(k, v)| Value::String(format!("{}={}", k, list(&v)))).collect()),
+                ),
+                ("alert-exec", Value::Object(commands)) => (
+                    "alert-exec".to_string(),
+                    Value::Array(commands.into_iter().map(|(k, v)| Value::String(format!("{}={}", k, text(&v)))).collect()),
+                ),
+                ("alerts", Value::Object(rules)) => (
+                    "alert".to_string(),
+                    Value::Array(rules.into_iter().map(|(k, v)| Value::String(format!("{}={}", k, text(&v)))).collect()),
+                ),
+                ("derived", Value::Object(metrics)) => (
+                    "derive".to_string(),
+                    Value::Array(metrics.into_iter().map(|(k, v)| Value::String(format!("{}={}", k, text(&v)))).collect()),
+                ),
+                (key, value) => (key.to_string(), value),
+            });
+        }
+    }
+
+    let profile = matches.get_one::<String>("profile").cloned().or_else(|| {
+        values.iter().find(|(key, _)| key == "profile").map(|(_, value)| text(value))
+    });
+    if let Some((_, collectors, interval)) = PROFILES.iter().find(|(name, _, _)| Some(*name) == profile.as_deref()) {
+        for (key, value) in [("collectors", collectors), ("interval", interval)] {
+            if !values.iter().any(|(k, _)| k == key) {
+                values.push((key.to_string(), Value::String(value.to_string())));
+            }
+        }
+    }
+    if values.is_empty() {
+        return Ok(matches);
+    }
+
+    let mut merged = args[..1].to_vec();
+    for (key, value) in values {
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == key.as_str() && arg.get_long().is_some() && key != "config")
+            .ok_or_else(|| Error::config(format!("{}: {}", tr("未知的配置项"), key)))?;
+        // 命令行或环境变量已经设置了该选项(或与之冲突的选项)时忽略文件中的值
+        let overridden = command
+            .get_arguments()
+            .filter(|other| {
+                other.get_id() == arg.get_id()
+                    || command.get_arg_conflicts_with(arg).contains(other)
+                    || command.get_arg_conflicts_with(other).contains(&arg)
+            })
+            .any(|arg| matches!(
+                matches.value_source(arg.get_id().as_str()),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            ));
+        if overridden {
+            continue;
+        }
+        merged.extend(to_args(arg, &value).into_iter().map(OsString::from));
+    }
+    merged.extend(args[1..].iter().cloned());
+
+    Ok(command.clone().try_get_matches_from(merged)?)
+}
+
+/// 为还没有环境变量的选项加上 `SYSMON_` 前缀的环境变量
+fn with_env(command: Command) -> Command {
+    command.mut_args(|arg| {
+        // 敏感选项的环境变量值不显示在帮助中
+        let secret = SECRETS.contains(&arg.get_id().as_str()) || arg.is_hide_env_values_set();
+        let arg = arg.hide_env_values(secret);
+        if arg.get_env().is_some() || arg.get_long().is_none() || arg.get_id() == "version" {
+            return arg;
+        }
+        let name = format!("SYSMON_{}", arg.get_id().as_str().to_uppercase().replace('-', "_"));
+        // 开关类选项的环境变量接受 1/0、yes/no、on/off 等写法
+        let arg = match arg.get_action() {
+            ArgAction::SetTrue => arg.value_parser(BoolishValueParser::new()),
+            _ => arg,
+        };
+        arg.env(name)
+    })
+}
+
+/// 按扩展名读取TOML或YAML配置文件
+fn load(path: &Path) -> Result<serde_json::Map<String, Value>, Error> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| Error::config(format!("{} {}: {}", tr("无法读取配置文件"), path.display(), e)))?;
+    let value: Value = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml" | "yml") => serde_yaml::from_str(&text)
+            .map_err(|e| Error::config(format!("{} {}: {}", tr("配置文件格式错误"), path.display(), e)))?,
+        _ => toml::from_str(&text)
+            .map_err(|e| Error::config(format!("{} {}: {}", tr("配置文件格式错误"), path.display(), e)))?,
+    };
+
+    match value {
+        Value::Object(map) => Ok(map),
+        Value::Null => Ok(serde_json::Map::new()),
+        _ => Err(Error::config(format!("{} {}", tr("配置文件格式错误"), path.display()))),
+    }
+}
+
+/// 把一个配置值转换为等价的命令行参数
+fn to_args(arg: &Arg, value: &Value) -> Vec<String> {
+    let long = arg.get_long().unwrap_or_default();
+    match (arg.get_action(), value) {
+        (ArgAction::Count, Value::Number(n)) => vec![format!("--{}", long); n.as_u64().unwrap_or(0) as usize],
+        (_, Value::Bool(true)) => vec![format!("--{}", long)],
+        (_, Value::Bool(false)) => Vec::new(),
+        (ArgAction::Append, Value::Array(items)) => {
+            items.iter().map(|item| format!("--{}={}", long, text(item))).collect()
+        }
+        (_, Value::Array(items)) => {
+            let items: Vec<String> = items.iter().map(text).collect();
+            vec![format!("--{}={}", long, items.join(","))]
+        }
+        (_, value) => vec![format!("--{}={}", long, text(value))],
+    }
+}
+
+/// 字符串或字符串数组，数组用逗号连接
+fn list(value: &Value) -> String {
+    match value {
+        Value::Array(items) => items.iter().map(text).collect::<Vec<_>>().join(","),
+        other => text(other),
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// 输出时隐藏的敏感选项，`header` 和 `oauth-param` 保留名称、只隐藏值
+const SECRETS: [&str; 11] = [
+    "token",
+    "basic-auth",
+    "oauth-client-secret",
+    "oauth-param",
+    "header",
+    "proxy",
+    "signing-key",
+    "spool-key",
+    "alert-webhook",
+    "smtp-password",
+    "crash-webhook",
+];
+
+/// 隐藏一个敏感选项的值
+fn mask(id: &str, value: toml::Value) -> toml::Value {
+    let hidden = |text: &str| match id {
+        "header" => text.split_once(':').map(|(name, _)| format!("{}: ********", name)),
+        "oauth-param" => text.split_once('=').map(|(name, _)| format!("{}=********", name)),
+        _ => None,
+    }
+    .unwrap_or_else(|| "********".to_string());
+    match value {
+        toml::Value::Array(items) => toml::Value::Array(
+            items.iter().map(|item| toml::Value::String(hidden(item.as_str().unwrap_or_default()))).collect(),
+        ),
+        value => toml::Value::String(hidden(value.as_str().unwrap_or_default())),
+    }
+}
+
+/// 把合并后的选项输出为TOML，可以直接作为配置文件使用
+pub fn effective(command: &Command, matches: &ArgMatches) -> Result<String, Error> {
+    let mut table = toml::Table::new();
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        if arg.get_long().is_none() || matches!(id, "config" | "help" | "version") {
+            continue;
+        }
+        let value = match arg.get_action() {
+            ArgAction::SetTrue => toml::Value::Boolean(matches.get_flag(id)),
+            ArgAction::Count => toml::Value::Integer(matches.get_count(id).into()),
+            action => match matches.get_raw(id) {
+                Some(values) => {
+                    let values: Vec<toml::Value> = values
+                        .map(|v| toml::Value::String(v.to_string_lossy().into_owned()))
+                        .collect();
+                    if matches!(action, ArgAction::Append) || arg.get_value_delimiter().is_some() {
+                        toml::Value::Array(values)
+                    } else {
+                        values.into_iter().next().unwrap_or(toml::Value::String(String::new()))
+                    }
+                }
+                None => continue,
+            },
+        };
+        let value = if SECRETS.contains(&id) { mask(id, value) } else { value };
+        table.insert(id.to_string(), value);
+    }
+    toml::to_string(&table).map_err(Error::runtime)
+}
@@ -0,0 +1,148 @@
+// src/crash.rs
+use crate::error::EXIT_PANIC;
+use crate::i18n::tr;
+use crate::SCHEMA_VERSION;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+/// 崩溃报告最多等待webhook的时间，之后不再等待直接退出
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 最近一次采集完成的时间(毫秒)，还没有采集过时为0
+static LAST_SAMPLE: AtomicI64 = AtomicI64::new(0);
+
+thread_local! {
+    /// 当前线程正在 [`supervise`] 中运行，panic后可以恢复
+    static SUPERVISED: Cell<bool> = const { Cell::new(false) };
+    /// 受监督的代码panic时由钩子记下，交给 [`supervise`] 返回
+    static CAUGHT: RefCell<Option<Panic>> = const { RefCell::new(None) };
+}
+
+/// 受监督的代码中的一次panic
+#[derive(Debug, Clone)]
+pub struct Panic {
+    pub message: String,
+    /// 源文件和行号，没有安装钩子时没有
+    pub location: Option<String>,
+}
+
+/// 运行 `f`，panic时返回 [`Panic`] 而不是结束线程；钩子不输出到标准错误，由调用者记录日志并恢复。
+/// 用 `panic = "abort"` 编译(`embedded` 配置)时panic仍然结束程序
+pub fn supervise<R>(f: impl FnOnce() -> R) -> Result<R, Panic> {
+    let outer = SUPERVISED.replace(true);
+    let result = std::panic::catch_unwind(AssertUnwindSafe(f));
+    SUPERVISED.set(outer);
+    result.map_err(|payload| {
+        CAUGHT.take().unwrap_or_else(|| Panic { message: message(payload.as_ref()), location: None })
+    })
+}
+
+/// 当前线程是否在 [`supervise`] 中运行，终端界面的钩子据此决定是否恢复终端
+pub fn supervised() -> bool {
+    SUPERVISED.get()
+}
+
+/// 记下一次采集完成的时间，崩溃报告中给出
+pub fn sampled(timestamp: DateTime<Utc>) {
+    LAST_SAMPLE.store(timestamp.timestamp_millis(), Ordering::Relaxed);
+}
+
+fn last_sample() -> Option<DateTime<Utc>> {
+    match LAST_SAMPLE.load(Ordering::Relaxed) {
+        0 => None,
+        millis => DateTime::from_timestamp_millis(millis),
+    }
+}
+
+/// 程序崩溃时记录到日志、发送到 `--crash-webhook` 的报告
+#[derive(Debug, Serialize)]
+pub struct CrashReport {
+    pub version: u32,
+    pub event: &'static str,
+    pub timestamp: DateTime<Utc>,
+    pub host: String,
+    /// panic的线程名
+    pub thread: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// 最近一次采集完成的时间，接收端据此判断停止上报的主机是崩溃还是失联
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_sample: Option<DateTime<Utc>>,
+}
+
+/// 安装panic钩子：受监督的代码(采集项)panic时只记下信息，由采集线程重新初始化采集项；
+/// 其他地方panic时记录崩溃报告，设置了 `webhook` 时发送，原来的钩子输出信息后以退出状态101结束程序，
+/// 而不是留下一个不再采集或发送的进程。终端界面在这之后安装自己的钩子，先恢复终端
+pub fn install(host: String, webhook: Option<(reqwest::Client, String)>) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let location = info.location().map(|location| format!("{}:{}", location.file(), location.line()));
+        if supervised() {
+            CAUGHT.set(Some(Panic { message: message(info.payload()), location }));
+            return;
+        }
+        let report = CrashReport {
+            version: SCHEMA_VERSION,
+            event: "crash",
+            timestamp: Utc::now(),
+            host: host.clone(),
+            thread: std::thread::current().name().unwrap_or("unnamed").to_string(),
+            message: message(info.payload()),
+            location,
+            last_sample: last_sample(),
+        };
+        tracing::error!(
+            thread = %report.thread,
+            error = %report.message,
+            location = report.location.as_deref().unwrap_or(""),
+            last_sample = report.last_sample.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            "{}",
+            tr("程序崩溃")
+        );
+        if let Some((client, url)) = &webhook {
+            notify(client, url, &report);
+        }
+        previous(info);
+        std::process::exit(EXIT_PANIC.into());
+    }));
+}
+
+/// 在新线程中用单独的运行时发送，panic可能发生在tokio的工作线程中
+fn notify(client: &reqwest::Client, url: &str, report: &CrashReport) {
+    let Ok(body) = serde_json::to_string(report) else { return };
+    let (client, target) = (client.clone(), url.to_string());
+    let (done, wait) = std::sync::mpsc::channel();
+    let spawned = std::thread::Builder::new().name("crash-webhook".to_string()).spawn(move || {
+        let Ok(runtime) = tokio::runtime::Builder::new_current_thread().enable_all().build() else { return };
+        let result = runtime.block_on(async {
+            client
+                .post(&target)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body)
+                .send()
+                .await?
+                .error_for_status()
+        });
+        let _ = done.send(result.map(|_| ()));
+    });
+    if spawned.is_err() {
+        return;
+    }
+    match wait.recv_timeout(WEBHOOK_TIMEOUT) {
+        Ok(Ok(())) => tracing::info!(%url, "{}", tr("崩溃报告已发送")),
+        Ok(Err(error)) => tracing::warn!(%url, %error, "{}", tr("发送崩溃报告失败")),
+        Err(_) => tracing::warn!(%url, "{}", tr("发送崩溃报告超时")),
+    }
+}
+
+fn message(payload: &(dyn std::any::Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload.downcast_ref::<String>().cloned().unwrap_or_else(|| "Box<dyn Any>".to_string()),
+    }
+}
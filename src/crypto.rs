@@ -0,0 +1,74 @@
+// src/crypto.rs
+use base64::Engine;
+use rand::RngCore;
+use ring::aead::{self, Aad, LessSafeKey, Nonce, CHACHA20_POLY1305, NONCE_LEN};
+use ring::hkdf::{Salt, HKDF_SHA256};
+use ring::hmac::{self, HMAC_SHA256};
+use subtle::ConstantTimeEq;
+
+/// 请求头，请求体的HMAC-SHA256签名：`sha256=<十六进制>`
+pub const SIGNATURE_HEADER: &str = "X-Sysmon-Signature";
+
+/// 比较时间不取决于第一个不同的字节的位置
+pub fn constant_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 请求体的签名，作为 `X-Sysmon-Signature` 请求头的值
+pub fn sign(key: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(HMAC_SHA256, key.as_bytes());
+    format!("sha256={}", hex(hmac::sign(&key, body).as_ref()))
+}
+
+/// 以 `key` 为密钥由 `message` 派生的128位ID，十六进制
+pub fn derive_id(key: &[u8], message: &[u8]) -> String {
+    let key = hmac::Key::new(HMAC_SHA256, key);
+    hex(&hmac::sign(&key, message).as_ref()[..16])
+}
+
+/// 校验 `X-Sysmon-Signature` 请求头
+pub fn verify(key: &str, body: &[u8], signature: &str) -> bool {
+    constant_eq(sign(key, body).as_bytes(), signature.trim().as_bytes())
+}
+
+/// 磁盘缓存的加密：ChaCha20-Poly1305，每行使用随机的96位随机数；密钥由配置的密钥经HKDF-SHA256派生
+pub struct Cipher {
+    key: LessSafeKey,
+}
+
+impl Cipher {
+    pub fn new(key: &str) -> Self {
+        let prk = Salt::new(HKDF_SHA256, b"sysmon spool").extract(key.as_bytes());
+        // 输出长度为32字节，远小于HKDF的上限，不会失败
+        let okm = prk.expand(&[b"encryption"], &CHACHA20_POLY1305).unwrap();
+        Self { key: LessSafeKey::new(okm.into()) }
+    }
+
+    /// 加密一行，结果为 随机数+密文+校验值 的base64编码，不含换行符
+    pub fn seal(&self, plaintext: &str) -> String {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let mut data = plaintext.as_bytes().to_vec();
+        // 只有明文超过256GiB时才会失败
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+            .unwrap();
+        let sealed = [&nonce[..], &data].concat();
+        base64::engine::general_purpose::STANDARD.encode(sealed)
+    }
+
+    /// 解密 [`Cipher::seal`] 的结果，密钥不对或内容被改动时没有结果
+    pub fn open(&self, line: &str) -> Option<String> {
+        let mut data = base64::engine::general_purpose::STANDARD.decode(line.trim()).ok()?;
+        if data.len() < NONCE_LEN + aead::MAX_TAG_LEN {
+            return None;
+        }
+        let nonce = Nonce::try_assume_unique_for_key(&data[..NONCE_LEN]).ok()?;
+        let plaintext = self.key.open_in_place(nonce, Aad::empty(), &mut data[NONCE_LEN..]).ok()?;
+        String::from_utf8(plaintext.to_vec()).ok()
+    }
+}
@@ -0,0 +1,90 @@
+// src/daemon.rs
+use system_monitor::error::Error;
+use system_monitor::i18n::tr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 转入后台运行：两次fork脱离终端和会话，标准输入输出重定向到 /dev/null
+///
+/// 必须在启动tokio运行时之前调用，fork之后只有调用线程会保留下来。
+#[cfg(unix)]
+pub fn daemonize() -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    fork_and_exit_parent()?;
+    if unsafe { libc::setsid() } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    fork_and_exit_parent()?;
+
+    let null = fs::OpenOptions::new().read(true).write(true).open("/dev/null")?;
+    for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        if unsafe { libc::dup2(null.as_raw_fd(), fd) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// 没有fork的平台上不能转入后台，需要作为服务运行
+#[cfg(not(unix))]
+pub fn daemonize() -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, tr("--daemon 只能在Unix平台使用")))
+}
+
+#[cfg(unix)]
+fn fork_and_exit_parent() -> io::Result<()> {
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(()),
+        _ => std::process::exit(0),
+    }
+}
+
+/// 保存进程号的PID文件，退出时删除
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// 文件中记录的进程仍在运行时返回错误，已经退出的进程留下的文件会被覆盖
+    pub fn check(path: &Path) -> Result<(), Error> {
+        let pid = fs::read_to_string(path).ok().and_then(|s| s.trim().parse::<u32>().ok());
+        match pid {
+            Some(pid) if pid > 0 && running(pid) => {
+                Err(format!("{} ({}: {})", tr("已经有实例在运行"), path.display(), pid).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn create(path: &Path) -> Result<Self, Error> {
+        Self::check(path)?;
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, format!("{}\n", std::process::id()))
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| format!("{} {}: {}", tr("无法写入PID文件"), path.display(), e))?;
+        Ok(Self { path: path.to_path_buf() })
+    }
+}
+
+/// 进程是否仍在运行
+#[cfg(unix)]
+fn running(pid: u32) -> bool {
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[cfg(not(unix))]
+fn running(pid: u32) -> bool {
+    use sysinfo::{PidExt, SystemExt};
+
+    sysinfo::System::new().refresh_process(sysinfo::Pid::from_u32(pid))
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,393 @@
+// src/delivery.rs
+use crate::breaker::{Breaker, SinkHealth, DEFAULT_COOLDOWN, DEFAULT_THRESHOLD};
+use crate::delta::Encoder;
+use crate::error::Error;
+use crate::filter::FieldFilter;
+use crate::i18n::tr;
+use crate::spool::{Spool, SpoolState};
+use crate::transport::Transport;
+use crate::SystemStats;
+use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderValue};
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// 请求头，标明请求体中样本的序号范围，便于接收端去重
+const SEQ_HEADER: &str = "X-Sysmon-Seq";
+
+pub enum Outcome {
+    /// 请求体已直接发送
+    Sent,
+    /// 请求体和之前缓存的数据均已发送
+    Drained,
+    /// 试运行，请求体只记录下来，没有发送
+    DryRun,
+    /// 断路器断开，没有连接端点；有磁盘缓存时请求体写入缓存
+    Skipped,
+}
+
+/// 试运行时请求体的去向
+pub enum DryRun {
+    /// 写到标准错误
+    Log,
+    /// 逐行追加到文件
+    File(File),
+    /// 不记录(终端界面占用屏幕时)
+    Discard,
+}
+
+impl DryRun {
+    fn write(&mut self, body: &str) -> std::io::Result<()> {
+        match self {
+            DryRun::Log => writeln!(std::io::stderr(), "{}", body),
+            DryRun::File(file) => writeln!(file, "{}", body),
+            DryRun::Discard => Ok(()),
+        }
+    }
+}
+
+/// 负责发送请求体：失败时写入磁盘缓存，并记录接收端确认的序号
+pub struct Delivery {
+    transport: Transport,
+    spool: Option<Spool>,
+    state: SpoolState,
+    jitter: Duration,
+    dry_run: Option<DryRun>,
+    breaker: Breaker,
+    delta: Option<Encoder>,
+}
+
+impl Delivery {
+    pub fn new(transport: Transport, spool: Option<Spool>) -> Self {
+        let state = spool.as_ref().map(Spool::load_state).unwrap_or_default();
+        Self {
+            transport,
+            spool,
+            state,
+            jitter: Duration::ZERO,
+            dry_run: None,
+            breaker: Breaker::new("endpoint", DEFAULT_THRESHOLD, DEFAULT_COOLDOWN),
+            delta: None,
+        }
+    }
+
+    /// 端点连续失败 `threshold` 次后暂停发送，每过 `cooldown` 探测一次
+    pub fn breaker(mut self, (threshold, cooldown): (u32, Duration)) -> Self {
+        self.breaker.configure(threshold, cooldown);
+        self
+    }
+
+    /// 每次发送前随机等待 0 到 `jitter` 的时间，避免大量主机同时发送
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// 试运行：不连接端点、不写缓存，请求体交给 `dry_run` 记录
+    pub fn dry_run(mut self, dry_run: Option<DryRun>) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// `--delta`：发送前把请求体中的样本编码为增量样本，插件和模板输出仍然收到完整的样本
+    pub fn delta(mut self, encoder: Option<Encoder>) -> Self {
+        self.delta = encoder;
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_some()
+    }
+
+    /// 重新加载配置后换用新的连接，缓存和确认序号保持不变；端点改变时断路器从头计数
+    pub fn reconfigure(&mut self, transport: Transport, jitter: Duration, (threshold, cooldown): (u32, Duration), endpoint_changed: bool) {
+        self.transport = transport;
+        self.jitter = jitter;
+        self.breaker.configure(threshold, cooldown);
+        if endpoint_changed {
+            self.breaker.reset();
+            // 新的端点没有之前的样本，从完整样本开始
+            if let Some(delta) = &mut self.delta {
+                delta.reset();
+            }
+        }
+    }
+
+    /// 端点的断路器状态
+    pub fn health(&self) -> SinkHealth {
+        self.breaker.health()
+    }
+
+    /// 上次运行时产生的最大样本序号，重启后从这里继续
+    pub fn last_seq(&self) -> u64 {
+        self.state.seq
+    }
+
+    /// 磁盘缓存中等待发送的字节数
+    pub fn spooled_bytes(&self) -> u64 {
+        self.spool.as_ref().and_then(|spool| spool.size().ok()).unwrap_or(0)
+    }
+
+    pub async fn send(&mut self, body: String) -> Result<Outcome, Error> {
+        let body = match &mut self.delta {
+            Some(delta) => delta.encode(&body)?,
+            None => body,
+        };
+        if let Some(dry_run) = &mut self.dry_run {
+            dry_run.write(&body)?;
+            return Ok(Outcome::DryRun);
+        }
+
+        if let Some((_, last)) = seq_range(&body) {
+            self.state.seq = self.state.seq.max(last);
+        }
+
+        // 断开时不等超时，直接写入缓存；没有缓存时样本被丢弃
+        if !self.breaker.allow() {
+            match &mut self.spool {
+                Some(spool) => {
+                    spool.push(&body)?;
+                    spool.save_state(&self.state)?;
+                }
+                None => self.lost(),
+            }
+            return Ok(Outcome::Skipped);
+        }
+
+        if !self.jitter.is_zero() {
+            let delay = rand::thread_rng().gen_range(Duration::ZERO..=self.jitter);
+            tokio::time::sleep(delay).await;
+        }
+
+        let acked = AtomicU64::new(self.state.acked);
+        let result = self.deliver(body, &acked).await;
+        self.state.acked = acked.into_inner();
+        match &result {
+            Ok(_) => self.breaker.success(),
+            Err(e) => self.breaker.failure(e.to_string()),
+        }
+        if result.is_err() && self.spool.is_none() {
+            self.lost();
+        }
+
+        if let Some(spool) = &self.spool {
+            spool.save_state(&self.state)?;
+        }
+
+        result
+    }
+
+    /// 请求体没有送达也没有写入缓存，接收端缺少这些样本，之后的增量无法还原，下一个样本发送完整样本
+    fn lost(&mut self) {
+        if let Some(delta) = &mut self.delta {
+            delta.reset();
+        }
+    }
+
+    /// 尝试发送磁盘缓存中剩余的样本，全部发送时返回 true
+    pub async fn flush(&mut self) -> Result<bool, Error> {
+        let transport = &self.transport;
+        let acked = AtomicU64::new(self.state.acked);
+        let drained = match &mut self.spool {
+            Some(spool) if self.dry_run.is_none() && !spool.is_empty() => {
+                if !self.breaker.allow() {
+                    return Ok(false);
+                }
+                let acked = &acked;
+                let drained = spool
+                    .drain(|line| async move { post(transport, line, acked).await.is_ok() })
+                    .await?;
+                if drained {
+                    self.breaker.success();
+                } else {
+                    self.breaker.failure(tr("端点不可达，数据已写入缓存").to_string());
+                }
+                drained
+            }
+            _ => true,
+        };
+        self.state.acked = acked.into_inner();
+
+        if let Some(spool) = &self.spool {
+            spool.save_state(&self.state)?;
+        }
+        Ok(drained)
+    }
+
+    /// 直接发送一个不带序号的事件，失败或断路器断开时不写缓存
+    pub async fn notify(&mut self, body: String) -> Result<(), Error> {
+        if let Some(dry_run) = &mut self.dry_run {
+            dry_run.write(&body)?;
+            return Ok(());
+        }
+        if !self.breaker.allow() {
+            return Err(tr("端点断路，暂停发送").into());
+        }
+        match self.transport.send(body, HeaderMap::new()).await {
+            Ok(_) => self.breaker.success(),
+            Err(e) => {
+                self.breaker.failure(e.to_string());
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn deliver(&mut self, body: String, acked: &AtomicU64) -> Result<Outcome, Error> {
+        let transport = &self.transport;
+
+        let spool = match &mut self.spool {
+            Some(spool) => spool,
+            None => {
+                post(transport, body, acked).await?;
+                return Ok(Outcome::Sent);
+            }
+        };
+
+        if spool.is_empty() {
+            match post(transport, body.clone(), acked).await {
+                Ok(()) => return Ok(Outcome::Sent),
+                Err(e) => {
+                    spool.push(&body)?;
+                    return Err(e);
+                }
+            }
+        }
+
+        spool.push(&body)?;
+        let drained = spool
+            .drain(|line| async move { post(transport, line, acked).await.is_ok() })
+            .await?;
+
+        if drained {
+            Ok(Outcome::Drained)
+        } else {
+            Err(tr("端点不可达，数据已写入缓存").into())
+        }
+    }
+}
+
+/// 发送一个请求体；接收端已确认过的样本直接跳过
+async fn post(transport: &Transport, body: String, acked: &AtomicU64) -> Result<(), Error> {
+    let mut headers = HeaderMap::new();
+    if let Some((first, last)) = seq_range(&body) {
+        if last <= acked.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        headers.insert(SEQ_HEADER, HeaderValue::from_str(&format!("{}-{}", first, last)).map_err(Error::runtime)?);
+    }
+
+    let response = transport.send(body, headers).await?;
+
+    if let Some(ack) = parse_ack(&response) {
+        acked.fetch_max(ack, Ordering::Relaxed);
+    }
+
+    Ok(())
+}
+
+/// 请求体中样本序号的范围，请求体可以是单个样本或样本数组
+fn seq_range(body: &str) -> Option<(u64, u64)> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let seqs: Vec<u64> = match &value {
+        serde_json::Value::Array(samples) => samples
+            .iter()
+            .filter_map(|sample| sample.get("seq")?.as_u64())
+            .collect(),
+        sample => sample.get("seq").and_then(|seq| seq.as_u64()).into_iter().collect(),
+    };
+    Some((*seqs.iter().min()?, *seqs.iter().max()?))
+}
+
+/// 解析接收端响应中的 `{"ack": N}`，表示序号不大于N的样本均已保存
+fn parse_ack(response: &str) -> Option<u64> {
+    let value: serde_json::Value = serde_json::from_str(response).ok()?;
+    value.get("ack")?.as_u64()
+}
+
+/// 累积多个样本，以JSON数组的形式在一次请求中发送
+pub struct Batch {
+    samples: Vec<SystemStats>,
+    size: usize,
+    interval: Option<Duration>,
+    started: Instant,
+    /// 请求体中保留的字段
+    fields: FieldFilter,
+}
+
+impl Batch {
+    pub fn new(size: usize, interval: Option<Duration>) -> Self {
+        Self {
+            samples: Vec::new(),
+            size: size.max(1),
+            interval,
+            started: Instant::now(),
+            fields: FieldFilter::default(),
+        }
+    }
+
+    /// 按 `--fields` 裁剪请求体中的样本，不影响显示和本地输出
+    pub fn fields(mut self, fields: FieldFilter) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn set_fields(&mut self, fields: FieldFilter) {
+        self.fields = fields;
+    }
+
+    /// 重新加载配置时更新批量参数，已缓存的样本保留
+    pub fn configure(&mut self, size: usize, interval: Option<Duration>) {
+        self.size = size.max(1);
+        self.interval = interval;
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.size > 1 || self.interval.is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn push(&mut self, stats: SystemStats) {
+        if self.samples.is_empty() {
+            self.started = Instant::now();
+        }
+        self.samples.push(stats);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        if self.samples.len() >= self.size {
+            return true;
+        }
+        match self.interval {
+            Some(interval) => !self.samples.is_empty() && self.started.elapsed() >= interval,
+            None => false,
+        }
+    }
+
+    pub fn take_body(&mut self) -> serde_json::Result<String> {
+        let samples = std::mem::take(&mut self.samples);
+        if self.fields.is_empty() {
+            return self.body(&samples);
+        }
+        let mut samples = samples.iter().map(serde_json::to_value).collect::<serde_json::Result<Vec<_>>>()?;
+        samples.iter_mut().for_each(|sample| self.fields.apply(sample));
+        self.body(&samples)
+    }
+
+    fn body<T: serde::Serialize>(&self, samples: &[T]) -> serde_json::Result<String> {
+        if self.enabled() {
+            serde_json::to_string(samples)
+        } else {
+            serde_json::to_string(&samples[0])
+        }
+    }
+}
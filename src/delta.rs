@@ -0,0 +1,356 @@
+// src/delta.rs
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// 增量样本中总是保留的字段：接收端据此排序、去重、确认和区分主机
+const REQUIRED_FIELDS: [&str; 5] = ["version", "seq", "timestamp", "instance_id", "tags"];
+
+/// 样本中的增量元数据：`{"base": 完整样本的序号}` 表示完整样本，
+/// 增量样本还有 `"prev"`，是前一个样本的序号，接收端据此发现缺失的样本
+const DELTA_FIELD: &str = "delta";
+
+/// `--delta`：每隔 `full_every` 发送一次完整样本，之间只发送变化超过 `epsilon`(相对于接收端已知的值)的字段。
+/// 增量样本是对前一个样本的JSON Merge Patch(RFC 7386)：只有变化的字段，消失的字段为 null，数组有一个元素变化时整个发送
+pub struct Encoder {
+    full_every: Duration,
+    epsilon: f64,
+    /// 接收端还原出的上一个样本，变化不超过 `epsilon` 的数值保留旧值
+    state: Option<Value>,
+    base: u64,
+    prev: u64,
+    full_at: Instant,
+}
+
+impl Encoder {
+    pub fn new(full_every: Duration, epsilon: f64) -> Self {
+        Self { full_every, epsilon: epsilon.max(0.0), state: None, base: 0, prev: 0, full_at: Instant::now() }
+    }
+
+    /// 下一个样本发送完整样本，端点变化或样本未能送达时调用
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+
+    /// 把请求体(单个样本或样本数组)中的样本依次编码
+    pub fn encode(&mut self, body: &str) -> serde_json::Result<String> {
+        match serde_json::from_str(body)? {
+            Value::Array(samples) => {
+                serde_json::to_string(&samples.into_iter().map(|sample| self.encode_sample(sample)).collect::<Vec<_>>())
+            }
+            sample => serde_json::to_string(&self.encode_sample(sample)),
+        }
+    }
+
+    fn encode_sample(&mut self, mut sample: Value) -> Value {
+        let seq = sample.get("seq").and_then(Value::as_u64).unwrap_or(0);
+        let state = match &mut self.state {
+            Some(state) if self.full_at.elapsed() < self.full_every => state,
+            _ => {
+                self.state = Some(sample.clone());
+                self.full_at = Instant::now();
+                (self.base, self.prev) = (seq, seq);
+                if let Value::Object(fields) = &mut sample {
+                    fields.insert(DELTA_FIELD.to_string(), serde_json::json!({ "base": seq }));
+                }
+                return sample;
+            }
+        };
+        let mut patch = match diff(state, &sample, self.epsilon) {
+            Some(Value::Object(patch)) => patch,
+            _ => Map::new(),
+        };
+        for field in REQUIRED_FIELDS {
+            if let Some(value) = sample.get(field) {
+                let mut value = value.clone();
+                // 标签整个发送，但作为 Merge Patch 还要带上已删除的标签(null)，否则接收端会保留它们
+                if let (Value::Object(value), Some(Value::Object(change))) = (&mut value, patch.get(field)) {
+                    for (key, _) in change.iter().filter(|(_, change)| change.is_null()) {
+                        value.insert(key.clone(), Value::Null);
+                    }
+                }
+                patch.insert(field.to_string(), value);
+            }
+        }
+        patch.insert(DELTA_FIELD.to_string(), serde_json::json!({ "base": self.base, "prev": self.prev }));
+        self.prev = seq;
+        Value::Object(patch)
+    }
+}
+
+/// `new` 相对于 `old` 的变化，同时把 `old` 更新为接收端应用变化后的值；没有变化时为 None
+fn diff(old: &mut Value, new: &Value, epsilon: f64) -> Option<Value> {
+    match (&mut *old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut patch = Map::new();
+            for (key, value) in new {
+                if value.is_null() {
+                    if old.remove(key).is_some() {
+                        patch.insert(key.clone(), Value::Null);
+                    }
+                    continue;
+                }
+                match old.get_mut(key) {
+                    Some(current) => {
+                        if let Some(change) = diff(current, value, epsilon) {
+                            patch.insert(key.clone(), change);
+                        }
+                    }
+                    None => {
+                        old.insert(key.clone(), value.clone());
+                        patch.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            old.retain(|key, _| {
+                let kept = new.get(key).is_some_and(|value| !value.is_null());
+                if !kept {
+                    patch.insert(key.clone(), Value::Null);
+                }
+                kept
+            });
+            (!patch.is_empty()).then_some(Value::Object(patch))
+        }
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            if a == b || (a - b).abs() <= epsilon * a.abs() {
+                None
+            } else {
+                *old = new.clone();
+                Some(new.clone())
+            }
+        }
+        // Merge Patch不能只改数组的一部分，有元素变化时整个替换
+        (Value::Array(items), Value::Array(values)) => {
+            let changed = items.len() != values.len()
+                || items.iter().zip(values).any(|(item, value)| diff(&mut item.clone(), value, epsilon).is_some());
+            if changed {
+                *old = new.clone();
+                Some(new.clone())
+            } else {
+                None
+            }
+        }
+        (old, new) if *old == *new => None,
+        (old, new) => {
+            *old = new.clone();
+            Some(new.clone())
+        }
+    }
+}
+
+/// 对 `target` 应用JSON Merge Patch
+fn apply(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(fields) = target else { unreachable!() };
+    for (key, value) in patch {
+        if value.is_null() {
+            fields.remove(&key);
+        } else {
+            apply(fields.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// 还原 [`Encoder`] 编码的样本，按主机保留上一个样本
+#[derive(Default)]
+pub struct Decoder {
+    states: HashMap<String, Value>,
+}
+
+/// [`Decoder::decode`] 的结果
+pub enum Decoded {
+    /// 完整的样本，没有增量元数据
+    Sample(Value),
+    /// 前一个样本缺失，在下一个完整样本之前无法还原
+    Missing { prev: u64 },
+}
+
+impl Decoder {
+    /// 还原 `host` 的一个样本；不带增量元数据的样本原样返回
+    pub fn decode(&mut self, host: &str, mut sample: Value) -> Decoded {
+        let Some(Value::Object(meta)) = sample.as_object_mut().and_then(|fields| fields.remove(DELTA_FIELD)) else {
+            return Decoded::Sample(sample);
+        };
+        let Some(prev) = meta.get("prev").and_then(Value::as_u64) else {
+            self.states.insert(host.to_string(), sample.clone());
+            return Decoded::Sample(sample);
+        };
+        match self.states.get_mut(host) {
+            Some(state) if state.get("seq").and_then(Value::as_u64) == Some(prev) => {
+                apply(state, sample);
+                Decoded::Sample(state.clone())
+            }
+            _ => Decoded::Missing { prev },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encode(encoder: &mut Encoder, sample: Value) -> Value {
+        serde_json::from_str(&encoder.encode(&sample.to_string()).unwrap()).unwrap()
+    }
+
+    fn sample(seq: u64, fields: Value) -> Value {
+        let mut sample = json!({ "version": 1, "seq": seq, "timestamp": format!("2024-01-01T00:00:{:02}Z", seq) });
+        sample.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+        sample
+    }
+
+    fn decoded(decoder: &mut Decoder, sample: Value) -> Value {
+        match decoder.decode("web-01", sample) {
+            Decoded::Sample(sample) => sample,
+            Decoded::Missing { prev } => panic!("missing {}", prev),
+        }
+    }
+
+    #[test]
+    fn sends_full_then_changed_fields() {
+        let mut encoder = Encoder::new(Duration::from_secs(3600), 0.0);
+        let first = encode(&mut encoder, sample(1, json!({ "cpu": [1.0, 2.0], "mem": { "total": 10, "used": 5 } })));
+        assert_eq!(first["delta"], json!({ "base": 1 }));
+        assert_eq!(first["mem"], json!({ "total": 10, "used": 5 }));
+
+        let second = encode(&mut encoder, sample(2, json!({ "cpu": [1.0, 3.0], "mem": { "total": 10, "used": 6 } })));
+        assert_eq!(
+            second,
+            json!({
+                "version": 1,
+                "seq": 2,
+                "timestamp": "2024-01-01T00:00:02Z",
+                "cpu": [1.0, 3.0],
+                "mem": { "used": 6 },
+                "delta": { "base": 1, "prev": 1 },
+            })
+        );
+
+        let third = encode(&mut encoder, sample(3, json!({ "cpu": [1.0, 3.0] })));
+        assert_eq!(third["mem"], Value::Null);
+        assert!(third.as_object().unwrap().contains_key("mem"));
+        assert!(!third.as_object().unwrap().contains_key("cpu"));
+        assert_eq!(third["delta"], json!({ "base": 1, "prev": 2 }));
+    }
+
+    #[test]
+    fn keeps_required_fields() {
+        let mut encoder = Encoder::new(Duration::from_secs(3600), 0.0);
+        let fields = json!({ "instance_id": "i-1", "tags": { "env": "prod" }, "host": "a" });
+        encode(&mut encoder, sample(1, fields.clone()));
+        let delta = encode(&mut encoder, sample(2, fields));
+        assert_eq!(delta["instance_id"], "i-1");
+        assert_eq!(delta["tags"], json!({ "env": "prod" }));
+        assert!(!delta.as_object().unwrap().contains_key("host"));
+    }
+
+    #[test]
+    fn ignores_changes_within_epsilon_of_the_known_value() {
+        let mut encoder = Encoder::new(Duration::from_secs(3600), 0.1);
+        encode(&mut encoder, sample(1, json!({ "net": { "rx": 100 } })));
+        assert!(!encode(&mut encoder, sample(2, json!({ "net": { "rx": 109 } }))).as_object().unwrap().contains_key("net"));
+        assert!(!encode(&mut encoder, sample(3, json!({ "net": { "rx": 91 } }))).as_object().unwrap().contains_key("net"));
+        // 逐步的变化按接收端已知的值累计
+        assert_eq!(encode(&mut encoder, sample(4, json!({ "net": { "rx": 111 } })))["net"], json!({ "rx": 111 }));
+        assert!(!encode(&mut encoder, sample(5, json!({ "net": { "rx": 121 } }))).as_object().unwrap().contains_key("net"));
+    }
+
+    #[test]
+    fn replaces_whole_arrays() {
+        let mut encoder = Encoder::new(Duration::from_secs(3600), 0.0);
+        encode(&mut encoder, sample(1, json!({ "top": [{ "pid": 1, "cpu": 1.0 }, { "pid": 2, "cpu": 1.0 }] })));
+        let delta = encode(&mut encoder, sample(2, json!({ "top": [{ "pid": 1, "cpu": 1.0 }, { "pid": 2, "cpu": 2.0 }] })));
+        assert_eq!(delta["top"], json!([{ "pid": 1, "cpu": 1.0 }, { "pid": 2, "cpu": 2.0 }]));
+        let delta = encode(&mut encoder, sample(3, json!({ "top": [{ "pid": 1, "cpu": 1.0 }] })));
+        assert_eq!(delta["top"], json!([{ "pid": 1, "cpu": 1.0 }]));
+    }
+
+    #[test]
+    fn sends_full_samples_periodically_and_after_reset() {
+        let mut encoder = Encoder::new(Duration::ZERO, 0.0);
+        encode(&mut encoder, sample(1, json!({})));
+        assert_eq!(encode(&mut encoder, sample(2, json!({})))["delta"], json!({ "base": 2 }));
+
+        let mut encoder = Encoder::new(Duration::from_secs(3600), 0.0);
+        encode(&mut encoder, sample(1, json!({})));
+        encode(&mut encoder, sample(2, json!({})));
+        encoder.reset();
+        assert_eq!(encode(&mut encoder, sample(3, json!({})))["delta"], json!({ "base": 3 }));
+        assert_eq!(encode(&mut encoder, sample(4, json!({})))["delta"], json!({ "base": 3, "prev": 3 }));
+    }
+
+    #[test]
+    fn encodes_batches_in_order() {
+        let mut encoder = Encoder::new(Duration::from_secs(3600), 0.0);
+        let batch = json!([sample(1, json!({ "cpu": [1.0] })), sample(2, json!({ "cpu": [2.0] }))]);
+        let encoded: Value = serde_json::from_str(&encoder.encode(&batch.to_string()).unwrap()).unwrap();
+        assert_eq!(encoded[0]["delta"], json!({ "base": 1 }));
+        assert_eq!(encoded[1]["delta"], json!({ "base": 1, "prev": 1 }));
+        assert!(encoder.encode("not json").is_err());
+    }
+
+    #[test]
+    fn round_trips_through_the_decoder() {
+        let samples = [
+            sample(1, json!({ "tags": { "env": "prod", "team": "a" }, "disk": { "/": { "used": 1 }, "/mnt": { "used": 2 } } })),
+            sample(2, json!({ "tags": { "env": "prod" }, "disk": { "/": { "used": 3 } }, "cpu": [5.0] })),
+            sample(3, json!({ "tags": { "env": "dev", "zone": "b" }, "disk": { "/": { "used": 3 } } })),
+            sample(4, json!({ "disk": { "/": { "used": 4 } } })),
+        ];
+        let mut encoder = Encoder::new(Duration::from_secs(3600), 0.0);
+        let mut decoder = Decoder::default();
+        for sample in samples {
+            let encoded = encode(&mut encoder, sample.clone());
+            assert_eq!(decoded(&mut decoder, encoded), sample);
+        }
+    }
+
+    #[test]
+    fn reports_missing_samples_until_the_next_full_one() {
+        let mut encoder = Encoder::new(Duration::from_secs(3600), 0.0);
+        let mut decoder = Decoder::default();
+        decoded(&mut decoder, encode(&mut encoder, sample(1, json!({ "cpu": [1.0] }))));
+        encode(&mut encoder, sample(2, json!({ "cpu": [2.0] })));
+        let third = encode(&mut encoder, sample(3, json!({ "cpu": [3.0] })));
+        assert!(matches!(decoder.decode("web-01", third.clone()), Decoded::Missing { prev: 2 }));
+        // 其他主机的状态互不影响
+        assert!(matches!(decoder.decode("web-02", third), Decoded::Missing { prev: 2 }));
+
+        encoder.reset();
+        let full = sample(4, json!({ "cpu": [4.0] }));
+        assert_eq!(decoded(&mut decoder, encode(&mut encoder, full.clone())), full);
+        let plain = json!({ "seq": 9 });
+        assert_eq!(decoded(&mut decoder, plain.clone()), plain);
+    }
+
+    #[test]
+    fn applies_merge_patches_like_rfc_7386() {
+        for (target, patch, expected) in [
+            (json!({ "a": "b" }), json!({ "a": "c" }), json!({ "a": "c" })),
+            (json!({ "a": "b" }), json!({ "b": "c" }), json!({ "a": "b", "b": "c" })),
+            (json!({ "a": "b" }), json!({ "a": null }), json!({})),
+            (json!({ "a": "b", "b": "c" }), json!({ "a": null }), json!({ "b": "c" })),
+            (json!({ "a": ["b"] }), json!({ "a": "c" }), json!({ "a": "c" })),
+            (json!({ "a": "c" }), json!({ "a": ["b"] }), json!({ "a": ["b"] })),
+            (json!({ "a": { "b": "c" } }), json!({ "a": { "b": "d", "c": null } }), json!({ "a": { "b": "d" } })),
+            (json!({ "a": [{ "b": "c" }] }), json!({ "a": [1] }), json!({ "a": [1] })),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({ "a": "b" }), json!(["c"]), json!(["c"])),
+            (json!({ "e": null }), json!({ "a": 1 }), json!({ "e": null, "a": 1 })),
+            (json!([1, 2]), json!({ "a": "b", "c": null }), json!({ "a": "b" })),
+            (json!({}), json!({ "a": { "bb": { "ccc": null } } }), json!({ "a": { "bb": {} } })),
+        ] {
+            let mut target = target;
+            apply(&mut target, patch);
+            assert_eq!(target, expected);
+        }
+    }
+}
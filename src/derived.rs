@@ -0,0 +1,421 @@
+// src/derived.rs
+use crate::alert::Metric;
+use crate::i18n::tr;
+use crate::SystemStats;
+
+/// 对一个指标的各实例汇总的函数
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Count,
+}
+
+impl Function {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "sum" => Function::Sum,
+            "avg" => Function::Avg,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "count" => Function::Count,
+            _ => return None,
+        })
+    }
+
+    /// 没有实例时 `sum` 和 `count` 为0，其余没有值
+    fn apply(self, values: &[f64]) -> Option<f64> {
+        match self {
+            Function::Sum => Some(values.iter().sum()),
+            Function::Count => Some(values.len() as f64),
+            _ if values.is_empty() => None,
+            Function::Avg => Some(values.iter().sum::<f64>() / values.len() as f64),
+            Function::Min => values.iter().copied().reduce(f64::min),
+            Function::Max => values.iter().copied().reduce(f64::max),
+        }
+    }
+}
+
+/// 表达式的语法树
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64),
+    /// 只有一个值的指标，例如 `mem.used`、`disk["/"].used`
+    Metric(Metric),
+    /// 对各实例汇总，例如 `sum(net.*.rx)`
+    Aggregate(Function, Metric),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, u8, Box<Expr>),
+}
+
+impl Expr {
+    /// 样本中缺少引用的指标、汇总的实例为空或除以0时没有值
+    fn eval(&self, stats: &SystemStats) -> Option<f64> {
+        let value = match self {
+            Expr::Number(value) => *value,
+            Expr::Metric(metric) => match metric.values(stats).as_slice() {
+                [(_, value)] => *value,
+                _ => return None,
+            },
+            Expr::Aggregate(function, metric) => {
+                let values: Vec<f64> = metric.values(stats).into_iter().map(|(_, value)| value).collect();
+                function.apply(&values)?
+            }
+            Expr::Neg(expr) => -expr.eval(stats)?,
+            Expr::Binary(left, op, right) => {
+                let (left, right) = (left.eval(stats)?, right.eval(stats)?);
+                match op {
+                    b'+' => left + right,
+                    b'-' => left - right,
+                    b'*' => left * right,
+                    _ => left / right,
+                }
+            }
+        };
+        value.is_finite().then_some(value)
+    }
+
+    /// 引用的其他派生指标
+    fn references<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Expr::Metric(metric) | Expr::Aggregate(_, metric) if metric.section() == "derived" => names.extend(metric.key()),
+            Expr::Neg(expr) => expr.references(names),
+            Expr::Binary(left, _, right) => {
+                left.references(names);
+                right.references(names);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 递归下降解析 `+ - * / ( )`、数字、指标和汇总函数
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(text: &'a str) -> Result<Expr, String> {
+        let mut parser = Self { text, pos: 0 };
+        let expr = parser.expr()?;
+        parser.skip_space();
+        match parser.peek() {
+            None => Ok(expr),
+            Some(_) => Err(parser.error()),
+        }
+    }
+
+    fn error(&self) -> String {
+        match &self.text[self.pos.min(self.text.len())..] {
+            "" => tr("表达式不完整").to_string(),
+            rest => format!("{}: {}", tr("无法解析的表达式"), rest),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// 跳过空白后下一个字符是 `c` 时读掉它
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_space();
+        let matched = self.peek() == Some(c);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        loop {
+            let op = if self.eat(b'+') {
+                b'+'
+            } else if self.eat(b'-') {
+                b'-'
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Binary(Box::new(left), op, Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.factor()?;
+        loop {
+            let op = if self.eat(b'*') {
+                b'*'
+            } else if self.eat(b'/') {
+                b'/'
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Binary(Box::new(left), op, Box::new(self.factor()?));
+        }
+    }
+
+    fn factor(&mut self) -> Result<Expr, String> {
+        if self.eat(b'-') {
+            return Ok(Expr::Neg(Box::new(self.factor()?)));
+        }
+        if self.eat(b'(') {
+            let expr = self.expr()?;
+            return if self.eat(b')') { Ok(expr) } else { Err(self.error()) };
+        }
+        match self.peek() {
+            Some(b) if b.is_ascii_digit() || b == b'.' => self.number(),
+            Some(b) if b.is_ascii_lowercase() => {
+                let start = self.pos;
+                let name = self.reference();
+                let function = Function::parse(name).filter(|_| self.eat(b'('));
+                let Some(function) = function else {
+                    let metric = metric(name).ok_or_else(|| self.error_at(start))?;
+                    if !metric.single() {
+                        return Err(format!("{}: {}", tr("有多个实例的指标需要用 sum、avg、min、max 或 count 汇总"), name));
+                    }
+                    return Ok(Expr::Metric(metric));
+                };
+                self.skip_space();
+                let start = self.pos;
+                let name = self.reference();
+                let metric = metric(name).ok_or_else(|| self.error_at(start))?;
+                if !self.eat(b')') {
+                    return Err(self.error());
+                }
+                Ok(Expr::Aggregate(function, metric))
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    fn error_at(&self, pos: usize) -> String {
+        format!("{}: {}", tr("无效的指标"), &self.text[pos..self.pos])
+    }
+
+    fn number(&mut self) -> Result<Expr, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit() || b == b'.') {
+            self.pos += 1;
+        }
+        self.text[start..self.pos].parse().map(Expr::Number).map_err(|_| self.error())
+    }
+
+    /// 读取一个指标或函数名：字母、数字、`_`、`.`、`*` 和 `["..."]`，引号中可以有任意字符
+    fn reference(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(b) = self.peek() {
+            match b {
+                b'a'..=b'z' | b'0'..=b'9' | b'_' | b'.' | b'*' => self.pos += 1,
+                b'[' => match self.text[self.pos..].find("\"]") {
+                    Some(end) => self.pos += end + 2,
+                    None => break,
+                },
+                _ => break,
+            }
+        }
+        &self.text[start..self.pos]
+    }
+}
+
+/// 指标的写法与告警规则相同，另外可以用 `net.*.rx` 表示各实例
+fn metric(name: &str) -> Option<Metric> {
+    let metric = Metric::parse(&name.replace(".*", ""))?;
+    // 不指定名称时会取到所有已经算出的派生指标，结果取决于定义的顺序
+    (metric.section() != "derived" || metric.key().is_some()).then_some(metric)
+}
+
+/// 派生指标：每次采集后按 `--derive NAME=EXPR` 的表达式用样本中的值计算，结果放在样本的 `derived` 中
+#[derive(Debug, Clone, Default)]
+pub struct Derived {
+    /// 按引用关系排好的顺序，被引用的派生指标在前
+    metrics: Vec<(String, Expr)>,
+}
+
+impl Derived {
+    pub fn new(definitions: &[String]) -> Result<Self, String> {
+        let mut metrics: Vec<(String, Expr)> = Vec::new();
+        for definition in definitions {
+            let (name, expr) = parse_definition(definition)?;
+            if metrics.iter().any(|(n, _)| *n == name) {
+                return Err(format!("{}: {}", tr("派生指标的名称重复"), name));
+            }
+            metrics.push((name, expr));
+        }
+
+        // 按引用关系排序：深度优先，被引用的先加入；正在访问的再次遇到说明循环引用
+        fn visit(index: usize, metrics: &[(String, Expr)], state: &mut [u8], order: &mut Vec<usize>) -> Result<(), String> {
+            match state[index] {
+                2 => return Ok(()),
+                1 => return Err(format!("{}: {}", tr("派生指标循环引用"), metrics[index].0)),
+                _ => {}
+            }
+            state[index] = 1;
+            let mut references = Vec::new();
+            metrics[index].1.references(&mut references);
+            for name in references {
+                let referenced = metrics
+                    .iter()
+                    .position(|(n, _)| n == name)
+                    .ok_or_else(|| format!("{}: {}", tr("未定义的派生指标"), name))?;
+                visit(referenced, metrics, state, order)?;
+            }
+            state[index] = 2;
+            order.push(index);
+            Ok(())
+        }
+        let mut state = vec![0; metrics.len()];
+        let mut order = Vec::new();
+        for index in 0..metrics.len() {
+            visit(index, &metrics, &mut state, &mut order)?;
+        }
+        let mut metrics: Vec<Option<(String, Expr)>> = metrics.into_iter().map(Some).collect();
+        Ok(Self { metrics: order.into_iter().filter_map(|index| metrics[index].take()).collect() })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+
+    /// 计算各派生指标写入 `stats.derived`，缺少引用的指标等没有值的省略
+    pub fn evaluate(&self, stats: &mut SystemStats) {
+        stats.derived.clear();
+        for (name, expr) in &self.metrics {
+            if let Some(value) = expr.eval(stats) {
+                stats.derived.insert(name.clone(), value);
+            }
+        }
+    }
+}
+
+fn parse_definition(value: &str) -> Result<(String, Expr), String> {
+    let invalid = || format!("{}: {}", tr("无效的派生指标"), value);
+    let (name, expr) = value.split_once('=').ok_or_else(invalid)?;
+    let name = name.trim();
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(invalid());
+    }
+    let expr = Parser::parse(expr).map_err(|e| format!("{}: {}", invalid(), e))?;
+    Ok((name.to_string(), expr))
+}
+
+/// `--derive NAME=EXPR` 的校验
+pub fn derive_arg(value: &str) -> Result<String, String> {
+    parse_definition(value).map(|_| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> SystemStats {
+        serde_json::from_value(json!({
+            "version": 1,
+            "seq": 0,
+            "timestamp": "2024-01-01T00:00:00Z",
+            "mem": { "total": 400, "used": 100 },
+            "disk": { "/": { "total": 100, "used": 40 }, "/var": { "total": 300, "used": 60 } },
+            "net": { "eth0": { "rx": 10, "tx": 1 }, "eth1": { "rx": 30, "tx": null } },
+        }))
+        .unwrap()
+    }
+
+    fn eval(expr: &str) -> Option<f64> {
+        Parser::parse(expr).unwrap().eval(&sample())
+    }
+
+    fn derive(definitions: &[&str]) -> Result<Derived, String> {
+        Derived::new(&definitions.iter().map(|d| d.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn follows_precedence_and_associativity() {
+        assert_eq!(eval("1 + 2 * 3"), Some(7.0));
+        assert_eq!(eval("(1 + 2) * 3"), Some(9.0));
+        assert_eq!(eval("10 - 4 - 3"), Some(3.0));
+        assert_eq!(eval("24 / 4 / 2"), Some(3.0));
+        assert_eq!(eval("-2 * -(1 + .5)"), Some(3.0));
+        assert_eq!(eval(" 2*3-1 "), Some(5.0));
+    }
+
+    #[test]
+    fn reads_single_metrics() {
+        assert_eq!(eval("mem.used / mem.total * 100"), Some(25.0));
+        assert_eq!(eval(r#"disk["/var"].used"#), Some(60.0));
+        assert_eq!(eval("swap.used"), None);
+    }
+
+    #[test]
+    fn aggregates_instances() {
+        assert_eq!(eval("sum(net.*.rx)"), Some(40.0));
+        assert_eq!(eval("avg(net.rx)"), Some(20.0));
+        assert_eq!(eval("min(disk.used)"), Some(40.0));
+        assert_eq!(eval("max(disk.used_percent)"), Some(40.0));
+        // 还没有速率的网卡不计入
+        assert_eq!(eval("count(net.tx)"), Some(1.0));
+        assert_eq!(eval("sum(temp)"), Some(0.0));
+        assert_eq!(eval("count(temp)"), Some(0.0));
+        assert_eq!(eval("avg(temp)"), None);
+        assert_eq!(eval("sum( disk.used ) / 2"), Some(50.0));
+    }
+
+    #[test]
+    fn omits_non_finite_results() {
+        assert_eq!(eval("1 / 0"), None);
+        assert_eq!(eval("sum(temp) / count(temp)"), None);
+        assert_eq!(eval("mem.used + avg(temp)"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expr in ["", "1 +", "(1", "1)", "1 2", "mem.used +* 2", "gpu.used", "disk.used", "sum(gpu)", "sum(net.rx", "1..2", "derived.x + derived"] {
+            assert!(Parser::parse(expr).is_err(), "{}", expr);
+        }
+        assert!(Parser::parse("disk.used").unwrap_err().contains(tr("有多个实例的指标需要用 sum、avg、min、max 或 count 汇总")));
+        assert_eq!(Parser::parse("1 +").unwrap_err(), tr("表达式不完整"));
+    }
+
+    #[test]
+    fn validates_definitions() {
+        assert!(derive_arg("mem_pct = mem.used / mem.total").is_ok());
+        for value in ["mem.used", "1x=1", "a-b=1", "=1", "a=", "a=1 +"] {
+            assert!(derive_arg(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn evaluates_in_dependency_order() {
+        let derived = derive(&[r#"c=derived["b"].value * 2"#, r#"b=derived["a"] + 1"#, "a=mem.used"]).unwrap();
+        let mut stats = sample();
+        stats.derived.insert("stale".into(), 1.0);
+        derived.evaluate(&mut stats);
+        assert_eq!(stats.derived, [("a".into(), 100.0), ("b".into(), 101.0), ("c".into(), 202.0)].into());
+    }
+
+    #[test]
+    fn omits_metrics_depending_on_missing_values() {
+        let derived = derive(&["a=swap.used", r#"b=derived["a"] + 1"#, "c=1"]).unwrap();
+        let mut stats = sample();
+        derived.evaluate(&mut stats);
+        assert_eq!(stats.derived, [("c".into(), 1.0)].into());
+    }
+
+    #[test]
+    fn rejects_bad_references() {
+        assert!(derive(&["a=1", "a=2"]).unwrap_err().contains(tr("派生指标的名称重复")));
+        assert!(derive(&[r#"a=derived["b"]"#]).unwrap_err().contains(tr("未定义的派生指标")));
+        assert!(derive(&[r#"a=derived["b"]"#, r#"b=derived["a"]"#]).unwrap_err().contains(tr("派生指标循环引用")));
+        assert!(derive(&[r#"a=derived["a"] + 1"#]).unwrap_err().contains(tr("派生指标循环引用")));
+    }
+}
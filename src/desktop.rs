@@ -0,0 +1,36 @@
+// src/desktop.rs
+use crate::alert::Notification;
+use tokio::task::JoinSet;
+
+/// 编译时是否启用了 `desktop` 特性
+pub const AVAILABLE: bool = cfg!(feature = "desktop");
+
+/// 通过D-Bus弹出桌面通知，触发时为紧急通知，解除时为普通通知
+#[cfg(feature = "desktop")]
+pub fn notify(alert: &Notification, tasks: &mut JoinSet<()>) {
+    use crate::alert::State;
+    use crate::i18n::tr;
+    use notify_rust::{Notification as Desktop, Urgency};
+
+    let (title, urgency, icon) = match alert.event.state {
+        State::Firing => (tr("告警触发"), Urgency::Critical, "dialog-warning"),
+        State::Resolved => (tr("告警解除"), Urgency::Normal, "dialog-information"),
+    };
+    let mut notification = Desktop::new();
+    notification
+        .appname(env!("CARGO_PKG_NAME"))
+        .summary(&format!("{}: {}", title, alert.event.rule))
+        .body(&alert.summary())
+        .icon(icon)
+        .urgency(urgency);
+
+    // 发送D-Bus消息是阻塞的，放到阻塞线程中
+    tasks.spawn_blocking(move || {
+        if let Err(e) = notification.show() {
+            tracing::warn!(error = %e, "{}", tr("显示桌面通知失败"));
+        }
+    });
+}
+
+#[cfg(not(feature = "desktop"))]
+pub fn notify(_alert: &Notification, _tasks: &mut JoinSet<()>) {}
@@ -0,0 +1,97 @@
+// src/diff.rs
+use crate::display::{format_bytes, percent};
+use crate::i18n::tr;
+use crate::SystemStats;
+use std::collections::BTreeSet;
+
+/// 判断变化是否显著的阈值
+pub struct Thresholds {
+    /// 使用率(CPU、内存、交换空间、磁盘)变化的最小百分点
+    pub percent: f64,
+    /// 进程数量变化的最小值
+    pub count: usize,
+}
+
+/// 比较两个样本，返回显著变化的说明，每行一项
+pub fn diff(a: &SystemStats, b: &SystemStats, thresholds: &Thresholds) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    if a.cpu.len() != b.cpu.len() {
+        lines.push(format!("{}: {} → {}", tr("CPU核心数"), a.cpu.len(), b.cpu.len()));
+    }
+    if !a.cpu.is_empty() && !b.cpu.is_empty() {
+        let avg = |cpu: &[f32]| cpu.iter().sum::<f32>() as f64 / cpu.len() as f64;
+        usage(&mut lines, "cpu", avg(&a.cpu), avg(&b.cpu), None, thresholds);
+    }
+
+    for (name, a, b) in [("mem", &a.mem, &b.mem), ("swap", &a.swap, &b.swap)] {
+        if let (Some(a), Some(b)) = (a, b) {
+            memory(&mut lines, name, (a.used, a.total), (b.used, b.total), thresholds);
+        }
+    }
+
+    let mounts: BTreeSet<&String> = a.disk.keys().chain(b.disk.keys()).collect();
+    for mount in mounts {
+        match (a.disk.get(mount), b.disk.get(mount)) {
+            (Some(a), Some(b)) => {
+                memory(&mut lines, &format!("disk {}", mount), (a.used, a.total), (b.used, b.total), thresholds)
+            }
+            (None, Some(_)) => lines.push(format!("+ disk {}", mount)),
+            (Some(_), None) => lines.push(format!("- disk {}", mount)),
+            (None, None) => {}
+        }
+    }
+
+    let interfaces: BTreeSet<&String> = a.net.keys().chain(b.net.keys()).collect();
+    for interface in interfaces {
+        match (a.net.contains_key(interface), b.net.contains_key(interface)) {
+            (false, true) => lines.push(format!("+ net {}", interface)),
+            (true, false) => lines.push(format!("- net {}", interface)),
+            _ => {}
+        }
+    }
+
+    if let (Some(a), Some(b)) = (&a.proc, &b.proc) {
+        for (name, a, b) in [
+            ("total", a.total, b.total),
+            ("running", a.running, b.running),
+            ("sleeping", a.sleeping, b.sleeping),
+            ("zombie", a.zombie, b.zombie),
+        ] {
+            if a.abs_diff(b) >= thresholds.count.max(1) {
+                lines.push(format!("proc {}: {} → {} ({:+})", name, a, b, b as i64 - a as i64));
+            }
+        }
+    }
+
+    lines
+}
+
+/// 内存、交换空间和磁盘：`(used, total)`，同时给出使用量的变化
+fn memory(lines: &mut Vec<String>, name: &str, a: (u64, u64), b: (u64, u64), thresholds: &Thresholds) {
+    // 总量为0(没有配置swap)时没有百分比可比
+    if a.1 == 0 || b.1 == 0 {
+        if a.1 != b.1 {
+            lines.push(format!("{} total: {} → {}", name, format_bytes(a.1), format_bytes(b.1)));
+        }
+        return;
+    }
+    let delta = if b.0 >= a.0 {
+        format!("+{}", format_bytes(b.0 - a.0))
+    } else {
+        format!("-{}", format_bytes(a.0 - b.0))
+    };
+    usage(lines, name, percent(a.0, a.1), percent(b.0, b.1), Some(delta), thresholds);
+}
+
+fn usage(lines: &mut Vec<String>, name: &str, a: f64, b: f64, delta: Option<String>, thresholds: &Thresholds) {
+    if (b - a).abs() < thresholds.percent {
+        return;
+    }
+    let mut line = format!("{}: {:.1}% → {:.1}% ({:+.1}%", name, a, b, b - a);
+    if let Some(delta) = delta {
+        line.push_str(&format!(", {}", delta));
+    }
+    line.push(')');
+    lines.push(line);
+}
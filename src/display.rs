@@ -0,0 +1,378 @@
+// src/display.rs
+use crate::breaker::{CircuitState, SinkHealth};
+use crate::filter::DisplayFilter;
+use crate::history::{sparkline, History};
+use crate::i18n::tr;
+use crate::SystemStats;
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// 文本显示中的区块
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Section {
+    Cpu,
+    Mem,
+    Disk,
+    Net,
+    Proc,
+}
+
+impl Section {
+    pub const ALL: [Section; 5] = [Section::Cpu, Section::Mem, Section::Disk, Section::Net, Section::Proc];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "cpu" => Some(Section::Cpu),
+            "mem" => Some(Section::Mem),
+            "disk" => Some(Section::Disk),
+            "net" => Some(Section::Net),
+            "proc" => Some(Section::Proc),
+            _ => None,
+        }
+    }
+}
+
+/// 每个CPU核心的显示方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoreView {
+    /// 核心数不超过 `AUTO_CORES` 时逐个显示，否则汇总
+    Auto,
+    Full,
+    Summary,
+}
+
+const AUTO_CORES: usize = 16;
+
+/// 文本显示的选项
+#[derive(Debug, Clone)]
+pub struct DisplayOptions {
+    /// 百分比条的宽度(字符数)，为0时只显示数字
+    pub bar_width: usize,
+    /// 不输出任何转义序列和趋势图，每次输出一个带时间戳的文本块
+    pub plain: bool,
+    /// 按顺序显示的区块
+    pub sections: Vec<Section>,
+    pub cores: CoreView,
+}
+
+/// 显示在顶部的主机和代理状态：主机名、运行时间、版本、上次发送时间和端点状态
+pub struct Header {
+    host: String,
+    last_sent: Option<DateTime<Local>>,
+    failures: u32,
+    /// 各发送目标的断路器状态
+    sinks: BTreeMap<String, SinkHealth>,
+}
+
+impl Header {
+    pub fn new(host: String) -> Self {
+        Self {
+            host,
+            last_sent: None,
+            failures: 0,
+            sinks: BTreeMap::new(),
+        }
+    }
+
+    /// 记录一次发送结果和发送之后各发送目标的状态
+    pub fn record(&mut self, sent: bool, sinks: BTreeMap<String, SinkHealth>) {
+        self.sinks = sinks;
+        if sent {
+            self.last_sent = Some(Local::now());
+            self.failures = 0;
+        } else {
+            self.failures += 1;
+        }
+    }
+
+    pub fn line(&self, uptime_secs: u64) -> String {
+        let last_sent = self.last_sent
+            .map_or_else(|| "-".to_string(), |t| t.format("%H:%M:%S").to_string());
+        let open = |sink: &SinkHealth| sink.state == CircuitState::Open;
+        let mut health = match (self.failures, self.last_sent) {
+            _ if self.sinks.get("endpoint").is_some_and(open) => {
+                format!("{} ({} {})", tr("端点断路"), tr("连续失败次数"), self.sinks["endpoint"].consecutive_failures)
+            }
+            (0, None) => tr("等待发送").to_string(),
+            (0, Some(_)) => tr("端点正常").to_string(),
+            (n, _) => format!("{} ({} {})", tr("端点异常"), tr("连续失败次数"), n),
+        };
+        let exporters: Vec<&str> = self.sinks
+            .iter()
+            .filter(|(_, sink)| open(sink))
+            .filter_map(|(name, _)| name.strip_prefix("exporter:"))
+            .collect();
+        if !exporters.is_empty() {
+            health = format!("{}  {}: {}", health, tr("导出插件断路"), exporters.join(", "));
+        }
+        let templates: Vec<&str> = self.sinks
+            .iter()
+            .filter(|(_, sink)| open(sink))
+            .filter_map(|(name, _)| name.strip_prefix("template:"))
+            .collect();
+        if !templates.is_empty() {
+            health = format!("{}  {}: {}", health, tr("模板输出断路"), templates.join(", "));
+        }
+
+        format!("{}  {} {}  v{}  {} {}  {}",
+                self.host,
+                tr("已运行"),
+                format_uptime(uptime_secs),
+                env!("CARGO_PKG_VERSION"),
+                tr("上次发送"),
+                last_sent,
+                health
+        )
+    }
+}
+
+fn format_uptime(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else {
+        format!("{}h {}m", hours, minutes)
+    }
+}
+
+/// 通过crossterm清屏，Windows的旧版控制台不支持ANSI转义序列
+#[cfg(feature = "tui")]
+fn clear_screen() {
+    use crossterm::cursor::MoveTo;
+    use crossterm::terminal::{Clear, ClearType};
+
+    let _ = crossterm::execute!(std::io::stdout(), Clear(ClearType::All), MoveTo(0, 0));
+}
+
+/// 没有crossterm时直接输出ANSI转义序列
+#[cfg(not(feature = "tui"))]
+fn clear_screen() {
+    print!("\x1b[2J\x1b[H");
+}
+
+pub fn display_stats(stats: &SystemStats, history: &History, header: &str, filter: &DisplayFilter, options: &DisplayOptions) {
+    if options.plain {
+        println!("=== {} {} ===", tr("系统资源监控"), stats.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"));
+    } else {
+        clear_screen();
+        println!();
+        println!("=== {} ===", tr("系统资源监控"));
+    }
+    println!("{}", header);
+
+    let width = options.bar_width;
+    let spark = |values: Vec<u64>, width: usize, max: Option<u64>| {
+        if options.plain { String::new() } else { format!("  {}", sparkline(&values, width, max)) }
+    };
+
+    // 关闭的采集项没有数据，对应的区块不显示
+    let sections = options.sections.iter().filter(|section| match section {
+        Section::Cpu => !stats.cpu.is_empty(),
+        Section::Mem => stats.mem.is_some(),
+        Section::Disk => !stats.disk.is_empty(),
+        Section::Net => !stats.net.is_empty(),
+        Section::Proc => stats.proc.is_some(),
+    });
+    for section in sections {
+        match section {
+            Section::Cpu => {
+                println!("{}: {}", tr("CPU核心数"), stats.cpu.len());
+                let expand = match options.cores {
+                    CoreView::Auto => stats.cpu.len() <= AUTO_CORES,
+                    CoreView::Full => true,
+                    CoreView::Summary => false,
+                };
+                if expand {
+                    for (i, usage) in stats.cpu.iter().enumerate() {
+                        println!("  {} {:>3}: {}", tr("核心"), i, gauge(*usage as f64, width));
+                    }
+                } else if let Some((busiest, max)) = stats.cpu.iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.total_cmp(b.1))
+                {
+                    let min = stats.cpu.iter().copied().fold(f32::INFINITY, f32::min);
+                    let busy = stats.cpu.iter().filter(|&&usage| usage >= 90.0).count();
+                    println!("  {}: {:.1}%, {}: {:.1}% ({} {}), {}: {}",
+                             tr("最低"), min,
+                             tr("最高"), max, tr("核心"), busiest,
+                             tr("繁忙(≥90%)"), busy
+                    );
+                }
+                let avg_cpu: f32 = stats.cpu.iter().sum::<f32>() / stats.cpu.len() as f32;
+                println!("{}: {:.1}%{}", tr("平均CPU使用率"), avg_cpu, spark(history.cpu(), 40, Some(100)));
+                // 刷新间隔较长时，平均值掩盖了窗口内的短时峰值
+                if let Some(window) = stats.cpu_window.as_ref().filter(|w| !w.peak.is_empty()) {
+                    let peak = window.peak.iter().sum::<f32>() / window.peak.len() as f32;
+                    println!("  {}: {:.1}s, {}: {}, {}: {:.1}%",
+                             tr("测量窗口"), window.ms as f64 / 1000.0,
+                             tr("采样次数"), window.samples,
+                             tr("各核心最高值的平均"), peak
+                    );
+                }
+            }
+            Section::Mem => {
+                if let Some(mem) = &stats.mem {
+                    println!("{}: {} {} / {}{}",
+                             tr("内存"),
+                             gauge(percent(mem.used, mem.total), width),
+                             format_bytes(mem.used),
+                             format_bytes(mem.total),
+                             spark(history.mem(), 40, Some(100))
+                    );
+                }
+
+                if stats.swap.as_ref().is_some_and(|swap| swap.total == 0) {
+                    println!("{}: {}", tr("交换空间"), tr("未配置"));
+                } else if let Some(swap) = &stats.swap {
+                    println!("{}: {} {} / {}",
+                             tr("交换空间"),
+                             gauge(percent(swap.used, swap.total), width),
+                             format_bytes(swap.used),
+                             format_bytes(swap.total)
+                    );
+                }
+            }
+            Section::Disk => {
+                println!("{}:", tr("磁盘"));
+                for (mount, disk) in stats.disk.iter().filter(|(mount, _)| filter.mount.matches(mount)) {
+                    println!("  {}: {} {} / {}",
+                             mount,
+                             gauge(percent(disk.used, disk.total), width),
+                             format_bytes(disk.used),
+                             format_bytes(disk.total)
+                    );
+                }
+            }
+            Section::Net => {
+                println!("{}:", tr("网络接口"));
+                for (interface, net_stats) in stats.net.iter().filter(|(name, _)| filter.iface.matches(name)) {
+                    println!("  {}: {} {}, {} {}{}",
+                             interface,
+                             tr("接收"),
+                             format_optional_rate(net_stats.rx),
+                             tr("发送"),
+                             format_optional_rate(net_stats.tx),
+                             spark(history.net(interface), 20, None)
+                    );
+                }
+            }
+            Section::Proc => {
+                if let Some(proc) = &stats.proc {
+                    println!("{}:", tr("进程统计"));
+                    println!("  {}: {}, {}: {}, {}: {}, {}: {}",
+                             tr("总计"), proc.total,
+                             tr("运行"), proc.running,
+                             tr("睡眠"), proc.sleeping,
+                             tr("僵尸"), proc.zombie
+                    );
+                }
+            }
+        }
+    }
+
+    if options.plain {
+        println!();
+    } else {
+        println!("\n{}", tr("按 Ctrl+C 退出"));
+    }
+}
+
+/// 字节数的单位制
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Units {
+    /// 1000 进制：kB、MB、GB
+    Si,
+    /// 1024 进制：KiB、MiB、GiB
+    Iec,
+}
+
+static UNITS: OnceLock<(Units, bool)> = OnceLock::new();
+
+/// 设置显示使用的单位制，以及网络速率是否以比特/秒显示
+pub fn set_units(units: Units, bits: bool) {
+    let _ = UNITS.set((units, bits));
+}
+
+fn scale(value: f64, units: Units, si: &[&'static str], iec: &[&'static str]) -> String {
+    let (base, names) = match units {
+        Units::Si => (1000.0, si),
+        Units::Iec => (1024.0, iec),
+    };
+    let mut size = value;
+    let mut unit_index = 0;
+
+    while size >= base && unit_index < names.len() - 1 {
+        size /= base;
+        unit_index += 1;
+    }
+
+    format!("{:.2} {}", size, names[unit_index])
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    let (units, _) = UNITS.get().copied().unwrap_or((Units::Iec, false));
+    scale(
+        bytes as f64,
+        units,
+        &["B", "kB", "MB", "GB", "TB", "PB"],
+        &["B", "KiB", "MiB", "GiB", "TiB", "PiB"],
+    )
+}
+
+/// 网络速率(输入为字节/秒)，按设置显示为字节/秒或比特/秒
+/// 还没有速率时显示为 `-`
+pub fn format_optional_rate(bytes_per_sec: Option<u64>) -> String {
+    bytes_per_sec.map_or_else(|| "-".to_string(), format_rate)
+}
+
+pub fn format_rate(bytes_per_sec: u64) -> String {
+    match UNITS.get().copied().unwrap_or((Units::Iec, false)) {
+        (units, true) => scale(
+            bytes_per_sec as f64 * 8.0,
+            units,
+            &["bit/s", "kbit/s", "Mbit/s", "Gbit/s", "Tbit/s"],
+            &["bit/s", "Kibit/s", "Mibit/s", "Gibit/s", "Tibit/s"],
+        ),
+        _ => format!("{}/s", format_bytes(bytes_per_sec)),
+    }
+}
+
+/// 按比例绘制的文本百分比条，例如 `[#####.....]  52.0%`
+pub fn gauge(percent: f64, width: usize) -> String {
+    if width == 0 {
+        return format!("{:5.1}%", percent);
+    }
+    let filled = ((percent.clamp(0.0, 100.0) / 100.0) * width as f64).round() as usize;
+    format!("[{}{}] {:5.1}%", "#".repeat(filled), ".".repeat(width - filled), percent)
+}
+
+pub fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 { 0.0 } else { used as f64 / total as f64 * 100.0 }
+}
+
+/// 单行紧凑输出：时间、平均CPU、内存、交换空间以及所有显示网卡的总流量，关闭的采集项不显示
+pub fn compact_line(stats: &SystemStats, filter: &DisplayFilter) -> String {
+    let mut line = stats.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string();
+
+    if !stats.cpu.is_empty() {
+        let avg_cpu = stats.cpu.iter().sum::<f32>() / stats.cpu.len() as f32;
+        line += &format!(" cpu {:5.1}%", avg_cpu);
+    }
+    if let (Some(mem), Some(swap)) = (&stats.mem, &stats.swap) {
+        line += &format!(" mem {:5.1}%", percent(mem.used, mem.total));
+        // 没有配置swap时不显示
+        if swap.total > 0 {
+            line += &format!(" swap {:5.1}%", percent(swap.used, swap.total));
+        }
+    }
+    if !stats.net.is_empty() {
+        let (rx, tx) = stats.net
+            .iter()
+            .filter(|(name, _)| filter.iface.matches(name))
+            .fold((0, 0), |(rx, tx), (_, net)| (rx + net.rx.unwrap_or(0), tx + net.tx.unwrap_or(0)));
+        line += &format!(" net ↓{} ↑{}", format_rate(rx), format_rate(tx));
+    }
+
+    line
+}
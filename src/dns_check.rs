@@ -0,0 +1,182 @@
+// src/dns_check.rs
+use crate::collector::{Collector, Context, Section};
+use crate::i18n::tr;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// 采集项的名称
+pub const NAME: &str = "dns";
+
+/// 一个域名在一次采集中的解析结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DnsCheckStats {
+    pub ok: bool,
+    /// 解析用时(毫秒)，超时时为 `null`
+    pub lookup_ms: Option<f64>,
+    /// 得到的地址数，指定服务器时为回答中的记录数(包括CNAME)
+    pub answers: usize,
+    /// 失败的原因：超时、NXDOMAIN、SERVFAIL等
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `--dns-check NAME[@SERVER]`：没有指定服务器时通过系统的解析器(`getaddrinfo`)解析
+#[derive(Debug, Clone)]
+struct Check {
+    name: String,
+    server: Option<SocketAddr>,
+}
+
+impl Check {
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (name, server) = match value.split_once('@') {
+            Some((name, server)) => (name, Some(parse_server(server)?)),
+            None => (value, None),
+        };
+        let name = name.trim_end_matches('.');
+        let valid = !name.is_empty()
+            && name.len() <= 253
+            && name.split('.').all(|label| !label.is_empty() && label.len() <= 63);
+        valid.then(|| Self { name: name.to_string(), server })
+    }
+
+    fn run(&self, timeout: Duration) -> DnsCheckStats {
+        let started = Instant::now();
+        let result = match self.server {
+            Some(server) => query(&self.name, server, timeout),
+            None => system(&self.name, timeout),
+        };
+        let lookup_ms = Some(started.elapsed().as_micros() as f64 / 1000.0);
+        match result {
+            Ok(answers) if answers > 0 => DnsCheckStats { ok: true, lookup_ms, answers, error: None },
+            Ok(_) => DnsCheckStats { lookup_ms, error: Some(tr("没有解析到地址").to_string()), ..Default::default() },
+            // 超时的解析没有有意义的用时
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => DnsCheckStats { error: Some(e.to_string()), ..Default::default() },
+            Err(e) => DnsCheckStats { lookup_ms, error: Some(e.to_string()), ..Default::default() },
+        }
+    }
+}
+
+/// 服务器只能是IP地址，可以带端口(默认53)，IPv6地址带端口时写成 `[::1]:53`
+fn parse_server(value: &str) -> Option<SocketAddr> {
+    let value = value.trim();
+    value
+        .parse::<SocketAddr>()
+        .ok()
+        .or_else(|| value.trim_matches(['[', ']']).parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 53)))
+}
+
+/// `--dns-check` 的校验
+pub fn dns_check_arg(value: &str) -> Result<String, String> {
+    Check::parse(value)
+        .map(|_| value.trim().to_string())
+        .ok_or_else(|| format!("{}: {}", tr("无效的DNS检查"), value))
+}
+
+/// 每次采集解析各域名，给出解析用时和失败原因；各域名同时解析，每个最多等待 `timeout`。
+/// 系统的解析器会用到本机的缓存(nscd、systemd-resolved等)，指定服务器时直接向它发送一个A记录查询
+pub struct DnsChecks {
+    checks: Vec<(String, Check)>,
+    timeout: Duration,
+}
+
+impl DnsChecks {
+    /// `values` 为 `--dns-check` 的值，样本中以它为键
+    pub fn new(values: &[String], timeout: Duration) -> Self {
+        let checks = values
+            .iter()
+            .filter_map(|value| Some((value.trim().to_string(), Check::parse(value)?)))
+            .collect();
+        Self { checks, timeout }
+    }
+}
+
+impl Collector for DnsChecks {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn collect(&mut self, _ctx: &mut Context) -> Section {
+        let timeout = self.timeout;
+        let results = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .checks
+                .iter()
+                .map(|(key, check)| (key, scope.spawn(move || check.run(timeout))))
+                .collect();
+            handles
+                .into_iter()
+                .map(|(key, handle)| (key.clone(), handle.join().unwrap_or_default()))
+                .collect()
+        });
+        Section::Dns(results)
+    }
+}
+
+/// `getaddrinfo` 没有超时，在单独的线程中解析，超时后不再等待它
+fn system(name: &str, timeout: Duration) -> io::Result<usize> {
+    let (tx, rx) = mpsc::channel();
+    let host = name.to_string();
+    std::thread::spawn(move || {
+        let _ = tx.send((host.as_str(), 0).to_socket_addrs().map(|addrs| addrs.count()));
+    });
+    rx.recv_timeout(timeout)
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, tr("解析超时")))?
+}
+
+/// 向 `server` 发送一个递归的A记录查询，返回回答中的记录数
+fn query(name: &str, server: SocketAddr, timeout: Duration) -> io::Result<usize> {
+    let id: u16 = rand::random();
+    let mut packet = Vec::with_capacity(name.len() + 18);
+    packet.extend_from_slice(&id.to_be_bytes());
+    // 标志：期望递归；问题数1
+    packet.extend_from_slice(&[0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    for label in name.split('.') {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    // 根标签、类型A、类IN
+    packet.extend_from_slice(&[0x00, 0x00, 0x01, 0x00, 0x01]);
+
+    let bind: SocketAddr = if server.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+    let socket = UdpSocket::bind(bind)?;
+    socket.connect(server)?;
+    socket.send(&packet)?;
+
+    let started = Instant::now();
+    let mut buf = [0u8; 512];
+    loop {
+        let remaining = timeout.checked_sub(started.elapsed()).filter(|d| !d.is_zero());
+        let remaining = remaining.ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, tr("解析超时")))?;
+        socket.set_read_timeout(Some(remaining))?;
+        let n = match socket.recv(&mut buf) {
+            Ok(n) => n,
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, tr("解析超时")));
+            }
+            Err(e) => return Err(e),
+        };
+        // 忽略其他查询的回应
+        if n < 12 || buf[0..2] != id.to_be_bytes() || buf[2] & 0x80 == 0 {
+            continue;
+        }
+        return match buf[3] & 0x0f {
+            0 => Ok(u16::from_be_bytes([buf[6], buf[7]]) as usize),
+            code => {
+                let rcode = match code {
+                    1 => "FORMERR",
+                    2 => "SERVFAIL",
+                    3 => "NXDOMAIN",
+                    4 => "NOTIMP",
+                    5 => "REFUSED",
+                    _ => "RCODE",
+                };
+                Err(io::Error::other(format!("{} ({})", rcode, code)))
+            }
+        };
+    }
+}
@@ -0,0 +1,141 @@
+// src/email.rs
+use crate::alert::{Notification, State};
+use crate::error::Error;
+use crate::i18n::tr;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+/// 与SMTP服务器之间的加密方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Security {
+    /// 明文连接后用STARTTLS升级，默认端口587
+    StartTls,
+    /// 直接以TLS连接，默认端口465
+    Tls,
+    /// 不加密，默认端口25，只适合本机或内网的中继
+    Plain,
+}
+
+/// 邮件通知的设置
+pub struct Settings<'a> {
+    /// `HOST` 或 `HOST:PORT`
+    pub server: &'a str,
+    pub security: Security,
+    pub credentials: Option<(String, String)>,
+    pub from: &'a str,
+    pub to: Vec<String>,
+    /// 按规则名指定的收件人，代替 `to`
+    pub rules: BTreeMap<String, Vec<String>>,
+    pub timeout: Duration,
+}
+
+/// 告警状态变化时发送邮件
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    rules: BTreeMap<String, Vec<Mailbox>>,
+}
+
+fn mailboxes(addresses: &[String]) -> Result<Vec<Mailbox>, Error> {
+    addresses
+        .iter()
+        .map(|a| a.parse().map_err(|e| Error::config(format!("{} {}: {}", tr("无效的邮件地址"), a, e))))
+        .collect()
+}
+
+impl Mailer {
+    pub fn new(settings: Settings) -> Result<Self, Error> {
+        let (host, port) = match settings.server.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse::<u16>().map_err(|_| Error::config(format!("{}: {}", tr("无效的SMTP服务器"), settings.server)))?)),
+            None => (settings.server, None),
+        };
+        let mut builder = match settings.security {
+            Security::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(Error::config)?,
+            Security::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(Error::config)?,
+            Security::Plain => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        };
+        if let Some(port) = port {
+            builder = builder.port(port);
+        }
+        if let Some((user, password)) = settings.credentials {
+            builder = builder.credentials(Credentials::new(user, password));
+        }
+
+        let to = mailboxes(&settings.to)?;
+        let mut rules = BTreeMap::new();
+        for (rule, addresses) in &settings.rules {
+            rules.insert(rule.clone(), mailboxes(addresses)?);
+        }
+        Ok(Self {
+            transport: builder.timeout(Some(settings.timeout)).build(),
+            from: settings.from.parse().map_err(|e| format!("{} {}: {}", tr("无效的邮件地址"), settings.from, e))?,
+            to,
+            rules,
+        })
+    }
+
+    /// 在后台发送一封告警邮件，失败只记录日志
+    pub fn notify(&self, alert: &Notification, dry_run: bool, tasks: &mut JoinSet<()>) {
+        let event = alert.event;
+        let recipients = self.rules.get(&event.rule).unwrap_or(&self.to);
+        if recipients.is_empty() {
+            return;
+        }
+        let title = match event.state {
+            State::Firing => tr("告警触发"),
+            State::Resolved => tr("告警解除"),
+        };
+        let subject = format!("[{}] {}", title, alert.summary());
+        let mut body = format!("{}\n\n", alert.summary());
+        for (key, value) in [
+            (tr("主机"), alert.host.to_string()),
+            (tr("规则"), event.rule.clone()),
+            (tr("实例"), event.instance.clone()),
+            (tr("条件"), event.expr.clone()),
+            (tr("当前值"), event.value.to_string()),
+            (tr("开始时间"), event.since.to_rfc3339()),
+            (tr("时间"), event.timestamp.to_rfc3339()),
+        ] {
+            if !value.is_empty() {
+                body.push_str(&format!("{}: {}\n", key, value));
+            }
+        }
+        for (key, value) in alert.tags {
+            body.push_str(&format!("{}: {}\n", key, value));
+        }
+
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+        for to in recipients {
+            message = message.to(to.clone());
+        }
+        let to = recipients.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+        let message = match message.body(body) {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::warn!(%to, error = %e, "{}", tr("发送告警邮件失败"));
+                return;
+            }
+        };
+        if dry_run {
+            tracing::info!(%to, "{}", tr("试运行，告警邮件未发送"));
+            return;
+        }
+
+        let transport = self.transport.clone();
+        tasks.spawn(async move {
+            match transport.send(message).await {
+                Ok(_) => tracing::debug!(%to, "{}", tr("告警邮件已发送")),
+                Err(e) => tracing::warn!(%to, error = %e, "{}", tr("发送告警邮件失败")),
+            }
+        });
+    }
+}
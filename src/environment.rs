@@ -0,0 +1,215 @@
+// src/environment.rs
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// 运行环境：物理机、虚拟机、WSL或容器，有些指标在某些环境中没有意义或不准确，接收端据此判断
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Environment {
+    pub kind: Kind,
+    /// 虚拟机的虚拟化平台：`kvm`、`qemu`、`vmware`、`hyperv`、`xen`、`virtualbox`、`bhyve`、`parallels` 等，
+    /// 无法判断具体平台时为 `unknown`；容器中能判断宿主是虚拟机时也给出
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hypervisor: Option<String>,
+    /// 容器的运行时：`docker`、`podman`、`containerd`、`kubernetes`、`lxc`、`systemd-nspawn`、`jail` 等
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    BareMetal,
+    Vm,
+    Wsl,
+    Container,
+}
+
+impl Environment {
+    /// 这个环境中默认不启用的采集项：容器和WSL中读不到宿主的传感器，`temp` 为空或者是宿主的数据
+    pub fn skipped(&self) -> &'static [&'static str] {
+        match self.kind {
+            Kind::Container | Kind::Wsl => &["temp"],
+            Kind::BareMetal | Kind::Vm => &[],
+        }
+    }
+}
+
+/// 本机的运行环境，第一次调用时检测
+pub fn current() -> &'static Environment {
+    static CURRENT: OnceLock<Environment> = OnceLock::new();
+    CURRENT.get_or_init(detect)
+}
+
+fn detect() -> Environment {
+    let hypervisor = hypervisor();
+    let (kind, container) = if wsl() {
+        (Kind::Wsl, None)
+    } else if let Some(container) = container() {
+        (Kind::Container, Some(container))
+    } else if hypervisor.is_some() {
+        (Kind::Vm, None)
+    } else {
+        (Kind::BareMetal, None)
+    };
+    // WSL运行在Hyper-V上，不再单独给出
+    let hypervisor = hypervisor.filter(|_| kind != Kind::Wsl);
+    Environment { kind, hypervisor, container }
+}
+
+/// WSL的内核版本号中带有 `microsoft`
+fn wsl() -> bool {
+    cfg!(target_os = "linux")
+        && (std::env::var_os("WSL_DISTRO_NAME").is_some()
+            || std::fs::read_to_string("/proc/sys/kernel/osrelease")
+                .is_ok_and(|release| release.to_lowercase().contains("microsoft")))
+}
+
+/// 依次检查容器运行时留下的文件、1号进程的 `container` 环境变量和cgroup路径
+#[cfg(target_os = "linux")]
+fn container() -> Option<String> {
+    if std::path::Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    if std::path::Path::new("/.dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    // systemd-nspawn、lxc、podman等在1号进程的环境变量中设置 `container`，读取需要root权限
+    let environ = std::fs::read("/proc/1/environ").unwrap_or_default();
+    let container = environ
+        .split(|&b| b == 0)
+        .find_map(|var| var.strip_prefix(b"container="))
+        .map(|value| String::from_utf8_lossy(value).into_owned())
+        .filter(|value| !value.is_empty());
+    if container.is_some() {
+        return container;
+    }
+    if std::env::var_os("KUBERNETES_SERVICE_HOST").is_some() {
+        return Some("kubernetes".to_string());
+    }
+    let cgroup = std::fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+    [("kubepods", "kubernetes"), ("docker", "docker"), ("libpod", "podman"), ("containerd", "containerd"), ("lxc", "lxc")]
+        .iter()
+        .find(|(pattern, _)| cgroup.contains(pattern))
+        .map(|(_, name)| name.to_string())
+}
+
+/// FreeBSD的jail
+#[cfg(target_os = "freebsd")]
+fn container() -> Option<String> {
+    (sysctl_int(c"security.jail.jailed") == Some(1)).then(|| "jail".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+fn container() -> Option<String> {
+    None
+}
+
+/// 虚拟化平台：Linux上先读DMI，FreeBSD上读 `kern.vm_guest`，macOS上读 `kern.hv_vmm_present`，
+/// 最后查看CPUID中的hypervisor标志和厂商；都没有时为物理机
+fn hypervisor() -> Option<String> {
+    platform_hypervisor().or_else(cpuid_hypervisor)
+}
+
+#[cfg(target_os = "linux")]
+fn platform_hypervisor() -> Option<String> {
+    let read = |name: &str| std::fs::read_to_string(format!("/sys/class/dmi/id/{}", name)).unwrap_or_default();
+    let dmi = format!("{} {} {}", read("sys_vendor"), read("product_name"), read("bios_vendor")).to_lowercase();
+    let known = [
+        ("kvm", "kvm"),
+        ("qemu", "qemu"),
+        ("vmware", "vmware"),
+        ("virtualbox", "virtualbox"),
+        ("innotek", "virtualbox"),
+        ("xen", "xen"),
+        ("bochs", "bochs"),
+        ("parallels", "parallels"),
+        ("bhyve", "bhyve"),
+        ("amazon ec2", "kvm"),
+        ("google compute engine", "kvm"),
+        ("openstack", "kvm"),
+    ];
+    if let Some((_, name)) = known.iter().find(|(pattern, _)| dmi.contains(pattern)) {
+        return Some(name.to_string());
+    }
+    if dmi.contains("microsoft corporation") && dmi.contains("virtual machine") {
+        return Some("hyperv".to_string());
+    }
+    // 没有DMI的Xen半虚拟化客户机
+    std::fs::read_to_string("/sys/hypervisor/type").ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+#[cfg(target_os = "freebsd")]
+fn platform_hypervisor() -> Option<String> {
+    let guest = sysctl_string(c"kern.vm_guest")?;
+    match guest.as_str() {
+        "none" => None,
+        "hv" => Some("hyperv".to_string()),
+        "generic" => Some("unknown".to_string()),
+        _ => Some(guest),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn platform_hypervisor() -> Option<String> {
+    let mut present: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>();
+    let ok = unsafe {
+        libc::sysctlbyname(c"kern.hv_vmm_present".as_ptr(), (&mut present as *mut libc::c_int).cast(), &mut len, std::ptr::null_mut(), 0) == 0
+    };
+    (ok && present == 1).then(|| cpuid_hypervisor().unwrap_or_else(|| "unknown".to_string()))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd", target_os = "macos")))]
+fn platform_hypervisor() -> Option<String> {
+    None
+}
+
+/// CPUID第1页ECX的第31位表示运行在虚拟机中，0x40000000页给出厂商；
+/// 开启了Hyper-V(包括基于虚拟化的安全)的Windows物理机上也会报告 `hyperv`
+#[cfg(target_arch = "x86_64")]
+fn cpuid_hypervisor() -> Option<String> {
+    use std::arch::x86_64::__cpuid;
+
+    #[allow(unused_unsafe)]
+    let (features, vendor) = unsafe { (__cpuid(1), __cpuid(0x4000_0000)) };
+    if features.ecx & (1 << 31) == 0 {
+        return None;
+    }
+    let vendor: Vec<u8> = [vendor.ebx, vendor.ecx, vendor.edx].iter().flat_map(|r| r.to_le_bytes()).collect();
+    let name = match &vendor[..] {
+        b"KVMKVMKVM\0\0\0" => "kvm",
+        b"Microsoft Hv" => "hyperv",
+        b"VMwareVMware" => "vmware",
+        b"XenVMMXenVMM" => "xen",
+        b"VBoxVBoxVBox" => "virtualbox",
+        b"TCGTCGTCGTCG" => "qemu",
+        b"bhyve bhyve " => "bhyve",
+        b" lrpepyh  vr" | b"prl hyperv  " => "parallels",
+        b"ACRNACRNACRN" => "acrn",
+        _ => "unknown",
+    };
+    Some(name.to_string())
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn cpuid_hypervisor() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "freebsd")]
+fn sysctl_int(name: &std::ffi::CStr) -> Option<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>();
+    let ok = unsafe {
+        libc::sysctlbyname(name.as_ptr(), (&mut value as *mut libc::c_int).cast(), &mut len, std::ptr::null(), 0) == 0
+    };
+    ok.then_some(value)
+}
+
+#[cfg(target_os = "freebsd")]
+fn sysctl_string(name: &std::ffi::CStr) -> Option<String> {
+    let mut buf = [0u8; 64];
+    let mut len = buf.len();
+    let ok = unsafe { libc::sysctlbyname(name.as_ptr(), buf.as_mut_ptr().cast(), &mut len, std::ptr::null(), 0) == 0 };
+    ok.then(|| String::from_utf8_lossy(&buf[..len]).trim_end_matches('\0').to_string())
+}
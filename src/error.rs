@@ -0,0 +1,92 @@
+// src/error.rs
+use crate::i18n::tr;
+use serde::Serialize;
+
+/// 退出状态：`check` 超出阈值、`diff` 有变化，不是错误
+pub const EXIT_FAILED: u8 = 1;
+pub const EXIT_CONFIG: u8 = 2;
+pub const EXIT_RUNTIME: u8 = 3;
+pub const EXIT_UNREACHABLE: u8 = 4;
+/// 与Rust默认的panic退出状态相同
+pub const EXIT_PANIC: u8 = 101;
+
+/// 程序结束时的错误，按类别给出不同的退出状态，包装本程序的脚本据此区分失败的原因
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// 命令行、环境变量或配置文件有误，或者引用的文件(模板、场景、证书等)无效
+    #[error("{0}")]
+    Config(String),
+    /// `--once` 时样本没有送达端点
+    #[error("{}: {}", tr("端点不可达"), .0)]
+    Unreachable(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// 端点返回的非2xx状态
+    #[error("HTTP {text}")]
+    Status { code: u16, text: String },
+    /// 运行中的其他错误
+    #[error("{0}")]
+    Runtime(String),
+}
+
+impl Error {
+    pub fn config(error: impl std::fmt::Display) -> Self {
+        Error::Config(error.to_string())
+    }
+
+    pub fn runtime(error: impl std::fmt::Display) -> Self {
+        Error::Runtime(error.to_string())
+    }
+
+    /// 在日志和停止事件中标明错误类别
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Config(_) => "config",
+            Error::Unreachable(_) => "unreachable",
+            Error::Io(_) => "io",
+            Error::Json(_) => "json",
+            Error::Status { .. } | Error::Runtime(_) => "runtime",
+        }
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Config(_) => EXIT_CONFIG,
+            Error::Unreachable(_) => EXIT_UNREACHABLE,
+            Error::Io(_) | Error::Json(_) | Error::Status { .. } | Error::Runtime(_) => EXIT_RUNTIME,
+        }
+    }
+
+    /// 附在停止事件中的错误
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport { kind: self.kind(), message: self.to_string(), exit_code: self.exit_code() }
+    }
+}
+
+impl From<String> for Error {
+    fn from(error: String) -> Self {
+        Error::Runtime(error)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(error: &str) -> Self {
+        Error::Runtime(error.to_string())
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Error::Runtime(error.to_string())
+    }
+}
+
+/// 错误的类别、信息和退出状态
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    pub kind: &'static str,
+    pub message: String,
+    pub exit_code: u8,
+}
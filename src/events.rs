@@ -0,0 +1,328 @@
+// src/events.rs
+use crate::alert;
+use crate::breaker::SinkHealth;
+use crate::error::Error;
+use crate::i18n::tr;
+use crate::lifecycle::Change;
+use crate::template_sink::TemplateSink;
+use crate::SystemStats;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// 进程退出后这段时间内同名的进程再启动时视为重启
+const RESTART_WINDOW: Duration = Duration::from_secs(300);
+
+/// 事件文件最多每隔这么久清理一次过期的事件
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 状态变化的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    /// 网卡出现或连接恢复
+    InterfaceUp,
+    /// 网卡断开连接或消失
+    InterfaceDown,
+    MountAdded,
+    MountRemoved,
+    SwapEnabled,
+    SwapDisabled,
+    /// 符合 `--proc-name` 等条件的进程退出后，同名的进程在5分钟内重新启动
+    ProcessRestarted,
+    /// 告警规则开始触发
+    ThresholdCrossed,
+    /// 告警规则解除
+    ThresholdCleared,
+}
+
+impl Kind {
+    fn message(self) -> &'static str {
+        match self {
+            Kind::InterfaceUp => tr("网卡已连接"),
+            Kind::InterfaceDown => tr("网卡已断开"),
+            Kind::MountAdded => tr("新的挂载点"),
+            Kind::MountRemoved => tr("挂载点已卸载"),
+            Kind::SwapEnabled => tr("交换空间已启用"),
+            Kind::SwapDisabled => tr("交换空间已停用"),
+            Kind::ProcessRestarted => tr("进程已重启"),
+            Kind::ThresholdCrossed => tr("告警触发"),
+            Kind::ThresholdCleared => tr("告警解除"),
+        }
+    }
+}
+
+/// 一次状态变化。与周期性的样本分开，只在变化时产生，接收端不需要比较相邻的样本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub kind: Kind,
+    pub timestamp: DateTime<Utc>,
+    /// 网卡名、挂载点、进程名或告警规则的名称
+    pub subject: String,
+    /// 补充信息，例如磁盘容量、前后的进程号、告警的值和阈值
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub detail: BTreeMap<String, Value>,
+}
+
+/// 比较相邻的样本，得到网卡、挂载点和交换空间的变化，由 `lifecycle` 得到进程重启，由告警引擎得到阈值的越过和解除。
+/// 第一个样本只记下当前的状态；采集项没有结果(例如被 `--disable`)时不比较这一部分
+#[derive(Default)]
+pub struct Detector {
+    /// 上一个样本中的网卡和是否处于连接状态
+    interfaces: Option<HashMap<String, bool>>,
+    /// 上一个样本中的挂载点和容量
+    mounts: Option<BTreeMap<String, u64>>,
+    swap: Option<u64>,
+    /// 最近退出的进程：进程名 → (进程号, 最后一次看到的时间)
+    exited: HashMap<String, (u32, DateTime<Utc>)>,
+}
+
+impl Detector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn detect(&mut self, stats: &SystemStats, alerts: &[alert::Event]) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut push = |kind: Kind, subject: &str, detail: &[(&str, Value)]| {
+            events.push(Event {
+                kind,
+                timestamp: stats.timestamp,
+                subject: subject.to_string(),
+                detail: detail.iter().map(|(key, value)| (key.to_string(), value.clone())).collect(),
+            });
+        };
+
+        if !stats.net.is_empty() {
+            // 没有连接状态的网卡只要存在就算连接
+            let current: HashMap<String, bool> = stats.net.iter().map(|(name, net)| (name.clone(), net.up != Some(false))).collect();
+            if let Some(previous) = &self.interfaces {
+                for (name, &up) in &current {
+                    match previous.get(name) {
+                        Some(&was_up) if was_up == up => {}
+                        Some(_) => push(if up { Kind::InterfaceUp } else { Kind::InterfaceDown }, name, &[]),
+                        None if up => push(Kind::InterfaceUp, name, &[("added", Value::Bool(true))]),
+                        None => {}
+                    }
+                }
+                for (name, _) in previous.iter().filter(|(name, up)| **up && !current.contains_key(*name)) {
+                    push(Kind::InterfaceDown, name, &[("removed", Value::Bool(true))]);
+                }
+            }
+            self.interfaces = Some(current);
+        }
+
+        if !stats.disk.is_empty() {
+            let current: BTreeMap<String, u64> = stats.disk.iter().map(|(mount, disk)| (mount.clone(), disk.total)).collect();
+            if let Some(previous) = &self.mounts {
+                for (mount, total) in current.iter().filter(|(mount, _)| !previous.contains_key(*mount)) {
+                    push(Kind::MountAdded, mount, &[("total", Value::from(*total))]);
+                }
+                for (mount, total) in previous.iter().filter(|(mount, _)| !current.contains_key(*mount)) {
+                    push(Kind::MountRemoved, mount, &[("total", Value::from(*total))]);
+                }
+            }
+            self.mounts = Some(current);
+        }
+
+        if let Some(swap) = &stats.swap {
+            match self.swap {
+                Some(0) if swap.total > 0 => push(Kind::SwapEnabled, "swap", &[("total", Value::from(swap.total))]),
+                Some(total) if total > 0 && swap.total == 0 => push(Kind::SwapDisabled, "swap", &[("total", Value::from(total))]),
+                _ => {}
+            }
+            self.swap = Some(swap.total);
+        }
+
+        // 同一次采集中先退出再启动的也算重启
+        for exit in stats.lifecycle.iter().filter(|event| event.event == Change::Exit) {
+            self.exited.insert(exit.name.clone(), (exit.pid, exit.last_seen.unwrap_or(stats.timestamp)));
+        }
+        for start in stats.lifecycle.iter().filter(|event| event.event == Change::Start) {
+            if let Some((pid, last_seen)) = self.exited.remove(&start.name) {
+                let down = (start.started - last_seen).num_seconds().max(0);
+                push(Kind::ProcessRestarted, &start.name, &[
+                    ("previous_pid", Value::from(pid)),
+                    ("pid", Value::from(start.pid)),
+                    ("down_s", Value::from(down)),
+                ]);
+            }
+        }
+        let window = chrono::Duration::from_std(RESTART_WINDOW).unwrap_or_default();
+        self.exited.retain(|_, (_, last_seen)| stats.timestamp - *last_seen <= window);
+
+        for alert in alerts.iter().filter(|alert| !alert.repeat) {
+            let kind = match alert.state {
+                alert::State::Firing => Kind::ThresholdCrossed,
+                alert::State::Resolved => Kind::ThresholdCleared,
+            };
+            let mut detail = vec![
+                ("expr", Value::from(alert.expr.clone())),
+                ("value", Value::from(alert.value)),
+                ("threshold", Value::from(alert.threshold)),
+            ];
+            if !alert.instance.is_empty() {
+                detail.push(("instance", Value::from(alert.instance.clone())));
+            }
+            push(kind, &alert.rule, &detail);
+        }
+        events
+    }
+}
+
+/// `--event-sink` 的取值
+enum Target {
+    Endpoint,
+    Log,
+    File(PathBuf),
+    Template(String),
+}
+
+impl Target {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "endpoint" => Some(Target::Endpoint),
+            "log" => Some(Target::Log),
+            _ => match value.strip_prefix("file:") {
+                Some(path) if !path.is_empty() => Some(Target::File(PathBuf::from(path))),
+                Some(_) => None,
+                None => crate::template_sink::template_sink_arg(value).ok().map(Target::Template),
+            },
+        }
+    }
+}
+
+/// `--event-sink` 的校验：`endpoint`、`log`、`file:PATH` 或与 `--template-sink` 相同的 `TEMPLATE=TARGET`
+pub fn event_sink_arg(value: &str) -> Result<String, String> {
+    match Target::parse(value) {
+        Some(_) => Ok(value.to_string()),
+        None => Err(format!("{}: {}", tr("无效的事件输出"), value)),
+    }
+}
+
+/// 逐行追加JSON的事件文件，超过保留时间的事件定期删除
+struct EventFile {
+    path: PathBuf,
+    retention: Duration,
+    pruned: Option<Instant>,
+}
+
+impl EventFile {
+    fn write(&mut self, events: &[Event]) -> std::io::Result<()> {
+        if self.pruned.is_none_or(|pruned| pruned.elapsed() >= PRUNE_INTERVAL) {
+            self.pruned = Some(Instant::now());
+            self.prune()?;
+        }
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        for event in events {
+            writeln!(file, "{}", serde_json::to_string(event)?)?;
+        }
+        Ok(())
+    }
+
+    /// 写到临时文件再替换，无法解析的行保留
+    fn prune(&self) -> std::io::Result<()> {
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let cutoff = Utc::now() - chrono::Duration::from_std(self.retention).unwrap_or(chrono::Duration::MAX);
+        let kept: Vec<&str> = text
+            .lines()
+            .filter(|line| serde_json::from_str::<Event>(line).map_or(true, |event| event.timestamp >= cutoff))
+            .collect();
+        if kept.len() == text.lines().count() {
+            return Ok(());
+        }
+        let temp = self.path.with_extension("tmp");
+        std::fs::write(&temp, kept.iter().map(|line| format!("{}\n", line)).collect::<String>())?;
+        std::fs::rename(&temp, &self.path)
+    }
+}
+
+/// 把状态变化分发给 `--event-sink` 指定的各个输出；发给端点的由调用方作为 `change` 事件放入发送队列
+pub struct Bus {
+    endpoint: bool,
+    log: bool,
+    files: Vec<EventFile>,
+    templates: Vec<TemplateSink>,
+}
+
+impl Bus {
+    /// 模板输出使用 `client`、`timeout` 和 `breaker`，与 [`TemplateSink::load`] 相同；事件文件中的事件保留 `retention`
+    pub fn new(
+        sinks: &[String],
+        retention: Duration,
+        client: reqwest::Client,
+        timeout: Duration,
+        breaker: (u32, Duration),
+    ) -> Result<Self, Error> {
+        let mut bus = Self { endpoint: false, log: false, files: Vec::new(), templates: Vec::new() };
+        for sink in sinks {
+            match Target::parse(sink).ok_or_else(|| Error::config(format!("{}: {}", tr("无效的事件输出"), sink)))? {
+                Target::Endpoint => bus.endpoint = true,
+                Target::Log => bus.log = true,
+                Target::File(path) => bus.files.push(EventFile { path, retention, pruned: None }),
+                Target::Template(value) => bus.templates.push(TemplateSink::load(&value, client.clone(), timeout, breaker)?),
+            }
+        }
+        Ok(bus)
+    }
+
+    /// 是否有任何输出，没有时不需要检测
+    pub fn is_empty(&self) -> bool {
+        !self.endpoint && !self.log && self.files.is_empty() && self.templates.is_empty()
+    }
+
+    /// 是否要把事件发给端点
+    pub fn to_endpoint(&self) -> bool {
+        self.endpoint
+    }
+
+    /// 每次采集后调用，没有事件时也调用，以便定期清理事件文件
+    pub fn publish(&mut self, events: &[Event]) {
+        if self.log {
+            for event in events {
+                let detail = serde_json::to_string(&event.detail).unwrap_or_default();
+                tracing::info!(kind = ?event.kind, subject = %event.subject, %detail, "{}", event.kind.message());
+            }
+        }
+        for file in &mut self.files {
+            if let Err(e) = file.write(events) {
+                tracing::warn!(path = %file.path.display(), error = %e, "{}", tr("写入事件文件失败"));
+            }
+        }
+        if events.is_empty() {
+            return;
+        }
+        let body = serde_json::to_string(events).unwrap_or_default();
+        for template in &self.templates {
+            if !template.export(body.clone()) {
+                tracing::warn!(template = template.name(), events = events.len(), "{}", tr("模板输出忙，事件被丢弃"));
+            }
+        }
+    }
+
+    /// 模板输出的断路器状态，与发送样本的模板输出一样以 `template:名称` 为键
+    pub fn health(&self) -> BTreeMap<String, SinkHealth> {
+        self.templates.iter().map(|template| (format!("template:{}", template.name()), template.health())).collect()
+    }
+
+    /// 等模板输出发送完已排队的事件，退出前调用
+    pub async fn close(self) {
+        futures::future::join_all(self.templates.into_iter().map(TemplateSink::close)).await;
+    }
+}
+
+/// 终端界面和日志中显示的一行，例如 `网卡已断开: eth0`
+pub fn describe(event: &Event) -> String {
+    format!("{}: {}", event.kind.message(), event.subject)
+}
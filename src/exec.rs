@@ -0,0 +1,130 @@
+// src/exec.rs
+use crate::collector::{Collector, Context, Section};
+use crate::i18n::tr;
+use crate::mock;
+use crate::Collectors;
+use serde_json::{Map, Value};
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// 等待命令结束时检查的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 运行命令的shell：Unix上为 `sh -c`，Windows上为 `cmd /C`
+pub(crate) const SHELL: [&str; 2] = if cfg!(windows) { ["cmd", "/C"] } else { ["sh", "-c"] };
+
+/// `--exec NAME=COMMAND` 的校验，名称不能与内置采集项和 `probes`、`http`、`dns`、`tls`、`logs`、`lifecycle`、`churn`、`talkers` 相同
+pub fn exec_arg(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((name, command)) if !name.trim().is_empty() && !command.trim().is_empty() => {
+            if Collectors::NAMES.contains(&name.trim()) || Collectors::OPTIONAL.contains(&name.trim()) || name.trim() == mock::NAME {
+                return Err(format!("{}: {}", tr("命令采集项不能与内置采集项同名"), name.trim()));
+            }
+            Ok(value.to_string())
+        }
+        _ => Err(format!("{}: {}", tr("无效的命令采集项"), value)),
+    }
+}
+
+/// 定期用 `sh -c`(Windows上为 `cmd /C`)运行一条命令，把标准输出解析为JSON或 `key=value` 行，以名称为键加入样本；
+/// 命令失败或超时时记录警告，样本中为 `{"error": "..."}`
+pub struct Exec {
+    name: &'static str,
+    command: String,
+    timeout: Duration,
+}
+
+impl Exec {
+    pub fn new(name: &str, command: &str, timeout: Duration) -> Self {
+        // 采集项的名称在整个运行期间不变，只在启动时创建一次
+        let name = Box::leak(name.trim().to_string().into_boxed_str());
+        Self { name, command: command.to_string(), timeout }
+    }
+
+    fn run(&self) -> Result<Value, String> {
+        let mut child = Command::new(SHELL[0])
+            .arg(SHELL[1])
+            .arg(&self.command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+
+        // 在单独的线程中读取输出，输出很多时命令不会因为管道写满而卡住
+        let mut stdout = child.stdout.take().unwrap();
+        let mut stderr = child.stderr.take().unwrap();
+        let output = std::thread::spawn(move || {
+            let mut out = String::new();
+            let _ = stdout.read_to_string(&mut out);
+            out
+        });
+        let errors = std::thread::spawn(move || {
+            let mut err = String::new();
+            let _ = stderr.read_to_string(&mut err);
+            err
+        });
+
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+                break status;
+            }
+            if started.elapsed() >= self.timeout {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("{} ({})", tr("命令超时"), humantime::format_duration(self.timeout)));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+        let output = output.join().unwrap_or_default();
+        let errors = errors.join().unwrap_or_default();
+
+        if !status.success() {
+            let reason = errors.lines().find(|line| !line.trim().is_empty()).unwrap_or("").trim();
+            return Err(format!("{}: {} {}", tr("命令失败"), status, reason).trim_end().to_string());
+        }
+        parse_output(&output)
+    }
+}
+
+impl Collector for Exec {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn collect(&mut self, _ctx: &mut Context) -> Section {
+        match self.run() {
+            Ok(value) => Section::Custom(value),
+            Err(error) => {
+                tracing::warn!(collector = self.name, command = %self.command, %error, "{}", tr("命令采集项失败"));
+                Section::Custom(serde_json::json!({ "error": error }))
+            }
+        }
+    }
+}
+
+/// 输出是JSON时原样使用，否则按 `key=value` 行解析，数值和 `true`/`false` 转换为对应的类型；空行和 `#` 开头的行忽略
+fn parse_output(output: &str) -> Result<Value, String> {
+    if let Ok(value) = serde_json::from_str::<Value>(output) {
+        return Ok(value);
+    }
+    let mut fields = Map::new();
+    for line in output.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("{}: {}", tr("无法解析命令输出"), line));
+        };
+        let value = value.trim();
+        let value = match value.parse::<f64>() {
+            Ok(number) if number.is_finite() => serde_json::from_str(value).unwrap_or(Value::from(number)),
+            _ => match value {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => Value::String(value.to_string()),
+            },
+        };
+        fields.insert(key.trim().to_string(), value);
+    }
+    Ok(Value::Object(fields))
+}
@@ -0,0 +1,336 @@
+// src/filter.rs
+use crate::i18n::tr;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// 按通配符(`*` 和 `?`)选择要显示的名称：指定了 show 时只显示匹配的名称，匹配 hide 的名称总是隐藏
+#[derive(Debug, Clone, Default)]
+pub struct NameFilter {
+    show: Vec<String>,
+    hide: Vec<String>,
+}
+
+impl NameFilter {
+    pub fn new(show: Vec<String>, hide: Vec<String>) -> Self {
+        Self { show, hide }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let shown = self.show.is_empty() || self.show.iter().any(|p| glob_match(p, name));
+        shown && !self.hide.iter().any(|p| glob_match(p, name))
+    }
+}
+
+/// 按正则表达式选择名称：指定了 include 时只保留匹配的名称，匹配 exclude 的名称总是丢弃
+#[derive(Debug, Clone, Default)]
+pub struct RegexFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl RegexFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| Regex::new(p).map_err(|e| format!("{} {}: {}", tr("无效的正则表达式"), p, e)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|r| r.is_match(name));
+        included && !self.exclude.iter().any(|r| r.is_match(name))
+    }
+}
+
+/// 进程统计和进程列表使用的过滤条件：进程名、用户(用户名或UID)和cgroup，未指定的条件不过滤
+#[derive(Debug, Clone, Default)]
+pub struct ProcessFilter {
+    pub name: RegexFilter,
+    pub users: Vec<String>,
+    pub cgroup: RegexFilter,
+}
+
+/// 显示时使用的网卡和挂载点过滤条件
+#[derive(Debug, Clone, Default)]
+pub struct DisplayFilter {
+    pub iface: NameFilter,
+    pub mount: NameFilter,
+}
+
+/// 发送时总是保留的字段，接收端据此排序、去重和确认
+const REQUIRED_FIELDS: [&str; 3] = ["version", "seq", "timestamp"];
+
+/// 按 `--fields` 裁剪请求体中的样本：`PATH` 只保留这些字段，`-PATH` 去掉这些字段。
+/// 路径的各级用 `.` 分隔，可以用 `*` 和 `?` 通配，名称中有 `.` 时写成 `["名称"]`；数组对其中的每个元素生效
+#[derive(Debug, Clone, Default)]
+pub struct FieldFilter {
+    include: Vec<Vec<String>>,
+    exclude: Vec<Vec<String>>,
+}
+
+impl FieldFilter {
+    pub fn new(values: &[String]) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for value in values {
+            match value.trim().strip_prefix('-') {
+                Some(path) => filter.exclude.push(parse_path(path).ok_or_else(|| invalid_field(value))?),
+                None => {
+                    let path = value.trim().strip_prefix('+').unwrap_or(value.trim());
+                    filter.include.push(parse_path(path).ok_or_else(|| invalid_field(value))?);
+                }
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// 裁剪一个样本
+    pub fn apply(&self, sample: &mut Value) {
+        let Value::Object(fields) = sample else { return };
+        let include: Vec<&[String]> = self.include.iter().map(Vec::as_slice).collect();
+        let exclude: Vec<&[String]> = self.exclude.iter().map(Vec::as_slice).collect();
+        prune_object(fields, (!include.is_empty()).then_some(&include), &exclude, &REQUIRED_FIELDS);
+    }
+}
+
+/// `include` 为 `None` 时全部保留，只按 `exclude` 去掉
+fn prune(value: &mut Value, include: Option<&[&[String]]>, exclude: &[&[String]]) {
+    match value {
+        Value::Object(fields) => prune_object(fields, include, exclude, &[]),
+        Value::Array(items) => items.iter_mut().for_each(|item| prune(item, include, exclude)),
+        _ => {}
+    }
+}
+
+/// `keep` 中的字段不受影响
+fn prune_object(fields: &mut Map<String, Value>, include: Option<&[&[String]]>, exclude: &[&[String]], keep: &[&str]) {
+    /// 第一级与 `key` 匹配的路径的其余部分
+    fn matching<'a>(paths: &[&'a [String]], key: &str) -> Vec<&'a [String]> {
+        paths.iter().filter(|path| glob_match(&path[0], key)).map(|path| &path[1..]).collect()
+    }
+
+    fields.retain(|key, value| {
+        if keep.contains(&key.as_str()) {
+            return true;
+        }
+        let exclude = matching(exclude, key);
+        if exclude.iter().any(|rest| rest.is_empty()) {
+            return false;
+        }
+        let include = match include {
+            None => None,
+            Some(include) => {
+                let include = matching(include, key);
+                if include.is_empty() {
+                    return false;
+                }
+                // 路径到这一级为止时保留整个字段
+                (!include.iter().any(|rest| rest.is_empty())).then_some(include)
+            }
+        };
+        if include.is_some() || !exclude.is_empty() {
+            prune(value, include.as_deref(), &exclude);
+        }
+        true
+    });
+}
+
+/// `disk.*.total`、`tls["api.example.com"].subject`
+fn parse_path(path: &str) -> Option<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut rest = path;
+    loop {
+        let (segment, after) = match rest.strip_prefix("[\"") {
+            Some(quoted) => {
+                let (segment, after) = quoted.split_once("\"]")?;
+                (segment, after)
+            }
+            None => match rest.find(['.', '[']) {
+                Some(end) => rest.split_at(end),
+                None => (rest, ""),
+            },
+        };
+        if segment.is_empty() {
+            return None;
+        }
+        segments.push(segment.to_string());
+        rest = match after.strip_prefix('.') {
+            Some(next) => next,
+            None if after.is_empty() => return Some(segments),
+            None if after.starts_with('[') => after,
+            None => return None,
+        };
+    }
+}
+
+fn invalid_field(value: &str) -> String {
+    format!("{}: {}", tr("无效的字段"), value)
+}
+
+/// `--fields` 的校验
+pub fn field_arg(value: &str) -> Result<String, String> {
+    FieldFilter::new(&[value.to_string()]).map(|_| value.trim().to_string())
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp + 1;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn apply(fields: &[&str], mut sample: Value) -> Value {
+        let fields: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+        FieldFilter::new(&fields).unwrap().apply(&mut sample);
+        sample
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(glob_match("eth*", "eth0"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("e?h0", "eth0"));
+        assert!(glob_match("*0", "eth0"));
+        assert!(glob_match("/mnt/*/data", "/mnt/a/b/data"));
+        assert!(glob_match("a**b", "ab"));
+        assert!(!glob_match("eth?", "eth10"));
+        assert!(!glob_match("eth", "eth0"));
+        assert!(!glob_match("?", ""));
+        assert!(glob_match("网*", "网卡"));
+    }
+
+    #[test]
+    fn filters_names() {
+        let all = NameFilter::default();
+        assert!(all.matches("lo"));
+        let filter = NameFilter::new(vec!["eth*".to_string(), "wl*".to_string()], vec!["eth1".to_string()]);
+        assert!(filter.matches("eth0"));
+        assert!(filter.matches("wlan0"));
+        assert!(!filter.matches("eth1"));
+        assert!(!filter.matches("lo"));
+        assert!(!NameFilter::new(vec![], vec!["docker*".to_string()]).matches("docker0"));
+    }
+
+    #[test]
+    fn filters_by_regex() {
+        assert!(RegexFilter::default().is_empty());
+        assert!(RegexFilter::default().matches("anything"));
+        let filter = RegexFilter::new(&["^nginx".to_string(), "php".to_string()], &["worker$".to_string()]).unwrap();
+        assert!(!filter.is_empty());
+        assert!(filter.matches("nginx"));
+        assert!(filter.matches("php-fpm"));
+        assert!(!filter.matches("nginx-worker"));
+        assert!(!filter.matches("sshd"));
+        assert!(RegexFilter::new(&[], &["(".to_string()]).is_err());
+    }
+
+    #[test]
+    fn parses_paths() {
+        assert_eq!(parse_path("disk.*.total").unwrap(), ["disk", "*", "total"]);
+        assert_eq!(parse_path(r#"tls["api.example.com"].subject"#).unwrap(), ["tls", "api.example.com", "subject"]);
+        assert_eq!(parse_path(r#"tls["a.b"]["c.d"]"#).unwrap(), ["tls", "a.b", "c.d"]);
+        assert_eq!(parse_path(r#"["mem"]"#).unwrap(), ["mem"]);
+        for path in ["", "disk.", ".disk", "disk..total", r#"tls["a"#, "tls[a]", r#"tls[""]"#, r#"tls["a"]x"#] {
+            assert!(parse_path(path).is_none(), "{}", path);
+        }
+        assert!(FieldFilter::new(&["-".to_string()]).is_err());
+        assert!(FieldFilter::default().is_empty());
+        assert_eq!(field_arg(" +mem.used ").unwrap(), "+mem.used");
+        assert!(field_arg("mem..used").is_err());
+    }
+
+    #[test]
+    fn keeps_included_fields_and_required_ones() {
+        let sample = json!({
+            "version": 1, "seq": 2, "timestamp": "t", "host": "a",
+            "mem": { "total": 10, "used": 5 },
+            "disk": { "/": { "total": 10, "used": 1 }, "/boot": { "total": 2, "used": 1 } },
+            "top": [{ "pid": 1, "name": "a", "cpu": 1.0 }, { "pid": 2, "name": "b", "cpu": 2.0 }],
+        });
+        assert_eq!(
+            apply(&["mem.used", "disk.*.total", "top.pid"], sample.clone()),
+            json!({
+                "version": 1, "seq": 2, "timestamp": "t",
+                "mem": { "used": 5 },
+                "disk": { "/": { "total": 10 }, "/boot": { "total": 2 } },
+                "top": [{ "pid": 1 }, { "pid": 2 }],
+            })
+        );
+        // 同时写了整个字段和其中一部分时保留整个字段
+        assert_eq!(apply(&["mem", "mem.used"], sample.clone())["mem"], json!({ "total": 10, "used": 5 }));
+        assert_eq!(
+            apply(&[r#"disk["/"]"#], sample)["disk"],
+            json!({ "/": { "total": 10, "used": 1 } })
+        );
+    }
+
+    #[test]
+    fn removes_excluded_fields() {
+        let sample = json!({
+            "version": 1, "seq": 2, "timestamp": "t", "host": "a",
+            "mem": { "total": 10, "used": 5 },
+            "disk": { "/": { "total": 10, "used": 1 }, "/boot": { "total": 2, "used": 1 } },
+            "top": [{ "pid": 1, "cmd": "x" }],
+        });
+        assert_eq!(
+            apply(&["-host", "-disk.*.used", "-top.cmd", "-seq"], sample.clone()),
+            json!({
+                "version": 1, "seq": 2, "timestamp": "t",
+                "mem": { "total": 10, "used": 5 },
+                "disk": { "/": { "total": 10 }, "/boot": { "total": 2 } },
+                "top": [{ "pid": 1 }],
+            })
+        );
+        // 去掉的字段优先于保留的字段
+        assert_eq!(
+            apply(&["disk", "-disk./boot"], sample.clone()),
+            json!({ "version": 1, "seq": 2, "timestamp": "t", "disk": { "/": { "total": 10, "used": 1 } } })
+        );
+        assert_eq!(apply(&["mem", "-mem"], sample)["mem"], Value::Null);
+        let mut batch = json!([1, "x"]);
+        FieldFilter::new(&["mem".to_string()]).unwrap().apply(&mut batch);
+        assert_eq!(batch, json!([1, "x"]));
+    }
+}
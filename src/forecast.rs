@@ -0,0 +1,116 @@
+// src/forecast.rs
+use crate::SystemStats;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+/// 窗口内最多保留的点数，样本更密时按 窗口/点数 的间隔抽取
+const MAX_POINTS: u32 = 360;
+
+/// 每MB的字节数
+const MB: f64 = 1e6;
+
+/// 内存和各挂载点用量在窗口内的线性趋势，使用 `--forecast` 时才有
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Forecast {
+    /// 拟合所用数据的时间跨度(秒)，不超过 `--forecast` 的窗口
+    pub span_s: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mem: Option<Trend>,
+    /// 按挂载点
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub disk: BTreeMap<String, Trend>,
+}
+
+/// 用量的变化速度和按这个速度用满的时间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trend {
+    /// 每小时用量的变化(MB)，减少时为负
+    pub growth_mb_per_hour: f64,
+    /// 按当前速度还有多少小时用满，用量没有增长时省略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours_until_full: Option<f64>,
+}
+
+impl Trend {
+    /// `slope` 为每秒增长的字节数
+    fn new(slope: f64, used: u64, total: u64) -> Self {
+        let hours_until_full = (slope > 0.0).then(|| round(total.saturating_sub(used) as f64 / slope / 3600.0));
+        Self { growth_mb_per_hour: round(slope * 3600.0 / MB), hours_until_full }
+    }
+}
+
+/// 一个抽取的点：内存和各挂载点已用的字节数
+struct Point {
+    at: DateTime<Utc>,
+    mem: Option<u64>,
+    disk: BTreeMap<String, u64>,
+}
+
+/// 保留最近 `window` 内的用量，用最小二乘法拟合直线
+pub struct Forecaster {
+    window: Duration,
+    points: VecDeque<Point>,
+}
+
+impl Forecaster {
+    pub fn new(window: Duration) -> Self {
+        Self { window, points: VecDeque::new() }
+    }
+
+    /// 记下样本中的用量，返回窗口内的趋势；数据的跨度不到窗口的十分之一时没有
+    pub fn update(&mut self, stats: &SystemStats) -> Option<Forecast> {
+        let step = chrono::Duration::from_std(self.window / MAX_POINTS).ok()?;
+        if self.points.back().is_none_or(|last| stats.timestamp - last.at >= step) {
+            self.points.push_back(Point {
+                at: stats.timestamp,
+                mem: stats.mem.as_ref().map(|mem| mem.used),
+                disk: stats.disk.iter().map(|(mount, disk)| (mount.clone(), disk.used)).collect(),
+            });
+        }
+        let oldest = stats.timestamp - chrono::Duration::from_std(self.window).ok()?;
+        while self.points.front().is_some_and(|point| point.at < oldest) {
+            self.points.pop_front();
+        }
+
+        let first = self.points.front()?.at;
+        let span = (stats.timestamp - first).to_std().unwrap_or_default();
+        if span < self.window / 10 {
+            return None;
+        }
+        let seconds = |point: &Point| (point.at - first).num_milliseconds() as f64 / 1000.0;
+        let mem = stats.mem.as_ref().and_then(|mem| {
+            let slope = slope(self.points.iter().filter_map(|point| Some((seconds(point), point.mem? as f64))))?;
+            Some(Trend::new(slope, mem.used, mem.total))
+        });
+        let disk = stats
+            .disk
+            .iter()
+            .filter_map(|(mount, disk)| {
+                let slope = slope(self.points.iter().filter_map(|point| Some((seconds(point), *point.disk.get(mount)? as f64))))?;
+                Some((mount.clone(), Trend::new(slope, disk.used, disk.total)))
+            })
+            .collect();
+        Some(Forecast { span_s: span.as_secs(), mem, disk })
+    }
+}
+
+/// 最小二乘法拟合的斜率，少于3个点或时间都相同时没有
+fn slope(points: impl Iterator<Item = (f64, f64)>) -> Option<f64> {
+    let points: Vec<(f64, f64)> = points.collect();
+    if points.len() < 3 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let (mean_x, mean_y) = points.iter().fold((0.0, 0.0), |(x, y), (px, py)| (x + px / n, y + py / n));
+    let (covariance, variance) = points
+        .iter()
+        .fold((0.0, 0.0), |(c, v), (x, y)| (c + (x - mean_x) * (y - mean_y), v + (x - mean_x) * (x - mean_x)));
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// 保留两位小数
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
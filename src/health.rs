@@ -0,0 +1,120 @@
+// src/health.rs
+use crate::i18n::tr;
+use crate::serve::{read_request, respond};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
+
+/// 存活检查容忍采集循环停顿的刷新间隔数，以及最短的容忍时间
+const LIVENESS_TICKS: u32 = 3;
+const LIVENESS_MIN: Duration = Duration::from_secs(10);
+
+/// 读取请求头的超时时间
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 进程内部的健康状态，采集循环写入，`GET /healthz` 和 `GET /readyz` 读取
+#[derive(Default)]
+pub struct Health {
+    state: RwLock<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// 最近一次完成采集的时间和当时的刷新间隔
+    collected: Option<(Instant, Duration)>,
+    /// 没有通过的就绪检查及原因
+    failing: BTreeMap<&'static str, String>,
+}
+
+impl Health {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// 采集循环完成了一次采集，`tick` 为当前的刷新间隔
+    pub fn collected(&self, tick: Duration) {
+        self.state.write().unwrap().collected = Some((Instant::now(), tick));
+    }
+
+    /// 更新一项就绪检查，`failure` 为没有通过的原因，`None` 表示通过
+    pub fn check(&self, name: &'static str, failure: Option<String>) {
+        let mut state = self.state.write().unwrap();
+        match failure {
+            Some(reason) => state.failing.insert(name, reason),
+            None => state.failing.remove(name),
+        };
+    }
+
+    /// 存活：采集循环在最近几个刷新间隔内完成过采集，卡住时失败；还没有完成第一次采集时视为存活
+    pub fn live(&self) -> Result<(), String> {
+        match self.state.read().unwrap().collected {
+            Some((at, tick)) if at.elapsed() > (tick * LIVENESS_TICKS).max(LIVENESS_MIN) => {
+                let elapsed = Duration::from_secs(at.elapsed().as_secs());
+                Err(format!("{} ({})", tr("采集循环没有响应"), humantime::format_duration(elapsed)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 就绪：存活、已经采集到样本，且所有就绪检查都通过；失败时返回每项原因
+    pub fn ready(&self) -> Result<(), Vec<String>> {
+        let mut failures = Vec::new();
+        if let Err(reason) = self.live() {
+            failures.push(format!("live: {}", reason));
+        }
+        let state = self.state.read().unwrap();
+        if state.collected.is_none() {
+            failures.push(format!("collect: {}", tr("还没有采集到样本")));
+        }
+        failures.extend(state.failing.iter().map(|(name, reason)| format!("{}: {}", name, reason)));
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// `/healthz` 和 `/readyz` 的响应：通过时为 `ok`，否则为503和逐行的原因；其他路径返回 `None`
+    pub fn response(&self, path: &str) -> Option<(&'static str, String)> {
+        let result = match path {
+            "/healthz" => self.live().map_err(|reason| vec![reason]),
+            "/readyz" => self.ready(),
+            _ => return None,
+        };
+        Some(match result {
+            Ok(()) => ("200 OK", "ok\n".to_string()),
+            Err(failures) => ("503 Service Unavailable", failures.join("\n") + "\n"),
+        })
+    }
+}
+
+/// 只提供 `GET /healthz` 和 `GET /readyz` 的HTTP服务，`--health-listen` 使用
+pub async fn run(listener: TcpListener, health: Arc<Health>) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let health = health.clone();
+        tokio::spawn(async move {
+            let _ = handle(stream, health).await;
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream, health: Arc<Health>) -> std::io::Result<()> {
+    let Ok(request) = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream, 0)).await else {
+        return Ok(());
+    };
+    let (request, _) = request?;
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+
+    let (status, body) = match (method, health.response(path)) {
+        ("GET", Some(response)) => response,
+        ("GET", None) => ("404 Not Found", String::new()),
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+    respond(stream, status, "text/plain", &body, false).await
+}
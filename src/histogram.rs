@@ -0,0 +1,98 @@
+// src/histogram.rs
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 每个2的幂区间再细分的格数的位数：格宽不超过数值的1/128，分位数的相对误差小于1%
+const SUB_BITS: u32 = 8;
+const SUB: u64 = 1 << SUB_BITS;
+const HALF: u64 = SUB / 2;
+
+/// 耗时的分布(毫秒)，分位数取所在格的上限
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Percentiles {
+    /// 记录的次数
+    pub count: u64,
+    /// 合计的耗时，与 `count` 一起给出平均值
+    pub sum: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+/// 与HdrHistogram相同的对数-线性分格：以微秒记录，小于256的值各占一格，之后每个2的幂区间分为128格。
+/// 只保存各格的计数，内存占用取决于出现过的最大值而不是记录的次数，长时间运行也不会增长
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    count: u64,
+    sum: u64,
+    max: u64,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        let index = index(micros);
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.count += 1;
+        self.sum = self.sum.saturating_add(micros);
+        self.max = self.max.max(micros);
+    }
+
+    /// 启动以来的分布，还没有记录过时没有
+    pub fn percentiles(&self) -> Option<Percentiles> {
+        (self.count > 0).then(|| Percentiles {
+            count: self.count,
+            sum: millis(self.sum),
+            p50: millis(self.quantile(0.50)),
+            p95: millis(self.quantile(0.95)),
+            p99: millis(self.quantile(0.99)),
+            max: millis(self.max),
+        })
+    }
+
+    /// 至少有 `q` 比例的记录不超过的值(微秒)
+    fn quantile(&self, q: f64) -> u64 {
+        let rank = ((q * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return upper(index).min(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+/// 数值所在的格：最高位在第 `SUB_BITS` 位及以上时，右移到只剩 `SUB_BITS` 位，按移动的位数和剩下的高位分格
+fn index(value: u64) -> usize {
+    if value < SUB {
+        return value as usize;
+    }
+    let shift = 64 - value.leading_zeros() - SUB_BITS;
+    (SUB + (shift as u64 - 1) * HALF + ((value >> shift) - HALF)) as usize
+}
+
+/// 一格中最大的值
+fn upper(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB {
+        return index;
+    }
+    let shift = (index - SUB) / HALF + 1;
+    let sub = (index - SUB) % HALF + HALF;
+    (sub << shift) + ((1 << shift) - 1)
+}
+
+fn millis(micros: u64) -> f64 {
+    micros as f64 / 1000.0
+}
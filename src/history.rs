@@ -0,0 +1,118 @@
+// src/history.rs
+use crate::filter::NameFilter;
+use crate::SystemStats;
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// 最近若干个样本，用于绘制历史趋势，也可以按时间查询最近一段时间的样本
+pub struct History {
+    samples: VecDeque<SystemStats>,
+    capacity: usize,
+    /// 只保留这段时间内的样本，为空时只按数量保留
+    window: Option<Duration>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            window: None,
+        }
+    }
+
+    /// 保留最近 `window` 内的样本(例如最近60分钟)，样本数仍不超过 `capacity`
+    pub fn with_window(capacity: usize, window: Duration) -> Self {
+        Self { window: Some(window), ..Self::new(capacity) }
+    }
+
+    pub fn push(&mut self, stats: SystemStats) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        if let Some(window) = self.window.and_then(|w| chrono::Duration::from_std(w).ok()) {
+            let oldest = stats.timestamp - window;
+            while self.samples.front().is_some_and(|s| s.timestamp < oldest) {
+                self.samples.pop_front();
+            }
+        }
+        self.samples.push_back(stats);
+    }
+
+    pub fn latest(&self) -> Option<&SystemStats> {
+        self.samples.back()
+    }
+
+    /// 按时间顺序的所有样本
+    pub fn samples(&self) -> impl Iterator<Item = &SystemStats> {
+        self.samples.iter()
+    }
+
+    /// 时间不早于 `since` 的样本，按时间顺序
+    pub fn since(&self, since: DateTime<Utc>) -> impl Iterator<Item = &SystemStats> {
+        let start = self.samples.partition_point(|s| s.timestamp < since);
+        self.samples.range(start..)
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// 平均CPU使用率(%)
+    pub fn cpu(&self) -> Vec<u64> {
+        self.samples
+            .iter()
+            .map(|s| (s.cpu.iter().sum::<f32>() / s.cpu.len().max(1) as f32).round() as u64)
+            .collect()
+    }
+
+    /// 内存使用率(%)
+    pub fn mem(&self) -> Vec<u64> {
+        self.samples
+            .iter()
+            .map(|s| s.mem.as_ref().map_or(0, |m| (m.used * 100).checked_div(m.total).unwrap_or(0)))
+            .collect()
+    }
+
+    /// 网卡的总流量(接收+发送，字节/秒)
+    pub fn net(&self, interface: &str) -> Vec<u64> {
+        self.samples
+            .iter()
+            .map(|s| s.net.get(interface).map_or(0, |n| n.rx.unwrap_or(0) + n.tx.unwrap_or(0)))
+            .collect()
+    }
+
+    /// 所有匹配网卡的接收和发送速率之和(字节/秒)
+    pub fn traffic(&self, filter: &NameFilter) -> (Vec<u64>, Vec<u64>) {
+        self.samples
+            .iter()
+            .map(|s| {
+                s.net
+                    .iter()
+                    .filter(|(name, _)| filter.matches(name))
+                    .fold((0, 0), |(rx, tx), (_, n)| (rx + n.rx.unwrap_or(0), tx + n.tx.unwrap_or(0)))
+            })
+            .unzip()
+    }
+}
+
+/// 用方块字符绘制最近 `width` 个数值的迷你趋势图，`max` 为空时按数据最大值缩放
+pub fn sparkline(values: &[u64], width: usize, max: Option<u64>) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let max = max.unwrap_or_else(|| values.iter().copied().max().unwrap_or(0)).max(1);
+
+    values
+        .iter()
+        .map(|&v| {
+            let level = (v.min(max) * (SPARK_CHARS.len() as u64 - 1) + max / 2) / max;
+            SPARK_CHARS[level as usize]
+        })
+        .collect()
+}
@@ -0,0 +1,123 @@
+// src/hook.rs
+use crate::alert::{Notification, State};
+use crate::exec::SHELL;
+use crate::i18n::tr;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// 告警状态变化时运行的命令，`RULE=COMMAND`，规则名为 `*` 时对全部规则运行
+pub struct Hooks {
+    commands: Vec<(String, String)>,
+    timeout: Duration,
+    /// 同时运行的命令数上限，超过时排队等待
+    slots: Arc<Semaphore>,
+}
+
+/// `--alert-exec RULE=COMMAND` 的校验
+pub fn hook_arg(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((rule, command)) if !rule.trim().is_empty() && !command.trim().is_empty() => Ok(value.to_string()),
+        _ => Err(format!("{}: {}", tr("无效的告警命令"), value)),
+    }
+}
+
+impl Hooks {
+    pub fn new(commands: Vec<(String, String)>, timeout: Duration, concurrency: usize) -> Self {
+        Self { commands, timeout, slots: Arc::new(Semaphore::new(concurrency.max(1))) }
+    }
+
+    /// 用 `sh -c`(Windows上为 `cmd /C`)运行与规则匹配的命令：告警信息通过 `ALERT_*` 环境变量传递，标准输入为JSON格式的告警；
+    /// 超时的命令会被杀掉，试运行时只记录日志
+    pub fn run(&self, alert: &Notification, dry_run: bool, tasks: &mut JoinSet<()>) {
+        let event = alert.event;
+        let json = serde_json::to_string(alert).unwrap_or_default();
+        let state = match event.state {
+            State::Firing => "firing",
+            State::Resolved => "resolved",
+        };
+        let env = [
+            ("ALERT_RULE", event.rule.clone()),
+            ("ALERT_STATE", state.to_string()),
+            ("ALERT_INSTANCE", event.instance.clone()),
+            ("ALERT_EXPR", event.expr.clone()),
+            ("ALERT_VALUE", event.value.to_string()),
+            ("ALERT_THRESHOLD", event.threshold.to_string()),
+            ("ALERT_SINCE", event.since.to_rfc3339()),
+            ("ALERT_REPEAT", (event.repeat as u8).to_string()),
+            ("ALERT_HOST", alert.host.to_string()),
+        ];
+
+        for (_, command) in self.commands.iter().filter(|(rule, _)| rule == "*" || *rule == event.rule) {
+            let command = command.clone();
+            if dry_run {
+                tracing::info!(rule = %event.rule, %command, "{}", tr("试运行，告警命令未运行"));
+                continue;
+            }
+            let mut child = Command::new(SHELL[0]);
+            child
+                .arg(SHELL[1])
+                .arg(&command)
+                .envs(env.clone())
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true);
+            #[cfg(unix)]
+            child.process_group(0);
+            let (rule, json, timeout, slots) = (event.rule.clone(), json.clone(), self.timeout, self.slots.clone());
+
+            tasks.spawn(async move {
+                let Ok(_slot) = slots.acquire_owned().await else { return };
+                let mut child = match child.spawn() {
+                    Ok(child) => child,
+                    Err(e) => {
+                        tracing::warn!(%rule, %command, error = %e, "{}", tr("告警命令失败"));
+                        return;
+                    }
+                };
+                let pid = child.id();
+                if let Some(mut stdin) = child.stdin.take() {
+                    // 命令不读标准输入时写入会失败，忽略
+                    let _ = stdin.write_all(json.as_bytes()).await;
+                }
+                match tokio::time::timeout(timeout, child.wait_with_output()).await {
+                    Ok(Ok(output)) if output.status.success() => {
+                        tracing::info!(%rule, %command, "{}", tr("告警命令已完成"));
+                    }
+                    Ok(Ok(output)) => {
+                        let stderr = String::from_utf8_lossy(&output.stderr);
+                        tracing::warn!(%rule, %command, status = %output.status, stderr = %stderr.trim(), "{}", tr("告警命令失败"));
+                    }
+                    Ok(Err(e)) => tracing::warn!(%rule, %command, error = %e, "{}", tr("告警命令失败")),
+                    Err(_) => {
+                        if let Some(pid) = pid {
+                            kill_tree(pid);
+                        }
+                        tracing::warn!(%rule, %command, timeout_ms = timeout.as_millis() as u64, "{}", tr("告警命令超时，已终止"));
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// 终止超时的命令，连同它启动的子进程：Unix上命令在单独的进程组中运行，终止整个进程组
+#[cfg(unix)]
+fn kill_tree(pid: u32) {
+    unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) };
+}
+
+/// 终止超时的命令，连同它启动的子进程：Windows上用 `taskkill /T` 终止整个进程树
+#[cfg(windows)]
+fn kill_tree(pid: u32) {
+    let _ = std::process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
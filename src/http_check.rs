@@ -0,0 +1,152 @@
+// src/http_check.rs
+use crate::collector::{Collector, Context, Section};
+use crate::error::Error;
+use crate::i18n::tr;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// 采集项的名称
+pub const NAME: &str = "http";
+
+/// 一个检查在一次采集中的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpCheckStats {
+    /// 状态码符合预期且响应中包含预期的文本
+    pub ok: bool,
+    /// 没有收到响应(连接失败、超时等)时为 `null`
+    pub status: Option<u16>,
+    /// 从发出请求到读完响应的毫秒数，没有收到响应时为 `null`
+    pub response_ms: Option<f64>,
+    /// 没有通过的原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `--http-check [NAME=]URL [status=CODE] [contains=TEXT] [insecure]`
+#[derive(Debug, Clone)]
+struct Check {
+    name: String,
+    url: Url,
+    /// 预期的状态码，`x` 匹配任意一位，例如 `2xx`
+    status: String,
+    contains: Option<String>,
+    insecure: bool,
+}
+
+impl Check {
+    fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("{}: {}", tr("无效的HTTP检查"), value);
+        let value = value.trim();
+        // `contains=` 之后的内容都是要查找的文本，可以包含空格
+        let (value, contains) = match value.split_once(" contains=") {
+            Some((value, text)) if !text.is_empty() => (value, Some(text.to_string())),
+            Some(_) => return Err(invalid()),
+            None => (value, None),
+        };
+        let mut words = value.split_whitespace();
+        let target = words.next().ok_or_else(invalid)?;
+        // URL的查询参数中也可能有 `=`，等号前面没有 `://` 时才是名称
+        let (name, url) = match target.split_once('=') {
+            Some((name, url)) if !name.contains("://") && !name.is_empty() => (name.to_string(), url),
+            _ => (target.to_string(), target),
+        };
+        let url = Url::parse(url).map_err(|_| invalid())?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid());
+        }
+        let mut check = Self { name, url, status: "2xx".to_string(), contains, insecure: false };
+        for word in words {
+            match word.split_once('=') {
+                Some(("status", status))
+                    if status.len() == 3 && status.chars().all(|c| c.is_ascii_digit() || c == 'x') =>
+                {
+                    check.status = status.to_string();
+                }
+                None if word == "insecure" => check.insecure = true,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(check)
+    }
+
+    fn expected(&self, status: u16) -> bool {
+        self.status.chars().zip(status.to_string().chars()).all(|(want, got)| want == 'x' || want == got)
+    }
+
+    async fn run(&self, client: &Client) -> HttpCheckStats {
+        let started = Instant::now();
+        let response = match client.get(self.url.clone()).send().await {
+            Ok(response) => response,
+            Err(e) => return HttpCheckStats { error: Some(e.to_string()), ..Default::default() },
+        };
+        let status = response.status().as_u16();
+        // 不需要查找文本时也读完响应，响应时间包括传输响应体
+        let body = response.text().await;
+        let response_ms = Some(started.elapsed().as_micros() as f64 / 1000.0);
+        let error = match body {
+            Err(e) => Some(e.to_string()),
+            Ok(_) if !self.expected(status) => Some(format!("{}: {}", tr("状态码不符合预期"), status)),
+            Ok(body) => match &self.contains {
+                Some(text) if !body.contains(text.as_str()) => Some(format!("{}: {}", tr("响应中没有预期的文本"), text)),
+                _ => None,
+            },
+        };
+        HttpCheckStats { ok: error.is_none(), status: Some(status), response_ms, error }
+    }
+}
+
+/// `--http-check` 的校验
+pub fn http_check_arg(value: &str) -> Result<String, String> {
+    Check::parse(value).map(|_| value.trim().to_string())
+}
+
+/// 每次采集向各目标发送一个GET请求，检查状态码和响应内容，给出响应时间；各目标同时检查，一次采集最多用时 `timeout`。
+/// 检查的通常是本机或同一网络中的服务，不经过代理，每次都建立新的连接
+pub struct HttpChecks {
+    checks: Vec<(Check, Client)>,
+}
+
+impl HttpChecks {
+    pub fn new(values: &[String], timeout: Duration) -> Result<Self, Error> {
+        let mut checks = Vec::new();
+        for value in values {
+            let check = Check::parse(value).map_err(Error::config)?;
+            if checks.iter().any(|(c, _): &(Check, Client)| c.name == check.name) {
+                return Err(Error::config(format!("{}: {}", tr("HTTP检查的名称重复"), check.name)));
+            }
+            let client = Client::builder()
+                .timeout(timeout)
+                .no_proxy()
+                .pool_max_idle_per_host(0)
+                .danger_accept_invalid_certs(check.insecure)
+                .build()?;
+            checks.push((check, client));
+        }
+        Ok(Self { checks })
+    }
+}
+
+impl Collector for HttpChecks {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn collect(&mut self, _ctx: &mut Context) -> Section {
+        // 采集项可能在主循环的异步运行时中同步调用(`--embedded`)，在单独的线程中另建运行时
+        let results = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+                    let checks = self.checks.iter().map(|(check, client)| async move { (check.name.clone(), check.run(client).await) });
+                    Ok::<_, std::io::Error>(runtime.block_on(futures::future::join_all(checks)))
+                })
+                .join()
+        });
+        match results {
+            Ok(Ok(results)) => Section::Http(results.into_iter().collect::<BTreeMap<_, _>>()),
+            _ => Section::Unavailable,
+        }
+    }
+}
@@ -0,0 +1,562 @@
+// src/i18n.rs
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    Zh,
+    En,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// 英文翻译表，以中文原文作为键
+const EN: &[(&str, &str)] = &[
+    // 命令行帮助
+    ("监控Linux系统资源使用情况并发送JSON数据", "Monitor Linux system resource usage and send it as JSON"),
+    ("显示版本后退出，与 --verbose 一起使用时显示提交号、构建时间、编译器和特性", "Print version and exit; with --verbose also print commit, build date, compiler and features"),
+    ("输出更详细的日志，可重复使用(-vv)", "More verbose logging, repeatable (-vv)"),
+    ("只输出警告和错误日志，重复使用(-qq)时只输出错误", "Only log warnings and errors; repeated (-qq) only errors"),
+    ("日志格式，json 时每条日志为一行JSON", "Log format; json writes one JSON object per log line"),
+    ("把日志追加到文件而不是标准错误", "Append logs to this file instead of standard error"),
+    ("日志文件超过该大小(字节)时轮转", "Rotate the log file when it exceeds this size (bytes)"),
+    ("每隔该时长轮转一次日志文件(例如 1d，按UTC零点对齐)", "Rotate the log file every DURATION (e.g. 1d, aligned to UTC midnight)"),
+    ("轮转后保留的旧日志文件数", "Number of rotated log files to keep"),
+    ("转入后台运行，不显示任何内容；日志只写到 --log-file 指定的文件", "Run in the background without any display; logs only go to the --log-file file"),
+    ("低占用模式，用于路由器、网关等小型设备：不显示，只用一个线程，各采集项依次采集，不需要进程列表时不建立进程表", "Low-footprint mode for small devices such as routers and gateways: no display, a single thread, collectors run one after another and no process table is kept unless a process list is needed"),
+    ("把进程号写入该文件，退出时删除；文件中的进程仍在运行时拒绝启动", "Write the process ID to this file and remove it on exit; refuse to start while the recorded process is running"),
+    ("输出以 Type=notify 和看门狗运行的加固systemd服务文件后退出", "Print a hardened systemd service file using Type=notify and the watchdog, then exit"),
+    ("TOML或YAML配置文件，命令行参数优先于文件中的设置", "TOML or YAML configuration file; command-line flags override its settings"),
+    ("界面语言(zh 或 en)，默认根据 LANG 环境变量判断", "Interface language (zh or en), detected from LANG by default"),
+    ("刷新间隔，可以是秒数(如 0.5)或带单位的时长(如 250ms、1m)", "Refresh interval, in seconds (e.g. 0.5) or with a unit (e.g. 250ms, 1m)"),
+    ("接收数据的端点URL，也可以是 unix:///path/to.sock 形式的Unix套接字", "Endpoint URL receiving the data, or a Unix socket as unix:///path/to.sock"),
+    ("不显示监控信息，只发送数据", "Do not display anything, only send data"),
+    ("显示趋势图时保留的样本数", "Number of samples kept for trend graphs"),
+    ("在内存中保留最近这段时间的样本(例如 60m)，用于趋势图和 serve 的 GET /history", "Keep samples from this recent period in memory (e.g. 60m) for trend graphs and GET /history in serve"),
+    ("不输出转义序列，每个间隔输出一个带时间戳的文本块(输出不是终端时自动启用)", "Never emit escape sequences, print a timestamped block per interval (automatic when output is not a terminal)"),
+    ("文本显示中的区块及其顺序(逗号分隔)，默认 cpu,mem,disk,net,proc", "Sections of the text display and their order (comma-separated), default cpu,mem,disk,net,proc"),
+    ("逐个显示每个CPU核心(full)还是只显示汇总(summary)，auto 时超过16个核心显示汇总", "Show every CPU core (full) or only a summary (summary); auto summarizes above 16 cores"),
+    ("显示字节数的单位制：si 为1000进制(kB、MB)，iec 为1024进制(KiB、MiB)", "Units for byte counts: si uses powers of 1000 (kB, MB), iec powers of 1024 (KiB, MiB)"),
+    ("网络速率以比特/秒显示", "Show network rates in bits per second"),
+    ("终端界面中用盲文点阵绘制高分辨率的CPU和网络历史曲线(也可按 g 切换)", "Draw high-resolution braille graphs of CPU and network history in the terminal UI (toggle with g)"),
+    ("文本显示中百分比条的宽度，为0时只显示数字", "Width of percentage bars in the text display, 0 shows numbers only"),
+    ("发送失败时缓存数据的目录", "Directory spooling samples when sending fails"),
+    ("缓存目录的最大容量(字节)", "Maximum size of the spool directory (bytes)"),
+    ("用这个密钥加密缓存目录中的样本，export 读取时也需要", "Encrypt samples in the spool directory with this key; export needs it too"),
+    ("每次请求发送的样本数，大于1时以JSON数组发送", "Samples per request; sent as a JSON array when greater than 1"),
+    ("批量发送的最长等待时间，秒数或带单位的时长(如 500ms)", "Maximum time to wait before sending a batch, in seconds or with a unit (e.g. 500ms)"),
+    ("把这段时间内采集的样本汇总成一个样本再发送，例如每秒采集、每30秒发送", "Combine the samples collected over this period into one sample before sending, e.g. collect every second and send every 30s"),
+    ("汇总时统计最小值、最大值、均值和95分位数的指标，可重复使用或用逗号分隔", "Metrics to summarize with min, max, average and 95th percentile when aggregating, can be repeated or comma separated"),
+    ("以Bearer方式发送的认证令牌", "Bearer token for authentication"),
+    ("Basic认证的用户名和密码", "User name and password for Basic authentication"),
+    ("从文件读取以Bearer方式发送的令牌，文件被其他程序更新后重新读取", "Read the Bearer token from a file, reread after another program updates it"),
+    ("OAuth2令牌端点：用客户端凭据获取令牌，过期前刷新", "OAuth2 token endpoint: obtain a token with client credentials and refresh it before it expires"),
+    ("OAuth2客户端ID", "OAuth2 client ID"),
+    ("OAuth2客户端密钥", "OAuth2 client secret"),
+    ("请求令牌的权限范围，多个用空格分隔", "Scope requested for the token, separated by spaces"),
+    ("附加到令牌请求中的参数，例如 audience=https://ingest.example.com，可重复使用", "Extra parameter for the token request, e.g. audience=https://ingest.example.com; may be repeated"),
+    ("客户端凭据的发送方式：basic 为Basic认证头，post 为请求体中的 client_id 和 client_secret", "How client credentials are sent: basic in a Basic authentication header, post as client_id and client_secret in the request body"),
+    ("用这个密钥对每个请求体计算HMAC-SHA256签名，放在 X-Sysmon-Signature 请求头中，接收端据此校验来源和完整性", "Sign each request body with HMAC-SHA256 using this key, sent in the X-Sysmon-Signature header so the receiver can verify origin and integrity"),
+    ("附加到每个请求的请求头，可重复使用", "Header added to every request, repeatable"),
+    ("用于校验服务器证书的CA证书(PEM)", "CA certificate used to verify the server (PEM)"),
+    ("双向TLS使用的客户端证书(PEM)", "Client certificate for mutual TLS (PEM)"),
+    ("客户端证书对应的私钥(PKCS#8 PEM)", "Private key of the client certificate (PKCS#8 PEM)"),
+    ("不校验服务器证书(仅用于测试环境)", "Skip server certificate verification (lab use only)"),
+    ("代理地址，支持 http://、https://、socks5:// 和 socks5h://，未指定时使用 HTTP_PROXY/HTTPS_PROXY 环境变量", "Proxy URL (http://, https://, socks5:// or socks5h://); HTTP_PROXY/HTTPS_PROXY are used when omitted"),
+    ("不经过代理的主机列表(逗号分隔)，默认读取 NO_PROXY 环境变量", "Comma-separated hosts that bypass the proxy, defaults to NO_PROXY"),
+    ("连接端点的超时时间，秒数或带单位的时长(如 1.5s)", "Timeout for connecting to the endpoint, in seconds or with a unit (e.g. 1.5s)"),
+    ("单次请求的超时时间，秒数或带单位的时长(如 30s)", "Timeout for a single request, in seconds or with a unit (e.g. 30s)"),
+    ("空闲连接的保持时间，秒数或带单位的时长(如 2m)，为0时不复用连接", "Idle connection keep-alive, in seconds or with a unit (e.g. 2m), 0 disables connection reuse"),
+    ("每次发送前随机等待的最长时间，秒数或带单位的时长(如 200ms)", "Maximum random delay before each send, in seconds or with a unit (e.g. 200ms)"),
+    ("端点或导出插件连续失败多少次后暂停发送，为0时不暂停", "Consecutive failures after which sends to the endpoint or an exporter plugin are paused, 0 to never pause"),
+    ("暂停发送后每隔多久探测一次，秒数或带单位的时长(如 1m)", "How often to probe a paused sink, in seconds or with a unit (e.g. 1m)"),
+    ("每秒最多发送的请求数", "Maximum requests per second"),
+    ("附加到每个样本的标签，可重复使用或用逗号分隔", "Tag attached to every sample, repeatable or comma-separated"),
+    ("代替系统的主机名，出现在样本、事件、告警通知和终端界面中", "Use this instead of the system host name in samples, events, alert notifications and the terminal UI"),
+    ("附加到每个样本和事件的实例ID，主机名变化时接收端据此区分主机；为 machine-id 时由本机的机器ID派生", "Instance ID attached to every sample and event so receivers can tell hosts apart when host names change; machine-id derives it from the local machine ID"),
+    ("无效的主机标识", "Invalid host identity"),
+    ("无法读取机器ID", "Cannot read machine ID"),
+    ("告警规则，例如 'mem=mem.used_percent > 90 for 5m'，可重复使用", "Alert rule, e.g. 'mem=mem.used_percent > 90 for 5m'; repeatable"),
+    ("告警触发和解除时通知的webhook，FORMAT 为 json(默认)、slack、teams 或 pagerduty(此时URL为路由键)，可重复使用", "Webhook notified when an alert fires or resolves; FORMAT is json (default), slack, teams or pagerduty (URL is then the routing key); repeatable"),
+    ("程序崩溃(panic)时把崩溃报告以JSON POST到这个地址，然后以退出状态101退出", "POST a JSON crash report to this URL when the program panics, then exit with status 101"),
+    ("发送告警邮件的SMTP服务器", "SMTP server used to send alert emails"),
+    ("与SMTP服务器之间的加密方式，默认端口分别为587、465、25", "Encryption to the SMTP server; default ports are 587, 465 and 25 respectively"),
+    ("SMTP认证的用户名", "SMTP authentication user"),
+    ("SMTP认证的密码", "SMTP authentication password"),
+    ("告警邮件的发件人，默认为 system_monitor@主机名", "Sender of alert emails, defaults to system_monitor@HOSTNAME"),
+    ("告警邮件的收件人，可重复使用或用逗号分隔", "Recipients of alert emails, repeatable or comma-separated"),
+    ("指定规则的告警邮件收件人，代替 --mail-to，可重复使用", "Recipients for a specific rule, replacing --mail-to; repeatable"),
+    ("告警触发和解除时弹出桌面通知(需要以 desktop 特性编译)", "Show desktop notifications when alerts fire or resolve (requires the desktop feature)"),
+    ("告警触发和解除时用 sh -c 运行的命令，RULE 为 * 时对全部规则运行，可重复使用", "Command run with sh -c when alerts fire or resolve, RULE * matches all rules, can be repeated"),
+    ("告警命令的超时时间，超时后终止命令及其子进程", "Timeout for alert commands, after which the command and its children are killed"),
+    ("同时运行的告警命令数上限，超过时排队等待", "Maximum number of alert commands running at once, further commands wait"),
+    ("告警触发和解除时另外向端点发送事件", "Also send an event to the endpoint when alerts fire or resolve"),
+    ("维护窗口，例如 'backup=0 2 * * sun for 3h'(本地时间)：窗口内不发送告警通知，暂停向端点和其他输出发送样本，可重复使用", "Maintenance window, e.g. 'backup=0 2 * * sun for 3h' (local time): no alert notifications and no samples sent to the endpoint or other sinks during the window, repeatable"),
+    ("静默时段，例如 'night=0 22 * * * for 9h'(本地时间)：时段内不发送告警通知，样本照常发送，可重复使用", "Quiet hours, e.g. 'night=0 22 * * * for 9h' (local time): no alert notifications during the period, samples are sent as usual, repeatable"),
+    ("检测指标是否偏离近期的基线，例如 net.tx、disk[\"/var\"].used，可重复使用或用逗号分隔", "Detect when a metric deviates from its recent baseline, e.g. net.tx or disk[\"/var\"].used, can be repeated or comma separated"),
+    ("异常检测基线的时间窗口，越长基线变化越慢", "Time window of the anomaly detection baseline, longer windows adapt more slowly"),
+    ("偏离基线超过多少个标准差时视为异常", "Number of standard deviations from the baseline that counts as an anomaly"),
+    ("每个间隔输出一行摘要，不刷新整个屏幕", "Print one summary line per interval instead of repainting the screen"),
+    ("输出格式，json 时每个间隔向标准输出写一行JSON样本", "Output format; json prints one JSON sample per line each interval"),
+    ("采集档案：minimal 每10秒采集CPU和内存，standard 每5秒采集除进程外的全部，full 每秒采集全部；单独指定的选项优先", "Collection profile: minimal collects CPU and memory every 10s, standard everything but processes every 5s, full everything every second; explicit options take precedence"),
+    ("只启用这些采集项(逗号分隔)：cpu、mem、disk、net、proc、temp，默认全部启用(容器和WSL中不启用temp)", "Only enable these collectors (comma-separated): cpu, mem, disk, net, proc, temp; all by default (except temp in containers and WSL)"),
+    ("数据来源：system 为本机，mock:FILE 按场景文件生成模拟数据代替内置采集项", "Data source: system for this host, mock:FILE to replace the built-in collectors with synthetic data from a scenario file"),
+    ("关闭这些采集项(逗号分隔)", "Disable these collectors (comma-separated)"),
+    ("加载WASM导出插件，发往端点的每批样本也交给插件导出，CONFIG传给插件的 init，可重复使用", "Load a WASM exporter plugin that also receives every batch sent to the endpoint, CONFIG is passed to its init, can be repeated"),
+    ("发往端点的每个样本再按模板(Handlebars语法)渲染成文本，发送到 http(s)://URL、file:PATH、tcp://HOST:PORT 或 udp://HOST:PORT，可重复使用", "Also render every sample sent to the endpoint through a template (Handlebars syntax) and send the text to http(s)://URL, file:PATH, tcp://HOST:PORT or udp://HOST:PORT, can be repeated"),
+    ("定期运行命令，把输出(JSON或 key=value 行)以NAME为键加入样本，可重复使用", "Run a command periodically and add its output (JSON or key=value lines) to the sample under NAME, can be repeated"),
+    ("--exec 命令的超时时间，超时的命令会被杀掉", "Timeout for --exec commands, commands that time out are killed"),
+    ("每次采集时探测的目标：icmp://HOST 或 HOST 发送ICMP回显，tcp://HOST:PORT 或 HOST:PORT 建立TCP连接，可重复使用", "Target to probe on every collection: icmp://HOST or HOST sends ICMP echo requests, tcp://HOST:PORT or HOST:PORT opens TCP connections; can be repeated"),
+    ("每次采集向每个目标发送的探测数", "Number of probes sent to each target per collection"),
+    ("等待探测回应的时间，超时的探测计为丢失，默认与 --collect-timeout 相同", "How long to wait for probe replies, probes that time out count as lost; defaults to --collect-timeout"),
+    ("无效的探测目标", "Invalid probe target"),
+    ("无法解析主机名", "Could not resolve host name"),
+    ("没有发送ICMP的权限", "No permission to send ICMP"),
+    ("Windows上的ICMP探测只支持IPv4", "ICMP probes on Windows only support IPv4"),
+    ("每次采集时请求的URL，检查状态码(默认2xx)和响应中的文本，insecure 不验证证书，可重复使用", "URL to request on every collection, checking the status code (2xx by default) and text in the response; insecure skips certificate verification; can be repeated"),
+    ("HTTP检查的超时时间，默认与 --collect-timeout 相同", "Timeout for HTTP checks; defaults to --collect-timeout"),
+    ("无效的HTTP检查", "Invalid HTTP check"),
+    ("HTTP检查的名称重复", "Duplicate HTTP check name"),
+    ("状态码不符合预期", "Unexpected status code"),
+    ("响应中没有预期的文本", "Expected text not found in response"),
+    ("每次采集时解析的域名，默认通过系统的解析器，@SERVER 直接查询这个DNS服务器(IP地址)，可重复使用", "Name to resolve on every collection, through the system resolver by default; @SERVER queries that DNS server (IP address) directly; can be repeated"),
+    ("DNS检查的超时时间，默认与 --collect-timeout 相同", "Timeout for DNS checks; defaults to --collect-timeout"),
+    ("无效的DNS检查", "Invalid DNS check"),
+    ("没有解析到地址", "No addresses resolved"),
+    ("解析超时", "Lookup timed out"),
+    ("定期连接的TLS端点(端口默认443)，给出证书链中最先过期的证书距离过期的天数，默认每小时检查一次，可重复使用", "TLS endpoint to connect to periodically (port 443 by default), reporting days until the first certificate in the chain expires; checked hourly by default; can be repeated"),
+    ("TLS检查的超时时间，默认与 --collect-timeout 相同", "Timeout for TLS checks; defaults to --collect-timeout"),
+    ("无效的TLS检查", "Invalid TLS check"),
+    ("服务器没有发送证书", "The server sent no certificate"),
+    ("无法解析证书", "Could not parse the certificate"),
+    ("握手超时", "Handshake timed out"),
+    ("跟踪的日志文件，统计新写入的行中匹配 --watch-pattern 的行数，文件轮转后继续跟踪，可重复使用", "Log file to follow, counting newly written lines that match --watch-pattern; keeps following across rotation; can be repeated"),
+    ("在 --watch-log 的文件中统计的正则表达式，样本中以NAME为键，可重复使用", "Regular expression to count in --watch-log files, keyed by NAME in the sample; can be repeated"),
+    ("无效的日志模式", "Invalid log pattern"),
+    ("日志模式的名称重复", "Duplicate log pattern name"),
+    ("无法读取日志文件", "Could not read log file"),
+    ("每次采集后由样本中的值计算的派生指标，例如 'mem_used_pct=mem.used / mem.total * 100'、'net_rx=sum(net.*.rx)'，样本中以NAME为键，可重复使用", "Derived metric computed from sample values after each collection, e.g. 'mem_used_pct=mem.used / mem.total * 100' or 'net_rx=sum(net.*.rx)', keyed by NAME in the sample; can be repeated"),
+    ("按最近这段时间(例如 6h)内存和各挂载点用量的线性趋势，给出每小时的增长和预计用满的小时数，告警规则可以引用", "Fit a linear trend to memory and per-mount-point usage over this recent window (e.g. 6h), reporting growth per hour and estimated hours until full; usable in alert rules"),
+    ("无效的派生指标", "Invalid derived metric"),
+    ("请求体中只保留这些字段(逗号分隔)，以 - 开头的去掉，例如 'cpu,mem,disk.*.used' 或 '-top,-agent.environment'，可重复使用；不影响显示", "Keep only these fields in the request body (comma-separated), removing those starting with -, e.g. 'cpu,mem,disk.*.used' or '-top,-agent.environment'; can be repeated; does not affect the display"),
+    ("每隔这么久发送一次完整样本，之间只发送变化超过 --delta-epsilon 的字段，接收端需要还原(aggregate 子命令会自动还原)", "Send a full sample this often and only fields that changed by more than --delta-epsilon in between; the receiver must reconstruct them (the aggregate subcommand does so automatically)"),
+    ("使用 --delta 时，数值相对于上次发送的值变化超过这个百分比才发送，为0时任何变化都发送", "With --delta, send a number only when it changed by more than this percentage of the last sent value; 0 sends every change"),
+    ("发往某个输出(endpoint、exporter:名称、template:名称)的样本换算单位和精度，例如 'template:influx=bytes=KiB,percent=fraction,precision=2'，可重复使用；不影响显示", "Convert units and precision of samples sent to one sink (endpoint, exporter:NAME, template:NAME), e.g. 'template:influx=bytes=KiB,percent=fraction,precision=2'; can be repeated; does not affect the display"),
+    ("无效的字段", "Invalid field"),
+    ("无效的单位设置", "Invalid unit settings"),
+    ("没有这个输出", "No such sink"),
+    ("无法解析的表达式", "Could not parse expression"),
+    ("表达式不完整", "Incomplete expression"),
+    ("无效的指标", "Invalid metric"),
+    ("有多个实例的指标需要用 sum、avg、min、max 或 count 汇总", "Metrics with several instances must be combined with sum, avg, min, max or count"),
+    ("派生指标的名称重复", "Duplicate derived metric name"),
+    ("派生指标循环引用", "Derived metric references itself"),
+    ("未定义的派生指标", "Undefined derived metric"),
+    ("在样本中报告上次采集以来启动和退出的进程，按 --proc-name、--proc-user、--proc-cgroup 过滤", "Report processes started and exited since the last collection in the sample, filtered by --proc-name, --proc-user and --proc-cgroup"),
+    ("进程事件过多，超出的没有报告", "Too many process events, the excess was not reported"),
+    ("统计上次采集以来进程的创建和退出，以及两次采集之间启动又退出的进程用掉的CPU(只在Linux上可用，需要root)", "Count processes created and exited since the last collection and the CPU used by processes that started and exited between two collections (Linux only, requires root)"),
+    ("用eBPF按进程和对端端口统计网络流量，在样本中列出收发最多的几项(需要以 --features ebpf 编译，只在Linux上可用，需要root)", "Account network traffic per process and remote port with eBPF, listing the top senders and receivers in samples (requires building with --features ebpf, Linux only, requires root)"),
+    ("--net-talkers 需要在Linux上以 --features ebpf 编译", "--net-talkers requires building on Linux with --features ebpf"),
+    ("无法加载eBPF程序，不按进程统计网络流量", "Failed to load eBPF programs, not accounting network traffic per process"),
+    ("eBPF程序被拒绝", "eBPF program rejected"),
+    ("没有挂载cgroup v2", "cgroup v2 is not mounted"),
+    ("已挂载eBPF程序，按进程统计网络流量", "eBPF programs attached, accounting network traffic per process"),
+    ("无法查询套接字", "Failed to query sockets"),
+    ("无法监听进程退出，不统计短命进程", "Cannot listen for process exits, short-lived processes are not counted"),
+    ("只在Linux上可用", "Only available on Linux"),
+    ("通过logind订阅休眠、恢复、合盖和登录会话的变化，向端点发送事件，休眠期间暂停采集(Linux)", "Subscribe to suspend, resume, lid and login session changes through logind, send them to the endpoint as events and pause collection while suspended (Linux)"),
+    ("检测网卡、挂载点、交换空间、进程重启和告警阈值的状态变化，发送到 endpoint、log、file:PATH 或模板输出 TEMPLATE=TARGET，可重复使用", "Detect state changes of interfaces, mounts, swap, process restarts and alert thresholds and send them to endpoint, log, file:PATH or a template sink TEMPLATE=TARGET, can be repeated"),
+    ("file: 事件输出中的事件保留的时间，更早的事件定期删除", "How long file: event sinks keep events; older events are removed periodically"),
+    ("--power-events 需要在Linux上以 --features logind 编译", "--power-events requires building on Linux with --features logind"),
+    ("无法订阅logind的信号", "Could not subscribe to logind signals"),
+    ("系统即将休眠，暂停采集", "System is suspending, collection paused"),
+    ("系统已恢复，继续采集", "System resumed, collection continues"),
+    ("笔记本盖子已合上", "Lid closed"),
+    ("笔记本盖子已打开", "Lid opened"),
+    ("新的登录会话", "New login session"),
+    ("登录会话已结束", "Login session ended"),
+    ("发送队列已满，系统事件被丢弃", "Send queue is full, system event dropped"),
+    ("每次采集后运行的Lua脚本，脚本中的 collect(stats) 返回的值以文件名为键加入样本，可重复使用", "Lua script run after each collection, the value returned by its collect(stats) is added to the sample under the file name, can be repeated"),
+    ("单独设置某个采集项的采集间隔，例如 proc=10s、disk=1m，可重复使用", "Collection interval for a single collector, e.g. proc=10s or disk=1m, repeatable"),
+    ("在后台刷新CPU的间隔，样本中的占用率是两次采集之间各次刷新的平均值，并给出各核心的最高值；为0时只在采集时刷新", "How often CPU usage is refreshed in the background; samples report the average over all refreshes since the previous sample plus per-core peaks; 0 refreshes only when collecting"),
+    ("无法启动CPU采样线程，只在采集时刷新CPU", "Cannot start the CPU sampling thread; CPU is refreshed only when collecting"),
+    ("一次采集最多等待的时间，超时的采集项沿用上次的结果，默认为刷新间隔的一半", "Maximum time to wait for one collection; collectors that time out keep their previous result (default: half the refresh interval)"),
+    ("本程序自身的CPU占用率(单核的百分比)超过这个值时放慢采集", "Slow down collection when this program's own CPU usage (percent of one core) exceeds this value"),
+    ("本程序自身的常驻内存(字节)超过这个值时放慢采集", "Slow down collection when this program's own resident memory (bytes) exceeds this value"),
+    ("主机1分钟平均负载除以CPU核数超过这个值时放慢采集", "Slow down collection when the host's 1-minute load average divided by the number of CPUs exceeds this value"),
+    ("过载时采集间隔最多放慢的倍数", "Maximum factor by which the collection interval is lengthened under overload"),
+    ("放慢到最大倍数后仍然过载时暂停这些采集项(逗号分隔)", "Pause these collectors when still overloaded at the maximum slowdown (comma-separated)"),
+    ("只采集名称匹配正则表达式的网卡，可重复使用", "Only collect interfaces whose name matches the regex, repeatable"),
+    ("不采集名称匹配正则表达式的网卡，可重复使用", "Do not collect interfaces whose name matches the regex, repeatable"),
+    ("只统计名称匹配正则表达式的进程，可重复使用", "Only count processes whose name matches the regex, repeatable"),
+    ("只统计属于该用户(用户名或UID)的进程，可重复使用", "Only count processes owned by this user (name or UID), repeatable"),
+    ("只统计cgroup路径匹配正则表达式的进程，可重复使用", "Only count processes whose cgroup path matches the regex, repeatable"),
+    ("在样本中附带CPU占用最高的N个进程", "Include the N processes using the most CPU in each sample"),
+    ("等待一个刷新间隔后输出并发送一个样本然后退出，发送失败时退出状态为4", "Wait one interval, print and send a single sample, then exit; exit status is 4 if sending fails"),
+    ("采集N个样本后退出", "Exit after collecting N samples"),
+    ("运行指定的时长(如 30s、5m)后退出", "Exit after running for the given duration (e.g. 30s, 5m)"),
+    ("试运行：照常采集和显示，但不发送数据也不写缓存，请求体写到标准错误或追加到指定文件", "Dry run: collect and display as usual but never send or spool; payloads go to stderr or are appended to FILE"),
+    ("只显示匹配的网卡(支持 * 和 ? 通配符)，可重复使用", "Only display interfaces matching the pattern (* and ? wildcards), repeatable"),
+    ("不显示匹配的网卡(支持 * 和 ? 通配符)，可重复使用", "Hide interfaces matching the pattern (* and ? wildcards), repeatable"),
+    ("只显示匹配的挂载点(支持 * 和 ? 通配符)，可重复使用", "Only display mount points matching the pattern (* and ? wildcards), repeatable"),
+    ("不显示匹配的挂载点(支持 * 和 ? 通配符)，可重复使用", "Hide mount points matching the pattern (* and ? wildcards), repeatable"),
+    ("启动时查询云主机元数据(实例ID、规格、区域)并附加到样本", "Query cloud instance metadata (ID, type, region) at startup and attach it to samples"),
+    ("以DaemonSet运行时从downward API的环境变量 NODE_NAME、POD_NAME、POD_NAMESPACE 读取节点、Pod和命名空间，作为 node、pod、namespace 标签", "When running as a DaemonSet, read the node, pod and namespace from the downward API variables NODE_NAME, POD_NAME and POD_NAMESPACE as node, pod and namespace tags"),
+    ("启动时从API服务器读取节点的可分配资源，样本中给出可分配和已使用的CPU与内存(服务账号需要 get nodes 权限)", "Read the node's allocatable resources from the API server at startup and report allocatable vs used CPU and memory (the service account needs get nodes)"),
+    ("持续采集、显示并发送数据(不指定子命令时的默认行为)", "Continuously collect, display and send data (the default without a subcommand)"),
+    ("拉取模式：不主动发送数据，通过HTTP提供最新的样本(GET /stats、GET /metrics)、最近的样本(GET /history?since=...)、样本流(GET /events)和仪表盘(GET /)", "Pull mode: never send, serve the latest sample (GET /stats, GET /metrics), recent samples (GET /history?since=...), a sample stream (GET /events) and a dashboard (GET /) over HTTP"),
+    ("连接已断开，正在重连", "Disconnected, reconnecting"),
+    ("要求请求带上 Authorization: Bearer TOKEN 或查询参数 token=TOKEN，/healthz 和 /readyz 除外；设置后才接受 POST /control/... 控制命令", "Require Authorization: Bearer TOKEN or the query parameter token=TOKEN on requests, except /healthz and /readyz; also enables POST /control/... commands"),
+    ("通过mDNS在局域网中广播 _sysmon._tcp 服务，便于仪表盘和汇总服务发现本机(需要以 --features mdns 编译)", "Announce a _sysmon._tcp service over mDNS so dashboards and aggregators on the LAN can discover this host (requires building with --features mdns)"),
+    ("HTTP服务的监听地址", "Address the HTTP server listens on"),
+    ("在该地址提供 GET /healthz 和 GET /readyz，供负载均衡和Kubernetes探测(serve 在自己的地址上提供)", "Serve GET /healthz and GET /readyz on this address for load balancer and Kubernetes probes (serve provides them on its own address)"),
+    ("发送队列中等待的样本超过N时 /readyz 失败", "Fail /readyz when more than N samples are waiting in the send queue"),
+    ("汇总模式：不采集本机，接收多个代理推送的样本，通过HTTP提供每个主机的状态和最新样本(GET /hosts、GET /metrics)", "Aggregator mode: collect nothing locally, receive samples pushed by many agents and serve each host's state and latest sample over HTTP (GET /hosts, GET /metrics)"),
+    ("HTTP服务的监听地址，代理的 --endpoint 指向这里", "Address the HTTP server listens on; point the agents' --endpoint here"),
+    ("主机超过这段时间没有推送样本时视为失联", "Consider a host stale after this long without a sample"),
+    ("要求请求带上 Authorization: Bearer TOKEN(代理用 --token 设置)，/healthz 除外", "Require Authorization: Bearer TOKEN on requests (agents set it with --token), except /healthz"),
+    ("只接受用这个密钥签名的推送(代理用 --signing-key 设置)，签名不符时返回401", "Only accept pushes signed with this key (agents set it with --signing-key); others get 401"),
+    ("轮询模式：不采集本机，按 --interval 通过SSH读取多台主机的 /proc，合并输出各主机的样本", "Polling mode: collect nothing locally, read /proc on many hosts over SSH every --interval and print every host's samples in one stream"),
+    ("要轮询的主机，可重复使用或用逗号分隔", "Hosts to poll, can be repeated or comma separated"),
+    ("从文件读取要轮询的主机，每行一个，# 开头的行为注释", "Read the hosts to poll from a file, one per line; lines starting with # are comments"),
+    ("传给 ssh -o 的选项，例如 ControlMaster=auto，可重复使用", "Option passed to ssh -o, e.g. ControlMaster=auto, repeatable"),
+    ("连接并读取一台主机最多等待的时间", "How long to wait for connecting to and reading one host"),
+    ("同时通过HTTP提供各主机的状态和最新样本(GET /hosts、GET /metrics)，与 aggregate 相同", "Also serve each host's state and latest sample over HTTP (GET /hosts, GET /metrics), like aggregate"),
+    ("主机超过这段时间没有轮询成功时视为失联", "Consider a host stale after this long without a successful poll"),
+    ("要求HTTP请求带上 Authorization: Bearer TOKEN，/healthz 除外", "Require Authorization: Bearer TOKEN on HTTP requests, except /healthz"),
+    ("采集一个样本并与阈值比较，有指标超出阈值时退出状态为1", "Collect one sample and compare it with thresholds; exit status is 1 if any is exceeded"),
+    ("指标使用率的上限，例如 cpu=90、mem=80、swap=50、disk=85，可重复使用", "Maximum usage of a metric, e.g. cpu=90, mem=80, swap=50 or disk=85, repeatable"),
+    ("把缓存目录中尚未发送的样本逐行输出为JSON", "Print the unsent samples in the spool directory as JSON lines"),
+    ("持续采集并把每个样本逐行追加到文件，不发送数据", "Collect continuously and append every sample to a file as a JSON line, without sending"),
+    ("保存样本的文件", "File the samples are saved to"),
+    ("不压缩录制文件，保留全部原始样本", "Do not compact the recording; keep every raw sample"),
+    ("压缩录制文件：较早的样本按分钟或小时汇总，删除超过保留时间的样本", "Compact a recording: downsample older samples to minute or hourly averages and delete those past the retention"),
+    ("原样保留样本的时间，更早的按分钟汇总", "How long samples are kept as recorded; older ones are averaged per minute"),
+    ("按分钟汇总的样本保留的时间，更早的按小时汇总", "How long per-minute averages are kept; older ones are averaged per hour"),
+    ("样本保留的时间，更早的删除；为0时不删除", "How long samples are kept before being deleted; 0 keeps them forever"),
+    ("录制文件已压缩", "Recording compacted"),
+    ("无法压缩录制文件", "Cannot compact recording"),
+    ("无法写入录制文件", "Cannot write recording"),
+    ("个样本", "samples"),
+    ("字节", "bytes"),
+    ("按原来的时间间隔重放录制的样本，照常显示和发送", "Replay recorded samples at their original pace, displaying and sending them as usual"),
+    ("录制的样本文件", "File of recorded samples"),
+    ("重放速度的倍数，例如 10 表示以10倍速重放", "Replay speed multiplier, e.g. 10 replays ten times faster"),
+    ("比较两个样本快照并列出显著的变化，有变化时退出状态为1", "Compare two sample snapshots and list significant changes; exit status is 1 if anything changed"),
+    ("之前的快照文件(JSON)", "Earlier snapshot file (JSON)"),
+    ("之后的快照文件(JSON)", "Later snapshot file (JSON)"),
+    ("使用率变化小于该百分点时不列出", "Omit usage changes smaller than this many percentage points"),
+    ("进程数量变化小于N时不列出", "Omit process count changes smaller than N"),
+    ("测量每个采集项的耗时和内存分配，以及按刷新间隔运行时本程序的CPU开销", "Measure the latency and allocations of each collector, and the agent's own CPU cost at the refresh interval"),
+    ("每个采集项运行的次数", "Number of runs per collector"),
+    ("按刷新间隔运行以测量CPU开销的时长", "How long to run at the refresh interval to measure CPU cost"),
+    ("配置文件相关的命令", "Configuration file commands"),
+    ("检查配置并输出合并命令行、环境变量和配置文件后的有效配置，有错误时以非零状态退出", "Validate the configuration and print the effective settings merged from flags, environment and file; exits non-zero on errors"),
+    // 运行时消息
+    ("开始监控系统资源...", "Starting system resource monitor..."),
+    ("刷新间隔", "Refresh interval"),
+    ("数据端点", "Endpoint"),
+    ("按 Ctrl+C 退出", "Press Ctrl+C to exit"),
+    ("数据发送成功", "Data sent"),
+    ("缓存数据已全部发送", "Spooled data fully sent"),
+    ("试运行，数据未发送", "Dry run, data not sent"),
+    ("试运行，不发送数据", "dry run, nothing is sent"),
+    ("无法打开文件", "Cannot open file"),
+    ("发送数据失败", "Failed to send data"),
+    ("端点不可达", "Endpoint unreachable"),
+    ("程序因错误退出", "Exiting due to an error"),
+    ("程序崩溃", "The program crashed"),
+    ("崩溃报告已发送", "Crash report sent"),
+    ("发送崩溃报告失败", "Failed to send the crash report"),
+    ("发送崩溃报告超时", "Timed out sending the crash report"),
+    ("采集项崩溃，已重新初始化", "Collector panicked and was reinitialized"),
+    ("错误", "Error"),
+    ("端点不可达，数据已写入缓存", "Endpoint unreachable, data spooled"),
+    ("端点断路，暂停发送", "Endpoint circuit open, sending paused"),
+    ("发送目标连续失败，断路器断开，暂停发送", "Sink failed repeatedly, circuit breaker opened, sending paused"),
+    ("发送目标已恢复，断路器闭合", "Sink recovered, circuit breaker closed"),
+    ("缓存已满，丢弃", "Spool full, dropping"),
+    ("无法解密缓存中的样本，请检查 --spool-key", "Cannot decrypt spooled sample, check --spool-key"),
+    ("未能获取云主机元数据", "Could not fetch cloud instance metadata"),
+    ("未能获取节点可分配的资源", "Could not fetch node allocatable resources"),
+    ("已获取节点可分配的资源", "Fetched node allocatable resources"),
+    ("没有在Kubernetes的Pod中运行", "Not running in a Kubernetes pod"),
+    ("没有节点名，需要通过downward API设置 NODE_NAME 环境变量", "No node name, set the NODE_NAME environment variable through the downward API"),
+    ("节点没有可分配的资源", "Node has no allocatable resource"),
+    ("无效的资源数量", "Invalid resource quantity"),
+    ("无效的标签", "Invalid tag"),
+    ("配置已重新加载", "Configuration reloaded"),
+    ("重新加载配置失败", "Failed to reload configuration"),
+    ("无法读取配置文件", "Cannot read configuration file"),
+    ("配置文件格式错误", "Malformed configuration file"),
+    ("未知的配置项", "Unknown configuration key"),
+    ("无效的请求头", "Invalid header"),
+    ("无效的正则表达式", "Invalid regular expression"),
+    ("无效的时长", "Invalid duration"),
+    ("无效的端点", "Invalid endpoint"),
+    ("Unix套接字端点只能在Unix平台使用", "Unix socket endpoints are only available on Unix platforms"),
+    ("--proc-cgroup 只能在Linux上使用", "--proc-cgroup is only available on Linux"),
+    ("配置检查通过", "Configuration is valid"),
+    ("无效的采集间隔", "Invalid collector interval"),
+    ("未知的用户", "Unknown user"),
+    ("无效的阈值", "Invalid threshold"),
+    ("无法监听", "Cannot listen on"),
+    ("正在提供数据", "Serving data at"),
+    ("已通过mDNS广播服务", "Announced service over mDNS"),
+    ("监听的是回环地址，局域网中的其他主机无法访问广播的服务", "Listening on a loopback address; other hosts on the LAN cannot reach the announced service"),
+    ("mDNS广播失败", "mDNS announcement failed"),
+    ("采集循环没有响应", "The collection loop is not responding"),
+    ("还没有采集到样本", "No sample collected yet"),
+    ("采集项出错", "Collectors failing"),
+    ("发送队列积压的样本", "Samples backlogged in the send queue"),
+    ("缓存已达到容量上限，正在丢弃旧样本", "The spool is at its size limit and dropping old samples"),
+    ("缓存所在的磁盘空间不足", "The disk holding the spool is almost full"),
+    ("无法读取缓存目录", "Cannot read the spool directory"),
+    ("控制接口需要设置 --auth-token", "The control API requires --auth-token"),
+    ("收到控制命令", "Received control command"),
+    ("控制命令失败", "Control command failed"),
+    ("未知的采集项", "Unknown collector"),
+    ("没有使用 --log-file", "Not logging to a file (--log-file)"),
+    ("轮转日志文件失败", "Failed to rotate the log file"),
+    ("正在接收推送的样本", "Receiving pushed samples at"),
+    ("发现新的主机", "New host"),
+    ("无法还原增量样本", "Cannot reconstruct delta sample"),
+    ("缺少前一个样本，等待下一个完整样本", "Previous sample missing; waiting for the next full sample"),
+    ("主机恢复推送", "Host is pushing again"),
+    ("主机已停止", "Host stopped"),
+    ("主机失联", "Host went stale"),
+    ("无法解析推送的样本", "Cannot parse pushed samples"),
+    ("推送的签名不符，已拒绝", "Push rejected: signature mismatch"),
+    ("无效的远程主机", "Invalid remote host"),
+    ("无法读取主机列表", "Cannot read host list"),
+    ("没有要轮询的主机，使用 --host 或 --hosts-file 指定", "No hosts to poll; use --host or --hosts-file"),
+    ("正在提供各主机的样本", "Serving host samples at"),
+    ("远程主机已恢复", "Remote host is reachable again"),
+    ("无法采集远程主机", "Cannot poll remote host"),
+    ("无法运行ssh", "Cannot run ssh"),
+    ("远程采集超时", "Remote collection timed out"),
+    ("正常", "OK"),
+    ("超出阈值", "threshold exceeded"),
+    ("导出需要指定 --spool-dir", "export requires --spool-dir"),
+    ("无效的重放速度", "Invalid replay speed"),
+    ("无法读取录制文件", "Cannot read recording"),
+    ("录制文件格式错误", "Malformed recording"),
+    ("录制文件中没有样本", "No samples in recording"),
+    ("无效的数据源", "Invalid source"),
+    ("无效的模拟场景", "Invalid mock scenario"),
+    ("这个字段不能模拟", "This field cannot be mocked"),
+    ("over、every、length 和 hold 必须大于0，steps 至少要有一个值", "over, every, length and hold must be greater than 0, and steps needs at least one value"),
+    ("收到响应", "Response received"),
+    ("解析端点地址", "Resolved endpoint addresses"),
+    ("已连接到端点地址", "Connected to endpoint address"),
+    ("端点地址已切换", "Endpoint address changed"),
+    ("已获取令牌", "Token obtained"),
+    ("获取令牌失败", "Failed to obtain token"),
+    ("响应中没有 access_token", "No access_token in response"),
+    ("不支持的令牌类型", "Unsupported token type"),
+    ("无法读取令牌文件", "Cannot read token file"),
+    ("令牌文件为空", "Token file is empty"),
+    ("令牌被拒绝，重新获取", "Token rejected, obtaining a new one"),
+    ("发送停止事件失败", "Failed to send the stopping event"),
+    ("退出前发送数据超时", "Timed out sending data before exit"),
+    ("发送队列已满，样本被丢弃", "Send queue is full, sample dropped"),
+    ("发送队列已满，告警事件被丢弃", "Send queue is full, alert event dropped"),
+    ("发送队列已满，状态变化事件被丢弃", "Send queue is full, state change event dropped"),
+    ("无效的事件输出", "Invalid event sink"),
+    ("写入事件文件失败", "Failed to write event file"),
+    ("模板输出忙，事件被丢弃", "Template sink busy, events dropped"),
+    ("网卡已连接", "Interface up"),
+    ("网卡已断开", "Interface down"),
+    ("新的挂载点", "Mount added"),
+    ("挂载点已卸载", "Mount removed"),
+    ("交换空间已启用", "Swap enabled"),
+    ("交换空间已停用", "Swap disabled"),
+    ("进程已重启", "Process restarted"),
+    ("已停止", "Stopped"),
+    ("刷新间隔超过看门狗超时的一半，可能被systemd误判为无响应", "Refresh interval exceeds half the watchdog timeout; systemd may consider the agent hung"),
+    ("无法打开日志文件", "Cannot open log file"),
+    ("已经有实例在运行", "Another instance is already running"),
+    ("无法写入PID文件", "Cannot write PID file"),
+    ("--daemon 只能在Unix平台使用", "--daemon is only available on Unix platforms"),
+    ("无效的告警规则", "Invalid alert rule"),
+    ("告警触发", "Alert firing"),
+    ("告警解除", "Alert resolved"),
+    ("告警仍在触发", "Alert still firing"),
+    ("无效的维护窗口", "Invalid maintenance window"),
+    ("维护窗口开始", "Maintenance window started"),
+    ("维护窗口结束", "Maintenance window ended"),
+    ("维护窗口内的告警，不发送通知", "Alert fired during maintenance window, not notified"),
+    ("无效的webhook", "Invalid webhook"),
+    ("无效的令牌端点", "Invalid token endpoint"),
+    ("无效的令牌请求参数", "Invalid token request parameter"),
+    ("试运行，告警通知未发送", "Dry run, alert notification not sent"),
+    ("告警通知已发送", "Alert notification sent"),
+    ("发送告警通知失败", "Failed to send alert notification"),
+    ("无效的收件人", "Invalid recipients"),
+    ("无效的邮件地址", "Invalid email address"),
+    ("无效的SMTP服务器", "Invalid SMTP server"),
+    ("使用 --smtp-server 时需要用 --mail-to 或 --alert-mail-to 指定收件人", "--smtp-server requires recipients via --mail-to or --alert-mail-to"),
+    ("试运行，告警邮件未发送", "Dry run, alert email not sent"),
+    ("告警邮件已发送", "Alert email sent"),
+    ("发送告警邮件失败", "Failed to send alert email"),
+    ("退出前发送告警通知超时", "Timed out sending alert notifications before exit"),
+    ("退出前发送状态变化事件超时", "Timed out sending state change events before exit"),
+    ("桌面通知需要以 --features desktop 编译", "Desktop notifications require building with --features desktop"),
+    ("mDNS广播需要以 --features mdns 编译", "mDNS announcement requires building with --features mdns"),
+    ("终端界面需要以 --features tui 编译", "The terminal UI requires building with --features tui"),
+    ("显示桌面通知失败", "Failed to show desktop notification"),
+    ("无效的告警命令", "Invalid alert command"),
+    ("试运行，告警命令未运行", "Dry run, alert command not run"),
+    ("告警命令已完成", "Alert command finished"),
+    ("告警命令失败", "Alert command failed"),
+    ("告警命令超时，已终止", "Alert command timed out and was killed"),
+    ("无效的指标", "Invalid metric"),
+    ("无效的异常阈值", "Invalid anomaly threshold"),
+    ("指标偏离基线", "Metric deviates from baseline"),
+    ("无效的限制", "Invalid limit"),
+    ("采集超时，沿用上次的结果", "Collection timed out, keeping the previous result"),
+    ("运行脚本失败", "Script failed"),
+    ("加载导出插件失败", "Failed to load exporter plugin"),
+    ("重新加载导出插件失败", "Failed to reload exporter plugin"),
+    ("导出插件已导出样本", "Exporter plugin exported samples"),
+    ("导出插件失败", "Exporter plugin failed"),
+    ("导出插件断路，批次被丢弃", "Exporter plugin circuit open, batch dropped"),
+    ("导出插件忙，样本被丢弃", "Exporter plugin busy, samples dropped"),
+    ("导出插件需要以 --features wasm 编译", "Exporter plugins require building with --features wasm"),
+    ("无效的模板输出", "Invalid template sink"),
+    ("无效的模板", "Invalid template"),
+    ("行", "line"),
+    ("标签没有结束", "Unterminated tag"),
+    ("多余的 {{else}}", "Unexpected {{else}}"),
+    ("多余的结束标签", "Unexpected closing tag"),
+    ("未知的块", "Unknown block"),
+    ("结束标签与块不匹配", "Closing tag does not match block"),
+    ("块没有结束", "Unclosed block"),
+    ("无效的表达式", "Invalid expression"),
+    ("未知的助手", "Unknown helper"),
+    ("助手的参数个数不对", "Wrong number of helper arguments"),
+    ("模板输出已发送", "Template sink sent samples"),
+    ("模板输出失败", "Template sink failed"),
+    ("模板输出断路，批次被丢弃", "Template sink circuit open, batch dropped"),
+    ("模板输出忙，样本被丢弃", "Template sink busy, samples dropped"),
+    ("命令采集项失败", "Command collector failed"),
+    ("命令超时", "Command timed out"),
+    ("命令失败", "Command failed"),
+    ("无法解析命令输出", "Cannot parse command output"),
+    ("无效的命令采集项", "Invalid command collector"),
+    ("命令采集项不能与内置采集项同名", "A command collector cannot use the name of a built-in collector"),
+    ("无法读取脚本", "Cannot read script"),
+    ("加载脚本失败", "Failed to load script"),
+    ("脚本中没有定义 collect 函数", "Script does not define a collect function"),
+    ("自定义脚本需要以 --features lua 编译", "Custom scripts require building with --features lua"),
+    ("网卡计数器已重置，本次没有速率", "Interface counters were reset, no rate for this sample"),
+    ("负载过高，放慢采集", "Load too high, slowing down collection"),
+    ("负载下降，逐步恢复采集", "Load decreasing, stepping collection back up"),
+    ("负载已恢复，恢复正常采集", "Load recovered, collecting at the normal rate"),
+    ("发送告警事件失败", "Failed to send alert event"),
+    ("无效的HTTP响应", "Invalid HTTP response"),
+    ("连接超时", "Connection timed out"),
+    ("请求超时", "Request timed out"),
+    // 显示
+    ("主机", "Host"),
+    ("规则", "Rule"),
+    ("实例", "Instance"),
+    ("条件", "Condition"),
+    ("当前值", "Value"),
+    ("开始时间", "Since"),
+    ("时间", "Time"),
+    ("提交", "Commit"),
+    ("构建时间", "Built"),
+    ("目标平台", "Target"),
+    ("编译器", "Compiler"),
+    ("特性", "Features"),
+    ("采集项", "Collector"),
+    ("次数", "Runs"),
+    ("平均耗时", "Mean"),
+    ("最长耗时", "Max"),
+    ("分配次数", "Allocs/run"),
+    ("分配字节", "Bytes/run"),
+    ("CPU开销", "CPU cost"),
+    ("CPU时间", "CPU time"),
+    ("系统资源监控", "System Resource Monitor"),
+    ("已运行", "up"),
+    ("上次发送", "last sent"),
+    ("等待发送", "waiting to send"),
+    ("端点正常", "endpoint OK"),
+    ("端点异常", "endpoint failing"),
+    ("端点断路", "endpoint circuit open"),
+    ("导出插件断路", "exporter circuit open"),
+    ("模板输出断路", "template sink circuit open"),
+    ("连续失败次数", "consecutive failures"),
+    ("q 退出, Tab 切换面板, ↑↓ 滚动, g 切换曲线", "q quit, Tab switch pane, ↑↓ scroll, g toggle graphs"),
+    ("CPU核心数", "CPU cores"),
+    ("核心", "Core"),
+    ("平均", "avg"),
+    ("平均CPU使用率", "Average CPU usage"),
+    ("测量窗口", "Window"),
+    ("采样次数", "Samples"),
+    ("各核心最高值的平均", "Mean per-core peak"),
+    ("未配置", "not configured"),
+    ("最低", "min"),
+    ("最高", "max"),
+    ("繁忙(≥90%)", "busy (≥90%)"),
+    ("内存", "Memory"),
+    ("交换空间", "Swap"),
+    ("磁盘", "Disks"),
+    ("网络接口", "Network interfaces"),
+    ("接口", "Interface"),
+    ("接收", "RX"),
+    ("发送", "TX"),
+    ("趋势", "Trend"),
+    ("流量", "Traffic"),
+    ("进程", "Processes"),
+    ("进程统计", "Processes"),
+    ("总计", "total"),
+    ("运行", "running"),
+    ("睡眠", "sleeping"),
+    ("僵尸", "zombie"),
+    ("名称", "Name"),
+    ("排序", "sort"),
+    ("结束进程", "kill"),
+    ("已发送信号", "Signal sent to"),
+    ("发送信号失败", "Failed to signal"),
+    ("进程已经退出", "Process has exited"),
+    ("PID已经属于另一个进程", "PID now belongs to another process"),
+];
+
+/// 根据 `--lang` 参数和 SYSMON_LANG、LC_ALL/LC_MESSAGES/LANG 环境变量确定界面语言
+pub fn detect() -> Lang {
+    let args: Vec<String> = std::env::args().collect();
+    let arg = args.iter().enumerate().find_map(|(i, arg)| {
+        arg.strip_prefix("--lang=")
+            .map(str::to_string)
+            .or_else(|| (arg == "--lang").then(|| args.get(i + 1).cloned()).flatten())
+    });
+
+    let locale = arg.or_else(|| {
+        ["SYSMON_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+    });
+
+    match locale.as_deref() {
+        None | Some("C" | "POSIX") => Lang::Zh,
+        Some(locale) if locale.starts_with("zh") || locale.starts_with("C.") => Lang::Zh,
+        Some(_) => Lang::En,
+    }
+}
+
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+/// 翻译界面文字，找不到译文时返回原文
+pub fn tr(text: &'static str) -> &'static str {
+    match LANG.get() {
+        Some(Lang::En) => EN
+            .iter()
+            .find(|(zh, _)| *zh == text)
+            .map_or(text, |(_, en)| en),
+        _ => text,
+    }
+}
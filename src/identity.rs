@@ -0,0 +1,58 @@
+// src/identity.rs
+use crate::crypto;
+use crate::i18n::tr;
+
+/// `--instance-id machine-id`：由本机的机器ID派生实例ID
+pub const MACHINE_ID: &str = "machine-id";
+
+/// 派生实例ID时的应用标识，得到的ID与其他程序由同一个机器ID派生的不同
+const APP_ID: &[u8] = b"system_monitor";
+
+/// 由机器ID派生的实例ID：以机器ID为密钥对应用标识计算HMAC-SHA256，取前16字节的十六进制。
+/// 与systemd的 `sd_id128_get_machine_app_specific` 一样不直接上报机器ID，主机名变化、重启后不变
+pub fn machine_instance_id() -> Result<String, String> {
+    let id = machine_id().filter(|id| !id.is_empty()).ok_or_else(|| tr("无法读取机器ID").to_string())?;
+    Ok(crypto::derive_id(id.as_bytes(), APP_ID))
+}
+
+/// systemd和dbus的机器ID，FreeBSD安装时生成的 /etc/hostid
+#[cfg(all(unix, not(target_os = "macos")))]
+fn machine_id() -> Option<String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id", "/etc/hostid"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok().map(|id| id.trim().to_string()).filter(|id| !id.is_empty()))
+}
+
+/// 硬件的 IOPlatformUUID
+#[cfg(target_os = "macos")]
+fn machine_id() -> Option<String> {
+    let output = std::process::Command::new("ioreg").args(["-rd1", "-c", "IOPlatformExpertDevice"]).output().ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let line = output.lines().find(|line| line.contains("\"IOPlatformUUID\""))?;
+    Some(line.rsplit('"').nth(1)?.to_string())
+}
+
+/// 安装Windows时生成的 MachineGuid
+#[cfg(windows)]
+fn machine_id() -> Option<String> {
+    let output = std::process::Command::new("reg")
+        .args(["query", r"HKLM\SOFTWARE\Microsoft\Cryptography", "/v", "MachineGuid"])
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let line = output.lines().find(|line| line.trim_start().starts_with("MachineGuid"))?;
+    Some(line.split_whitespace().last()?.to_string())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn machine_id() -> Option<String> {
+    None
+}
+
+/// `--hostname` 和 `--instance-id` 的校验：不能为空，不能有空白和控制字符
+pub fn identity_arg(value: &str) -> Result<String, String> {
+    if value.is_empty() || value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("{}: {:?}", tr("无效的主机标识"), value));
+    }
+    Ok(value.to_string())
+}
@@ -0,0 +1,113 @@
+// src/kubernetes.rs
+use crate::error::Error;
+use crate::i18n::tr;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const API_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pod中挂载的服务账号：令牌、API服务器的CA证书和所在的命名空间
+const SERVICE_ACCOUNT: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// 节点可分配的资源和样本中节点已使用的资源，使用 `--kubernetes-allocatable` 时附加到样本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KubernetesMetadata {
+    pub node: String,
+    pub allocatable: Resources,
+    /// 与 `allocatable` 比较的已使用量：各核心占用率之和换算的核数，以及已用内存
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub used: Option<Resources>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Resources {
+    /// CPU核数，可以是小数
+    pub cpu: f64,
+    /// 内存(字节)
+    pub memory: u64,
+}
+
+/// 是否运行在Kubernetes的Pod中
+pub fn in_cluster() -> bool {
+    std::env::var_os("KUBERNETES_SERVICE_HOST").is_some()
+}
+
+/// 通过downward API注入的环境变量得到的标签：`NODE_NAME` 为 `node`，`POD_NAME` 为 `pod`，`POD_NAMESPACE` 为 `namespace`；
+/// 没有设置时Pod名取主机名，命名空间取服务账号所在的命名空间
+pub fn labels() -> BTreeMap<String, String> {
+    let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    let namespace = || std::fs::read_to_string(format!("{}/namespace", SERVICE_ACCOUNT)).ok().map(|s| s.trim().to_string());
+    [
+        ("node", env("NODE_NAME")),
+        ("pod", env("POD_NAME").or_else(|| env("HOSTNAME"))),
+        ("namespace", env("POD_NAMESPACE").or_else(namespace)),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| Some((key.to_string(), value?)))
+    .collect()
+}
+
+/// 用Pod的服务账号从API服务器读取节点的 `status.allocatable`，服务账号需要 `get nodes` 权限
+pub async fn allocatable(node: &str) -> Result<Resources, Error> {
+    #[derive(Deserialize)]
+    struct Node {
+        status: Status,
+    }
+    #[derive(Deserialize)]
+    struct Status {
+        allocatable: BTreeMap<String, String>,
+    }
+
+    let host = std::env::var("KUBERNETES_SERVICE_HOST").map_err(Error::runtime)?;
+    let port = std::env::var("KUBERNETES_SERVICE_PORT").unwrap_or_else(|_| "443".to_string());
+    let host = if host.contains(':') { format!("[{}]", host) } else { host };
+    let token = std::fs::read_to_string(format!("{}/token", SERVICE_ACCOUNT))?;
+    let ca = std::fs::read(format!("{}/ca.crt", SERVICE_ACCOUNT))?;
+
+    let client = reqwest::Client::builder()
+        .timeout(API_TIMEOUT)
+        .no_proxy()
+        .add_root_certificate(reqwest::Certificate::from_pem(&ca)?)
+        .build()?;
+    let node: Node = client
+        .get(format!("https://{}:{}/api/v1/nodes/{}", host, port, node))
+        .bearer_auth(token.trim())
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let resource = |name: &str| {
+        let value = node.status.allocatable.get(name).ok_or_else(|| format!("{}: {}", tr("节点没有可分配的资源"), name))?;
+        quantity(value).ok_or_else(|| format!("{}: {}", tr("无效的资源数量"), value))
+    };
+    Ok(Resources { cpu: resource("cpu")?, memory: resource("memory")? as u64 })
+}
+
+/// 解析Kubernetes的资源数量，例如 `3920m`、`4`、`16283148Ki`、`1.5Gi`、`1e9`
+fn quantity(value: &str) -> Option<f64> {
+    const SUFFIXES: [(&str, f64); 15] = [
+        ("Ki", 1024.0),
+        ("Mi", 1024.0 * 1024.0),
+        ("Gi", 1024.0 * 1024.0 * 1024.0),
+        ("Ti", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("Pi", 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("Ei", 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("n", 1e-9),
+        ("u", 1e-6),
+        ("m", 1e-3),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+        ("P", 1e15),
+        ("E", 1e18),
+    ];
+    let (number, factor) = SUFFIXES
+        .iter()
+        .find_map(|(suffix, factor)| value.strip_suffix(suffix).map(|number| (number, *factor)))
+        .unwrap_or((value, 1.0));
+    number.parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0).map(|n| n * factor)
+}
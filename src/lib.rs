@@ -0,0 +1,305 @@
+// src/lib.rs
+//! 系统资源的采集、告警和发送。命令行程序的选项定义([`cli`])、主循环([`run`])和各子命令([`commands`])也在库中，
+//! `main.rs` 只负责读取配置、初始化日志和分派子命令。也可以直接嵌入到其他程序中：
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use system_monitor::Monitor;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let mut monitor = Monitor::builder()
+//!     .interval(Duration::from_secs(5))
+//!     .top(3)
+//!     .build()
+//!     .unwrap();
+//! // CPU占用率和网络速率按两次采集之间计算，第一次采集前先等待一个间隔
+//! tokio::time::sleep(monitor.interval()).await;
+//! let stats = monitor.collect().await;
+//! println!("{}", serde_json::to_string(&stats).unwrap());
+//! # }
+//! ```
+//!
+//! 发送样本见 [`delivery::Delivery`] 和 [`transport::Transport`]，在后台任务中发送见 [`pipeline::Pipeline`]，
+//! 告警见 [`alert::Engine`]，文本输出见 [`display`]。
+pub mod agent;
+pub mod aggregate;
+pub mod aggregator;
+pub mod alert;
+pub mod anomaly;
+pub mod bench;
+pub mod breaker;
+pub mod check;
+pub mod churn;
+pub mod cli;
+pub mod cloud;
+pub mod collector;
+pub mod commands;
+pub mod config;
+pub mod compact;
+pub mod crash;
+pub mod crypto;
+pub mod delta;
+pub mod delivery;
+pub mod derived;
+pub mod desktop;
+pub mod diff;
+pub mod display;
+pub mod dns_check;
+pub mod email;
+pub mod environment;
+pub mod error;
+pub mod events;
+pub mod exec;
+pub mod filter;
+pub mod forecast;
+pub mod health;
+pub mod histogram;
+pub mod history;
+pub mod hook;
+pub mod http_check;
+pub mod i18n;
+pub mod identity;
+pub mod kubernetes;
+pub mod lifecycle;
+pub mod log;
+pub mod logind;
+pub mod log_watch;
+pub mod maintenance;
+pub mod mdns;
+pub mod mock;
+mod monitor;
+pub mod options;
+pub mod pipeline;
+pub mod plugin;
+pub mod probe;
+pub mod prometheus;
+pub mod remote;
+pub mod replay;
+pub mod resolve;
+pub mod run;
+pub mod script;
+pub mod serve;
+mod signals;
+pub mod spool;
+pub mod systemd;
+pub mod talkers;
+pub mod template;
+pub mod template_sink;
+pub mod throttle;
+pub mod tls_check;
+pub mod token;
+pub mod transport;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(not(feature = "tui"))]
+#[path = "tui_stub.rs"]
+mod tui;
+pub mod units;
+pub mod version;
+pub mod webhook;
+mod worker;
+
+pub use monitor::{resolve_users, Collectors, Monitor, MonitorBuilder};
+
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+compile_error!("需要启用 native-tls 或 rustls 特性之一");
+
+use agent::AgentStats;
+use chrono::{DateTime, Utc};
+use cloud::CloudMetadata;
+use kubernetes::KubernetesMetadata;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// 请求体结构的版本号，字段发生不兼容变化时递增
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// 最短的刷新间隔，避免 `--interval 0` 变成空转
+pub const MIN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 一个样本，也是发送给端点的请求体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemStats {
+    pub version: u32,
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    /// 主机名，设置了 `--hostname` 时为设置的值
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub host: String,
+    /// 不随主机名变化的实例ID，使用 `--instance-id` 时才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloud: Option<CloudMetadata>,
+    /// 节点可分配的资源和已使用的资源，使用 `--kubernetes-allocatable` 时才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kubernetes: Option<KubernetesMetadata>,
+    /// 每个核心的CPU占用率(%)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu: Vec<f32>,
+    /// `cpu` 的测量窗口：占用率是这段时间内的平均值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_window: Option<CpuWindow>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mem: Option<MemoryStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swap: Option<MemoryStats>,
+    /// 按挂载点
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub disk: BTreeMap<String, DiskStats>,
+    /// 按网卡名
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub net: std::collections::HashMap<String, NetworkStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proc: Option<ProcessStats>,
+    /// CPU占用最高的进程，使用 `--top N` 时才有
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top: Vec<ProcessInfo>,
+    /// 按传感器的温度(°C)，没有可读的传感器时省略
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub temp: BTreeMap<String, f32>,
+    /// 按 `--probe` 的目标，使用 `--probe` 时才有
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub probes: BTreeMap<String, probe::ProbeStats>,
+    /// 按 `--http-check` 的名称，使用 `--http-check` 时才有
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub http: BTreeMap<String, http_check::HttpCheckStats>,
+    /// 按 `--dns-check` 的值，使用 `--dns-check` 时才有
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dns: BTreeMap<String, dns_check::DnsCheckStats>,
+    /// 按 `--tls-check` 的端点，使用 `--tls-check` 时才有
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tls: BTreeMap<String, tls_check::TlsCheckStats>,
+    /// 按 `--watch-pattern` 的名称，使用 `--watch-log` 时才有
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub logs: BTreeMap<String, log_watch::LogMatches>,
+    /// 上次采集以来启动和退出的进程，使用 `--proc-events` 时才有
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lifecycle: Vec<lifecycle::ProcessEvent>,
+    /// 上次采集以来进程的创建、退出和短命进程，使用 `--proc-churn` 时才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub churn: Option<churn::ProcessChurn>,
+    /// 按进程和对端端口统计的网络流量，使用 `--net-talkers` 时才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub talkers: Option<talkers::NetTalkers>,
+    /// 按 `--derive` 的名称，由样本中的其他值计算；引用的值缺失时省略
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub derived: BTreeMap<String, f64>,
+    /// 内存和磁盘用量的趋势，使用 `--forecast` 时才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forecast: Option<forecast::Forecast>,
+    /// 通过 [`MonitorBuilder::collector`] 注册的采集项，以采集项的名称为键
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+    /// 本次重新采集的采集项，其余字段沿用上次的值；只在设置了 `--collector-interval` 时出现
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refreshed: Option<Vec<String>>,
+    /// 本程序自身的资源占用和发送情况
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<AgentStats>,
+    /// 偏离基线的指标，使用 `--anomaly` 时才有
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anomalies: Vec<anomaly::Anomaly>,
+    /// 正在触发的告警，设置了 `--alert` 且有告警触发时才有
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<alert::Active>,
+    /// 正在生效的 `--maintenance` 窗口和 `--quiet-hours` 时段，不在窗口内时没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<maintenance::Maintenance>,
+    /// 使用 `--aggregate` 时这个样本汇总的窗口
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregate: Option<aggregate::Aggregate>,
+}
+
+/// 不带序号直接发送给端点的事件：正常退出前的 `stopping`，接收端可以据此区分主动停止和失联；
+/// 使用 `--alert-events` 时告警状态变化的 `alert`；使用 `--power-events` 时的休眠、恢复、合盖和登录会话变化；
+/// 使用 `--event-sink endpoint` 时网卡、挂载点等状态变化的 `change`；因错误退出时 `stopping` 事件带有 `error`
+#[derive(Debug, Serialize)]
+pub struct AgentEvent {
+    pub version: u32,
+    pub event: &'static str,
+    pub timestamp: DateTime<Utc>,
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert: Option<alert::Event>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<logind::Session>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change: Option<events::Event>,
+    /// 因错误退出时的 `stopping` 事件中的错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<error::ErrorReport>,
+}
+
+/// CPU占用率的测量窗口
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuWindow {
+    /// 窗口的长度(毫秒)，从上次采集的最后一次刷新算起
+    pub ms: u64,
+    /// 窗口内刷新CPU的次数，`--cpu-sample-interval` 比刷新间隔短时大于1
+    pub samples: u32,
+    /// 每个核心在各次刷新之间的最高占用率，只刷新一次时省略
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub peak: Vec<f32>,
+}
+
+/// 内存或swap的占用(字节)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryStats {
+    pub total: u64,
+    pub used: u64,
+}
+
+/// 一个挂载点的磁盘占用(字节)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskStats {
+    pub total: u64,
+    pub used: u64,
+}
+
+/// 一个网卡的流量(字节/秒)，网卡刚出现或计数器重置后的第一次采集还没有速率，为 `None`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkStats {
+    pub rx: Option<u64>,
+    pub tx: Option<u64>,
+    /// 连接状态，只在Linux上有；lo、tun等不报告状态的网卡没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub up: Option<bool>,
+}
+
+/// 按状态统计的进程数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessStats {
+    pub total: usize,
+    pub running: usize,
+    pub sleeping: usize,
+    pub zombie: usize,
+}
+
+/// 一个进程的CPU占用率(%)和内存(字节)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub cpu: f32,
+    pub mem: u64,
+    /// 启动时间(Unix时间戳，秒)，只在本地用来确认PID仍是同一个进程，不上报
+    #[serde(skip)]
+    pub start_time: u64,
+}
+
+/// 解析时长：不带单位的数字表示秒(可以是小数)，也可以写成 `250ms`、`1m30s` 等形式
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    match value.trim().parse::<f64>() {
+        Ok(secs) => Duration::try_from_secs_f64(secs).ok(),
+        Err(_) => humantime::parse_duration(value.trim()).ok(),
+    }
+}
@@ -0,0 +1,149 @@
+// src/lifecycle.rs
+use crate::collector::{Collector, Context, Section};
+use crate::i18n::tr;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use sysinfo::{Pid, PidExt, Process, ProcessExt, ProcessRefreshKind, System, SystemExt, UserExt};
+
+/// 采集项的名称
+pub const NAME: &str = "lifecycle";
+
+/// 一次采集最多报告的事件数，大量进程同时启动或退出时其余的只记录一条警告
+const MAX_EVENTS: usize = 500;
+
+/// 命令行最多保留的字符数
+const MAX_CMD: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    Start,
+    Exit,
+}
+
+/// 一个进程的启动或退出；退出的进程给出最后一次看到它时的情况，退出码只有父进程能取得，不在其中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessEvent {
+    pub event: Change,
+    pub pid: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ppid: Option<u32>,
+    pub name: String,
+    /// 用户名，无法解析时为UID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub cmd: String,
+    pub started: DateTime<Utc>,
+    /// 退出的进程最后一次被看到的时间，在这之后到本次采集之间退出
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<DateTime<Utc>>,
+    /// 退出的进程最后一次看到时已经运行的秒数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<u64>,
+    /// 退出的进程最后一次看到时的CPU占用率(%)和内存(字节)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mem: Option<u64>,
+}
+
+/// 上次采集时符合过滤条件的一个进程
+struct Seen {
+    /// 进程的启动时间，同一个PID启动时间不同说明PID已经被新的进程复用
+    start_time: u64,
+    event: ProcessEvent,
+    last_seen: DateTime<Utc>,
+    run_time: u64,
+    cpu: f32,
+    mem: u64,
+}
+
+/// 比较两次采集之间的进程表，报告符合 `--proc-name`、`--proc-user`、`--proc-cgroup` 的进程的启动和退出。
+/// 第一次采集只记下当前的进程；两次采集之间启动又退出的进程看不到
+#[derive(Default)]
+pub struct Lifecycle {
+    seen: Option<HashMap<u32, Seen>>,
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Collector for Lifecycle {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn init(&mut self, system: &mut System) {
+        system.refresh_users_list();
+    }
+
+    fn collect(&mut self, ctx: &mut Context) -> Section {
+        ctx.system.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_user());
+        let now = Utc::now();
+        let mut current = HashMap::new();
+        for (pid, process) in ctx.system.processes().iter().filter(|(_, p)| ctx.process_matches(p)) {
+            let seen = Seen {
+                start_time: process.start_time(),
+                event: event(ctx.system, *pid, process),
+                last_seen: now,
+                run_time: process.run_time(),
+                cpu: process.cpu_usage(),
+                mem: process.memory(),
+            };
+            current.insert(pid.as_u32(), seen);
+        }
+
+        let Some(mut previous) = self.seen.take() else {
+            self.seen = Some(current);
+            return Section::Lifecycle(Vec::new());
+        };
+        let mut events = Vec::new();
+        for (pid, seen) in &current {
+            match previous.get(pid) {
+                Some(last) if last.start_time == seen.start_time => {}
+                _ => events.push(seen.event.clone()),
+            }
+        }
+        previous.retain(|pid, last| current.get(pid).is_none_or(|seen| seen.start_time != last.start_time));
+        events.extend(previous.into_values().map(|last| ProcessEvent {
+            event: Change::Exit,
+            last_seen: Some(last.last_seen),
+            runtime: Some(last.run_time),
+            cpu: Some(last.cpu),
+            mem: Some(last.mem),
+            ..last.event
+        }));
+        events.sort_by_key(|event| (event.event, event.started, event.pid));
+        if events.len() > MAX_EVENTS {
+            tracing::warn!(events = events.len(), max = MAX_EVENTS, "{}", tr("进程事件过多，超出的没有报告"));
+            events.truncate(MAX_EVENTS);
+        }
+        self.seen = Some(current);
+        Section::Lifecycle(events)
+    }
+}
+
+fn event(system: &System, pid: Pid, process: &Process) -> ProcessEvent {
+    let user = process.user_id().map(|uid| match system.get_user_by_id(uid) {
+        Some(user) => user.name().to_string(),
+        None => uid.to_string(),
+    });
+    ProcessEvent {
+        event: Change::Start,
+        pid: pid.as_u32(),
+        ppid: process.parent().map(|ppid| ppid.as_u32()),
+        name: process.name().to_string(),
+        user,
+        cmd: process.cmd().join(" ").chars().take(MAX_CMD).collect(),
+        started: DateTime::from_timestamp(process.start_time() as i64, 0).unwrap_or_default(),
+        last_seen: None,
+        runtime: None,
+        cpu: None,
+        mem: None,
+    }
+}
@@ -0,0 +1,188 @@
+// src/log.rs
+use crate::error::Error;
+use crate::i18n::tr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::{self, MakeWriter};
+use tracing_subscriber::prelude::*;
+
+/// 终端界面运行时日志不写到标准错误，否则会打乱屏幕
+static TERMINAL_UI: AtomicBool = AtomicBool::new(false);
+
+pub fn set_terminal_ui(active: bool) {
+    TERMINAL_UI.store(active, Ordering::Relaxed);
+}
+
+/// 使用 `--log-file` 时的日志文件，供 [`rotate`] 使用
+static LOG_FILE: OnceLock<Arc<LogFile>> = OnceLock::new();
+
+/// 是否使用了 `--log-file`
+pub fn to_file() -> bool {
+    LOG_FILE.get().is_some()
+}
+
+/// 立即轮转日志文件(文件为空时不轮转)，没有使用 `--log-file` 时返回 false
+pub fn rotate() -> io::Result<bool> {
+    let Some(file) = LOG_FILE.get() else {
+        return Ok(false);
+    };
+    let mut state = file.state.lock().unwrap_or_else(|e| e.into_inner());
+    if state.size > 0 {
+        file.rotate(&mut state)?;
+    }
+    Ok(true)
+}
+
+/// 日志文件的轮转设置：超过 `max_size` 字节或进入新的 `every` 周期时轮转，保留 `keep` 个旧文件
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Rotation {
+    pub max_size: Option<u64>,
+    pub every: Option<Duration>,
+    pub keep: usize,
+}
+
+impl Rotation {
+    /// 时间所在的轮转周期，周期从UTC零点(Unix纪元)开始对齐
+    fn period(&self, time: SystemTime) -> u64 {
+        match self.every {
+            Some(every) if !every.is_zero() => {
+                let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                secs / every.as_secs().max(1)
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// 可轮转的日志文件，旧文件依次命名为 `PATH.1`(最新)到 `PATH.<keep>`
+struct LogFile {
+    path: PathBuf,
+    rotation: Rotation,
+    state: Mutex<LogFileState>,
+}
+
+struct LogFileState {
+    file: File,
+    size: u64,
+    period: u64,
+}
+
+impl LogFile {
+    fn open(path: &Path, rotation: Rotation) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let metadata = file.metadata()?;
+        // 上次运行留下的文件属于更早的周期时，第一次写入就会轮转
+        let period = rotation.period(metadata.modified().unwrap_or_else(|_| SystemTime::now()));
+        Ok(Self {
+            path: path.to_path_buf(),
+            rotation,
+            state: Mutex::new(LogFileState { file, size: metadata.len(), period }),
+        })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut path = self.path.as_os_str().to_owned();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&self, state: &mut LogFileState) -> io::Result<()> {
+        if self.rotation.keep == 0 {
+            let _ = std::fs::remove_file(&self.path);
+        } else {
+            let _ = std::fs::remove_file(self.rotated(self.rotation.keep));
+            for n in (1..self.rotation.keep).rev() {
+                let _ = std::fs::rename(self.rotated(n), self.rotated(n + 1));
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        state.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        state.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for &LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let period = self.rotation.period(SystemTime::now());
+        let full = self.rotation.max_size.is_some_and(|max| state.size + buf.len() as u64 > max);
+        if (full || period != state.period) && state.size > 0 {
+            // 轮转失败(例如目录不可写)时继续写原来的文件
+            let _ = self.rotate(&mut state);
+        }
+        state.period = period;
+        let written = state.file.write(buf)?;
+        state.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).file.flush()
+    }
+}
+
+/// 日志输出到标准错误(终端界面运行时丢弃)或追加到文件
+enum Target {
+    Stderr,
+    File(Arc<LogFile>),
+}
+
+impl<'a> MakeWriter<'a> for Target {
+    type Writer = Box<dyn Write + 'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        match self {
+            Target::File(file) => Box::new(file.as_ref()),
+            Target::Stderr if TERMINAL_UI.load(Ordering::Relaxed) => Box::new(io::sink()),
+            Target::Stderr => Box::new(io::stderr()),
+        }
+    }
+}
+
+/// 初始化日志：默认记录 info 及以上级别，`verbosity` 每加一级多记录一个级别，每减一级少记录一个级别；
+/// 指定了 `file` 时日志追加到该文件，并按 `rotation` 轮转
+pub fn init(
+    verbosity: i8,
+    json: bool,
+    file: Option<&Path>,
+    rotation: Rotation,
+) -> Result<(), Error> {
+    let level = match verbosity {
+        i8::MIN..=-3 => LevelFilter::OFF,
+        -2 => LevelFilter::ERROR,
+        -1 => LevelFilter::WARN,
+        0 => LevelFilter::INFO,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+
+    // 依赖库(HTTP客户端等)的日志最多记录到 warn 级别
+    let targets = Targets::new()
+        .with_default(level.min(LevelFilter::WARN))
+        .with_target(env!("CARGO_CRATE_NAME"), level);
+    let (target, ansi) = match file {
+        Some(path) => {
+            let file = LogFile::open(path, rotation)
+                .map_err(|e| format!("{} {}: {}", tr("无法打开日志文件"), path.display(), e))?;
+            let file = Arc::new(file);
+            let _ = LOG_FILE.set(file.clone());
+            (Target::File(file), false)
+        }
+        None => (Target::Stderr, io::stderr().is_terminal()),
+    };
+    let layer = fmt::layer().with_writer(target).with_target(false);
+    let layer = if json {
+        layer.json().boxed()
+    } else {
+        layer.with_ansi(ansi).boxed()
+    };
+    tracing_subscriber::registry().with(layer).with(targets).init();
+    Ok(())
+}
@@ -0,0 +1,192 @@
+// src/log_watch.rs
+use crate::collector::{Collector, Context, Section};
+use crate::i18n::tr;
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// 采集项的名称
+pub const NAME: &str = "logs";
+
+/// 一次采集最多读取的字节数，日志突然大量写入时其余的留到下次采集
+const MAX_READ: u64 = 16 << 20;
+
+/// 一个模式在一次采集期间的匹配情况
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogMatches {
+    /// 上次采集以来新写入的行中匹配的行数，所有文件合计
+    pub matches: u64,
+    /// 每秒匹配的行数
+    pub per_second: f64,
+}
+
+/// `--watch-pattern NAME=REGEX` 的校验
+pub fn log_pattern_arg(value: &str) -> Result<String, String> {
+    parse_pattern(value).map(|_| value.to_string())
+}
+
+fn parse_pattern(value: &str) -> Result<(String, Regex), String> {
+    match value.split_once('=') {
+        Some((name, regex)) if !name.trim().is_empty() && !regex.is_empty() => {
+            let regex = Regex::new(regex).map_err(|e| format!("{}: {}", tr("无效的正则表达式"), e))?;
+            Ok((name.trim().to_string(), regex))
+        }
+        _ => Err(format!("{}: {}", tr("无效的日志模式"), value)),
+    }
+}
+
+/// 跟踪一个日志文件：记录读到的位置，文件被轮转(改名后新建)时先读完旧文件再从头读新文件，被截断时从头读
+struct Tail {
+    path: PathBuf,
+    file: Option<File>,
+    /// 打开的文件的标识，路径指向另一个文件时说明发生了轮转
+    identity: Option<(u64, u64)>,
+    offset: u64,
+    /// 还没有换行符的最后一行，等写完再匹配
+    partial: Vec<u8>,
+    /// 上次读取失败，恢复之前不再重复记录警告
+    failing: bool,
+}
+
+impl Tail {
+    /// 启动时已经存在的文件从末尾开始，只统计之后写入的行
+    fn new(path: &str) -> Self {
+        let mut tail = Self { path: PathBuf::from(path), file: None, identity: None, offset: 0, partial: Vec::new(), failing: false };
+        if let Ok(file) = File::open(&tail.path) {
+            if let Ok(metadata) = file.metadata() {
+                tail.identity = Some(identity(&metadata));
+                tail.offset = metadata.len();
+                tail.file = Some(file);
+            }
+        }
+        tail
+    }
+
+    /// 读取新写入的完整的行
+    fn read(&mut self, mut line: impl FnMut(&[u8])) {
+        match std::fs::metadata(&self.path) {
+            Ok(metadata) if Some(identity(&metadata)) == self.identity => {
+                if metadata.len() < self.offset {
+                    // copytruncate 方式的轮转：文件被原地截断
+                    self.offset = 0;
+                    self.partial.clear();
+                }
+            }
+            Ok(_) => {
+                // 改名后新建的轮转：旧文件中剩下的行还没有读，最后一行没有换行符也不会再写完了
+                self.drain(&mut line);
+                if !self.partial.is_empty() {
+                    line(&std::mem::take(&mut self.partial));
+                }
+                if let Err(error) = self.reopen() {
+                    self.fail(error);
+                    return;
+                }
+            }
+            Err(error) => {
+                // 轮转后还没有新建文件，先读完旧文件
+                self.drain(&mut line);
+                self.fail(error);
+                return;
+            }
+        }
+        self.drain(&mut line);
+        self.failing = false;
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        self.file = None;
+        let file = File::open(&self.path)?;
+        self.identity = Some(identity(&file.metadata()?));
+        self.file = Some(file);
+        self.offset = 0;
+        Ok(())
+    }
+
+    fn drain(&mut self, line: &mut impl FnMut(&[u8])) {
+        let Some(file) = &mut self.file else { return };
+        let mut buf = Vec::new();
+        let read = file.seek(SeekFrom::Start(self.offset)).and_then(|_| file.take(MAX_READ).read_to_end(&mut buf));
+        if read.is_err() || buf.is_empty() {
+            return;
+        }
+        self.offset += buf.len() as u64;
+        self.partial.extend_from_slice(&buf);
+        let Some(end) = self.partial.iter().rposition(|&b| b == b'\n') else { return };
+        for text in self.partial[..end].split(|&b| b == b'\n') {
+            line(text.strip_suffix(b"\r").unwrap_or(text));
+        }
+        self.partial.drain(..=end);
+    }
+
+    fn fail(&mut self, error: io::Error) {
+        if !self.failing {
+            tracing::warn!(path = %self.path.display(), %error, "{}", tr("无法读取日志文件"));
+        }
+        self.failing = true;
+    }
+}
+
+/// 文件的标识：Unix上为设备号和inode，其他平台上为创建时间
+#[cfg(unix)]
+fn identity(metadata: &Metadata) -> (u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn identity(metadata: &Metadata) -> (u64, u64) {
+    let created = metadata.created().ok().and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok()).unwrap_or_default();
+    (created.as_secs(), created.subsec_nanos() as u64)
+}
+
+/// 跟踪 `--watch-log` 的文件，每次采集统计新写入的行中匹配各 `--watch-pattern` 的行数，以模式的名称为键
+pub struct LogWatch {
+    tails: Vec<Tail>,
+    patterns: Vec<(String, Regex)>,
+    last: Instant,
+}
+
+impl LogWatch {
+    pub fn new(files: &[String], patterns: &[String]) -> Result<Self, String> {
+        let patterns: Vec<(String, Regex)> = patterns.iter().map(|value| parse_pattern(value)).collect::<Result<_, _>>()?;
+        if let Some((name, _)) = patterns.iter().enumerate().find_map(|(i, (name, _))| patterns[..i].iter().find(|(n, _)| n == name)) {
+            return Err(format!("{}: {}", tr("日志模式的名称重复"), name));
+        }
+        Ok(Self { tails: files.iter().map(|path| Tail::new(path)).collect(), patterns, last: Instant::now() })
+    }
+}
+
+impl Collector for LogWatch {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn collect(&mut self, _ctx: &mut Context) -> Section {
+        let mut counts = vec![0u64; self.patterns.len()];
+        for tail in &mut self.tails {
+            tail.read(|line| {
+                for (count, (_, regex)) in counts.iter_mut().zip(&self.patterns) {
+                    if regex.is_match(line) {
+                        *count += 1;
+                    }
+                }
+            });
+        }
+        let elapsed = self.last.elapsed().as_secs_f64();
+        self.last = Instant::now();
+        let matches = self
+            .patterns
+            .iter()
+            .zip(counts)
+            .map(|((name, _), matches)| {
+                let per_second = if elapsed > 0.0 { matches as f64 / elapsed } else { 0.0 };
+                (name.clone(), LogMatches { matches, per_second: (per_second * 1000.0).round() / 1000.0 })
+            })
+            .collect();
+        Section::Logs(matches)
+    }
+}
@@ -0,0 +1,139 @@
+// src/logind.rs
+use crate::error::Error;
+use serde::Serialize;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// 编译时是否启用了 `logind` 特性，只在Linux上可用
+pub const AVAILABLE: bool = cfg!(all(feature = "logind", target_os = "linux"));
+
+/// 一个登录会话
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Session {
+    pub id: String,
+    /// 会话所属的用户，启动前已经结束的会话无法得知
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+/// logind 和 UPower 报告的系统事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// 即将休眠(包括混合休眠和休眠到硬盘)
+    Suspend,
+    /// 从休眠中恢复，或休眠没有成功
+    Resume,
+    LidClosed,
+    LidOpened,
+    SessionNew(Session),
+    SessionRemoved(Session),
+}
+
+impl Event {
+    /// 发送给端点的事件名
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Suspend => "suspend",
+            Event::Resume => "resume",
+            Event::LidClosed => "lid_closed",
+            Event::LidOpened => "lid_opened",
+            Event::SessionNew(_) => "session_new",
+            Event::SessionRemoved(_) => "session_removed",
+        }
+    }
+
+    pub fn session(&self) -> Option<&Session> {
+        match self {
+            Event::SessionNew(session) | Event::SessionRemoved(session) => Some(session),
+            _ => None,
+        }
+    }
+}
+
+/// 连接系统总线，订阅 logind 的休眠和会话信号以及 UPower 的合盖状态，在后台线程中接收；
+/// 没有 UPower 时只是收不到合盖事件
+#[cfg(all(feature = "logind", target_os = "linux"))]
+pub fn watch() -> Result<UnboundedReceiver<Event>, Error> {
+    use crate::i18n::tr;
+    use std::collections::{BTreeMap, HashMap};
+    use zbus::blocking::fdo::DBusProxy;
+    use zbus::blocking::{connection, Connection, MessageIterator};
+    use zbus::message::Type;
+    use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+    use zbus::MatchRule;
+
+    const LOGIND: &str = "org.freedesktop.login1";
+    /// 查询会话的用户时最多等待的时间，logind 没有响应时不耽误之后的信号
+    const METHOD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+    /// 当前的会话及其用户
+    fn sessions(conn: &Connection) -> zbus::Result<BTreeMap<String, String>> {
+        let reply = conn.call_method(Some(LOGIND), "/org/freedesktop/login1", Some("org.freedesktop.login1.Manager"), "ListSessions", &())?;
+        let sessions: Vec<(String, u32, String, String, OwnedObjectPath)> = reply.body().deserialize()?;
+        Ok(sessions.into_iter().map(|(id, _, user, _, _)| (id, user)).collect())
+    }
+
+    fn error(e: impl std::fmt::Display) -> String {
+        format!("{}: {}", tr("无法订阅logind的信号"), e)
+    }
+
+    let conn = connection::Builder::system().and_then(|builder| builder.method_timeout(METHOD_TIMEOUT).build()).map_err(error)?;
+    let dbus = DBusProxy::new(&conn).map_err(error)?;
+    let logind = MatchRule::builder().msg_type(Type::Signal).sender(LOGIND).and_then(|rule| rule.path("/org/freedesktop/login1"));
+    let upower = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender("org.freedesktop.UPower")
+        .and_then(|rule| rule.path("/org/freedesktop/UPower"))
+        .and_then(|rule| rule.member("PropertiesChanged"));
+    dbus.add_match_rule(logind.map_err(error)?.build()).map_err(error)?;
+    dbus.add_match_rule(upower.map_err(error)?.build()).map_err(error)?;
+    let mut known = sessions(&conn).unwrap_or_default();
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    std::thread::Builder::new().name("logind".to_string()).spawn(move || {
+        for message in MessageIterator::from(&conn) {
+            let Ok(message) = message else { continue };
+            let header = message.header();
+            if header.message_type() != Type::Signal {
+                continue;
+            }
+            let body = message.body();
+            let event = match header.member().map(|member| member.as_str()) {
+                Some("PrepareForSleep") => match body.deserialize::<bool>() {
+                    Ok(true) => Event::Suspend,
+                    Ok(false) => Event::Resume,
+                    Err(_) => continue,
+                },
+                Some("SessionNew") => {
+                    let Ok((id, _)) = body.deserialize::<(String, OwnedObjectPath)>() else { continue };
+                    if let Ok(sessions) = sessions(&conn) {
+                        known.extend(sessions);
+                    }
+                    Event::SessionNew(Session { user: known.get(&id).cloned(), id })
+                }
+                Some("SessionRemoved") => {
+                    let Ok((id, _)) = body.deserialize::<(String, OwnedObjectPath)>() else { continue };
+                    Event::SessionRemoved(Session { user: known.remove(&id), id })
+                }
+                Some("PropertiesChanged") => {
+                    let Ok((_, changed, _)) = body.deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>() else { continue };
+                    match changed.get("LidIsClosed").and_then(|value| value.downcast_ref::<bool>().ok()) {
+                        Some(true) => Event::LidClosed,
+                        Some(false) => Event::LidOpened,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            // 主循环退出后不再接收
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    })?;
+    Ok(rx)
+}
+
+#[cfg(not(all(feature = "logind", target_os = "linux")))]
+pub fn watch() -> Result<UnboundedReceiver<Event>, Error> {
+    Err(Error::config(crate::i18n::tr("--power-events 需要在Linux上以 --features logind 编译")))
+}
@@ -0,0 +1,429 @@
+// src/maintenance.rs
+use crate::alert::{Active, Event, State};
+use crate::i18n::tr;
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// 窗口的最长持续时间，判断时逐分钟往前查找窗口的开始
+const MAX_DURATION: Duration = Duration::from_secs(31 * 86400);
+
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// 正在生效的维护窗口和静默时段，随样本一起上报
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Maintenance {
+    /// 生效的窗口名称
+    pub windows: Vec<String>,
+    /// 有 `--maintenance` 窗口生效、暂停发送；只有 `--quiet-hours` 时只抑制告警通知
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub paused: bool,
+    /// 生效的窗口中最晚的结束时间
+    pub until: DateTime<Utc>,
+}
+
+/// 五个字段的cron表达式(分 时 日 月 星期)，每个字段按位记录允许的值
+#[derive(Debug, Clone, PartialEq)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// 日和星期都有限制时满足其一即可，与cron相同
+    either: bool,
+}
+
+impl Cron {
+    /// 支持 `*`、`A-B`、`*/N`、`A-B/N`、逗号分隔的列表、月份和星期的英文缩写，以及 `@daily` 等简写
+    fn parse(expr: &str) -> Option<Self> {
+        let expr = match expr.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expr => expr,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return None;
+        };
+        // 星期的7和0都是星期日
+        let weekdays = field(weekday, 0, 7, &WEEKDAYS)?;
+        Some(Self {
+            minutes: field(minute, 0, 59, &[])?,
+            hours: field(hour, 0, 23, &[])?,
+            days: field(day, 1, 31, &[])?,
+            months: field(month, 1, 12, &MONTHS)?,
+            weekdays: weekdays | (weekdays >> 7 & 1),
+            either: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+
+    fn matches(&self, time: &NaiveDateTime) -> bool {
+        let has = |set: u64, value: u32| set & (1 << value) != 0;
+        let day = has(self.days, time.day());
+        let weekday = has(self.weekdays, time.weekday().num_days_from_sunday());
+        has(self.minutes, time.minute())
+            && has(self.hours, time.hour())
+            && has(self.months, time.month())
+            && if self.either { day || weekday } else { day && weekday }
+    }
+}
+
+/// 解析cron表达式的一个字段，第n位表示允许值n；`names` 依次对应从 `min` 开始的值
+fn field(text: &str, min: u32, max: u32, names: &[&str]) -> Option<u64> {
+    let value = |text: &str| {
+        let value = match names.iter().position(|name| name.eq_ignore_ascii_case(text)) {
+            Some(index) => index as u32 + min,
+            None => text.parse().ok()?,
+        };
+        (min..=max).contains(&value).then_some(value)
+    };
+    let mut set = 0;
+    for item in text.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|step| *step > 0)?),
+            None => (item, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            // `5/15` 表示从5开始每隔15
+            None if item.contains('/') => (value(range)?, max),
+            None => (value(range)?, value(range)?),
+        };
+        if start > end {
+            return None;
+        }
+        set |= (start..=end).step_by(step).fold(0, |set, value| set | 1 << value);
+    }
+    Some(set)
+}
+
+/// 一个按cron表达式重复的时间窗口，按本地时间计算
+#[derive(Debug, Clone)]
+pub struct Window {
+    name: String,
+    cron: Cron,
+    duration: Duration,
+    /// 窗口内暂停发送(`--maintenance`)，否则只抑制告警通知(`--quiet-hours`)
+    pause: bool,
+}
+
+impl Window {
+    /// 解析 `NAME=CRON for DURATION`，例如 `backup=0 2 * * sun for 3h`
+    pub fn parse(value: &str, pause: bool) -> Result<Self, String> {
+        let invalid = || format!("{}: {}", tr("无效的维护窗口"), value);
+        let (name, expr) = value.split_once('=').ok_or_else(invalid)?;
+        let (cron, duration) = expr.rsplit_once(" for ").ok_or_else(invalid)?;
+        let name = name.trim();
+        let duration = crate::parse_duration(duration)
+            .filter(|duration| !duration.is_zero() && *duration <= MAX_DURATION)
+            .ok_or_else(invalid)?;
+        if name.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self { name: name.to_string(), cron: Cron::parse(cron).ok_or_else(invalid)?, duration, pause })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `now` 所在的这个窗口的结束时间，不在窗口内时为 None；窗口重叠时取最近开始的一次
+    fn end(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let duration = chrono::Duration::from_std(self.duration).ok()?;
+        let mut start = now.with_second(0)?.with_nanosecond(0)?;
+        while start + duration > now {
+            if self.cron.matches(&start) {
+                return Some(start + duration);
+            }
+            start -= chrono::Duration::minutes(1);
+        }
+        None
+    }
+}
+
+/// `--maintenance` 和 `--quiet-hours` 的校验
+pub fn window_arg(value: &str) -> Result<String, String> {
+    Window::parse(value, false).map(|_| value.to_string())
+}
+
+/// 按样本的时间判断生效的窗口，窗口内抑制告警通知、暂停发送
+#[derive(Default)]
+pub struct Schedule {
+    windows: Vec<Window>,
+    /// 上一个样本时生效的窗口
+    active: Vec<String>,
+    /// 上一个样本时已经暂停发送
+    paused: bool,
+    /// 这个样本不发送
+    holding: bool,
+    /// 窗口内开始触发、没有通知过的告警：(规则, 实例)
+    suppressed: BTreeSet<(String, String)>,
+}
+
+impl Schedule {
+    pub fn new(windows: Vec<Window>) -> Self {
+        Self { windows, ..Self::default() }
+    }
+
+    /// 重新加载配置时更新窗口，窗口内被抑制的告警仍按原来的状态处理
+    pub fn configure(&mut self, windows: Vec<Window>) {
+        self.windows = windows;
+    }
+
+    /// 用样本的时间戳判断生效的窗口(重放时同样适用)，窗口开始和结束时记录日志
+    pub fn update(&mut self, now: DateTime<Utc>) -> Option<Maintenance> {
+        let local = now.with_timezone(&Local).naive_local();
+        let mut active = Vec::new();
+        let mut until = None;
+        let mut pause = false;
+        for window in &self.windows {
+            let Some(end) = window.end(local) else { continue };
+            let end = now + (end - local);
+            if !self.active.contains(&window.name) {
+                tracing::info!(window = %window.name, until = %end, pause = window.pause, "{}", tr("维护窗口开始"));
+            }
+            active.push(window.name.clone());
+            until = until.max(Some(end));
+            pause |= window.pause;
+        }
+        for name in self.active.iter().filter(|name| !active.contains(name)) {
+            tracing::info!(window = %name, "{}", tr("维护窗口结束"));
+        }
+        // 暂停后的第一个样本照常发送，接收端从中得知之后的空缺是计划中的
+        self.holding = pause && self.paused;
+        self.paused = pause;
+        self.active = active;
+        Some(Maintenance { windows: self.active.clone(), paused: pause, until: until? })
+    }
+
+    /// 这个样本不发送：处在 `--maintenance` 窗口中，并且已经发送过标明暂停的样本
+    pub fn holding(&self) -> bool {
+        self.holding
+    }
+
+    /// 过滤 [`Schedule::update`] 之后的告警事件：窗口内不通知新触发的告警和重复通知，
+    /// 这些告警在窗口内解除时也不通知，窗口结束时仍在触发的补发一次触发通知
+    pub fn alerts(&mut self, events: Vec<Event>, active: &[Active], now: DateTime<Utc>) -> Vec<Event> {
+        let key = |event: &Event| (event.rule.clone(), event.instance.clone());
+        if !self.active.is_empty() {
+            return events
+                .into_iter()
+                .filter(|event| match event.state {
+                    State::Firing => {
+                        if !event.repeat {
+                            tracing::info!(rule = %event.rule, instance = %event.instance, value = event.value, "{}", tr("维护窗口内的告警，不发送通知"));
+                            self.suppressed.insert(key(event));
+                        }
+                        false
+                    }
+                    // 窗口开始前已经通知过的告警照常通知解除
+                    State::Resolved => !self.suppressed.remove(&key(event)),
+                })
+                .collect();
+        }
+
+        let suppressed = std::mem::take(&mut self.suppressed);
+        let mut events: Vec<Event> = events.into_iter().filter(|event| !suppressed.contains(&key(event))).collect();
+        events.extend(
+            active
+                .iter()
+                .filter(|alert| suppressed.contains(&(alert.rule.clone(), alert.instance.clone())))
+                .map(|alert| Event {
+                    rule: alert.rule.clone(),
+                    instance: alert.instance.clone(),
+                    state: State::Firing,
+                    expr: alert.expr.clone(),
+                    value: alert.value,
+                    threshold: alert.threshold,
+                    since: alert.since,
+                    timestamp: now,
+                    repeat: false,
+                }),
+        );
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn bits(values: impl IntoIterator<Item = u32>) -> u64 {
+        values.into_iter().fold(0, |set, value| set | 1 << value)
+    }
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_cron_fields() {
+        assert_eq!(field("*", 0, 59, &[]), Some(bits(0..=59)));
+        assert_eq!(field("5", 0, 59, &[]), Some(bits([5])));
+        assert_eq!(field("1-3,7", 0, 59, &[]), Some(bits([1, 2, 3, 7])));
+        assert_eq!(field("*/15", 0, 59, &[]), Some(bits([0, 15, 30, 45])));
+        assert_eq!(field("10-30/10", 0, 59, &[]), Some(bits([10, 20, 30])));
+        assert_eq!(field("5/20", 0, 59, &[]), Some(bits([5, 25, 45])));
+        assert_eq!(field("*/5", 1, 12, &MONTHS), Some(bits([1, 6, 11])));
+        assert_eq!(field("0,59", 0, 59, &[]), Some(bits([0, 59])));
+    }
+
+    #[test]
+    fn parses_month_and_weekday_names() {
+        assert_eq!(field("jan,Mar,DEC", 1, 12, &MONTHS), Some(bits([1, 3, 12])));
+        assert_eq!(field("mon-fri", 0, 7, &WEEKDAYS), Some(bits(1..=5)));
+        assert_eq!(field("sat-sun", 0, 7, &WEEKDAYS), None);
+    }
+
+    #[test]
+    fn rejects_invalid_cron_fields() {
+        for (text, min, max) in [
+            ("60", 0, 59),
+            ("0", 1, 31),
+            ("32", 1, 31),
+            ("13", 1, 12),
+            ("-1", 0, 59),
+            ("5-1", 0, 59),
+            ("*/0", 0, 59),
+            ("*/x", 0, 59),
+            ("1,", 0, 59),
+            ("", 0, 59),
+            ("1-", 0, 59),
+            ("mon", 0, 59),
+        ] {
+            assert_eq!(field(text, min, max, &[]), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parses_cron_expressions() {
+        let cron = Cron::parse("30 2 * * sun").unwrap();
+        assert_eq!((cron.minutes, cron.hours, cron.weekdays, cron.either), (bits([30]), bits([2]), bits([0]), false));
+        assert_eq!(Cron::parse("@daily"), Cron::parse("0 0 * * *"));
+        assert_eq!(Cron::parse("@midnight"), Cron::parse("0 0 * * *"));
+        assert_eq!(Cron::parse("@annually"), Cron::parse("0 0 1 1 *"));
+        // 星期的7也是星期日
+        assert_eq!(Cron::parse("0 0 * * 7").unwrap().weekdays, bits([0, 7]));
+        for expr in ["", "* * * *", "* * * * * *", "@reboot", "* * * * 8", "* 24 * * *"] {
+            assert_eq!(Cron::parse(expr), None, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn matches_day_or_weekday_like_cron() {
+        // 2024-03-01是星期五
+        let either = Cron::parse("0 0 13 * fri").unwrap();
+        assert!(either.either);
+        assert!(either.matches(&at(2024, 3, 1, 0, 0)));
+        assert!(either.matches(&at(2024, 3, 13, 0, 0)));
+        assert!(!either.matches(&at(2024, 3, 2, 0, 0)));
+        assert!(!either.matches(&at(2024, 3, 1, 0, 1)));
+
+        let weekday = Cron::parse("0 0 * * fri").unwrap();
+        assert!(weekday.matches(&at(2024, 3, 8, 0, 0)));
+        assert!(!weekday.matches(&at(2024, 3, 13, 0, 0)));
+
+        let sunday = Cron::parse("0 0 * * 7").unwrap();
+        assert!(sunday.matches(&at(2024, 3, 3, 0, 0)));
+
+        let month = Cron::parse("0 0 * feb *").unwrap();
+        assert!(month.matches(&at(2024, 2, 29, 0, 0)));
+        assert!(!month.matches(&at(2024, 3, 1, 0, 0)));
+    }
+
+    #[test]
+    fn parses_windows() {
+        let window = Window::parse(" backup = 0 2 * * sun for 3h", true).unwrap();
+        assert_eq!((window.name(), window.duration, window.pause), ("backup", Duration::from_secs(3 * 3600), true));
+        for value in ["0 2 * * * for 1h", "=0 2 * * * for 1h", "a=0 2 * * *", "a=0 2 * * * for 0s", "a=0 2 * * * for 32d", "a=0 2 * * for 1h"] {
+            assert!(window_arg(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn finds_the_end_of_the_current_window() {
+        let window = Window::parse("night=0 22 * * * for 4h", false).unwrap();
+        assert_eq!(window.end(at(2024, 3, 1, 21, 59)), None);
+        assert_eq!(window.end(at(2024, 3, 1, 22, 0)), Some(at(2024, 3, 2, 2, 0)));
+        // 跨过午夜
+        assert_eq!(window.end(at(2024, 3, 2, 1, 59)), Some(at(2024, 3, 2, 2, 0)));
+        assert_eq!(window.end(at(2024, 3, 2, 2, 0)), None);
+
+        // 重叠时取最近开始的一次
+        let hourly = Window::parse("h=0 * * * * for 90m", false).unwrap();
+        assert_eq!(hourly.end(at(2024, 3, 1, 1, 10)), Some(at(2024, 3, 1, 2, 30)));
+    }
+
+    fn event(rule: &str, state: State, repeat: bool) -> Event {
+        let now = DateTime::from_timestamp(0, 0).unwrap();
+        Event { rule: rule.into(), instance: String::new(), state, expr: "cpu > 1".into(), value: 2.0, threshold: 1.0, since: now, timestamp: now, repeat }
+    }
+
+    fn always(pause: bool) -> Vec<Window> {
+        vec![Window::parse("all=* * * * * for 1h", pause).unwrap()]
+    }
+
+    #[test]
+    fn holds_samples_after_the_first_paused_one() {
+        let now = DateTime::from_timestamp(0, 0).unwrap();
+        let mut schedule = Schedule::new(always(true));
+        let maintenance = schedule.update(now).unwrap();
+        assert_eq!((maintenance.windows, maintenance.paused), (vec!["all".to_string()], true));
+        assert!(!schedule.holding());
+        schedule.update(now);
+        assert!(schedule.holding());
+
+        schedule.configure(always(false));
+        assert!(!schedule.update(now).unwrap().paused);
+        assert!(!schedule.holding());
+        schedule.configure(Vec::new());
+        assert!(schedule.update(now).is_none());
+    }
+
+    #[test]
+    fn suppresses_alerts_inside_windows_and_renotifies_after() {
+        let now = DateTime::from_timestamp(0, 0).unwrap();
+        let mut schedule = Schedule::new(always(false));
+        schedule.update(now);
+        let events = vec![event("new", State::Firing, false), event("old", State::Resolved, false), event("old", State::Firing, true)];
+        // 窗口开始前已经触发的 `old` 照常通知解除
+        let kept = schedule.alerts(events, &[], now);
+        assert_eq!(kept.iter().map(|e| (e.rule.as_str(), e.state)).collect::<Vec<_>>(), [("old", State::Resolved)]);
+
+        schedule.configure(Vec::new());
+        schedule.update(now);
+        let active = Active {
+            rule: "new".into(),
+            instance: String::new(),
+            expr: "cpu > 1".into(),
+            value: 3.0,
+            threshold: 1.0,
+            since: now,
+            duration_ms: 0,
+        };
+        let events = schedule.alerts(vec![event("new", State::Firing, true)], &[active], now);
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].state, events[0].value, events[0].repeat), (State::Firing, 3.0, false));
+        assert!(schedule.alerts(Vec::new(), &[], now).is_empty());
+    }
+
+    #[test]
+    fn drops_resolutions_of_alerts_fired_inside_windows() {
+        let now = DateTime::from_timestamp(0, 0).unwrap();
+        let mut schedule = Schedule::new(always(false));
+        schedule.update(now);
+        assert!(schedule.alerts(vec![event("a", State::Firing, false)], &[], now).is_empty());
+        assert!(schedule.alerts(vec![event("a", State::Resolved, false)], &[], now).is_empty());
+        schedule.configure(Vec::new());
+        schedule.update(now);
+        assert!(schedule.alerts(Vec::new(), &[], now).is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+// src/mdns.rs
+use crate::error::Error;
+use crate::i18n::tr;
+use std::net::SocketAddr;
+
+/// 编译时是否启用了 `mdns` 特性
+pub const AVAILABLE: bool = cfg!(feature = "mdns");
+
+/// 广播的服务类型
+pub const SERVICE_TYPE: &str = "_sysmon._tcp.local.";
+
+/// 通过mDNS在局域网中广播 `serve` 的地址，仪表盘和汇总服务可以据此发现主机；释放时撤销广播
+pub struct Announcement {
+    #[cfg(feature = "mdns")]
+    daemon: mdns_sd::ServiceDaemon,
+    #[cfg(feature = "mdns")]
+    fullname: String,
+}
+
+impl Announcement {
+    /// 以主机名为实例名广播 `addr` 的端口，TXT记录为 `properties`；监听所有地址时广播本机的全部地址
+    #[cfg(feature = "mdns")]
+    pub fn start(host: &str, addr: SocketAddr, properties: &[(String, String)]) -> Result<Self, Error> {
+        use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+        let error = |e: mdns_sd::Error| format!("{}: {}", tr("mDNS广播失败"), e);
+        let daemon = ServiceDaemon::new().map_err(error)?;
+        let hostname = format!("{}.local.", host);
+        let service = if addr.ip().is_unspecified() {
+            ServiceInfo::new(SERVICE_TYPE, host, &hostname, "", addr.port(), properties).map(ServiceInfo::enable_addr_auto)
+        } else {
+            ServiceInfo::new(SERVICE_TYPE, host, &hostname, addr.ip(), addr.port(), properties)
+        }
+        .map_err(error)?;
+        let fullname = service.get_fullname().to_string();
+        daemon.register(service).map_err(error)?;
+        Ok(Self { daemon, fullname })
+    }
+
+    #[cfg(not(feature = "mdns"))]
+    pub fn start(_host: &str, _addr: SocketAddr, _properties: &[(String, String)]) -> Result<Self, Error> {
+        Err(Error::config(tr("mDNS广播需要以 --features mdns 编译")))
+    }
+}
+
+#[cfg(feature = "mdns")]
+impl Drop for Announcement {
+    fn drop(&mut self) {
+        // 撤销后其他主机立即看到服务下线，不必等记录过期
+        if let Ok(status) = self.daemon.unregister(&self.fullname) {
+            let _ = status.recv_timeout(std::time::Duration::from_secs(1));
+        }
+        let _ = self.daemon.shutdown();
+    }
+}
@@ -0,0 +1,272 @@
+// src/mock.rs
+use crate::collector::{Collector, Context, Section};
+use crate::error::Error;
+use crate::i18n::tr;
+use crate::SystemStats;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// 采集项的名称
+pub const NAME: &str = "mock";
+
+/// 由数据源生成、不能在场景中设置的字段
+const RESERVED: [&str; 18] = [
+    "version", "seq", "timestamp", "host", "instance_id", "tags", "cloud", "kubernetes", "lifecycle", "churn", "talkers", "derived", "refreshed", "agent", "anomalies",
+    "alerts", "maintenance", "aggregate",
+];
+
+/// 场景文件：`seed` 和若干 `[[metric]]`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Scenario {
+    /// 噪声的随机数种子，同一个种子每次运行得到相同的数值
+    #[serde(default)]
+    seed: u64,
+    #[serde(default, rename = "metric")]
+    metrics: Vec<MetricSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetricSpec {
+    /// 样本中的字段，例如 `mem.used`、`cpu[0]`、`disk["/"].used`
+    path: String,
+    #[serde(flatten)]
+    shape: Shape,
+    /// 叠加的均匀噪声的幅度，取值在 ±noise 之间
+    #[serde(default)]
+    noise: f64,
+}
+
+fn one() -> u64 {
+    1
+}
+
+/// 数值随样本序号(从0开始)的变化
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "shape", rename_all = "lowercase")]
+enum Shape {
+    /// 固定的值
+    Flat { value: f64 },
+    /// 在 `over` 个样本内从 `from` 线性变到 `to`，之后保持；`repeat` 时回到 `from` 重新开始
+    Ramp {
+        from: f64,
+        to: f64,
+        over: u64,
+        #[serde(default)]
+        repeat: bool,
+    },
+    /// 平时为 `base`，从第 `start` 个样本起的 `length` 个样本为 `peak`；设置了 `every` 时每隔这么多样本重复
+    Spike {
+        base: f64,
+        peak: f64,
+        #[serde(default)]
+        start: u64,
+        every: Option<u64>,
+        #[serde(default = "one")]
+        length: u64,
+    },
+    /// 依次取 `values` 中的值，每个保持 `hold` 个样本，取完后从头开始
+    Steps {
+        values: Vec<f64>,
+        #[serde(default = "one")]
+        hold: u64,
+    },
+}
+
+impl Shape {
+    fn validate(&self) -> Result<(), String> {
+        let valid = match self {
+            Shape::Flat { .. } => true,
+            Shape::Ramp { over, .. } => *over > 0,
+            Shape::Spike { every, length, .. } => *length > 0 && every.is_none_or(|every| every > 0),
+            Shape::Steps { values, hold } => !values.is_empty() && *hold > 0,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(tr("over、every、length 和 hold 必须大于0，steps 至少要有一个值").to_string())
+        }
+    }
+
+    fn value(&self, index: u64) -> f64 {
+        match self {
+            Shape::Flat { value } => *value,
+            Shape::Ramp { from, to, over, repeat } => {
+                let position = if *repeat { index % (over + 1) } else { index.min(*over) };
+                from + (to - from) * position as f64 / *over as f64
+            }
+            Shape::Spike { base, peak, start, every, length } => {
+                let offset = index.checked_sub(*start);
+                let active = match (offset, every) {
+                    (Some(offset), Some(every)) => offset % every < *length,
+                    (Some(offset), None) => offset < *length,
+                    (None, _) => false,
+                };
+                if active { *peak } else { *base }
+            }
+            Shape::Steps { values, hold } => values[(index / hold % values.len() as u64) as usize],
+        }
+    }
+}
+
+/// 路径中的一段：对象的键或数组的下标
+#[derive(Debug, Clone)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// `mem.used`、`cpu[0]`、`disk["/"].used`：`[数字]` 是数组下标，`["..."]` 是任意的键
+fn parse_path(path: &str) -> Option<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = path.trim();
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix("[\"") {
+            let (key, after) = quoted.split_once("\"]")?;
+            segments.push(Segment::Key(key.to_string()));
+            rest = after;
+        } else if let Some(bracket) = rest.strip_prefix('[') {
+            let (index, after) = bracket.split_once(']')?;
+            segments.push(Segment::Index(index.parse().ok()?));
+            rest = after;
+        } else {
+            let name = if segments.is_empty() { rest } else { rest.strip_prefix('.')? };
+            let (key, after) = name.split_at(name.find(['.', '[']).unwrap_or(name.len()));
+            if key.is_empty() {
+                return None;
+            }
+            segments.push(Segment::Key(key.to_string()));
+            rest = after;
+        }
+    }
+    Some(segments)
+}
+
+/// 把 `value` 写到 `target` 中 `path` 的位置，缺少的对象和数组逐层创建，数组不够长时补0
+fn set(target: &mut Value, path: &[Segment], value: Value) {
+    let Some((first, rest)) = path.split_first() else {
+        *target = value;
+        return;
+    };
+    match first {
+        Segment::Key(key) => {
+            if !target.is_object() {
+                *target = Value::Object(serde_json::Map::new());
+            }
+            let Value::Object(object) = target else { unreachable!() };
+            set(object.entry(key.clone()).or_insert(Value::Null), rest, value);
+        }
+        Segment::Index(index) => {
+            if !target.is_array() {
+                *target = Value::Array(Vec::new());
+            }
+            let Value::Array(array) = target else { unreachable!() };
+            if array.len() <= *index {
+                array.resize(index + 1, Value::from(0));
+            }
+            set(&mut array[*index], rest, value);
+        }
+    }
+}
+
+struct Metric {
+    path: Vec<Segment>,
+    shape: Shape,
+    noise: f64,
+    /// 样本中的整数字段，写入前取整并且不小于0
+    integer: bool,
+}
+
+/// 场景中的噪声用SplitMix64生成，不依赖随机数库的版本，同一个种子在任何平台上都得到相同的序列
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// [-1, 1) 之间均匀分布
+    fn uniform(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+    }
+}
+
+/// 模拟数据源：代替内置采集项，按 `--source mock:FILE` 的场景文件生成可重现的数值(平线、斜坡、尖峰、给定的序列)，
+/// 用来在没有真实负载的情况下测试导出插件、告警规则和仪表盘。第N次采集总是得到相同的数值，时间戳仍为当前时间
+pub struct MockSource {
+    metrics: Vec<Metric>,
+    rng: SplitMix64,
+    index: u64,
+}
+
+impl MockSource {
+    /// 读取场景文件，检查各字段能否构成样本
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let error = |e: String| Error::config(format!("{} {}: {}", tr("无效的模拟场景"), path.display(), e));
+        let text = std::fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        let scenario: Scenario = toml::from_str(&text).map_err(|e| error(e.to_string()))?;
+
+        let mut metrics = Vec::new();
+        for spec in scenario.metrics {
+            let segments = parse_path(&spec.path).ok_or_else(|| error(format!("{}: {}", tr("无效的字段"), spec.path)))?;
+            match segments.first() {
+                Some(Segment::Key(key)) if !RESERVED.contains(&key.as_str()) => {}
+                _ => return Err(error(format!("{}: {}", tr("这个字段不能模拟"), spec.path))),
+            }
+            spec.shape.validate().map_err(|e| error(format!("{}: {}", spec.path, e)))?;
+            metrics.push(Metric { path: segments, shape: spec.shape, noise: spec.noise.abs(), integer: false });
+        }
+
+        let sample = build(&metrics, |_| Value::from(0));
+        serde_json::from_value::<SystemStats>(sample).map_err(|e| error(e.to_string()))?;
+        // 写入小数后不能构成样本的是整数字段
+        for index in 0..metrics.len() {
+            let sample = build(&metrics, |i| if i == index { Value::from(0.5) } else { Value::from(0) });
+            metrics[index].integer = serde_json::from_value::<SystemStats>(sample).is_err();
+        }
+        Ok(Self { metrics, rng: SplitMix64(scenario.seed), index: 0 })
+    }
+}
+
+/// 由各指标的值组成样本的JSON，`value(i)` 给出第i个指标的值
+fn build(metrics: &[Metric], mut value: impl FnMut(usize) -> Value) -> Value {
+    let mut sample = serde_json::json!({ "version": 0, "seq": 0, "timestamp": chrono::Utc::now() });
+    for (i, metric) in metrics.iter().enumerate() {
+        set(&mut sample, &metric.path, value(i));
+    }
+    sample
+}
+
+impl Collector for MockSource {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn collect(&mut self, _ctx: &mut Context) -> Section {
+        let mut values = Vec::with_capacity(self.metrics.len());
+        for metric in &self.metrics {
+            let value = metric.shape.value(self.index) + metric.noise * self.rng.uniform();
+            values.push(if metric.integer { Value::from(value.round().max(0.0) as u64) } else { Value::from(value) });
+        }
+        let sample = build(&self.metrics, |i| values[i].take());
+        self.index += 1;
+        match serde_json::from_value::<SystemStats>(sample) {
+            Ok(stats) => Section::Mock(Box::new(stats)),
+            Err(_) => Section::Unavailable,
+        }
+    }
+}
+
+/// `--source` 的校验：`system` 或 `mock:FILE`
+pub fn source_arg(value: &str) -> Result<String, String> {
+    match value.strip_prefix("mock:") {
+        Some(path) if !path.is_empty() => Ok(value.to_string()),
+        None if value == "system" => Ok(value.to_string()),
+        _ => Err(format!("{}: {}", tr("无效的数据源"), value)),
+    }
+}
@@ -0,0 +1,801 @@
+// src/monitor.rs
+use crate::agent::Agent;
+use crate::breaker::SinkHealth;
+use crate::cloud::CloudMetadata;
+use crate::collector::{self, process_matches, Collector, Section};
+use crate::crash;
+use crate::error::Error;
+use crate::{churn, dns_check, http_check, lifecycle, log_watch, probe, talkers, tls_check};
+use crate::derived::Derived;
+use crate::environment;
+use crate::filter::{ProcessFilter, RegexFilter};
+use crate::forecast::Forecaster;
+use crate::i18n::tr;
+use crate::kubernetes::{KubernetesMetadata, Resources};
+use crate::script::Script;
+use crate::throttle::{Limits, Throttle};
+use crate::worker::{Done, Request, Worker};
+use crate::{ProcessInfo, SystemStats, MIN_INTERVAL, SCHEMA_VERSION};
+use chrono::Utc;
+use std::collections::BTreeMap;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use std::time::{Duration, Instant};
+use sysinfo::{CpuRefreshKind, Pid, PidExt, ProcessExt, RefreshKind, Signal, System, SystemExt, Uid, UserExt};
+
+/// 按两次读取之间的差值计算占用率或速率的采集项，休眠恢复后需要重新建立基准
+const RATE_COLLECTORS: [&str; 2] = ["cpu", "net"];
+
+/// 启用的采集项，关闭的采集项既不刷新也不出现在请求体中
+#[derive(Debug, Clone)]
+pub struct Collectors {
+    enabled: Vec<String>,
+    disabled: Vec<String>,
+    /// 没有指定启用列表时，按运行环境默认不启用的采集项，见 [`Environment::skipped`](crate::environment::Environment::skipped)
+    skipped: Vec<String>,
+}
+
+impl Default for Collectors {
+    fn default() -> Self {
+        Self::new(&[], &[])
+    }
+}
+
+impl Collectors {
+    /// 内置采集项的名称
+    pub const NAMES: [&'static str; 6] = ["cpu", "mem", "disk", "net", "proc", "temp"];
+
+    /// 由选项启用的采集项的名称(`--probe`、`--http-check` 等)
+    pub const OPTIONAL: [&'static str; 8] = [
+        probe::NAME,
+        http_check::NAME,
+        dns_check::NAME,
+        tls_check::NAME,
+        log_watch::NAME,
+        lifecycle::NAME,
+        churn::NAME,
+        talkers::NAME,
+    ];
+
+    /// `--disable` 和 `--overload-disable` 可以关闭的采集项：内置和由选项启用的采集项，以及 `proc` 的别名 `processes`
+    pub fn switchable() -> Vec<&'static str> {
+        let mut names = Self::NAMES.to_vec();
+        names.push("processes");
+        names.extend(Self::OPTIONAL);
+        names
+    }
+
+    /// `enabled` 为空时启用运行环境中有意义的全部采集项，再关闭 `disabled` 中的采集项；`processes` 是 `proc` 的别名
+    pub fn new(enabled: &[String], disabled: &[String]) -> Self {
+        let names = |names: &[String]| {
+            names.iter().map(|n| if n == "processes" { "proc".to_string() } else { n.clone() }).collect()
+        };
+        let skipped = if enabled.is_empty() {
+            environment::current().skipped().iter().map(|n| n.to_string()).collect()
+        } else {
+            Vec::new()
+        };
+        Self { enabled: names(enabled), disabled: names(disabled), skipped }
+    }
+
+    pub fn enabled(&self, name: &str) -> bool {
+        (self.enabled.is_empty() || self.enabled.iter().any(|n| n == name))
+            && !self.disabled.iter().any(|n| n == name)
+            && !self.skipped.iter().any(|n| n == name)
+    }
+
+    /// 运行中启用一个采集项：不再关闭，指定了启用列表时加入其中
+    pub fn enable(&mut self, name: &str) {
+        let name = if name == "processes" { "proc" } else { name };
+        self.disabled.retain(|n| n != name);
+        self.skipped.retain(|n| n != name);
+        if !self.enabled.is_empty() && !self.enabled.iter().any(|n| n == name) {
+            self.enabled.push(name.to_string());
+        }
+    }
+
+    /// 运行中关闭一个采集项
+    pub fn disable(&mut self, name: &str) {
+        let name = if name == "processes" { "proc" } else { name };
+        if !self.disabled.iter().any(|n| n == name) {
+            self.disabled.push(name.to_string());
+        }
+    }
+}
+
+/// [`Monitor`] 的设置，未设置的项使用命令行选项的默认值
+pub struct MonitorBuilder {
+    host_name: Option<String>,
+    instance_id: Option<String>,
+    tags: BTreeMap<String, String>,
+    cloud: Option<CloudMetadata>,
+    kubernetes: Option<KubernetesMetadata>,
+    collectors: Collectors,
+    net_filter: RegexFilter,
+    process_filter: ProcessFilter,
+    top: usize,
+    interval: Duration,
+    intervals: BTreeMap<String, Duration>,
+    collect_timeout: Option<Duration>,
+    throttle: Throttle,
+    /// 代替内置采集项的数据源
+    source: Option<Box<dyn Collector>>,
+    /// 内置采集项之外注册的采集项
+    extra: Vec<Box<dyn Collector>>,
+    scripts: Vec<Script>,
+    derived: Derived,
+    low_footprint: bool,
+    cpu_sample_interval: Duration,
+    forecast: Option<Duration>,
+}
+
+impl Default for MonitorBuilder {
+    fn default() -> Self {
+        Self {
+            host_name: None,
+            instance_id: None,
+            tags: BTreeMap::new(),
+            cloud: None,
+            kubernetes: None,
+            collectors: Collectors::default(),
+            net_filter: RegexFilter::default(),
+            process_filter: ProcessFilter::default(),
+            top: 0,
+            interval: Duration::from_secs(1),
+            intervals: BTreeMap::new(),
+            collect_timeout: None,
+            throttle: Throttle::default(),
+            source: None,
+            extra: Vec::new(),
+            scripts: Vec::new(),
+            derived: Derived::default(),
+            low_footprint: false,
+            cpu_sample_interval: Duration::from_secs(1),
+            forecast: None,
+        }
+    }
+}
+
+impl MonitorBuilder {
+    /// 代替系统主机名上报的名称，默认每次采集时读取系统的主机名
+    pub fn host_name(mut self, name: Option<String>) -> Self {
+        self.host_name = name;
+        self
+    }
+
+    /// 附加在每个样本和事件上的实例ID
+    pub fn instance_id(mut self, id: Option<String>) -> Self {
+        self.instance_id = id;
+        self
+    }
+
+    /// 附加在每个样本上的标签
+    pub fn tags(mut self, tags: BTreeMap<String, String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// 附加在每个样本上的云主机信息
+    pub fn cloud(mut self, cloud: Option<CloudMetadata>) -> Self {
+        self.cloud = cloud;
+        self
+    }
+
+    /// 附加在每个样本上的节点可分配资源，样本中同时给出已使用的资源
+    pub fn kubernetes(mut self, kubernetes: Option<KubernetesMetadata>) -> Self {
+        self.kubernetes = kubernetes;
+        self
+    }
+
+    pub fn collectors(mut self, collectors: Collectors) -> Self {
+        self.collectors = collectors;
+        self
+    }
+
+    /// 采集时保留的网卡
+    pub fn net_filter(mut self, filter: RegexFilter) -> Self {
+        self.net_filter = filter;
+        self
+    }
+
+    /// 参与进程统计的进程
+    pub fn process_filter(mut self, filter: ProcessFilter) -> Self {
+        self.process_filter = filter;
+        self
+    }
+
+    /// 样本中附带的CPU占用最高的进程数
+    pub fn top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// 默认的采集间隔
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// 单独设置采集间隔的采集项
+    pub fn collector_intervals(mut self, intervals: BTreeMap<String, Duration>) -> Self {
+        self.intervals = intervals;
+        self
+    }
+
+    /// 一次采集最多等待的时间，默认等待全部采集项完成
+    pub fn collect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.collect_timeout = timeout;
+        self
+    }
+
+    /// 过载时放慢采集，默认不放慢
+    pub fn throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// 注册一个采集项，排在内置采集项之后，和内置采集项一样按名称启用、关闭和设置采集间隔
+    pub fn collector(mut self, collector: impl Collector + 'static) -> Self {
+        self.extra.push(Box::new(collector));
+        self
+    }
+
+    /// 用这个采集项(例如模拟数据源)代替全部内置采集项
+    pub fn source(mut self, source: impl Collector + 'static) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// 添加一个脚本，在各采集项完成后按添加的顺序运行
+    pub fn script(mut self, script: Script) -> Self {
+        self.scripts.push(script);
+        self
+    }
+
+    /// 每次采集后计算的派生指标
+    pub fn derived(mut self, derived: Derived) -> Self {
+        self.derived = derived;
+        self
+    }
+
+    /// 低占用模式：各采集项在调用 [`Monitor::collect`] 的线程中依次采集，不为每个采集项启动线程；
+    /// Linux上不需要进程列表时直接读取 /proc 统计进程数，不保留sysinfo的进程表
+    pub fn low_footprint(mut self, low_footprint: bool) -> Self {
+        self.low_footprint = low_footprint;
+        self
+    }
+
+    /// CPU在后台刷新的间隔，采集时给出两次采集之间各次刷新的平均值和最高值；为零时只在采集时刷新
+    pub fn cpu_sample_interval(mut self, interval: Duration) -> Self {
+        self.cpu_sample_interval = interval;
+        self
+    }
+
+    /// 按最近 `window` 内的用量计算内存和磁盘的趋势，默认不计算
+    pub fn forecast(mut self, window: Option<Duration>) -> Self {
+        self.forecast = window;
+        self
+    }
+
+    /// 进程过滤条件中的用户不存在或无法启动采集线程时报错
+    pub fn build(self) -> Result<Monitor, Error> {
+        let mut collectors = match self.source {
+            Some(source) => vec![source],
+            None => collector::builtin(self.low_footprint, self.cpu_sample_interval),
+        };
+        collectors.extend(self.extra);
+        let mut monitor =
+            Monitor::new(self.tags, self.cloud, self.collectors, self.net_filter, collectors, self.low_footprint)?;
+        let uids = resolve_users(&self.process_filter.users)?;
+        monitor.set_process_filter(self.process_filter, uids);
+        monitor.collect_timeout = self.collect_timeout;
+        monitor.top = self.top;
+        monitor.set_intervals(self.interval, self.intervals);
+        monitor.throttle = self.throttle;
+        monitor.scripts = self.scripts;
+        monitor.derived = self.derived;
+        monitor.kubernetes = self.kubernetes;
+        monitor.host_name = self.host_name;
+        monitor.instance_id = self.instance_id;
+        monitor.forecaster = self.forecast.map(Forecaster::new);
+        Ok(monitor)
+    }
+}
+
+/// 采集系统资源，保存计算速率和按间隔采集所需的状态
+pub struct Monitor {
+    system: System,
+    seq: u64,
+    /// `--hostname` 设置的主机名
+    host_name: Option<String>,
+    instance_id: Option<String>,
+    tags: BTreeMap<String, String>,
+    cloud: Option<CloudMetadata>,
+    kubernetes: Option<KubernetesMetadata>,
+    /// 注册的采集项，各在自己的线程中采集，结果按这个顺序写入样本
+    workers: Vec<Worker>,
+    results: UnboundedReceiver<Done>,
+    enabled: Collectors,
+    /// 采集时保留的网卡，被排除的网卡不参与速率计算
+    net_filter: RegexFilter,
+    proc_filter: ProcessFilter,
+    /// `proc_filter.users` 解析得到的UID
+    proc_uids: Vec<Uid>,
+    /// 请求体中附带的CPU占用最高的进程数
+    top: usize,
+    /// `--interval` 设置的默认采集间隔
+    interval: Duration,
+    /// 单独设置了采集间隔的采集项
+    intervals: BTreeMap<String, Duration>,
+    /// 主循环的刷新间隔，用于判断采集项是否到期
+    tick: Duration,
+    /// 一次采集最多等待的时间，超时的采集项沿用上次的结果
+    collect_timeout: Option<Duration>,
+    throttle: Throttle,
+    last_run: BTreeMap<&'static str, Instant>,
+    /// 各采集项上次的结果，未到期时沿用
+    last: BTreeMap<&'static str, Section>,
+    /// 各采集项上次采集的(分配次数, 分配字节数)，供 `bench` 使用
+    allocations: BTreeMap<&'static str, (u64, u64)>,
+    /// 重新建立速率基准时收到的其他采集项的结果，留到下次采集
+    deferred: Vec<Done>,
+    /// 采集完成后运行的脚本
+    scripts: Vec<Script>,
+    /// 在脚本之前计算，脚本可以读到派生指标
+    derived: Derived,
+    /// 在派生指标之前计算，派生指标和告警规则可以引用趋势
+    forecaster: Option<Forecaster>,
+    agent: Agent,
+}
+
+/// 进程过滤条件中的用户名或UID解析为UID，用户不存在时报错
+pub fn resolve_users(users: &[String]) -> Result<Vec<Uid>, Error> {
+    let mut system = System::new();
+    if !users.is_empty() {
+        system.refresh_users_list();
+    }
+    let mut uids = Vec::new();
+    for user in users {
+        let uid = match user.parse::<Uid>() {
+            Ok(uid) => uid,
+            Err(_) => system
+                .users()
+                .iter()
+                .find(|u| u.name() == user)
+                .map(|u| u.id().clone())
+                .ok_or_else(|| Error::config(format!("{}: {}", tr("未知的用户"), user)))?,
+        };
+        uids.push(uid);
+    }
+    Ok(uids)
+}
+
+impl Monitor {
+    pub fn builder() -> MonitorBuilder {
+        MonitorBuilder::default()
+    }
+
+    fn new(
+        tags: BTreeMap<String, String>,
+        cloud: Option<CloudMetadata>,
+        enabled: Collectors,
+        net_filter: RegexFilter,
+        collectors: Vec<Box<dyn Collector>>,
+        inline: bool,
+    ) -> std::io::Result<Self> {
+        // 采集项各自刷新自己的 `System`，这里只用于主机名、运行时间和CPU核数
+        let system = System::new_with_specifics(RefreshKind::new().with_cpu(CpuRefreshKind::new()));
+        let (sender, results) = mpsc::unbounded_channel();
+        let workers = collectors
+            .into_iter()
+            .enumerate()
+            .map(|(index, collector)| match inline {
+                true => Ok(Worker::inline(index, collector, sender.clone())),
+                false => Worker::spawn(index, collector, sender.clone()),
+            })
+            .collect::<std::io::Result<_>>()?;
+
+        Ok(Self {
+            workers,
+            results,
+            system,
+            seq: 0,
+            host_name: None,
+            instance_id: None,
+            tags,
+            cloud,
+            kubernetes: None,
+            enabled,
+            net_filter,
+            proc_filter: ProcessFilter::default(),
+            proc_uids: Vec::new(),
+            top: 0,
+            interval: Duration::from_secs(1),
+            intervals: BTreeMap::new(),
+            tick: Duration::from_secs(1),
+            collect_timeout: None,
+            throttle: Throttle::default(),
+            last_run: BTreeMap::new(),
+            last: BTreeMap::new(),
+            allocations: BTreeMap::new(),
+            deferred: Vec::new(),
+            scripts: Vec::new(),
+            derived: Derived::default(),
+            forecaster: None,
+            agent: Agent::default(),
+        })
+    }
+
+    pub fn host_name(&self) -> String {
+        self.host_name.clone().or_else(|| self.system.host_name()).unwrap_or_else(|| "unknown".to_string())
+    }
+
+    pub fn instance_id(&self) -> Option<&str> {
+        self.instance_id.as_deref()
+    }
+
+    pub fn uptime(&self) -> u64 {
+        self.system.uptime()
+    }
+
+    /// 设置进程过滤条件，用户名在这里解析为UID
+    /// `uids` 为 [`resolve_users`] 解析的 `filter.users`
+    pub fn set_process_filter(&mut self, filter: ProcessFilter, uids: Vec<Uid>) {
+        self.proc_filter = filter;
+        self.proc_uids = uids;
+    }
+
+    /// `proc` 采集项上次采集到的进程，它正在采集时返回 None
+    pub fn process_list(&self) -> Option<Vec<ProcessInfo>> {
+        let worker = self.workers.iter().find(|worker| worker.name() == "proc")?;
+        let system = worker.system().try_lock().ok()?;
+        let processes = system
+            .processes()
+            .values()
+            .filter(|process| process_matches(&self.proc_filter, &self.proc_uids, process))
+            .map(|process| ProcessInfo {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string(),
+                cpu: process.cpu_usage(),
+                mem: process.memory(),
+                start_time: process.start_time(),
+            })
+            .collect();
+        Some(processes)
+    }
+
+    /// 向进程发送 SIGTERM，`force` 时发送 SIGKILL；`target` 为确认时看到的进程，
+    /// PID对应的进程名或启动时间已经不同(进程退出后PID被复用)时不发送
+    pub fn kill(&self, target: &ProcessInfo, force: bool) -> Result<(), String> {
+        let signal = if force { Signal::Kill } else { Signal::Term };
+        let pid = Pid::from_u32(target.pid);
+        let mut system = System::new();
+        system.refresh_process(pid);
+        let process = system.process(pid).ok_or_else(|| tr("进程已经退出").to_string())?;
+        if process.name() != target.name || process.start_time() != target.start_time {
+            return Err(tr("PID已经属于另一个进程").to_string());
+        }
+        match process.kill_with(signal) {
+            Some(true) => Ok(()),
+            _ => Err(tr("发送信号失败").to_string()),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// 刷新间隔：各采集项间隔中最短的一个，调用 [`Monitor::collect`] 的间隔；过载放慢时按倍数延长
+    pub fn tick(&self) -> Duration {
+        self.tick * self.throttle.slowdown()
+    }
+
+    pub fn tags(&self) -> &BTreeMap<String, String> {
+        &self.tags
+    }
+
+    pub fn set_tags(&mut self, tags: BTreeMap<String, String>) {
+        self.tags = tags;
+    }
+
+    /// 启用的采集项
+    pub fn collectors(&self) -> &Collectors {
+        &self.enabled
+    }
+
+    pub fn set_collectors(&mut self, collectors: Collectors) {
+        self.enabled = collectors;
+    }
+
+    /// 出错的采集项：上次的结果是错误，或者超过采集超时仍未完成
+    pub fn failing(&self) -> Vec<&'static str> {
+        self.workers
+            .iter()
+            .filter(|w| self.enabled.enabled(w.name()))
+            .filter(|w| w.busy() || self.last.get(w.name()).is_some_and(Section::is_error))
+            .map(|w| w.name())
+            .collect()
+    }
+
+    /// 采集项上次采集时在它的线程中的(分配次数, 分配字节数)，没有启用 `bench` 特性时为0
+    pub fn allocations(&self, name: &str) -> (u64, u64) {
+        self.allocations.get(name).copied().unwrap_or_default()
+    }
+
+    /// 注册的全部采集项的名称，按采集的顺序
+    pub fn collector_names(&self) -> Vec<&'static str> {
+        self.workers.iter().map(|w| w.name()).collect()
+    }
+
+    pub fn set_net_filter(&mut self, filter: RegexFilter) {
+        self.net_filter = filter;
+    }
+
+    pub fn set_top(&mut self, top: usize) {
+        self.top = top;
+    }
+
+    /// 设置默认采集间隔和单独设置了间隔的采集项，刷新间隔取其中最短的一个
+    pub fn set_intervals(&mut self, interval: Duration, intervals: BTreeMap<String, Duration>) {
+        self.tick = intervals.values().copied().fold(interval, Duration::min).max(MIN_INTERVAL);
+        self.interval = interval;
+        self.intervals = intervals;
+    }
+
+    /// 只修改默认采集间隔，单独设置了间隔的采集项不变
+    pub fn set_interval(&mut self, interval: Duration) {
+        let intervals = std::mem::take(&mut self.intervals);
+        self.set_intervals(interval, intervals);
+    }
+
+    /// 一次采集最多等待的时间，None 时等待全部采集项完成
+    pub fn set_collect_timeout(&mut self, timeout: Option<Duration>) {
+        self.collect_timeout = timeout;
+    }
+
+    pub fn throttle(&self) -> &Throttle {
+        &self.throttle
+    }
+
+    /// 重新加载配置时更新过载限制，保留当前的放慢程度
+    pub fn set_throttle(&mut self, limits: Limits, max_slowdown: u32, shed: Vec<String>) {
+        self.throttle.configure(limits, max_slowdown, shed);
+    }
+
+    /// 主机1分钟平均负载除以CPU核数
+    pub fn load(&self) -> f64 {
+        self.system.load_average().one / self.system.cpus().len().max(1) as f64
+    }
+
+    /// 下一个样本的序号从 `seq + 1` 开始，用于接着缓存中已有的样本编号
+    pub fn set_seq(&mut self, seq: u64) {
+        self.seq = seq;
+    }
+
+    /// 重新加载配置时替换脚本
+    pub fn set_scripts(&mut self, scripts: Vec<Script>) {
+        self.scripts = scripts;
+    }
+
+    /// 重新加载配置时替换派生指标
+    pub fn set_derived(&mut self, derived: Derived) {
+        self.derived = derived;
+    }
+
+    /// 记录发送的耗时、结果和各发送目标的状态，出现在之后样本的 `agent` 中
+    pub fn sent(&mut self, elapsed: Option<Duration>, ok: bool, spool_bytes: u64, sinks: BTreeMap<String, SinkHealth>) {
+        self.agent.sent(elapsed, ok, spool_bytes, sinks);
+    }
+
+    /// 采集一个样本，本次未到期或超时的采集项沿用上次的值；等待采集线程的结果时不阻塞异步运行时
+    pub async fn collect(&mut self) -> SystemStats {
+        let started = Instant::now();
+        self.seq += 1;
+        let mut stats = SystemStats {
+            version: SCHEMA_VERSION,
+            seq: self.seq,
+            timestamp: Utc::now(),
+            host: self.host_name(),
+            instance_id: self.instance_id.clone(),
+            tags: self.tags.clone(),
+            cloud: self.cloud.clone(),
+            kubernetes: None,
+            cpu: Vec::new(),
+            cpu_window: None,
+            mem: None,
+            swap: None,
+            disk: BTreeMap::new(),
+            net: std::collections::HashMap::new(),
+            proc: None,
+            top: Vec::new(),
+            temp: BTreeMap::new(),
+            probes: BTreeMap::new(),
+            http: BTreeMap::new(),
+            dns: BTreeMap::new(),
+            tls: BTreeMap::new(),
+            logs: BTreeMap::new(),
+            lifecycle: Vec::new(),
+            churn: None,
+            talkers: None,
+            derived: BTreeMap::new(),
+            forecast: None,
+            extra: BTreeMap::new(),
+            refreshed: None,
+            agent: None,
+            anomalies: Vec::new(),
+            alerts: Vec::new(),
+            maintenance: None,
+            aggregate: None,
+        };
+
+        let request = self.request();
+        let mut refreshed = Vec::new();
+        let mut timings = BTreeMap::new();
+        // 先收下上次超时的采集项在这之后完成的结果
+        for done in std::mem::take(&mut self.deferred) {
+            self.finish(done, &mut refreshed, &mut timings);
+        }
+        while let Ok(done) = self.results.try_recv() {
+            self.finish(done, &mut refreshed, &mut timings);
+        }
+
+        // 到期的采集项同时开始采集
+        let slowdown = self.throttle.slowdown();
+        let mut waiting = vec![false; self.workers.len()];
+        for (index, worker) in self.workers.iter_mut().enumerate() {
+            let name = worker.name();
+            if !self.enabled.enabled(name) || self.throttle.sheds(name) {
+                self.last.remove(name);
+                continue;
+            }
+            // 没有单独设置间隔的采集项按 `--interval` 采集
+            let interval = *self.intervals.get(name).unwrap_or(&self.interval) * slowdown;
+            let due = self.last_run.get(name).is_none_or(|last| last.elapsed() + self.tick * slowdown / 2 >= interval);
+            if due && worker.start(request.clone()) {
+                self.last_run.insert(name, started);
+                waiting[index] = true;
+            }
+        }
+
+        let deadline = self.collect_timeout.map(|timeout| started + timeout);
+        while waiting.contains(&true) {
+            let Some(done) = self.next_result(deadline).await else { break };
+            waiting[done.index] = false;
+            self.finish(done, &mut refreshed, &mut timings);
+        }
+        let late: Vec<&str> = self.workers.iter().filter(|w| w.busy()).map(|w| w.name()).collect();
+        if !late.is_empty() {
+            tracing::debug!(collectors = %late.join(","), "{}", tr("采集超时，沿用上次的结果"));
+        }
+
+        let mut unavailable = Vec::new();
+        for worker in &self.workers {
+            let name = worker.name();
+            if let Some(section) = self.last.get(name).filter(|_| self.enabled.enabled(name) && !self.throttle.sheds(name)) {
+                if section.is_event() && !refreshed.contains(&name) {
+                    continue;
+                }
+                if matches!(section, Section::Unavailable) {
+                    unavailable.push(name.to_string());
+                }
+                section.clone().apply(name, &mut stats);
+            }
+        }
+        stats.timestamp = Utc::now();
+        stats.kubernetes = self.kubernetes.clone().map(|mut kubernetes| {
+            // 关闭了cpu或mem采集项时没有已使用量
+            if let Some(mem) = stats.mem.as_ref().filter(|_| !stats.cpu.is_empty()) {
+                let cpu = stats.cpu.iter().map(|&usage| usage as f64).sum::<f64>() / 100.0;
+                kubernetes.used = Some(Resources { cpu, memory: mem.used });
+            }
+            kubernetes
+        });
+        stats.forecast = self.forecaster.as_mut().and_then(|forecaster| forecaster.update(&stats));
+        self.derived.evaluate(&mut stats);
+
+        // 脚本看到的是本次的完整样本，耗时和采集项一起记在 `collectors_ms` 中
+        for script in &self.scripts {
+            let started = Instant::now();
+            match script.run(&stats) {
+                Ok(Some(value)) => {
+                    stats.extra.insert(script.name().to_string(), value);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!(script = script.name(), error = %e, "{}", tr("运行脚本失败")),
+            }
+            timings.insert(script.name().to_string(), started.elapsed().as_micros() as f64 / 1000.0);
+        }
+
+        if !self.intervals.is_empty() || !late.is_empty() {
+            let names = self.workers.iter().map(|w| w.name());
+            stats.refreshed = Some(
+                names
+                    .filter(|name| refreshed.contains(name) && self.enabled.enabled(name))
+                    .map(str::to_string)
+                    .collect(),
+            );
+        }
+        let mut agent = self.agent.sample(started.elapsed());
+        agent.unavailable.extend(unavailable);
+        let before = (self.throttle.slowdown(), self.throttle.shed().len());
+        if self.throttle.evaluate(agent.cpu as f64, agent.rss as f64, self.load()) {
+            let load = self.load();
+            let slowdown = self.throttle.slowdown();
+            let shed = self.throttle.shed().join(",");
+            if (slowdown, self.throttle.shed().len()) > before {
+                tracing::warn!(slowdown, %shed, agent_cpu = agent.cpu, load, "{}", tr("负载过高，放慢采集"));
+            } else if slowdown > 1 || !shed.is_empty() {
+                tracing::info!(slowdown, %shed, agent_cpu = agent.cpu, load, "{}", tr("负载下降，逐步恢复采集"));
+            } else {
+                tracing::info!(agent_cpu = agent.cpu, load, "{}", tr("负载已恢复，恢复正常采集"));
+            }
+        }
+        agent.slowdown = Some(self.throttle.slowdown()).filter(|&slowdown| slowdown > 1);
+        agent.shed = self.throttle.shed().to_vec();
+        agent.collectors_ms = timings;
+        stats.agent = Some(agent);
+        crash::sampled(stats.timestamp);
+        stats
+    }
+
+    /// 系统从休眠中恢复后调用：`cpu` 和 `net` 立即重新读取一次计数器，下一个样本的占用率和速率从这时算起，
+    /// 不包含休眠前后的变化；这次读取的结果不出现在样本中
+    pub async fn rebaseline(&mut self) {
+        let request = self.request();
+        let mut waiting = vec![false; self.workers.len()];
+        for (index, worker) in self.workers.iter_mut().enumerate() {
+            let name = worker.name();
+            if RATE_COLLECTORS.contains(&name) && self.enabled.enabled(name) && worker.start(request.clone()) {
+                waiting[index] = true;
+            }
+        }
+        let deadline = self.collect_timeout.map(|timeout| Instant::now() + timeout);
+        while waiting.contains(&true) {
+            let Some(done) = self.next_result(deadline).await else { break };
+            if waiting[done.index] {
+                waiting[done.index] = false;
+                self.workers[done.index].finished();
+            } else {
+                self.deferred.push(done);
+            }
+        }
+    }
+
+    /// 等待下一个采集项的结果，超过 `deadline` 时返回 None
+    async fn next_result(&mut self, deadline: Option<Instant>) -> Option<Done> {
+        match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline.into(), self.results.recv()).await.ok().flatten(),
+            None => self.results.recv().await,
+        }
+    }
+
+    fn request(&self) -> Request {
+        Request {
+            net_filter: self.net_filter.clone(),
+            process_filter: self.proc_filter.clone(),
+            process_uids: self.proc_uids.clone(),
+            top: self.top,
+        }
+    }
+
+    /// 记下一个采集项的结果
+    fn finish(&mut self, done: Done, refreshed: &mut Vec<&'static str>, timings: &mut BTreeMap<String, f64>) {
+        let worker = &mut self.workers[done.index];
+        worker.finished();
+        let name = worker.name();
+        if let Some(panic) = &done.panic {
+            let restarts = self.agent.restarted(name);
+            tracing::error!(
+                collector = name,
+                error = %panic.message,
+                location = panic.location.as_deref().unwrap_or(""),
+                restarts,
+                "{}",
+                tr("采集项崩溃，已重新初始化")
+            );
+        }
+        self.last.insert(name, done.section);
+        self.allocations.insert(name, done.allocations);
+        refreshed.push(name);
+        timings.insert(name.to_string(), done.elapsed.as_micros() as f64 / 1000.0);
+    }
+}
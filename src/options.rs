@@ -0,0 +1,586 @@
+// src/options.rs
+use crate::churn::{self, Churn};
+use crate::compact;
+use crate::crypto::Cipher;
+use crate::derived::Derived;
+use crate::display::{CoreView, DisplayOptions, Section};
+use crate::dns_check::{self, DnsChecks};
+use crate::error::Error;
+use crate::exec::Exec;
+use crate::filter::{DisplayFilter, FieldFilter, NameFilter, ProcessFilter, RegexFilter};
+use crate::history::History;
+use crate::http_check::{self, HttpChecks};
+use crate::i18n::tr;
+use crate::identity;
+use crate::kubernetes::{self, KubernetesMetadata};
+use crate::lifecycle::{self, Lifecycle};
+use crate::log_watch::{self, LogWatch};
+use crate::mock::{self, MockSource};
+use crate::probe::{self, Probes};
+use crate::script::Script;
+use crate::talkers::{self, Talkers};
+use crate::throttle::{Limits, Throttle};
+use crate::tls_check::{self, TlsChecks};
+use crate::token::{ClientCredentials, TokenProvider, TokenSource};
+use crate::transport::{Auth, Transport};
+use crate::units::SinkUnits;
+use crate::{
+    aggregate, alert, breaker, cloud, desktop, email, hook, maintenance, parse_duration, webhook, Collectors, MIN_INTERVAL, Monitor,
+};
+use clap::ArgMatches;
+use std::collections::BTreeMap;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// 分级保留选项，取值已经由 `cli` 中的 `duration_arg` 校验
+pub(crate) fn compaction_policy(sub: &ArgMatches) -> compact::Policy {
+    let default = compact::Policy::default();
+    compact::Policy {
+        raw: seconds(sub, "raw-for").unwrap_or(default.raw),
+        minute: seconds(sub, "minute-for").unwrap_or(default.minute),
+        retention: seconds(sub, "retention").unwrap_or(default.retention),
+    }
+}
+
+pub fn interval(matches: &ArgMatches) -> Duration {
+    seconds(matches, "interval")
+        .unwrap_or(Duration::from_secs(1))
+        .max(MIN_INTERVAL)
+}
+
+/// `--fields`，取值已经由 [`crate::filter::field_arg`] 校验
+pub(crate) fn parse_fields(matches: &ArgMatches) -> FieldFilter {
+    let values: Vec<String> = matches.get_many::<String>("fields").unwrap_or_default().cloned().collect();
+    FieldFilter::new(&values).unwrap_or_default()
+}
+
+/// `--sink-units`，取值已经由 [`crate::units::sink_units_arg`] 校验
+pub(crate) fn parse_sink_units(matches: &ArgMatches) -> SinkUnits {
+    let values: Vec<String> = matches.get_many::<String>("sink-units").unwrap_or_default().cloned().collect();
+    SinkUnits::new(&values).unwrap_or_default()
+}
+
+pub(crate) fn seconds(matches: &ArgMatches, id: &str) -> Option<Duration> {
+    matches.get_one::<String>(id).and_then(|s| parse_duration(s))
+}
+
+/// 断路器的连续失败阈值和冷却时间，端点和导出插件共用
+pub(crate) fn breaker_settings(matches: &ArgMatches) -> (u32, Duration) {
+    let threshold = *matches.get_one::<u32>("breaker-threshold").unwrap();
+    (threshold, seconds(matches, "breaker-cooldown").unwrap_or(breaker::DEFAULT_COOLDOWN))
+}
+
+pub(crate) fn batch_settings(matches: &ArgMatches) -> (usize, Option<Duration>) {
+    let batch_size = *matches.get_one::<u64>("batch-size").unwrap() as usize;
+    (batch_size, seconds(matches, "batch-interval"))
+}
+
+/// 根据端点、认证、TLS和代理等选项创建发送数据的连接
+pub(crate) fn build_transport(matches: &ArgMatches) -> Result<Transport, Error> {
+    let auth = if let Some(token) = matches.get_one::<String>("token") {
+        Auth::Bearer(token.clone())
+    } else if let Some(credentials) = matches.get_one::<String>("basic-auth") {
+        Auth::basic(credentials)
+    } else if let Some(path) = matches.get_one::<String>("token-file") {
+        Auth::Token(TokenProvider::new(TokenSource::File(PathBuf::from(path))))
+    } else if let Some(token_url) = matches.get_one::<String>("oauth-token-url") {
+        let params = matches.get_many::<String>("oauth-param").unwrap_or_default().filter_map(|param| param.split_once('='));
+        Auth::Token(TokenProvider::new(TokenSource::ClientCredentials(ClientCredentials {
+            token_url: token_url.clone(),
+            client_id: matches.get_one::<String>("oauth-client-id").cloned().unwrap_or_default(),
+            client_secret: matches.get_one::<String>("oauth-client-secret").cloned().unwrap_or_default(),
+            scope: matches.get_one::<String>("oauth-scope").cloned(),
+            params: params.map(|(name, value)| (name.trim().to_string(), value.to_string())).collect(),
+            post_credentials: matches.get_one::<String>("oauth-client-auth").is_some_and(|auth| auth == "post"),
+        })))
+    } else {
+        Auth::None
+    };
+    let endpoint = matches.get_one::<String>("endpoint").unwrap();
+    let mut transport = Transport::builder(endpoint).auth(auth);
+    for header in matches.get_many::<String>("header").unwrap_or_default() {
+        transport = transport.header(header)?;
+    }
+    if let Some(ca_cert) = matches.get_one::<String>("ca-cert") {
+        transport = transport.ca_cert(Path::new(ca_cert))?;
+    }
+    if let (Some(cert), Some(key)) = (
+        matches.get_one::<String>("client-cert"),
+        matches.get_one::<String>("client-key"),
+    ) {
+        transport = transport.client_cert(Path::new(cert), Path::new(key))?;
+    }
+    if let Some(proxy) = matches.get_one::<String>("proxy") {
+        let no_proxy = matches.get_one::<String>("no-proxy").map(String::as_str);
+        transport = transport.proxy(proxy, no_proxy)?;
+    }
+    Ok(transport
+        .insecure(matches.get_flag("insecure"))
+        .connect_timeout(seconds(matches, "connect-timeout").unwrap_or(Duration::from_secs(5)))
+        .request_timeout(seconds(matches, "request-timeout").unwrap_or(Duration::from_secs(10)))
+        .keepalive(seconds(matches, "keepalive").unwrap_or(Duration::from_secs(90)))
+        .rate_limit(matches.get_one::<f64>("rate-limit").copied())
+        .signing_key(matches.get_one::<String>("signing-key").cloned())
+        .build()?)
+}
+
+pub(crate) fn parse_tags(matches: &ArgMatches) -> Result<BTreeMap<String, String>, Error> {
+    let mut tags = BTreeMap::new();
+    for tag in matches.get_many::<String>("tag").unwrap_or_default() {
+        let (key, value) = tag
+            .split_once('=')
+            .ok_or_else(|| Error::config(format!("{}: {}", tr("无效的标签"), tag)))?;
+        tags.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    // `--tag` 中同名的标签优先
+    if matches.get_flag("kubernetes") {
+        for (key, value) in kubernetes::labels() {
+            tags.entry(key).or_insert(value);
+        }
+    }
+    Ok(tags)
+}
+
+pub(crate) fn parse_webhooks(matches: &ArgMatches) -> Vec<webhook::Webhook> {
+    matches.get_many::<String>("alert-webhook")
+        .unwrap_or_default()
+        .filter_map(|w| webhook::Webhook::parse(w).ok())
+        .collect()
+}
+
+/// 发送告警通知的HTTP客户端，沿用端点的超时和代理设置
+pub fn build_webhook_client(matches: &ArgMatches) -> reqwest::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(seconds(matches, "connect-timeout").unwrap_or(Duration::from_secs(5)))
+        .timeout(seconds(matches, "request-timeout").unwrap_or(Duration::from_secs(10)));
+    if let Some(proxy) = matches.get_one::<String>("proxy") {
+        let no_proxy = matches.get_one::<String>("no-proxy").and_then(|n| reqwest::NoProxy::from_string(n));
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?.no_proxy(no_proxy));
+    }
+    builder.build()
+}
+
+/// 设置了 `--smtp-server` 时创建发送告警邮件的客户端
+pub(crate) fn build_mailer(matches: &ArgMatches, host: &str) -> Result<Option<email::Mailer>, Error> {
+    let Some(server) = matches.get_one::<String>("smtp-server") else {
+        return Ok(None);
+    };
+    let addresses = |value: &str| value.split(',').map(|a| a.trim().to_string()).collect::<Vec<_>>();
+    let mut rules = BTreeMap::new();
+    for value in matches.get_many::<String>("alert-mail-to").unwrap_or_default() {
+        if let Some((rule, to)) = value.split_once('=') {
+            rules.insert(rule.trim().to_string(), addresses(to));
+        }
+    }
+    let to: Vec<String> = matches.get_many::<String>("mail-to").unwrap_or_default().map(|a| a.trim().to_string()).collect();
+    if to.is_empty() && rules.is_empty() {
+        return Err(Error::config(tr("使用 --smtp-server 时需要用 --mail-to 或 --alert-mail-to 指定收件人")));
+    }
+    let from = matches.get_one::<String>("mail-from").cloned().unwrap_or_else(|| format!("system_monitor@{}", host));
+    let mailer = email::Mailer::new(email::Settings {
+        server,
+        security: match matches.get_one::<String>("smtp-tls").map(String::as_str) {
+            Some("tls") => email::Security::Tls,
+            Some("none") => email::Security::Plain,
+            _ => email::Security::StartTls,
+        },
+        credentials: matches.get_one::<String>("smtp-user").cloned().zip(matches.get_one::<String>("smtp-password").cloned()),
+        from: &from,
+        to,
+        rules,
+        timeout: seconds(matches, "request-timeout").unwrap_or(Duration::from_secs(10)),
+    })?;
+    Ok(Some(mailer))
+}
+
+/// `--desktop-notify` 是否启用，没有编译桌面通知时报错
+pub(crate) fn desktop_notify(matches: &ArgMatches) -> Result<bool, Error> {
+    let enabled = matches.get_flag("desktop-notify");
+    if enabled && !desktop::AVAILABLE {
+        return Err(Error::config(tr("桌面通知需要以 --features desktop 编译")));
+    }
+    Ok(enabled)
+}
+
+/// 告警状态变化时的全部通知方式
+pub(crate) struct Notifiers {
+    client: reqwest::Client,
+    webhooks: Vec<webhook::Webhook>,
+    mailer: Option<email::Mailer>,
+    desktop: bool,
+    hooks: hook::Hooks,
+}
+
+impl Notifiers {
+    /// 在后台发送通知、运行命令，任务加入 `tasks`
+    pub(crate) fn notify(&self, notification: &alert::Notification, dry_run: bool, tasks: &mut JoinSet<()>) {
+        webhook::notify(&self.client, &self.webhooks, notification, dry_run, tasks);
+        if let Some(mailer) = &self.mailer {
+            mailer.notify(notification, dry_run, tasks);
+        }
+        if self.desktop {
+            desktop::notify(notification, tasks);
+        }
+        self.hooks.run(notification, dry_run, tasks);
+    }
+}
+
+/// 按选项创建告警通知方式，`host` 用于默认的发件人
+pub(crate) fn build_notifiers(matches: &ArgMatches, host: &str) -> Result<Notifiers, Error> {
+    let commands = matches.get_many::<String>("alert-exec")
+        .unwrap_or_default()
+        .filter_map(|c| c.split_once('='))
+        .map(|(rule, command)| (rule.trim().to_string(), command.to_string()))
+        .collect();
+    Ok(Notifiers {
+        client: build_webhook_client(matches)?,
+        webhooks: parse_webhooks(matches),
+        mailer: build_mailer(matches, host)?,
+        desktop: desktop_notify(matches)?,
+        hooks: hook::Hooks::new(
+            commands,
+            seconds(matches, "alert-exec-timeout").unwrap_or(Duration::from_secs(30)),
+            matches.get_one::<usize>("alert-exec-concurrency").copied().unwrap_or(4),
+        ),
+    })
+}
+
+/// 解析 `--alert NAME=RULE`，同名的规则以后出现的为准
+pub(crate) fn parse_alert_rules(matches: &ArgMatches) -> Vec<alert::Rule> {
+    let mut rules: Vec<alert::Rule> = Vec::new();
+    for rule in matches.get_many::<String>("alert").unwrap_or_default().filter_map(|r| alert::Rule::parse(r).ok()) {
+        rules.retain(|r| r.name != rule.name);
+        rules.push(rule);
+    }
+    rules
+}
+
+/// 解析 `--maintenance` 和 `--quiet-hours`，同名的窗口以后出现的为准
+pub(crate) fn parse_maintenance(matches: &ArgMatches) -> Vec<maintenance::Window> {
+    let mut windows: Vec<maintenance::Window> = Vec::new();
+    for (id, pause) in [("maintenance", true), ("quiet-hours", false)] {
+        for window in matches.get_many::<String>(id).unwrap_or_default().filter_map(|w| maintenance::Window::parse(w, pause).ok()) {
+            windows.retain(|w| w.name() != window.name());
+            windows.push(window);
+        }
+    }
+    windows
+}
+
+/// 解析 `--anomaly METRIC`，重复的指标只检测一次
+pub(crate) fn parse_anomaly_metrics(matches: &ArgMatches) -> Vec<(String, alert::Metric)> {
+    let mut metrics: Vec<(String, alert::Metric)> = Vec::new();
+    for name in matches.get_many::<String>("anomaly").unwrap_or_default().map(|m| m.trim()) {
+        if let Some(metric) = alert::Metric::parse(name).filter(|_| !metrics.iter().any(|(n, _)| n == name)) {
+            metrics.push((name.to_string(), metric));
+        }
+    }
+    metrics
+}
+
+/// `--anomaly-window` 和 `--anomaly-threshold`
+pub(crate) fn anomaly_settings(matches: &ArgMatches) -> (Duration, f64) {
+    let window = seconds(matches, "anomaly-window").filter(|w| !w.is_zero()).unwrap_or(Duration::from_secs(3600));
+    let threshold = matches.get_one::<String>("anomaly-threshold").and_then(|t| t.parse().ok()).unwrap_or(4.0);
+    (window, threshold)
+}
+
+/// `--aggregate` 和 `--aggregate-metric`，未指定指标时使用 `aggregate::DEFAULT_METRICS`
+pub(crate) fn aggregate_settings(matches: &ArgMatches) -> (Duration, Vec<(String, alert::Metric)>) {
+    let window = seconds(matches, "aggregate").unwrap_or(Duration::ZERO);
+    let names: Vec<&str> = match matches.get_many::<String>("aggregate-metric") {
+        Some(names) => names.map(|m| m.trim()).collect(),
+        None => aggregate::DEFAULT_METRICS.to_vec(),
+    };
+    let mut metrics: Vec<(String, alert::Metric)> = Vec::new();
+    for name in names {
+        if let Some(metric) = alert::Metric::parse(name).filter(|_| !metrics.iter().any(|(n, _)| n == name)) {
+            metrics.push((name.to_string(), metric));
+        }
+    }
+    (window, metrics)
+}
+
+/// `--max-agent-cpu`、`--max-rss`、`--max-load`、`--max-slowdown` 和 `--overload-disable`
+pub(crate) fn throttle_settings(matches: &ArgMatches) -> (Limits, u32, Vec<String>) {
+    let limit = |id: &str| matches.get_one::<String>(id).and_then(|v| v.parse().ok());
+    let limits = Limits { agent_cpu: limit("max-agent-cpu"), agent_rss: limit("max-rss"), load: limit("max-load") };
+    let max_slowdown = matches.get_one::<u32>("max-slowdown").copied().unwrap_or(8);
+    let shed = matches.get_many::<String>("overload-disable")
+        .unwrap_or_default()
+        .map(|name| if name == "processes" { "proc".to_string() } else { name.clone() })
+        .collect();
+    (limits, max_slowdown, shed)
+}
+
+/// `--source mock:FILE` 的场景文件
+pub(crate) fn mock_scenario(matches: &ArgMatches) -> Option<&str> {
+    matches.get_one::<String>("source")?.strip_prefix("mock:")
+}
+
+pub(crate) fn parse_collectors(matches: &ArgMatches) -> Collectors {
+    let names = |id: &str| -> Vec<String> {
+        matches.get_many::<String>(id).unwrap_or_default().cloned().collect()
+    };
+    let mut enabled = names("collectors");
+    // `--exec` 的命令采集项、`--probe` 的探测、`--http-check`、`--dns-check`、`--tls-check` 的检查、`--watch-log` 的日志、`--proc-events`、`--proc-churn` 和 `--net-talkers` 不在 `--collectors` 的可选值中，只列出内置采集项时照常运行
+    if !enabled.is_empty() {
+        enabled.extend(exec_commands(matches).map(|(name, _)| name.trim().to_string()));
+        if matches.contains_id("probe") {
+            enabled.push(probe::NAME.to_string());
+        }
+        if matches.contains_id("http-check") {
+            enabled.push(http_check::NAME.to_string());
+        }
+        if matches.contains_id("dns-check") {
+            enabled.push(dns_check::NAME.to_string());
+        }
+        if matches.contains_id("tls-check") {
+            enabled.push(tls_check::NAME.to_string());
+        }
+        if matches.contains_id("watch-log") {
+            enabled.push(log_watch::NAME.to_string());
+        }
+        if matches.get_flag("proc-events") {
+            enabled.push(lifecycle::NAME.to_string());
+        }
+        if matches.get_flag("proc-churn") {
+            enabled.push(churn::NAME.to_string());
+        }
+        if matches.get_flag("net-talkers") {
+            enabled.push(talkers::NAME.to_string());
+        }
+        if mock_scenario(matches).is_some() {
+            enabled.push(mock::NAME.to_string());
+        }
+    }
+    Collectors::new(&enabled, &names("disable"))
+}
+
+/// `--exec NAME=COMMAND`
+pub(crate) fn exec_commands(matches: &ArgMatches) -> impl Iterator<Item = (&str, &str)> {
+    matches.get_many::<String>("exec")
+        .unwrap_or_default()
+        .filter_map(|value| value.split_once('='))
+}
+
+/// `--collector-interval` 中的名称必须是内置采集项或 `--exec` 的命令采集项
+pub(crate) fn check_collector_intervals(matches: &ArgMatches, monitor: &Monitor) -> Result<(), Error> {
+    let names = monitor.collector_names();
+    match parse_collector_intervals(matches).into_keys().find(|name| !names.contains(&name.as_str())) {
+        Some(name) => Err(Error::config(format!("{}: {}", tr("无效的采集间隔"), name))),
+        None => Ok(()),
+    }
+}
+
+/// 解析 `--collector-interval NAME=DURATION`；没有单独设置时 `--tls-check` 每小时检查一次
+pub(crate) fn parse_collector_intervals(matches: &ArgMatches) -> BTreeMap<String, Duration> {
+    let mut intervals: BTreeMap<String, Duration> = matches.get_many::<String>("collector-interval")
+        .unwrap_or_default()
+        .filter_map(|value| {
+            let (name, duration) = value.split_once('=')?;
+            Some((name.trim().to_string(), parse_duration(duration)?))
+        })
+        .collect();
+    if matches.contains_id("tls-check") {
+        intervals.entry(tls_check::NAME.to_string()).or_insert(tls_check::DEFAULT_INTERVAL);
+    }
+    intervals
+}
+
+pub(crate) fn parse_net_filter(matches: &ArgMatches) -> Result<RegexFilter, Error> {
+    let names = |id: &str| -> Vec<String> {
+        matches.get_many::<String>(id).unwrap_or_default().cloned().collect()
+    };
+    Ok(RegexFilter::new(&names("net-include"), &names("net-exclude"))?)
+}
+
+pub(crate) fn parse_process_filter(matches: &ArgMatches) -> Result<ProcessFilter, Error> {
+    let names = |id: &str| -> Vec<String> {
+        matches.get_many::<String>(id).unwrap_or_default().cloned().collect()
+    };
+    if !cfg!(target_os = "linux") && matches.get_many::<String>("proc-cgroup").is_some() {
+        return Err(Error::config(tr("--proc-cgroup 只能在Linux上使用")));
+    }
+    Ok(ProcessFilter {
+        name: RegexFilter::new(&names("proc-name"), &[])?,
+        users: names("proc-user"),
+        cgroup: RegexFilter::new(&names("proc-cgroup"), &[])?,
+    })
+}
+
+pub(crate) fn parse_display_filter(matches: &ArgMatches) -> DisplayFilter {
+    let names = |id: &str| -> Vec<String> {
+        matches.get_many::<String>(id).unwrap_or_default().cloned().collect()
+    };
+    DisplayFilter {
+        iface: NameFilter::new(names("show-iface"), names("hide-iface")),
+        mount: NameFilter::new(names("show-mount"), names("hide-mount")),
+    }
+}
+
+pub(crate) fn parse_display_options(matches: &ArgMatches) -> DisplayOptions {
+    DisplayOptions {
+        bar_width: *matches.get_one::<usize>("bar-width").unwrap(),
+        plain: matches.get_flag("plain") || !io::stdout().is_terminal(),
+        sections: match matches.get_many::<String>("display") {
+            Some(names) => names.filter_map(|name| Section::parse(name)).collect(),
+            None => Section::ALL.to_vec(),
+        },
+        cores: match matches.get_one::<String>("cpu-cores").map(String::as_str) {
+            Some("full") => CoreView::Full,
+            Some("summary") => CoreView::Summary,
+            _ => CoreView::Auto,
+        },
+    }
+}
+
+/// 读取 `--script` 指定的脚本，没有以 `lua` 特性编译时报错
+pub(crate) fn load_scripts(matches: &ArgMatches) -> Result<Vec<Script>, Error> {
+    matches.get_many::<String>("script")
+        .unwrap_or_default()
+        .map(|path| Script::load(Path::new(path)))
+        .collect()
+}
+
+/// `--derive` 定义的派生指标，互相引用的名称不存在或循环引用时报错
+pub(crate) fn parse_derived(matches: &ArgMatches) -> Result<Derived, Error> {
+    let definitions: Vec<String> = matches.get_many::<String>("derive").unwrap_or_default().cloned().collect();
+    Ok(Derived::new(&definitions)?)
+}
+
+/// `--kubernetes-allocatable`：从API服务器读取 `node` 标签对应节点的可分配资源
+pub(crate) async fn kubernetes_metadata(
+    matches: &ArgMatches,
+    tags: &BTreeMap<String, String>,
+) -> Result<Option<KubernetesMetadata>, Error> {
+    if !matches.get_flag("kubernetes-allocatable") {
+        return Ok(None);
+    }
+    if !kubernetes::in_cluster() {
+        return Err(Error::config(tr("没有在Kubernetes的Pod中运行")));
+    }
+    let node = tags.get("node").ok_or(tr("没有节点名，需要通过downward API设置 NODE_NAME 环境变量"))?;
+    let allocatable = kubernetes::allocatable(node).await?;
+    info!(%node, cpu = allocatable.cpu, memory = allocatable.memory, "{}", tr("已获取节点可分配的资源"));
+    Ok(Some(KubernetesMetadata { node: node.clone(), allocatable, used: None }))
+}
+
+/// 按命令行创建采集器，指定了 `--cloud-metadata` 时先查询云主机元数据
+pub(crate) async fn build_monitor(matches: &ArgMatches) -> Result<Monitor, Error> {
+    let tags = parse_tags(matches)?;
+    let cloud = match matches.get_one::<String>("cloud-metadata") {
+        Some(provider) => {
+            let metadata = cloud::detect(provider).await;
+            if metadata.is_none() {
+                warn!(provider = %provider, "{}", tr("未能获取云主机元数据"));
+            }
+            metadata
+        }
+        None => None,
+    };
+    let kubernetes = match kubernetes_metadata(matches, &tags).await {
+        Ok(kubernetes) => kubernetes,
+        Err(error) => {
+            warn!(%error, "{}", tr("未能获取节点可分配的资源"));
+            None
+        }
+    };
+    let (limits, max_slowdown, shed) = throttle_settings(matches);
+
+    let instance_id = match matches.get_one::<String>("instance-id").map(String::as_str) {
+        Some(identity::MACHINE_ID) => Some(identity::machine_instance_id()?),
+        id => id.map(str::to_string),
+    };
+    let mut builder = Monitor::builder()
+        .host_name(matches.get_one::<String>("hostname").cloned())
+        .instance_id(instance_id)
+        .tags(tags)
+        .cloud(cloud)
+        .kubernetes(kubernetes)
+        .collectors(parse_collectors(matches))
+        .net_filter(parse_net_filter(matches)?)
+        .process_filter(parse_process_filter(matches)?)
+        .top(*matches.get_one::<usize>("top").unwrap())
+        .interval(interval(matches))
+        .collector_intervals(parse_collector_intervals(matches))
+        .throttle(Throttle::new(limits, max_slowdown, shed))
+        .low_footprint(matches.get_flag("embedded"))
+        .cpu_sample_interval(seconds(matches, "cpu-sample-interval").unwrap_or(Duration::from_secs(1)))
+        .forecast(seconds(matches, "forecast").filter(|window| !window.is_zero()));
+    if let Some(path) = mock_scenario(matches) {
+        builder = builder.source(MockSource::load(Path::new(path))?);
+    }
+    let timeout = seconds(matches, "exec-timeout").unwrap_or(Duration::from_secs(10));
+    for (name, command) in exec_commands(matches) {
+        builder = builder.collector(Exec::new(name, command, timeout));
+    }
+    let targets: Vec<String> = matches.get_many::<String>("probe").unwrap_or_default().cloned().collect();
+    if !targets.is_empty() {
+        let count = matches.get_one::<u32>("probe-count").copied().unwrap_or(3);
+        // 超过一次采集最多等待的时间时探测结果总是来不及写入样本
+        let timeout = seconds(matches, "probe-timeout").unwrap_or_else(|| collect_timeout(matches, interval(matches)));
+        builder = builder.collector(Probes::new(&targets, count, timeout));
+    }
+    let checks: Vec<String> = matches.get_many::<String>("http-check").unwrap_or_default().cloned().collect();
+    if !checks.is_empty() {
+        let timeout = seconds(matches, "http-check-timeout").unwrap_or_else(|| collect_timeout(matches, interval(matches)));
+        builder = builder.collector(HttpChecks::new(&checks, timeout)?);
+    }
+    let names: Vec<String> = matches.get_many::<String>("dns-check").unwrap_or_default().cloned().collect();
+    if !names.is_empty() {
+        let timeout = seconds(matches, "dns-check-timeout").unwrap_or_else(|| collect_timeout(matches, interval(matches)));
+        builder = builder.collector(DnsChecks::new(&names, timeout));
+    }
+    let endpoints: Vec<String> = matches.get_many::<String>("tls-check").unwrap_or_default().cloned().collect();
+    if !endpoints.is_empty() {
+        let timeout = seconds(matches, "tls-check-timeout").unwrap_or_else(|| collect_timeout(matches, interval(matches)));
+        builder = builder.collector(TlsChecks::new(&endpoints, timeout));
+    }
+    let files: Vec<String> = matches.get_many::<String>("watch-log").unwrap_or_default().cloned().collect();
+    if !files.is_empty() {
+        let patterns: Vec<String> = matches.get_many::<String>("watch-pattern").unwrap_or_default().cloned().collect();
+        builder = builder.collector(LogWatch::new(&files, &patterns)?);
+    }
+    if matches.get_flag("proc-events") {
+        builder = builder.collector(Lifecycle::new());
+    }
+    if matches.get_flag("proc-churn") {
+        builder = builder.collector(Churn::new());
+    }
+    if matches.get_flag("net-talkers") {
+        builder = builder.collector(Talkers::new()?);
+    }
+    for script in load_scripts(matches)? {
+        builder = builder.script(script);
+    }
+    let mut monitor = builder.derived(parse_derived(matches)?).build()?;
+    check_collector_intervals(matches, &monitor)?;
+    monitor.set_collect_timeout(Some(collect_timeout(matches, monitor.tick())));
+    Ok(monitor)
+}
+
+/// `--collect-timeout`，默认为刷新间隔的一半
+pub(crate) fn collect_timeout(matches: &ArgMatches, tick: Duration) -> Duration {
+    seconds(matches, "collect-timeout").unwrap_or(tick / 2)
+}
+
+/// `--history` 和 `--history-window`：设置了时间窗口时按时间保留，样本数至少能覆盖整个窗口
+pub(crate) fn history(matches: &ArgMatches, tick: Duration) -> History {
+    let len = *matches.get_one::<usize>("history").unwrap();
+    match seconds(matches, "history-window") {
+        Some(window) => {
+            let samples = (window.as_secs_f64() / tick.as_secs_f64()).ceil() as usize + 1;
+            History::with_window(len.max(samples), window)
+        }
+        None => History::new(len),
+    }
+}
+
+/// `--spool-key` 设置时加密缓存
+pub(crate) fn spool_cipher(matches: &ArgMatches) -> Option<Cipher> {
+    matches.get_one::<String>("spool-key").map(|key| Cipher::new(key))
+}
@@ -0,0 +1,253 @@
+// src/pipeline.rs
+use crate::breaker::SinkHealth;
+use crate::delivery::{Batch, Delivery, Outcome};
+use crate::filter::FieldFilter;
+use crate::i18n::tr;
+use crate::plugin::Plugin;
+use crate::template_sink::TemplateSink;
+use crate::transport::Transport;
+use crate::units::SinkUnits;
+use crate::SystemStats;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// 采集和发送之间的队列长度：端点变慢时样本在这里排队，采集不受影响；队列满时丢弃新的样本
+pub const QUEUE_SIZE: usize = 1024;
+
+enum Message {
+    Sample(Box<SystemStats>),
+    /// 立即发送批量中的样本
+    Flush,
+    /// 不带序号直接发送的事件
+    Event(String),
+    Reconfigure {
+        transport: Box<Transport>,
+        jitter: Duration,
+        batch_size: usize,
+        batch_interval: Option<Duration>,
+        fields: FieldFilter,
+        units: SinkUnits,
+        breaker: (u32, Duration),
+        endpoint: String,
+    },
+    /// 发送剩余的批量样本和磁盘缓存，最后发送停止事件，然后结束
+    Shutdown(String),
+}
+
+/// 一次发送的结果，交回采集循环更新状态
+pub struct Report {
+    pub sent: bool,
+    /// 发送的耗时，断路器断开、没有连接端点时没有
+    pub elapsed: Option<Duration>,
+    /// 发送后磁盘缓存中等待发送的字节数
+    pub spooled_bytes: u64,
+    /// 显示在终端界面的状态
+    pub status: String,
+    /// 端点(`endpoint`)、各导出插件(`exporter:名称`)和模板输出(`template:名称`)的断路器状态
+    pub sinks: BTreeMap<String, SinkHealth>,
+}
+
+/// 在单独的任务中发送样本，采集循环只往队列里放样本，不等待端点
+pub struct Pipeline {
+    queue: mpsc::Sender<Message>,
+    task: JoinHandle<()>,
+}
+
+impl Pipeline {
+    /// 启动发送任务，每次发送的结果从返回的接收端取；发往端点的每批样本也交给 `plugins` 导出、`templates` 渲染，
+    /// 各自按 `units` 换算
+    pub fn spawn(
+        delivery: Delivery,
+        batch: Batch,
+        endpoint: String,
+        plugins: Vec<Plugin>,
+        templates: Vec<TemplateSink>,
+        units: SinkUnits,
+    ) -> (Self, mpsc::UnboundedReceiver<Report>) {
+        let (queue, messages) = mpsc::channel(QUEUE_SIZE);
+        let (reports, receiver) = mpsc::unbounded_channel();
+        let sender = Sender { delivery, batch, endpoint, plugins, templates, units, reports };
+        (Self { queue, task: tokio::spawn(sender.run(messages)) }, receiver)
+    }
+
+    /// 放入一个样本，队列满时丢弃并返回 false
+    pub fn push(&self, stats: SystemStats) -> bool {
+        self.queue.try_send(Message::Sample(Box::new(stats))).is_ok()
+    }
+
+    /// 队列中等待发送的样本和事件数
+    pub fn backlog(&self) -> usize {
+        QUEUE_SIZE - self.queue.capacity()
+    }
+
+    /// 让批量中的样本立即发送
+    pub async fn flush(&self) {
+        let _ = self.queue.send(Message::Flush).await;
+    }
+
+    /// 直接发送一个事件，队列满时丢弃并返回 false
+    pub fn event(&self, body: String) -> bool {
+        self.queue.try_send(Message::Event(body)).is_ok()
+    }
+
+    /// 重新加载配置后换用新的连接、批量参数、发送的字段和单位以及断路器参数，已排队和缓存的样本保留
+    pub async fn reconfigure(
+        &self,
+        transport: Transport,
+        jitter: Duration,
+        (batch_size, batch_interval): (usize, Option<Duration>),
+        (fields, units): (FieldFilter, SinkUnits),
+        breaker: (u32, Duration),
+        endpoint: String,
+    ) {
+        let message = Message::Reconfigure {
+            transport: Box::new(transport),
+            jitter,
+            batch_size,
+            batch_interval,
+            fields,
+            units,
+            breaker,
+            endpoint,
+        };
+        let _ = self.queue.send(message).await;
+    }
+
+    /// 发送队列中剩余的样本、批量和磁盘缓存，最后发送停止事件；最多等待 `timeout`
+    pub async fn shutdown(self, stopping: String, timeout: Duration) -> bool {
+        let _ = self.queue.send(Message::Shutdown(stopping)).await;
+        tokio::time::timeout(timeout, self.task).await.is_ok()
+    }
+}
+
+struct Sender {
+    delivery: Delivery,
+    batch: Batch,
+    endpoint: String,
+    plugins: Vec<Plugin>,
+    templates: Vec<TemplateSink>,
+    units: SinkUnits,
+    reports: mpsc::UnboundedSender<Report>,
+}
+
+impl Sender {
+    async fn run(mut self, mut messages: mpsc::Receiver<Message>) {
+        while let Some(message) = messages.recv().await {
+            match message {
+                Message::Sample(stats) => {
+                    self.batch.push(*stats);
+                    if self.batch.is_ready() {
+                        self.send().await;
+                    }
+                }
+                Message::Flush => {
+                    if !self.batch.is_empty() {
+                        self.send().await;
+                    }
+                }
+                Message::Event(body) => {
+                    if let Err(e) = self.delivery.notify(body).await {
+                        tracing::warn!(endpoint = %self.endpoint, error = %e, "{}", tr("发送告警事件失败"));
+                    }
+                }
+                Message::Reconfigure { transport, jitter, batch_size, batch_interval, fields, units, breaker, endpoint } => {
+                    self.delivery.reconfigure(*transport, jitter, breaker, endpoint != self.endpoint);
+                    self.batch.configure(batch_size, batch_interval);
+                    self.batch.set_fields(fields);
+                    self.units = units;
+                    self.endpoint = endpoint;
+                }
+                Message::Shutdown(stopping) => {
+                    self.shutdown(stopping).await;
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn send(&mut self) {
+        let endpoint = &self.endpoint;
+        let samples = self.batch.len();
+        let body = match self.batch.take_body() {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::warn!(%endpoint, samples, error = %e, "{}", tr("发送数据失败"));
+                return;
+            }
+        };
+        // 试运行时插件和模板输出也不发送
+        if !self.delivery.is_dry_run() {
+            for plugin in &self.plugins {
+                let body = self.units.convert(&format!("exporter:{}", plugin.name()), &body).unwrap_or_else(|_| body.clone());
+                if !plugin.export(body) {
+                    tracing::warn!(plugin = plugin.name(), samples, "{}", tr("导出插件忙，样本被丢弃"));
+                }
+            }
+            for template in &self.templates {
+                let body = self.units.convert(&format!("template:{}", template.name()), &body).unwrap_or_else(|_| body.clone());
+                if !template.export(body) {
+                    tracing::warn!(template = template.name(), samples, "{}", tr("模板输出忙，样本被丢弃"));
+                }
+            }
+        }
+        let body = self.units.convert("endpoint", &body).unwrap_or(body);
+        let bytes = body.len();
+        let started = Instant::now();
+        let result = self.delivery.send(body).await;
+        let elapsed = started.elapsed();
+        let elapsed_ms = elapsed.as_millis() as u64;
+        let status = match &result {
+            Ok(Outcome::Sent) => {
+                tracing::debug!(%endpoint, samples, bytes, elapsed_ms, "{}", tr("数据发送成功"));
+                tr("数据发送成功").to_string()
+            }
+            Ok(Outcome::Drained) => {
+                tracing::info!(%endpoint, samples, bytes, elapsed_ms, "{}", tr("缓存数据已全部发送"));
+                tr("缓存数据已全部发送").to_string()
+            }
+            Ok(Outcome::DryRun) => {
+                tracing::debug!(samples, bytes, "{}", tr("试运行，数据未发送"));
+                tr("试运行，数据未发送").to_string()
+            }
+            Ok(Outcome::Skipped) => {
+                tracing::debug!(%endpoint, samples, bytes, "{}", tr("端点断路，暂停发送"));
+                tr("端点断路，暂停发送").to_string()
+            }
+            Err(e) => {
+                tracing::warn!(%endpoint, samples, bytes, elapsed_ms, error = %e, "{}", tr("发送数据失败"));
+                format!("{}: {}", tr("发送数据失败"), e)
+            }
+        };
+        let skipped = matches!(result, Ok(Outcome::Skipped));
+        let mut sinks = BTreeMap::from([("endpoint".to_string(), self.delivery.health())]);
+        sinks.extend(self.plugins.iter().map(|plugin| (format!("exporter:{}", plugin.name()), plugin.health())));
+        sinks.extend(self.templates.iter().map(|template| (format!("template:{}", template.name()), template.health())));
+        let _ = self.reports.send(Report {
+            sent: result.is_ok() && !skipped,
+            elapsed: (!skipped).then_some(elapsed),
+            spooled_bytes: self.delivery.spooled_bytes(),
+            status,
+            sinks,
+        });
+    }
+
+    async fn shutdown(&mut self, stopping: String) {
+        if !self.batch.is_empty() {
+            self.send().await;
+        }
+        let endpoint = &self.endpoint;
+        match self.delivery.flush().await {
+            Ok(true) => {}
+            Ok(false) => tracing::warn!(%endpoint, "{}", tr("端点不可达，数据已写入缓存")),
+            Err(e) => tracing::warn!(%endpoint, error = %e, "{}", tr("发送数据失败")),
+        }
+        if let Err(e) = self.delivery.notify(stopping).await {
+            tracing::warn!(%endpoint, error = %e, "{}", tr("发送停止事件失败"));
+        }
+        let plugins = std::mem::take(&mut self.plugins);
+        let _ = tokio::task::spawn_blocking(move || plugins.into_iter().for_each(Plugin::close)).await;
+        futures::future::join_all(std::mem::take(&mut self.templates).into_iter().map(TemplateSink::close)).await;
+    }
+}
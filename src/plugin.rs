@@ -0,0 +1,161 @@
+// src/plugin.rs
+use crate::breaker::{Breaker, SinkHealth};
+use crate::error::Error;
+use crate::i18n::tr;
+use std::path::Path;
+use std::sync::mpsc::{SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// 编译时是否启用了 `wasm` 特性
+pub const AVAILABLE: bool = cfg!(feature = "wasm");
+
+/// 插件还没处理完时最多排队的批次，超过时丢弃新的批次
+#[cfg(feature = "wasm")]
+const QUEUE_SIZE: usize = 16;
+
+/// WASM组件形式的导出插件，接口见 `wit/exporter.wit`；在自己的线程中运行，插件慢或出错不影响发往端点的数据
+pub struct Plugin {
+    name: String,
+    bodies: SyncSender<String>,
+    thread: JoinHandle<()>,
+    /// 连续导出失败后暂停导出，由插件的线程更新
+    breaker: Arc<Mutex<Breaker>>,
+}
+
+/// `--exporter PATH[=CONFIG]` 拆成路径和传给插件 `init` 的配置
+pub fn parse_exporter(value: &str) -> (&str, &str) {
+    value.split_once('=').unwrap_or((value, ""))
+}
+
+#[cfg(feature = "wasm")]
+mod wasm {
+    use wasmtime::component::{Component, Linker, ResourceTable};
+    use wasmtime::{Engine, Store};
+    use wasmtime_wasi::{WasiCtx, WasiCtxView, WasiView};
+
+    wasmtime::component::bindgen!({ world: "exporter", path: "wit" });
+
+    /// 插件可以使用标准输出、标准错误和网络，不能访问文件系统和环境变量
+    pub struct State {
+        ctx: WasiCtx,
+        table: ResourceTable,
+    }
+
+    impl WasiView for State {
+        fn ctx(&mut self) -> WasiCtxView<'_> {
+            WasiCtxView { ctx: &mut self.ctx, table: &mut self.table }
+        }
+    }
+
+    /// 编译好的插件，出错后可以重新实例化
+    pub struct Module {
+        engine: Engine,
+        component: Component,
+        linker: Linker<State>,
+        config: String,
+    }
+
+    impl Module {
+        pub fn compile(path: &std::path::Path, config: &str) -> wasmtime::Result<Self> {
+            let engine = Engine::default();
+            let component = Component::from_file(&engine, path)?;
+            let mut linker = Linker::new(&engine);
+            wasmtime_wasi::p2::add_to_linker_sync(&mut linker)?;
+            Ok(Self { engine, component, linker, config: config.to_string() })
+        }
+
+        /// 创建实例并调用 `init`
+        pub fn instantiate(&self) -> Result<(Store<State>, Exporter), String> {
+            let ctx = WasiCtx::builder()
+                .inherit_stdout()
+                .inherit_stderr()
+                .inherit_network()
+                .allow_tcp(true)
+                .allow_udp(true)
+                .allow_ip_name_lookup(true)
+                .build();
+            let mut store = Store::new(&self.engine, State { ctx, table: ResourceTable::new() });
+            let exporter = Exporter::instantiate(&mut store, &self.component, &self.linker).map_err(|e| format!("{:#}", e))?;
+            exporter.call_init(&mut store, &self.config).map_err(|e| format!("{:#}", e))??;
+            Ok((store, exporter))
+        }
+    }
+}
+
+impl Plugin {
+    /// 编译插件并调用 `init`，失败时报错；之后每批样本在插件的线程中调用 `export-batch`，
+    /// `breaker` 断开期间收到的批次直接丢弃
+    #[cfg(feature = "wasm")]
+    pub fn load(path: &Path, config: &str, (threshold, cooldown): (u32, std::time::Duration)) -> Result<Self, Error> {
+        let name = path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy().into_owned();
+        let error = |e: String| Error::config(format!("{} {}: {}", tr("加载导出插件失败"), path.display(), e));
+        let module = wasm::Module::compile(path, config).map_err(|e| error(format!("{:#}", e)))?;
+        let mut instance = module.instantiate().map_err(error)?;
+
+        let (bodies, receiver) = std::sync::mpsc::sync_channel::<String>(QUEUE_SIZE);
+        let plugin = name.clone();
+        let breaker = Arc::new(Mutex::new(Breaker::new(format!("exporter:{}", name), threshold, cooldown)));
+        let state = breaker.clone();
+        let thread = std::thread::Builder::new()
+            .name(format!("exporter-{}", name))
+            .spawn(move || {
+                for body in receiver {
+                    if !state.lock().unwrap().allow() {
+                        tracing::debug!(%plugin, "{}", tr("导出插件断路，批次被丢弃"));
+                        continue;
+                    }
+                    let started = std::time::Instant::now();
+                    let (store, exporter) = &mut instance;
+                    let result = match exporter.call_export_batch(&mut *store, &body) {
+                        Ok(result) => result,
+                        // 插件崩溃(trap)后实例不能再用，重新实例化，下一批样本照常导出
+                        Err(e) => {
+                            match module.instantiate() {
+                                Ok(fresh) => instance = fresh,
+                                Err(e) => tracing::warn!(%plugin, error = %e, "{}", tr("重新加载导出插件失败")),
+                            }
+                            Err(format!("{:#}", e))
+                        }
+                    };
+                    let elapsed_ms = started.elapsed().as_millis() as u64;
+                    match result {
+                        Ok(()) => {
+                            tracing::debug!(%plugin, bytes = body.len(), elapsed_ms, "{}", tr("导出插件已导出样本"));
+                            state.lock().unwrap().success();
+                        }
+                        Err(error) => {
+                            tracing::warn!(%plugin, elapsed_ms, %error, "{}", tr("导出插件失败"));
+                            state.lock().unwrap().failure(error);
+                        }
+                    }
+                }
+            })?;
+        Ok(Self { name, bodies, thread, breaker })
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    pub fn load(_path: &Path, _config: &str, _breaker: (u32, std::time::Duration)) -> Result<Self, Error> {
+        Err(Error::config(tr("导出插件需要以 --features wasm 编译")))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 导出的断路器状态
+    pub fn health(&self) -> SinkHealth {
+        self.breaker.lock().unwrap().health()
+    }
+
+    /// 交给插件导出，插件还在处理之前的批次且队列已满时丢弃并返回 false
+    pub fn export(&self, body: String) -> bool {
+        !matches!(self.bodies.try_send(body), Err(TrySendError::Full(_)))
+    }
+
+    /// 等插件处理完已排队的批次后结束，退出前调用
+    pub fn close(self) {
+        drop(self.bodies);
+        let _ = self.thread.join();
+    }
+}
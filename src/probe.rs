@@ -0,0 +1,298 @@
+// src/probe.rs
+use crate::collector::{Collector, Context, Section};
+use crate::i18n::tr;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// 采集项的名称
+pub const NAME: &str = "probes";
+
+/// 一个目标在一次采集中的探测结果，时间为毫秒
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProbeStats {
+    /// 收到回应的探测的平均往返时间，全部丢失时为 `null`
+    pub rtt_ms: Option<f64>,
+    /// 相邻两次往返时间之差的平均值，收到的回应少于两个时为 `null`
+    pub jitter_ms: Option<f64>,
+    /// 丢失的比例(%)
+    pub loss_percent: f64,
+    pub sent: u32,
+    pub received: u32,
+    /// 无法解析主机名、没有权限发送ICMP或连接被拒绝等，探测照常计为丢失
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 探测方式：ICMP回显或TCP连接
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    Icmp(String),
+    Tcp(String, u16),
+}
+
+impl Target {
+    /// `icmp://HOST`、`tcp://HOST:PORT`，省略前缀时带端口为TCP，否则为ICMP；IPv6地址写成 `[::1]:443`
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (scheme, address) = match value.split_once("://") {
+            Some((scheme, address)) => (Some(scheme), address),
+            None => (None, value),
+        };
+        let (host, port) = match address.strip_prefix('[') {
+            Some(rest) => {
+                let (host, rest) = rest.split_once(']')?;
+                match rest {
+                    "" => (host, None),
+                    _ => (host, Some(rest.strip_prefix(':')?)),
+                }
+            }
+            // 不带方括号的IPv6地址中也有冒号，整个作为主机
+            None => match address.split_once(':') {
+                Some((host, port)) if !port.contains(':') => (host, Some(port)),
+                _ => (address, None),
+            },
+        };
+        if host.is_empty() {
+            return None;
+        }
+        match (scheme, port) {
+            (Some("icmp"), None) | (None, None) => Some(Target::Icmp(host.to_string())),
+            (Some("tcp"), Some(port)) | (None, Some(port)) => Some(Target::Tcp(host.to_string(), port.parse().ok()?)),
+            _ => None,
+        }
+    }
+
+    fn resolve(&self) -> io::Result<SocketAddr> {
+        let (host, port) = match self {
+            Target::Icmp(host) => (host.as_str(), 0),
+            Target::Tcp(host, port) => (host.as_str(), *port),
+        };
+        (host, port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, tr("无法解析主机名")))
+    }
+}
+
+/// `--probe` 的校验
+pub fn probe_arg(value: &str) -> Result<String, String> {
+    Target::parse(value)
+        .map(|_| value.trim().to_string())
+        .ok_or_else(|| format!("{}: {}", tr("无效的探测目标"), value))
+}
+
+/// 每次采集向各目标发送 `count` 个ICMP回显请求或建立 `count` 个TCP连接，给出往返时间、抖动和丢包率；
+/// 各目标同时探测，一次采集最多用时 `timeout`
+pub struct Probes {
+    targets: Vec<(String, Target)>,
+    count: u32,
+    timeout: Duration,
+}
+
+impl Probes {
+    /// `targets` 为 `--probe` 的值，样本中以它为键
+    pub fn new(targets: &[String], count: u32, timeout: Duration) -> Self {
+        let targets = targets
+            .iter()
+            .filter_map(|value| Some((value.trim().to_string(), Target::parse(value)?)))
+            .collect();
+        Self { targets, count: count.max(1), timeout }
+    }
+
+    fn probe(&self, target: &Target) -> ProbeStats {
+        let rtts = target.resolve().and_then(|addr| match target {
+            Target::Icmp(_) => icmp(addr.ip(), self.count, self.timeout),
+            Target::Tcp(..) => tcp(addr, self.count, self.timeout),
+        });
+        match rtts {
+            Ok((rtts, error)) => summarize(self.count, &rtts, error),
+            Err(e) => summarize(self.count, &[], Some(e.to_string())),
+        }
+    }
+}
+
+impl Collector for Probes {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn collect(&mut self, _ctx: &mut Context) -> Section {
+        let probes = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .targets
+                .iter()
+                .map(|(name, target)| (name, scope.spawn(|| self.probe(target))))
+                .collect();
+            handles
+                .into_iter()
+                .map(|(name, handle)| (name.clone(), handle.join().unwrap_or_default()))
+                .collect()
+        });
+        Section::Probes(probes)
+    }
+}
+
+/// 精确到微秒的毫秒数
+fn millis(duration: Duration) -> f64 {
+    duration.as_micros() as f64 / 1000.0
+}
+
+fn summarize(sent: u32, rtts: &[Duration], error: Option<String>) -> ProbeStats {
+    let received = rtts.len() as u32;
+    let rtt_ms = (!rtts.is_empty()).then(|| rtts.iter().map(|rtt| millis(*rtt)).sum::<f64>() / rtts.len() as f64);
+    let jitter_ms = (rtts.len() >= 2).then(|| {
+        let deltas = rtts.windows(2).map(|pair| (millis(pair[1]) - millis(pair[0])).abs());
+        deltas.sum::<f64>() / (rtts.len() - 1) as f64
+    });
+    // 计时只精确到微秒，多余的小数位是浮点误差
+    let round = |ms: f64| (ms * 1000.0).round() / 1000.0;
+    ProbeStats {
+        rtt_ms: rtt_ms.map(round),
+        jitter_ms: jitter_ms.map(round),
+        loss_percent: sent.saturating_sub(received) as f64 / sent as f64 * 100.0,
+        sent,
+        received,
+        error,
+    }
+}
+
+/// 同时建立 `count` 个连接，连接成功的耗时为往返时间；连接被拒绝等错误计为丢失并给出最后一个错误
+fn tcp(addr: SocketAddr, count: u32, timeout: Duration) -> io::Result<(Vec<Duration>, Option<String>)> {
+    let results: Vec<io::Result<Duration>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..count)
+            .map(|_| {
+                scope.spawn(move || {
+                    let started = Instant::now();
+                    TcpStream::connect_timeout(&addr, timeout).map(|_| started.elapsed())
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap_or_else(|_| Err(io::ErrorKind::Other.into()))).collect()
+    });
+    let error = results
+        .iter()
+        .filter_map(|result| result.as_ref().err())
+        .find(|e| e.kind() != io::ErrorKind::TimedOut)
+        .map(ToString::to_string);
+    Ok((results.into_iter().filter_map(Result::ok).collect(), error))
+}
+
+/// 先发出全部回显请求，再在 `timeout` 内按序号收集回应。先尝试不需要权限的ICMP数据报套接字
+/// (Linux上需要 `net.ipv4.ping_group_range` 包含本程序的组)，不行再用原始套接字(需要root)
+#[cfg(unix)]
+fn icmp(addr: IpAddr, count: u32, timeout: Duration) -> io::Result<(Vec<Duration>, Option<String>)> {
+    use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+    use std::io::Read;
+
+    let (domain, protocol, request, reply) = match addr {
+        IpAddr::V4(_) => (Domain::IPV4, Protocol::ICMPV4, 8, 0),
+        IpAddr::V6(_) => (Domain::IPV6, Protocol::ICMPV6, 128, 129),
+    };
+    let socket = Socket::new(domain, Type::DGRAM, Some(protocol))
+        .or_else(|_| Socket::new(domain, Type::RAW, Some(protocol)))
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", tr("没有发送ICMP的权限"), e)))?;
+
+    // 回应中带回这个随机数，用来区分其他程序的回显(原始套接字会收到本机所有的ICMP报文)
+    let token: [u8; 8] = rand::random();
+    let destination = SockAddr::from(SocketAddr::new(addr, 0));
+    let mut sent_at = Vec::with_capacity(count as usize);
+    for seq in 0..count as u16 {
+        let mut packet = [0u8; 16];
+        packet[0] = request;
+        packet[4..6].copy_from_slice(&(std::process::id() as u16).to_be_bytes());
+        packet[6..8].copy_from_slice(&seq.to_be_bytes());
+        packet[8..].copy_from_slice(&token);
+        // ICMPv6的校验和由内核计算
+        if addr.is_ipv4() {
+            let checksum = checksum(&packet);
+            packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+        }
+        socket.send_to(&packet, &destination)?;
+        sent_at.push(Instant::now());
+    }
+
+    let started = Instant::now();
+    let mut rtts = vec![None; count as usize];
+    let mut buf = [0u8; 1500];
+    while rtts.contains(&None) {
+        let Some(remaining) = timeout.checked_sub(started.elapsed()).filter(|d| !d.is_zero()) else {
+            break;
+        };
+        socket.set_read_timeout(Some(remaining))?;
+        let n = match (&socket).read(&mut buf) {
+            Ok(n) => n,
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e),
+        };
+        let mut packet = &buf[..n];
+        // 原始套接字和macOS的数据报套接字收到的IPv4报文带IP头
+        if addr.is_ipv4() && packet.first().is_some_and(|b| b >> 4 == 4) {
+            packet = packet.get((packet[0] & 0x0f) as usize * 4..).unwrap_or_default();
+        }
+        if packet.len() < 16 || packet[0] != reply || packet[8..16] != token {
+            continue;
+        }
+        let seq = u16::from_be_bytes([packet[6], packet[7]]) as usize;
+        if let Some(rtt @ None) = rtts.get_mut(seq) {
+            *rtt = Some(sent_at[seq].elapsed());
+        }
+    }
+    Ok((rtts.into_iter().flatten().collect(), None))
+}
+
+/// ICMP校验和：按16位求和取反
+#[cfg(unix)]
+fn checksum(packet: &[u8]) -> u16 {
+    let mut sum: u32 = packet.chunks(2).map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]) as u32).sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Windows上通过 `IcmpSendEcho` 发送，不需要管理员权限；只支持IPv4
+#[cfg(windows)]
+fn icmp(addr: IpAddr, count: u32, timeout: Duration) -> io::Result<(Vec<Duration>, Option<String>)> {
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::NetworkManagement::IpHelper::{IcmpCloseHandle, IcmpCreateFile, IcmpSendEcho, ICMP_ECHO_REPLY};
+
+    let IpAddr::V4(ip) = addr else {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, tr("Windows上的ICMP探测只支持IPv4")));
+    };
+    let destination = u32::from_ne_bytes(ip.octets());
+    let timeout_ms = timeout.as_millis().min(u32::MAX as u128) as u32;
+    let rtts = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..count)
+            .map(|_| {
+                scope.spawn(move || {
+                    let handle = unsafe { IcmpCreateFile() };
+                    if handle == INVALID_HANDLE_VALUE {
+                        return None;
+                    }
+                    let data = [0u8; 16];
+                    let mut reply = [0u8; std::mem::size_of::<ICMP_ECHO_REPLY>() + 16 + 8];
+                    let started = Instant::now();
+                    let replies = unsafe {
+                        IcmpSendEcho(
+                            handle,
+                            destination,
+                            data.as_ptr().cast(),
+                            data.len() as u16,
+                            std::ptr::null(),
+                            reply.as_mut_ptr().cast(),
+                            reply.len() as u32,
+                            timeout_ms,
+                        )
+                    };
+                    let rtt = started.elapsed();
+                    unsafe { IcmpCloseHandle(handle) };
+                    (replies > 0).then_some(rtt)
+                })
+            })
+            .collect();
+        handles.into_iter().filter_map(|handle| handle.join().ok().flatten()).collect()
+    });
+    Ok((rtts, None))
+}
@@ -0,0 +1,288 @@
+// src/prometheus.rs
+use crate::breaker::CircuitState;
+use crate::histogram::Percentiles;
+use crate::SystemStats;
+use std::fmt::Write;
+
+/// 按Prometheus文本格式输出一个样本，`serve` 的 `GET /metrics` 使用；还没有速率的网卡不输出
+pub fn render(stats: &SystemStats) -> String {
+    let mut out = Metrics::default();
+    out.stats(stats, &[]);
+    out.finish()
+}
+
+/// Prometheus文本，同名指标的样本按族归在一起输出，不同来源的样本可以先后加入
+#[derive(Default)]
+pub struct Metrics {
+    /// 按首次出现的顺序：指标名、`# HELP`/`# TYPE` 行和样本行
+    families: Vec<(String, String, String)>,
+}
+
+impl Metrics {
+    /// 声明一个指标族，已经声明过时不重复输出
+    pub fn family(&mut self, name: &str, kind: &str, help: &str) {
+        if self.families.iter().any(|(family, _, _)| family == name) {
+            return;
+        }
+        let header = format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind);
+        self.families.push((name.to_string(), header, String::new()));
+    }
+
+    /// 加入一个样本，所属的指标族需要先用 [`Metrics::family`] 声明
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let Some((_, _, text)) = self.families.iter_mut().find(|(family, _, _)| family == name) else {
+            return;
+        };
+        write_sample(text, name, labels, value);
+    }
+
+    /// 加入一个摘要：各分位数以及 `_sum`、`_count`，毫秒换算为秒
+    fn summary(&mut self, name: &str, help: &str, labels: &[(&str, &str)], percentiles: &Percentiles) {
+        self.family(name, "summary", help);
+        for (quantile, value) in [("0.5", percentiles.p50), ("0.95", percentiles.p95), ("0.99", percentiles.p99)] {
+            self.sample(name, &labeled(labels, ("quantile", quantile)), value / 1000.0);
+        }
+        let Some((_, _, text)) = self.families.iter_mut().find(|(family, _, _)| family == name) else {
+            return;
+        };
+        write_sample(text, &format!("{}_sum", name), labels, percentiles.sum / 1000.0);
+        write_sample(text, &format!("{}_count", name), labels, percentiles.count as f64);
+    }
+
+    /// 加入一个样本中的所有指标，每个指标都带上 `labels`，例如 `aggregate` 按主机加上 `host`
+    pub fn stats(&mut self, stats: &SystemStats, labels: &[(&str, &str)]) {
+        if !stats.cpu.is_empty() {
+            self.family("sysmon_cpu_usage_percent", "gauge", "CPU usage per core");
+            for (core, usage) in stats.cpu.iter().enumerate() {
+                let core = core.to_string();
+                self.sample("sysmon_cpu_usage_percent", &labeled(labels, ("cpu", &core)), *usage as f64);
+            }
+        }
+
+        for (name, memory) in [("memory", &stats.mem), ("swap", &stats.swap)] {
+            let Some(memory) = memory else { continue };
+            let total = format!("sysmon_{}_total_bytes", name);
+            let used = format!("sysmon_{}_used_bytes", name);
+            self.family(&total, "gauge", &format!("Total {}", name));
+            self.sample(&total, labels, memory.total as f64);
+            self.family(&used, "gauge", &format!("Used {}", name));
+            self.sample(&used, labels, memory.used as f64);
+        }
+
+        if !stats.disk.is_empty() {
+            self.family("sysmon_disk_total_bytes", "gauge", "Total disk space per mount point");
+            self.family("sysmon_disk_used_bytes", "gauge", "Used disk space per mount point");
+            for (mount, disk) in &stats.disk {
+                self.sample("sysmon_disk_total_bytes", &labeled(labels, ("mount", mount)), disk.total as f64);
+                self.sample("sysmon_disk_used_bytes", &labeled(labels, ("mount", mount)), disk.used as f64);
+            }
+        }
+
+        if let Some(forecast) = &stats.forecast {
+            if let Some(trend) = &forecast.mem {
+                self.family("sysmon_memory_growth_bytes_per_second", "gauge", "Linear trend of used memory over the forecast window");
+                self.sample("sysmon_memory_growth_bytes_per_second", labels, trend.growth_mb_per_hour * 1e6 / 3600.0);
+                if let Some(hours) = trend.hours_until_full {
+                    self.family("sysmon_memory_full_in_seconds", "gauge", "Time until memory is full at the current trend");
+                    self.sample("sysmon_memory_full_in_seconds", labels, hours * 3600.0);
+                }
+            }
+            if !forecast.disk.is_empty() {
+                self.family("sysmon_disk_growth_bytes_per_second", "gauge", "Linear trend of used disk space per mount point over the forecast window");
+                for (mount, trend) in &forecast.disk {
+                    self.sample("sysmon_disk_growth_bytes_per_second", &labeled(labels, ("mount", mount)), trend.growth_mb_per_hour * 1e6 / 3600.0);
+                }
+            }
+            if forecast.disk.values().any(|trend| trend.hours_until_full.is_some()) {
+                self.family("sysmon_disk_full_in_seconds", "gauge", "Time until the mount point is full at the current trend");
+                for (mount, trend) in &forecast.disk {
+                    if let Some(hours) = trend.hours_until_full {
+                        self.sample("sysmon_disk_full_in_seconds", &labeled(labels, ("mount", mount)), hours * 3600.0);
+                    }
+                }
+            }
+        }
+
+        if !stats.net.is_empty() {
+            let mut interfaces: Vec<_> = stats.net.iter().collect();
+            interfaces.sort_by(|a, b| a.0.cmp(b.0));
+            self.family("sysmon_network_receive_bytes_per_second", "gauge", "Bytes received per second per interface");
+            self.family("sysmon_network_transmit_bytes_per_second", "gauge", "Bytes transmitted per second per interface");
+            for (interface, net) in &interfaces {
+                if let Some(rx) = net.rx {
+                    self.sample("sysmon_network_receive_bytes_per_second", &labeled(labels, ("interface", interface)), rx as f64);
+                }
+                if let Some(tx) = net.tx {
+                    self.sample("sysmon_network_transmit_bytes_per_second", &labeled(labels, ("interface", interface)), tx as f64);
+                }
+            }
+        }
+
+        if let Some(proc) = &stats.proc {
+            self.family("sysmon_processes", "gauge", "Processes by state");
+            for (state, count) in [("running", proc.running), ("sleeping", proc.sleeping), ("zombie", proc.zombie)] {
+                self.sample("sysmon_processes", &labeled(labels, ("state", state)), count as f64);
+            }
+            self.family("sysmon_processes_total", "gauge", "Total processes");
+            self.sample("sysmon_processes_total", labels, proc.total as f64);
+        }
+
+        if let Some(churn) = &stats.churn {
+            self.family("sysmon_process_forks", "gauge", "Processes and threads created since the previous sample");
+            self.sample("sysmon_process_forks", labels, churn.forks as f64);
+            self.family("sysmon_process_exits", "gauge", "Processes exited since the previous sample");
+            self.sample("sysmon_process_exits", labels, churn.exits as f64);
+            self.family("sysmon_short_lived_processes", "gauge", "Processes started and exited between two samples");
+            self.sample("sysmon_short_lived_processes", labels, churn.short_lived as f64);
+            self.family("sysmon_short_lived_cpu_seconds", "gauge", "CPU time used by short-lived processes since the previous sample");
+            self.sample("sysmon_short_lived_cpu_seconds", labels, churn.cpu_s);
+        }
+
+        if !stats.temp.is_empty() {
+            self.family("sysmon_temperature_celsius", "gauge", "Temperature per sensor");
+            for (sensor, celsius) in &stats.temp {
+                self.sample("sysmon_temperature_celsius", &labeled(labels, ("sensor", sensor)), *celsius as f64);
+            }
+        }
+
+        if !stats.probes.is_empty() {
+            self.family("sysmon_probe_rtt_seconds", "gauge", "Mean round-trip time per probe target");
+            for (target, probe) in &stats.probes {
+                if let Some(rtt) = probe.rtt_ms {
+                    self.sample("sysmon_probe_rtt_seconds", &labeled(labels, ("target", target)), rtt / 1000.0);
+                }
+            }
+            self.family("sysmon_probe_jitter_seconds", "gauge", "Mean difference between consecutive round-trip times per probe target");
+            for (target, probe) in &stats.probes {
+                if let Some(jitter) = probe.jitter_ms {
+                    self.sample("sysmon_probe_jitter_seconds", &labeled(labels, ("target", target)), jitter / 1000.0);
+                }
+            }
+            self.family("sysmon_probe_loss_percent", "gauge", "Percentage of probes without a reply per target");
+            for (target, probe) in &stats.probes {
+                self.sample("sysmon_probe_loss_percent", &labeled(labels, ("target", target)), probe.loss_percent);
+            }
+        }
+
+        if !stats.http.is_empty() {
+            self.family("sysmon_http_check_up", "gauge", "Whether the HTTP check passed");
+            for (name, check) in &stats.http {
+                self.sample("sysmon_http_check_up", &labeled(labels, ("check", name)), if check.ok { 1.0 } else { 0.0 });
+            }
+            self.family("sysmon_http_check_duration_seconds", "gauge", "Time until the full response of the HTTP check was read");
+            for (name, check) in &stats.http {
+                if let Some(ms) = check.response_ms {
+                    self.sample("sysmon_http_check_duration_seconds", &labeled(labels, ("check", name)), ms / 1000.0);
+                }
+            }
+            self.family("sysmon_http_check_status_code", "gauge", "Status code returned to the HTTP check");
+            for (name, check) in &stats.http {
+                if let Some(status) = check.status {
+                    self.sample("sysmon_http_check_status_code", &labeled(labels, ("check", name)), status as f64);
+                }
+            }
+        }
+
+        if !stats.dns.is_empty() {
+            self.family("sysmon_dns_check_up", "gauge", "Whether the name resolved");
+            for (name, check) in &stats.dns {
+                self.sample("sysmon_dns_check_up", &labeled(labels, ("check", name)), if check.ok { 1.0 } else { 0.0 });
+            }
+            self.family("sysmon_dns_lookup_seconds", "gauge", "Time taken to resolve the name");
+            for (name, check) in &stats.dns {
+                if let Some(ms) = check.lookup_ms {
+                    self.sample("sysmon_dns_lookup_seconds", &labeled(labels, ("check", name)), ms / 1000.0);
+                }
+            }
+        }
+
+        if !stats.tls.is_empty() {
+            self.family("sysmon_tls_certificate_expiry_days", "gauge", "Days until the first certificate in the chain expires");
+            for (endpoint, check) in &stats.tls {
+                if let Some(days) = check.days_left {
+                    self.sample("sysmon_tls_certificate_expiry_days", &labeled(labels, ("endpoint", endpoint)), days);
+                }
+            }
+        }
+
+        if !stats.logs.is_empty() {
+            self.family("sysmon_log_matches_per_second", "gauge", "Log lines matching the pattern per second");
+            for (pattern, logs) in &stats.logs {
+                self.sample("sysmon_log_matches_per_second", &labeled(labels, ("pattern", pattern)), logs.per_second);
+            }
+        }
+
+        if !stats.derived.is_empty() {
+            self.family("sysmon_derived", "gauge", "Derived metric computed from the expression given by --derive");
+            for (name, value) in &stats.derived {
+                self.sample("sysmon_derived", &labeled(labels, ("name", name)), *value);
+            }
+        }
+
+        if let Some(kubernetes) = &stats.kubernetes {
+            let node = labeled(labels, ("node", &kubernetes.node));
+            self.family("sysmon_kubernetes_allocatable_cpu_cores", "gauge", "CPU cores allocatable on the node");
+            self.sample("sysmon_kubernetes_allocatable_cpu_cores", &node, kubernetes.allocatable.cpu);
+            self.family("sysmon_kubernetes_allocatable_memory_bytes", "gauge", "Memory allocatable on the node");
+            self.sample("sysmon_kubernetes_allocatable_memory_bytes", &node, kubernetes.allocatable.memory as f64);
+            if let Some(used) = &kubernetes.used {
+                self.family("sysmon_kubernetes_used_cpu_cores", "gauge", "CPU cores in use on the node");
+                self.sample("sysmon_kubernetes_used_cpu_cores", &node, used.cpu);
+                self.family("sysmon_kubernetes_used_memory_bytes", "gauge", "Memory in use on the node");
+                self.sample("sysmon_kubernetes_used_memory_bytes", &node, used.memory as f64);
+            }
+        }
+
+        if let Some(agent) = &stats.agent {
+            self.family("sysmon_agent_cpu_percent", "gauge", "CPU usage of the agent itself");
+            self.sample("sysmon_agent_cpu_percent", labels, agent.cpu as f64);
+            self.family("sysmon_agent_rss_bytes", "gauge", "Resident memory of the agent itself");
+            self.sample("sysmon_agent_rss_bytes", labels, agent.rss as f64);
+            self.family("sysmon_agent_collect_seconds", "gauge", "Duration of the last collection");
+            self.sample("sysmon_agent_collect_seconds", labels, agent.collect_ms / 1000.0);
+            if let Some(percentiles) = &agent.collect_percentiles {
+                self.summary("sysmon_agent_collect_duration_seconds", "Distribution of collection durations since start", labels, percentiles);
+            }
+            if let Some(percentiles) = &agent.send_percentiles {
+                self.summary("sysmon_agent_send_duration_seconds", "Distribution of send durations since start", labels, percentiles);
+            }
+            for (name, sink) in &agent.sinks {
+                let sink_labels = labeled(labels, ("sink", name));
+                self.family("sysmon_sink_up", "gauge", "Whether the sink's circuit breaker is closed (1) or open (0)");
+                self.sample("sysmon_sink_up", &sink_labels, if sink.state == CircuitState::Closed { 1.0 } else { 0.0 });
+                self.family("sysmon_sink_consecutive_failures", "gauge", "Consecutive failed sends to the sink");
+                self.sample("sysmon_sink_consecutive_failures", &sink_labels, sink.consecutive_failures as f64);
+                self.family("sysmon_sink_failures_total", "counter", "Failed sends to the sink since start");
+                self.sample("sysmon_sink_failures_total", &sink_labels, sink.failures as f64);
+                self.family("sysmon_sink_skipped_total", "counter", "Sends skipped while the sink's circuit breaker was open");
+                self.sample("sysmon_sink_skipped_total", &sink_labels, sink.skipped as f64);
+            }
+        }
+
+        self.family("sysmon_sample_timestamp_seconds", "gauge", "Time of the sample");
+        self.sample("sysmon_sample_timestamp_seconds", labels, stats.timestamp.timestamp_millis() as f64 / 1000.0);
+    }
+
+    pub fn finish(self) -> String {
+        self.families.into_iter().map(|(_, header, samples)| header + &samples).collect()
+    }
+}
+
+fn write_sample(text: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    text.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels.iter().map(|(k, v)| format!("{}=\"{}\"", k, escape(v))).collect();
+        let _ = write!(text, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(text, " {}", value);
+}
+
+/// `labels` 之后再加一个标签
+fn labeled<'a>(labels: &[(&'a str, &'a str)], label: (&'a str, &'a str)) -> Vec<(&'a str, &'a str)> {
+    labels.iter().copied().chain([label]).collect()
+}
+
+/// 标签值中的反斜杠、双引号和换行需要转义
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,287 @@
+// src/remote.rs
+use crate::i18n::tr;
+use crate::{DiskStats, MemoryStats, NetworkStats, ProcessStats, SystemStats, SCHEMA_VERSION};
+use chrono::Utc;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// 分隔远程命令各部分输出的行
+const SEPARATOR: &str = "--sysmon--\n";
+
+/// 在远程主机上运行的命令：只读取 /proc 和 `df`、`ps` 的输出，远程主机上不需要安装本程序
+const SCRIPT: &str = "cat /proc/stat; echo --sysmon--; cat /proc/meminfo; echo --sysmon--; cat /proc/net/dev; \
+                      echo --sysmon--; df -kP; echo --sysmon--; ps -e -o stat=";
+
+/// `--host [NAME=][USER@]HOST[:PORT]`：样本中的主机名默认为HOST
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub name: String,
+    destination: String,
+    port: Option<u16>,
+}
+
+impl Target {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (name, address) = match value.split_once('=') {
+            Some((name, address)) => (Some(name.trim()), address.trim()),
+            None => (None, value),
+        };
+        // 只有一个冒号时才是端口，IPv6地址写成 `[::1]:22`
+        let (destination, port) = match address.rsplit_once(':') {
+            Some((destination, port)) if !destination.contains(':') || destination.ends_with(']') => {
+                (destination, Some(port.parse().ok()?))
+            }
+            _ => (address, None),
+        };
+        let host = destination.rsplit_once('@').map_or(destination, |(_, host)| host);
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() || name.is_some_and(str::is_empty) {
+            return None;
+        }
+        Some(Self {
+            name: name.unwrap_or(host).to_string(),
+            destination: destination.replace(['[', ']'], ""),
+            port,
+        })
+    }
+
+    /// 显示用的地址，`USER@HOST:PORT`
+    pub fn address(&self) -> String {
+        match self.port {
+            Some(port) => format!("{}:{}", self.destination, port),
+            None => self.destination.clone(),
+        }
+    }
+}
+
+/// `--host` 的校验
+pub fn host_arg(value: &str) -> Result<String, String> {
+    match Target::parse(value) {
+        Some(_) => Ok(value.to_string()),
+        None => Err(format!("{}: {}", tr("无效的远程主机"), value)),
+    }
+}
+
+/// 通过系统的 `ssh` 命令轮询一台主机：每次运行 [`SCRIPT`]，在本地解析成样本。
+/// CPU占用率和网络速率按两次轮询之间的差值计算，第一次轮询的样本中没有
+pub struct Remote {
+    target: Target,
+    /// 额外的 `ssh -o` 选项，例如 `ControlMaster=auto`
+    options: Vec<String>,
+    timeout: Duration,
+    tags: BTreeMap<String, String>,
+    seq: u64,
+    /// 上次读到的各核心的(总时间, 空闲时间)
+    last_cpu: Vec<(u64, u64)>,
+    /// 上次读到的各网卡的(接收字节数, 发送字节数)
+    last_net: HashMap<String, (u64, u64)>,
+    last_update: Option<Instant>,
+}
+
+impl Remote {
+    /// 样本带有 `tags`，`host` 标签为主机名
+    pub fn new(target: Target, options: Vec<String>, timeout: Duration, mut tags: BTreeMap<String, String>) -> Self {
+        tags.insert("host".to_string(), target.name.clone());
+        Self {
+            target,
+            options,
+            timeout,
+            tags,
+            seq: 0,
+            last_cpu: Vec::new(),
+            last_net: HashMap::new(),
+            last_update: None,
+        }
+    }
+
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// 连接主机采集一次，超过 `timeout` 时放弃
+    pub async fn poll(&mut self) -> Result<SystemStats, String> {
+        let mut command = tokio::process::Command::new("ssh");
+        command
+            .args(["-T", "-o", "BatchMode=yes", "-o"])
+            .arg(format!("ConnectTimeout={}", self.timeout.as_secs().max(1)));
+        if let Some(port) = self.target.port {
+            command.arg("-p").arg(port.to_string());
+        }
+        for option in &self.options {
+            command.arg("-o").arg(option);
+        }
+        command.arg(&self.target.destination).arg(SCRIPT).stdin(std::process::Stdio::null()).kill_on_drop(true);
+
+        let output = match tokio::time::timeout(self.timeout, command.output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => return Err(format!("{}: {}", tr("无法运行ssh"), e)),
+            Err(_) => return Err(tr("远程采集超时").to_string()),
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // `ps` 等不可用时退出码不为0，但 /proc 已经读到
+        if !stdout.contains(SEPARATOR) {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(stderr.lines().rfind(|line| !line.trim().is_empty()).unwrap_or("ssh").trim().to_string());
+        }
+        Ok(self.parse(&stdout))
+    }
+
+    fn parse(&mut self, output: &str) -> SystemStats {
+        let mut parts = output.split(SEPARATOR);
+        let mut part = || parts.next().unwrap_or("");
+        let (stat, meminfo, net_dev, df, ps) = (part(), part(), part(), part(), part());
+
+        let now = Instant::now();
+        let elapsed = self.last_update.map(|last| (now - last).as_secs_f64());
+        self.last_update = Some(now);
+
+        let current_cpu = cpu_times(stat);
+        let cpu = if self.last_cpu.len() == current_cpu.len() {
+            current_cpu
+                .iter()
+                .zip(&self.last_cpu)
+                .map(|(&(total, idle), &(last_total, last_idle))| {
+                    let total = total.saturating_sub(last_total);
+                    let idle = idle.saturating_sub(last_idle);
+                    if total == 0 { 0.0 } else { (100.0 * (1.0 - idle as f64 / total as f64)).clamp(0.0, 100.0) as f32 }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.last_cpu = current_cpu;
+
+        let current_net = net_counters(net_dev);
+        let net = current_net
+            .iter()
+            .map(|(interface, &(rx, tx))| {
+                let rate = |last: u64, current: u64| {
+                    let elapsed = elapsed.filter(|elapsed| *elapsed > 0.0)?;
+                    current.checked_sub(last).map(|delta| (delta as f64 / elapsed) as u64)
+                };
+                let stats = match self.last_net.get(interface) {
+                    Some(&(last_rx, last_tx)) => NetworkStats { rx: rate(last_rx, rx), tx: rate(last_tx, tx), up: None },
+                    None => NetworkStats { rx: None, tx: None, up: None },
+                };
+                (interface.clone(), stats)
+            })
+            .collect();
+        self.last_net = current_net;
+
+        let memory = meminfo_values(meminfo);
+        let kib = |key: &str| memory.get(key).copied().unwrap_or(0) * 1024;
+        let (mem, swap) = if memory.is_empty() {
+            (None, None)
+        } else {
+            let mem = MemoryStats { total: kib("MemTotal"), used: kib("MemTotal").saturating_sub(kib("MemAvailable")) };
+            let swap = MemoryStats { total: kib("SwapTotal"), used: kib("SwapTotal").saturating_sub(kib("SwapFree")) };
+            (Some(mem), Some(swap))
+        };
+
+        self.seq += 1;
+        SystemStats {
+            version: SCHEMA_VERSION,
+            seq: self.seq,
+            timestamp: Utc::now(),
+            host: self.target.name.clone(),
+            instance_id: None,
+            tags: self.tags.clone(),
+            cloud: None,
+            kubernetes: None,
+            cpu,
+            cpu_window: None,
+            mem,
+            swap,
+            disk: disks(df),
+            net,
+            proc: processes(ps),
+            top: Vec::new(),
+            temp: BTreeMap::new(),
+            probes: BTreeMap::new(),
+            http: BTreeMap::new(),
+            dns: BTreeMap::new(),
+            tls: BTreeMap::new(),
+            logs: BTreeMap::new(),
+            lifecycle: Vec::new(),
+            churn: None,
+            talkers: None,
+            derived: BTreeMap::new(),
+            forecast: None,
+            extra: BTreeMap::new(),
+            refreshed: None,
+            agent: None,
+            anomalies: Vec::new(),
+            alerts: Vec::new(),
+            maintenance: None,
+            aggregate: None,
+        }
+    }
+}
+
+/// /proc/stat 中各核心(`cpuN` 行)的(总时间, 空闲时间)，空闲包括iowait；guest已经计入user，不重复累加
+fn cpu_times(stat: &str) -> Vec<(u64, u64)> {
+    stat.lines()
+        .filter(|line| line.starts_with("cpu") && line.as_bytes().get(3).is_some_and(u8::is_ascii_digit))
+        .map(|line| {
+            let fields: Vec<u64> = line.split_whitespace().skip(1).take(8).filter_map(|f| f.parse().ok()).collect();
+            let idle = fields.get(3).copied().unwrap_or(0) + fields.get(4).copied().unwrap_or(0);
+            (fields.iter().sum(), idle)
+        })
+        .collect()
+}
+
+/// /proc/meminfo 中的各项(KiB)
+fn meminfo_values(meminfo: &str) -> HashMap<&str, u64> {
+    meminfo
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key, value.split_whitespace().next()?.parse().ok()?))
+        })
+        .collect()
+}
+
+/// /proc/net/dev 中各网卡的(接收字节数, 发送字节数)
+fn net_counters(net_dev: &str) -> HashMap<String, (u64, u64)> {
+    net_dev
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (interface, counters) = line.split_once(':')?;
+            let counters: Vec<u64> = counters.split_whitespace().filter_map(|c| c.parse().ok()).collect();
+            Some((interface.trim().to_string(), (*counters.first()?, *counters.get(8)?)))
+        })
+        .collect()
+}
+
+/// `df -kP` 的输出：只保留设备名以 `/` 开头的文件系统，不统计tmpfs、overlay等；已用量与本机一样为总量减去可用量
+fn disks(df: &str) -> BTreeMap<String, DiskStats> {
+    df.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 || !fields[0].starts_with('/') {
+                return None;
+            }
+            let total = fields[1].parse::<u64>().ok()? * 1024;
+            let available = fields[3].parse::<u64>().ok()? * 1024;
+            (total > 0).then(|| (fields[5..].join(" "), DiskStats { total, used: total.saturating_sub(available) }))
+        })
+        .collect()
+}
+
+/// `ps -e -o stat=` 的输出按状态的第一个字符计数，没有 `ps` 时没有
+fn processes(ps: &str) -> Option<ProcessStats> {
+    let mut stats = ProcessStats::default();
+    for state in ps.lines().filter_map(|line| line.trim().chars().next()) {
+        stats.total += 1;
+        match state {
+            'R' => stats.running += 1,
+            'S' => stats.sleeping += 1,
+            'Z' => stats.zombie += 1,
+            _ => {}
+        }
+    }
+    (stats.total > 0).then_some(stats)
+}
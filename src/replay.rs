@@ -0,0 +1,68 @@
+// src/replay.rs
+use crate::error::Error;
+use crate::i18n::tr;
+use crate::SystemStats;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::Duration;
+
+/// 从 `record` 录制的文件中按原来的时间间隔(除以 `speed`)重放样本
+pub struct Replay {
+    samples: VecDeque<SystemStats>,
+    speed: f64,
+}
+
+impl Replay {
+    pub fn open(path: &Path, speed: f64) -> Result<Self, Error> {
+        Ok(Self { samples: load(path)?.into(), speed })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// 距离下一个样本的等待时间；`previous` 是刚取出的样本
+    pub fn wait(&self, previous: &SystemStats) -> Duration {
+        let next = match self.samples.front() {
+            Some(next) => next,
+            None => return Duration::ZERO,
+        };
+        let gap = (next.timestamp - previous.timestamp).to_std().unwrap_or_default();
+        gap.div_f64(self.speed)
+    }
+}
+
+impl Iterator for Replay {
+    type Item = SystemStats;
+
+    fn next(&mut self) -> Option<SystemStats> {
+        self.samples.pop_front()
+    }
+}
+
+/// 读取每行一个样本的JSON文件，批量发送的请求体(样本数组)会被拆开，文件中至少要有一个样本
+pub fn load(path: &Path) -> Result<Vec<SystemStats>, Error> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| Error::config(format!("{} {}: {}", tr("无法读取录制文件"), path.display(), e)))?;
+
+    // 也接受整个文件是一个(可能带缩进的)样本
+    if let Ok(sample) = serde_json::from_str::<SystemStats>(&content) {
+        return Ok(vec![sample]);
+    }
+
+    let mut samples = Vec::new();
+    for (i, line) in content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let parsed = if line.trim_start().starts_with('[') {
+            serde_json::from_str::<Vec<SystemStats>>(line)
+        } else {
+            serde_json::from_str::<SystemStats>(line).map(|s| vec![s])
+        };
+        let parsed = parsed.map_err(|e| Error::config(format!("{} {}:{}: {}", tr("录制文件格式错误"), path.display(), i + 1, e)))?;
+        samples.extend(parsed);
+    }
+    if samples.is_empty() {
+        return Err(Error::config(format!("{}: {}", tr("录制文件中没有样本"), path.display())));
+    }
+
+    Ok(samples)
+}
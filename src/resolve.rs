@@ -0,0 +1,255 @@
+// src/resolve.rs
+use crate::i18n::tr;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+
+/// 端点的域名解析：取全部A和AAAA记录，按RFC 6724排序，上次连接成功的地址排在最前面。
+/// 连接由hyper按顺序尝试：先尝试第一个地址所属的地址族，300ms内没有连上时同时尝试另一个地址族(Happy Eyeballs)，
+/// 同一个地址族的地址连接失败后依次换下一个，`--connect-timeout` 在它们之间平分
+#[derive(Default)]
+pub struct Resolver {
+    /// 按域名记录最近一次连接成功的地址
+    connected: Mutex<HashMap<String, IpAddr>>,
+}
+
+impl Resolver {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// 记下连接成功的地址，之后解析这个域名时排在最前面；与上次不同时记录日志
+    pub fn connected(&self, host: &str, addr: IpAddr) {
+        let previous = self.connected.lock().unwrap().insert(host.to_string(), addr);
+        match previous {
+            Some(previous) if previous != addr => tracing::info!(host, %previous, %addr, "{}", tr("端点地址已切换")),
+            None => tracing::debug!(host, %addr, "{}", tr("已连接到端点地址")),
+            _ => {}
+        }
+    }
+}
+
+impl Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        let connected = self.connected.lock().unwrap().get(&host).copied();
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            // 取源地址时每个目的地址要绑定并连接一个套接字，不在异步运行时的线程中进行
+            let mut addrs = tokio::task::spawn_blocking(move || sort(addrs)).await?;
+            if let Some(index) = connected.and_then(|ip| addrs.iter().position(|addr| addr.ip() == ip)) {
+                addrs[..=index].rotate_right(1);
+            }
+            tracing::debug!(host, addrs = ?addrs.iter().map(SocketAddr::ip).collect::<Vec<_>>(), "{}", tr("解析端点地址"));
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// 按RFC 6724第6节的规则排序。源地址由系统的路由决定：对每个目的地址连接一个UDP套接字(不发送数据)取本地地址，
+/// 连接失败(例如没有IPv6路由)的地址排在最后。不使用的规则：3、4(没有地址状态的信息)和7
+fn sort(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    sort_by_source(addrs, source)
+}
+
+/// `source` 给出连接每个目的地址时的源地址
+fn sort_by_source(addrs: Vec<SocketAddr>, source: impl Fn(IpAddr) -> Option<IpAddr>) -> Vec<SocketAddr> {
+    let mut candidates: Vec<(SocketAddr, Option<IpAddr>)> = addrs.into_iter().map(|addr| (addr, source(addr.ip()))).collect();
+    // sort_by是稳定排序，规则都相同时保持系统解析器给出的顺序(规则10)
+    candidates.sort_by(|(a, source_a), (b, source_b)| {
+        let (a, b) = (a.ip(), b.ip());
+        // 规则1：避开不可用的目的地址
+        source_b.is_some().cmp(&source_a.is_some())
+            // 规则2：作用域与源地址相同的优先
+            .then_with(|| matching(*source_b, b, scope).cmp(&matching(*source_a, a, scope)))
+            // 规则5：标签与源地址相同的优先
+            .then_with(|| matching(*source_b, b, |ip| policy(ip).1).cmp(&matching(*source_a, a, |ip| policy(ip).1)))
+            // 规则6：优先级高的优先
+            .then_with(|| policy(b).0.cmp(&policy(a).0))
+            // 规则8：作用域小的优先
+            .then_with(|| scope(a).cmp(&scope(b)))
+            // 规则9：与源地址的公共前缀长的优先；只用于IPv6，IPv4按这条规则排序会打乱轮询DNS的顺序
+            .then_with(|| match (a, b, source_a, source_b) {
+                (IpAddr::V6(a), IpAddr::V6(b), Some(IpAddr::V6(source_a)), Some(IpAddr::V6(source_b))) => {
+                    common_prefix(b, *source_b).cmp(&common_prefix(a, *source_a))
+                }
+                _ => std::cmp::Ordering::Equal,
+            })
+    });
+    candidates.into_iter().map(|(addr, _)| addr).collect()
+}
+
+/// 连接这个目的地址时系统选择的源地址，没有路由时为 None
+fn source(destination: IpAddr) -> Option<IpAddr> {
+    let unspecified: IpAddr = if destination.is_ipv4() { [0, 0, 0, 0].into() } else { Ipv6Addr::UNSPECIFIED.into() };
+    let socket = UdpSocket::bind((unspecified, 0)).ok()?;
+    socket.connect((destination, 9)).ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+/// 源地址存在并且 `key` 与目的地址的相同
+fn matching<T: PartialEq>(source: Option<IpAddr>, destination: IpAddr, key: impl Fn(IpAddr) -> T) -> bool {
+    source.is_some_and(|source| key(source) == key(destination))
+}
+
+/// RFC 6724 2.1节默认策略表中的优先级和标签，IPv4地址按 `::ffff:0:0/96` 计算
+fn policy(ip: IpAddr) -> (u8, u8) {
+    let ip = match ip {
+        IpAddr::V4(_) => return (35, 4),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(_) => return (35, 4),
+            None => ip.segments(),
+        },
+    };
+    match ip {
+        [0, 0, 0, 0, 0, 0, 0, 1] => (50, 0),
+        [0x2002, ..] => (30, 2),
+        [0x2001, 0, ..] => (5, 5),
+        [first, ..] if first & 0xfe00 == 0xfc00 => (3, 13),
+        [0, 0, 0, 0, 0, 0, ..] => (1, 3),
+        [first, ..] if first & 0xffc0 == 0xfec0 => (1, 11),
+        [0x3ffe, ..] => (1, 12),
+        _ => (40, 1),
+    }
+}
+
+/// RFC 6724 3.1节的作用域：链路本地(包括环回)为2，站点本地为5，其余为全局14
+fn scope(ip: IpAddr) -> u8 {
+    match ip {
+        IpAddr::V4(ip) if ip.is_loopback() || ip.is_link_local() => 2,
+        IpAddr::V4(_) => 14,
+        IpAddr::V6(ip) => match ip.segments()[0] {
+            _ if ip.is_loopback() => 2,
+            first if first & 0xff00 == 0xff00 => (first & 0x000f) as u8,
+            first if first & 0xffc0 == 0xfe80 => 2,
+            first if first & 0xffc0 == 0xfec0 => 5,
+            _ => 14,
+        },
+    }
+}
+
+/// 公共前缀的位数，最多比较前64位(网络前缀)
+fn common_prefix(a: Ipv6Addr, b: Ipv6Addr) -> u32 {
+    let a = u128::from(a) >> 64;
+    let b = u128::from(b) >> 64;
+    ((a ^ b) as u64).leading_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    /// 按 `routes` 中(目的地址, 源地址)取源地址排序，没有列出的目的地址不可达
+    fn sorted(destinations: &[&str], routes: &[(&str, &str)]) -> Vec<IpAddr> {
+        let addrs = destinations.iter().map(|d| SocketAddr::new(ip(d), 443)).collect();
+        let source = |destination: IpAddr| routes.iter().find(|(d, _)| ip(d) == destination).map(|(_, s)| ip(s));
+        sort_by_source(addrs, source).into_iter().map(|addr| addr.ip()).collect()
+    }
+
+    fn ips(texts: &[&str]) -> Vec<IpAddr> {
+        texts.iter().map(|text| ip(text)).collect()
+    }
+
+    // 以下例子来自RFC 6724第10.2节
+    #[test]
+    fn prefers_matching_scope() {
+        let routes = [("2001:db8:1::1", "2001:db8:1::2"), ("198.51.100.121", "169.254.13.78")];
+        assert_eq!(sorted(&["198.51.100.121", "2001:db8:1::1"], &routes), ips(&["2001:db8:1::1", "198.51.100.121"]));
+
+        let routes = [("2001:db8:1::1", "fe80::1"), ("198.51.100.121", "198.51.100.117")];
+        assert_eq!(sorted(&["2001:db8:1::1", "198.51.100.121"], &routes), ips(&["198.51.100.121", "2001:db8:1::1"]));
+    }
+
+    #[test]
+    fn prefers_ipv6_over_ipv4_by_precedence() {
+        let routes = [("2001:db8:1::1", "2001:db8:1::2"), ("10.1.2.3", "10.1.2.4")];
+        assert_eq!(sorted(&["10.1.2.3", "2001:db8:1::1"], &routes), ips(&["2001:db8:1::1", "10.1.2.3"]));
+
+        let routes = [("2002:c633:6401::1", "2002:c633:6401::2"), ("2001:db8:1::1", "2001:db8:1::2")];
+        assert_eq!(sorted(&["2002:c633:6401::1", "2001:db8:1::1"], &routes), ips(&["2001:db8:1::1", "2002:c633:6401::1"]));
+    }
+
+    #[test]
+    fn prefers_smaller_scope() {
+        let routes = [("2001:db8:1::1", "2001:db8:1::2"), ("fe80::1", "fe80::2")];
+        assert_eq!(sorted(&["2001:db8:1::1", "fe80::1"], &routes), ips(&["fe80::1", "2001:db8:1::1"]));
+    }
+
+    #[test]
+    fn prefers_matching_label() {
+        let routes = [("2002:c633:6401::1", "2002:c633:6401::2"), ("2001:db8:1::1", "2002:c633:6401::2")];
+        assert_eq!(sorted(&["2001:db8:1::1", "2002:c633:6401::1"], &routes), ips(&["2002:c633:6401::1", "2001:db8:1::1"]));
+    }
+
+    #[test]
+    fn moves_unreachable_destinations_last() {
+        // 没有IPv6路由时先连接IPv4
+        let routes = [("192.0.2.1", "192.0.2.100"), ("192.0.2.2", "192.0.2.100")];
+        assert_eq!(
+            sorted(&["2001:db8::1", "192.0.2.1", "2001:db8::2", "192.0.2.2"], &routes),
+            ips(&["192.0.2.1", "192.0.2.2", "2001:db8::1", "2001:db8::2"]),
+        );
+    }
+
+    #[test]
+    fn orders_mixed_results_and_keeps_resolver_order_for_ties() {
+        let routes = [
+            ("2001:db8:1::1", "2001:db8:1::100"),
+            ("2001:db8:2::1", "2001:db8:1::100"),
+            ("203.0.113.1", "192.0.2.100"),
+            ("203.0.113.2", "192.0.2.100"),
+        ];
+        // IPv6按与源地址的公共前缀排序，IPv4保持轮询DNS给出的顺序
+        assert_eq!(
+            sorted(&["203.0.113.2", "2001:db8:2::1", "203.0.113.1", "2001:db8:1::1"], &routes),
+            ips(&["2001:db8:1::1", "2001:db8:2::1", "203.0.113.2", "203.0.113.1"]),
+        );
+    }
+
+    #[test]
+    fn prefers_loopback() {
+        let routes = [("::1", "::1"), ("127.0.0.1", "127.0.0.1")];
+        assert_eq!(sorted(&["127.0.0.1", "::1"], &routes), ips(&["::1", "127.0.0.1"]));
+    }
+
+    #[test]
+    fn reads_the_policy_table() {
+        assert_eq!(policy(ip("::1")), (50, 0));
+        assert_eq!(policy(ip("2001:db8::1")), (40, 1));
+        assert_eq!(policy(ip("192.0.2.1")), (35, 4));
+        assert_eq!(policy(ip("::ffff:192.0.2.1")), (35, 4));
+        assert_eq!(policy(ip("2002::1")), (30, 2));
+        assert_eq!(policy(ip("2001::1")), (5, 5));
+        assert_eq!(policy(ip("fd00::1")), (3, 13));
+        assert_eq!(policy(ip("::192.0.2.1")), (1, 3));
+        assert_eq!(policy(ip("fec0::1")), (1, 11));
+        assert_eq!(policy(ip("3ffe::1")), (1, 12));
+    }
+
+    #[test]
+    fn computes_scopes() {
+        assert_eq!(scope(ip("127.0.0.1")), 2);
+        assert_eq!(scope(ip("169.254.1.1")), 2);
+        assert_eq!(scope(ip("10.0.0.1")), 14);
+        assert_eq!(scope(ip("::1")), 2);
+        assert_eq!(scope(ip("fe80::1")), 2);
+        assert_eq!(scope(ip("fec0::1")), 5);
+        assert_eq!(scope(ip("ff05::1")), 5);
+        assert_eq!(scope(ip("ff0e::1")), 14);
+        assert_eq!(scope(ip("2001:db8::1")), 14);
+    }
+
+    #[test]
+    fn compares_network_prefixes() {
+        let v6 = |text: &str| text.parse::<Ipv6Addr>().unwrap();
+        assert_eq!(common_prefix(v6("2001:db8:1::1"), v6("2001:db8:1::2")), 64);
+        assert_eq!(common_prefix(v6("2001:db8:1::1"), v6("2001:db8:2::1")), 46);
+        assert_eq!(common_prefix(v6("8000::"), v6("::")), 0);
+    }
+}
@@ -0,0 +1,660 @@
+// src/run.rs
+use crate::compact::Recording;
+use crate::delivery::{Batch, Delivery, DryRun};
+use crate::display::{self, compact_line, display_stats, Header, Units};
+use crate::error::Error;
+use crate::health::{self, Health};
+use crate::i18n::tr;
+use crate::options::{
+    self, aggregate_settings, anomaly_settings, batch_settings, breaker_settings, build_monitor, build_notifiers, build_transport,
+    build_webhook_client, check_collector_intervals, collect_timeout, compaction_policy, history, load_scripts, parse_alert_rules,
+    parse_anomaly_metrics, parse_collector_intervals, parse_collectors, parse_derived, parse_display_filter, parse_display_options,
+    parse_fields, parse_maintenance, parse_net_filter, parse_process_filter, parse_sink_units, parse_tags, seconds, spool_cipher,
+    throttle_settings,
+};
+use crate::pipeline::Pipeline;
+use crate::plugin::{parse_exporter, Plugin};
+use crate::replay::Replay;
+use crate::spool::{self, Spool};
+use crate::template_sink::TemplateSink;
+use crate::tui::{Action, Tui};
+use crate::{
+    aggregate, alert, anomaly, config, delta, events, log, logind, maintenance, resolve_users, signals, systemd, AgentEvent, Monitor,
+    SCHEMA_VERSION,
+};
+use chrono::Utc;
+use clap::{ArgMatches, Command};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, warn};
+
+/// 每个间隔在标准输出上显示的内容
+#[derive(Clone, Copy, PartialEq)]
+enum Output {
+    Text,
+    Compact,
+    Json,
+    Quiet,
+}
+
+/// 退出前发送剩余数据和停止事件的最长时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 等待后台发送的告警通知完成，最多等待 `SHUTDOWN_TIMEOUT`
+async fn wait_notifications(tasks: &mut JoinSet<()>) {
+    let wait = async { while tasks.join_next().await.is_some() {} };
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, wait).await.is_err() {
+        warn!(pending = tasks.len(), "{}", tr("退出前发送告警通知超时"));
+    }
+}
+
+/// 等待下一个 `--power-events` 的事件，没有使用时一直等待
+async fn next_power_event(events: &mut Option<tokio::sync::mpsc::UnboundedReceiver<logind::Event>>) -> Option<logind::Event> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 处理 `--power-events` 的事件：记录日志，发送给端点；休眠时暂停采集，恢复时重新建立占用率和速率的基准后继续。
+/// 返回终端界面的状态行
+async fn power_event(
+    event: &logind::Event,
+    monitor: &mut Monitor,
+    suspended: &mut bool,
+    pipeline: &Pipeline,
+    send: bool,
+    endpoint: &str,
+) -> Result<String, Error> {
+    let status = match event {
+        logind::Event::Suspend => {
+            *suspended = true;
+            tr("系统即将休眠，暂停采集")
+        }
+        logind::Event::Resume => {
+            *suspended = false;
+            monitor.rebaseline().await;
+            tr("系统已恢复，继续采集")
+        }
+        logind::Event::LidClosed => tr("笔记本盖子已合上"),
+        logind::Event::LidOpened => tr("笔记本盖子已打开"),
+        logind::Event::SessionNew(_) => tr("新的登录会话"),
+        logind::Event::SessionRemoved(_) => tr("登录会话已结束"),
+    };
+    let session = event.session();
+    info!(event = event.name(), session = session.map(|s| s.id.as_str()), user = session.and_then(|s| s.user.as_deref()), "{}", status);
+    if send {
+        let body = AgentEvent {
+            version: SCHEMA_VERSION,
+            event: event.name(),
+            timestamp: Utc::now(),
+            host: monitor.host_name(),
+            instance_id: monitor.instance_id().map(str::to_string),
+            tags: monitor.tags().clone(),
+            alert: None,
+            session: session.cloned(),
+            change: None,
+            error: None,
+        };
+        if !pipeline.event(serde_json::to_string(&body)?) {
+            warn!(%endpoint, "{}", tr("发送队列已满，系统事件被丢弃"));
+        }
+    }
+    Ok(match session {
+        Some(session) => format!("{}: {} {}", status, session.id, session.user.as_deref().unwrap_or_default()).trim_end().to_string(),
+        None => status.to_string(),
+    })
+}
+
+/// 从现在起每隔 `period` 触发一次的节拍；错过的节拍直接跳过，之后的节拍仍与开始时对齐
+pub(crate) fn ticker_for(period: Duration) -> tokio::time::Interval {
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    ticker
+}
+
+/// 就绪检查 `collectors`：出错或超时的采集项
+pub(crate) fn check_collectors(monitor: &Monitor) -> Option<String> {
+    let failing = monitor.failing();
+    (!failing.is_empty()).then(|| format!("{}: {}", tr("采集项出错"), failing.join(",")))
+}
+
+/// 采集、显示和发送样本的主循环
+pub async fn monitor(command: Command, matches: ArgMatches) -> Result<(), Error> {
+
+    let mut interval = options::interval(&matches);
+    let mut endpoint = matches.get_one::<String>("endpoint").unwrap().clone();
+    let daemon = matches.get_flag("daemon");
+    let no_display = matches.get_flag("no-display") || daemon || matches.get_flag("embedded");
+    let spool_max_size = *matches.get_one::<u64>("spool-max-size").unwrap();
+    // 试运行时不发送也不写缓存，`--dry-run` 不带文件名时请求体写到标准错误
+    let dry_run = match matches.get_one::<String>("dry-run").map(String::as_str) {
+        None => None,
+        Some("-") => Some(DryRun::Log),
+        Some(path) => Some(DryRun::File(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| Error::config(format!("{} {}: {}", tr("无法打开文件"), path, e)))?,
+        )),
+    };
+    let spool = match matches.get_one::<String>("spool-dir") {
+        Some(dir) if dry_run.is_none() => Some(Spool::open(Path::new(dir), spool_max_size).map_err(Error::config)?.cipher(spool_cipher(&matches))),
+        _ => None,
+    };
+    let spool_dir = matches.get_one::<String>("spool-dir").filter(|_| spool.is_some()).map(PathBuf::from);
+    let (batch_size, batch_interval) = batch_settings(&matches);
+    let batch = Batch::new(batch_size, batch_interval).fields(parse_fields(&matches));
+
+    // `record` 只把样本写入文件，`replay` 用文件中的样本代替实时采集
+    let mut recording = match matches.subcommand() {
+        Some(("record", sub)) => {
+            let policy = (!sub.get_flag("no-compact")).then(|| compaction_policy(sub));
+            Some(Recording::open(Path::new(sub.get_one::<String>("to").unwrap()), policy).map_err(Error::config)?)
+        }
+        _ => None,
+    };
+    let mut replay = match matches.subcommand() {
+        Some(("replay", sub)) => {
+            let speed = sub.get_one::<String>("speed").and_then(|s| s.parse().ok()).unwrap_or(1.0);
+            Some(Replay::open(Path::new(sub.get_one::<String>("from").unwrap()), speed).map_err(Error::config)?)
+        }
+        _ => None,
+    };
+
+    // 启动前发现的选项、配置文件和引用的文件(证书、场景、脚本、插件、模板)中的错误都是配置错误
+    let transport = build_transport(&matches).map_err(Error::config)?;
+    let mut monitor = build_monitor(&matches).await.map_err(Error::config)?;
+
+    let output = if no_display {
+        Output::Quiet
+    } else if matches.get_one::<String>("format").is_some_and(|f| f == "json") {
+        Output::Json
+    } else if matches.get_flag("compact") {
+        Output::Compact
+    } else {
+        Output::Text
+    };
+
+    // 单次模式：等待一个采集窗口，输出并发送一个样本后退出，发送失败时退出状态为 EXIT_UNREACHABLE
+    let once = matches.get_flag("once") || matches.subcommand_name() == Some("once");
+    if once {
+        tokio::time::sleep(interval).await;
+    } else {
+        if output != Output::Json && !daemon {
+            println!("{}", tr("开始监控系统资源..."));
+            println!("{}: {}", tr("刷新间隔"), humantime::format_duration(interval));
+            if dry_run.is_some() {
+                println!("{}: {} ({})", tr("数据端点"), endpoint, tr("试运行，不发送数据"));
+            } else {
+                println!("{}: {}", tr("数据端点"), endpoint);
+            }
+            println!("{}\n", tr("按 Ctrl+C 退出"));
+        }
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+
+    let mut history = history(&matches, interval);
+
+    let units = match matches.get_one::<String>("units").map(String::as_str) {
+        Some("si") => Units::Si,
+        _ => Units::Iec,
+    };
+    display::set_units(units, matches.get_flag("net-bits"));
+
+    let mut display_filter = parse_display_filter(&matches);
+    let mut display_options = parse_display_options(&matches);
+
+    if once || !cfg!(feature = "tui") {
+        display_options.plain = true;
+    }
+    let mut tui = if output == Output::Text && !display_options.plain {
+        log::set_terminal_ui(true);
+        Some(Tui::start(display_filter.clone(), matches.get_flag("braille"))?)
+    } else {
+        None
+    };
+
+    // 终端界面占用屏幕，试运行的请求体不再写到标准错误
+    let dry_run = match dry_run {
+        Some(DryRun::Log) if tui.is_some() => Some(DryRun::Discard),
+        dry_run => dry_run,
+    };
+    let dry_running = dry_run.is_some();
+    let delivery = Delivery::new(transport, spool)
+        .jitter(seconds(&matches, "jitter").unwrap_or(Duration::ZERO))
+        .breaker(breaker_settings(&matches))
+        .dry_run(dry_run)
+        .delta(seconds(&matches, "delta").map(|full_every| {
+            delta::Encoder::new(full_every, *matches.get_one::<f64>("delta-epsilon").unwrap() / 100.0)
+        }));
+    monitor.set_seq(delivery.last_seq());
+    // 发送在单独的任务中进行，端点变慢时不耽误采集；每次发送的结果从 `reports` 取回
+    let plugins = matches.get_many::<String>("exporter")
+        .unwrap_or_default()
+        .map(|value| {
+            let (path, config) = parse_exporter(value);
+            Plugin::load(Path::new(path), config, breaker_settings(&matches))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::config)?;
+    let templates = matches.get_many::<String>("template-sink")
+        .unwrap_or_default()
+        .map(|value| {
+            let timeout = seconds(&matches, "request-timeout").unwrap_or(Duration::from_secs(10));
+            TemplateSink::load(value, build_webhook_client(&matches)?, timeout, breaker_settings(&matches))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::config)?;
+    let sink_units = parse_sink_units(&matches);
+    let sinks: Vec<String> = plugins
+        .iter()
+        .map(|plugin| format!("exporter:{}", plugin.name()))
+        .chain(templates.iter().map(|template| format!("template:{}", template.name())))
+        .chain(["endpoint".to_string()])
+        .collect();
+    if let Some(sink) = sink_units.sinks().find(|sink| !sinks.iter().any(|name| name == sink)) {
+        return Err(Error::Config(format!("{}: {}", tr("没有这个输出"), sink)));
+    }
+    let (pipeline, mut reports) = Pipeline::spawn(delivery, batch, endpoint.clone(), plugins, templates, sink_units);
+    // 状态变化与样本分开检测和分发
+    let event_sinks: Vec<String> = matches.get_many::<String>("event-sink").unwrap_or_default().cloned().collect();
+    let mut bus = events::Bus::new(
+        &event_sinks,
+        seconds(&matches, "event-retention").unwrap_or(Duration::from_secs(7 * 86400)),
+        build_webhook_client(&matches).map_err(Error::config)?,
+        seconds(&matches, "request-timeout").unwrap_or(Duration::from_secs(10)),
+        breaker_settings(&matches),
+    )
+    .map_err(Error::config)?;
+    let mut detector = events::Detector::new();
+    let health = Health::new();
+    if let Some(listen) = matches.get_one::<String>("health-listen") {
+        let listener = tokio::net::TcpListener::bind(listen)
+            .await
+            .map_err(|e| Error::config(format!("{} {}: {}", tr("无法监听"), listen, e)))?;
+        tokio::spawn(health::run(listener, health.clone()));
+    }
+    let mut max_backlog = *matches.get_one::<usize>("max-backlog").unwrap();
+    let mut spooled_bytes = 0;
+    let (aggregate_window, aggregate_metrics) = aggregate_settings(&matches);
+    let mut aggregator = aggregate::Aggregator::new(aggregate_window, aggregate_metrics);
+
+    let mut header = Header::new(monitor.host_name());
+    let mut alerts = alert::Engine::new(parse_alert_rules(&matches));
+    let (anomaly_window, anomaly_threshold) = anomaly_settings(&matches);
+    let mut anomalies = anomaly::Detector::new(parse_anomaly_metrics(&matches), anomaly_window, anomaly_threshold);
+    let mut alert_events = matches.get_flag("alert-events");
+    let mut maintenance = maintenance::Schedule::new(parse_maintenance(&matches));
+    let mut power_events = if matches.get_flag("power-events") { Some(logind::watch()?) } else { None };
+    // 收到休眠事件后不再采集，直到恢复
+    let mut suspended = false;
+    let host = monitor.host_name();
+    let mut notifiers = build_notifiers(&matches, &host).map_err(Error::config)?;
+    // 后台发送的告警通知，退出前等待它们完成
+    let mut notifications = JoinSet::new();
+
+    // 采集到 `--count` 个样本或运行了 `--duration` 之后，发送缓存的批量数据并退出
+    let count = matches.get_one::<u64>("count").copied();
+    let duration = seconds(&matches, "duration");
+    let started = Instant::now();
+    let mut collected = 0;
+
+    // 由systemd启动时，第一次采集后报告就绪，之后每次采集完成时发送看门狗心跳
+    let mut notifier = systemd::Notifier::from_env();
+    if notifier.watchdog().is_some_and(|watchdog| watchdog / 2 < monitor.tick()) {
+        warn!(interval_ms = monitor.tick().as_millis() as u64, "{}", tr("刷新间隔超过看门狗超时的一半，可能被systemd误判为无响应"));
+    }
+
+    // 收到 Ctrl+C 或 SIGTERM 时正常返回，终端界面借此恢复终端；收到 SIGHUP 时重新加载配置
+    let mut sigint = signals::interrupt()?;
+    let mut sigterm = signals::terminate()?;
+    let mut sighup = signals::hangup()?;
+
+    let mut ticker = ticker_for(monitor.tick());
+    // 运行中出错时同样恢复终端、发送剩余的样本，停止事件中带上错误
+    let outcome: Result<(), Error> = async {
+        'collect: loop {
+            let mut stats = match &mut replay {
+                Some(replay) => replay.next().unwrap(),
+                None => monitor.collect().await,
+            };
+            stats.anomalies = anomalies.evaluate(&stats);
+            let events = alerts.evaluate(&stats);
+            stats.alerts = alerts.active(stats.timestamp);
+            stats.maintenance = maintenance.update(stats.timestamp);
+            let events = maintenance.alerts(events, &stats.alerts, stats.timestamp);
+            // 维护窗口内只在本地保留样本(显示、历史、`record`)
+            let holding = maintenance.holding();
+            let changes = if bus.is_empty() { Vec::new() } else { detector.detect(&stats, &events) };
+            for anomaly in stats.anomalies.iter().filter(|a| a.since == stats.timestamp) {
+                warn!(metric = %anomaly.metric, instance = %anomaly.instance, value = anomaly.value, mean = anomaly.mean, z = anomaly.z, "{}", tr("指标偏离基线"));
+                if let Some(tui) = &mut tui {
+                    tui.set_status(format!("{}: {} {}", tr("指标偏离基线"), anomaly.metric, anomaly.instance).trim_end().to_string());
+                }
+            }
+            match &mut recording {
+                Some(recording) => recording.write(&stats)?,
+                None if holding => {}
+                None => {
+                    if let Some(stats) = aggregator.push(stats.clone()) {
+                        if !pipeline.push(stats) {
+                            warn!(%endpoint, "{}", tr("发送队列已满，样本被丢弃"));
+                        }
+                    }
+                }
+            }
+            if !bus.is_empty() {
+                bus.publish(&changes);
+            }
+            for change in changes {
+                if let Some(tui) = &mut tui {
+                    tui.set_status(events::describe(&change));
+                }
+                if bus.to_endpoint() && recording.is_none() && !holding {
+                    let event = AgentEvent {
+                        version: SCHEMA_VERSION,
+                        event: "change",
+                        timestamp: change.timestamp,
+                        host: host.clone(),
+                        instance_id: monitor.instance_id().map(str::to_string),
+                        tags: monitor.tags().clone(),
+                        alert: None,
+                        session: None,
+                        change: Some(change),
+                        error: None,
+                    };
+                    if !pipeline.event(serde_json::to_string(&event)?) {
+                        warn!(%endpoint, "{}", tr("发送队列已满，状态变化事件被丢弃"));
+                    }
+                }
+            }
+            for event in events {
+                let status = match event.state {
+                    alert::State::Firing if event.repeat => {
+                        warn!(rule = %event.rule, instance = %event.instance, value = event.value, since = %event.since, "{}", tr("告警仍在触发"));
+                        tr("告警仍在触发")
+                    }
+                    alert::State::Firing => {
+                        warn!(rule = %event.rule, instance = %event.instance, value = event.value, threshold = event.threshold, "{}", tr("告警触发"));
+                        tr("告警触发")
+                    }
+                    alert::State::Resolved => {
+                        info!(rule = %event.rule, instance = %event.instance, value = event.value, "{}", tr("告警解除"));
+                        tr("告警解除")
+                    }
+                };
+                if let Some(tui) = &mut tui {
+                    tui.set_status(format!("{}: {} {}", status, event.rule, event.instance).trim_end().to_string());
+                }
+                let notification = alert::Notification { host: &host, tags: monitor.tags(), event: &event };
+                notifiers.notify(&notification, dry_running, &mut notifications);
+                if alert_events && recording.is_none() && !holding {
+                    let event = AgentEvent {
+                        version: SCHEMA_VERSION,
+                        event: "alert",
+                        timestamp: event.timestamp,
+                        host: host.clone(),
+                        instance_id: monitor.instance_id().map(str::to_string),
+                        tags: monitor.tags().clone(),
+                        alert: Some(event),
+                        session: None,
+                        change: None,
+                        error: None,
+                    };
+                    if !pipeline.event(serde_json::to_string(&event)?) {
+                        warn!(%endpoint, "{}", tr("发送队列已满，告警事件被丢弃"));
+                    }
+                }
+            }
+            while notifications.try_join_next().is_some() {}
+            history.push(stats);
+            notifier.collected();
+
+            collected += 1;
+            let last = once
+                || count.is_some_and(|count| collected >= count)
+                || duration.is_some_and(|duration| started.elapsed() + monitor.tick() > duration)
+                || replay.as_ref().is_some_and(Replay::is_empty);
+
+            if recording.is_none() && last {
+                if let Some(stats) = aggregator.take() {
+                    if !pipeline.push(stats) {
+                        warn!(%endpoint, "{}", tr("发送队列已满，样本被丢弃"));
+                    }
+                }
+                pipeline.flush().await;
+            }
+            // 单次模式等待这个样本发送完成，退出状态取决于发送结果
+            let mut sent = true;
+            let mut wait = once && recording.is_none();
+            loop {
+                let report = if wait { reports.recv().await } else { reports.try_recv().ok() };
+                let Some(mut report) = report else { break };
+                report.sinks.extend(bus.health());
+                wait = false;
+                sent = report.sent;
+                header.record(report.sent, report.sinks.clone());
+                monitor.sent(report.elapsed, report.sent, report.spooled_bytes, report.sinks);
+                spooled_bytes = report.spooled_bytes;
+                if let Some(tui) = &mut tui {
+                    tui.set_status(report.status);
+                }
+            }
+
+            health.collected(monitor.tick());
+            health.check("collectors", check_collectors(&monitor));
+            let backlog = pipeline.backlog();
+            health.check("backlog", (backlog > max_backlog).then(|| format!("{}: {}", tr("发送队列积压的样本"), backlog)));
+            if let Some(dir) = spool_dir.as_deref() {
+                health.check("spool", spool::full(dir, spooled_bytes, spool_max_size));
+            }
+
+            match (&mut tui, history.latest(), output) {
+                (Some(tui), _, _) => {
+                    tui.set_header(header.line(monitor.uptime()));
+                    // `proc` 采集超时还在采集时保留上次的进程列表
+                    if let Some(processes) = monitor.process_list().filter(|_| replay.is_none()) {
+                        tui.set_processes(processes);
+                    }
+                    tui.draw(&history)?
+                }
+                (None, Some(stats), Output::Text) => display_stats(stats, &history, &header.line(monitor.uptime()), &display_filter, &display_options),
+                (None, Some(stats), Output::Compact) => println!("{}", compact_line(stats, &display_filter)),
+                (None, Some(stats), Output::Json) => println!("{}", serde_json::to_string(stats)?),
+                _ => {}
+            }
+
+            // 与正常退出一样走下面的收尾(发送停止事件、删除PID文件)，退出状态由 main 按结果决定
+            if once {
+                wait_notifications(&mut notifications).await;
+                if !sent {
+                    return Err(Error::Unreachable(endpoint.clone()));
+                }
+                break 'collect;
+            }
+            if last {
+                break;
+            }
+
+            // 实时采集按固定的节拍进行，采集和发送的耗时不会累积到间隔上；刷新间隔变化(重新加载、过载放慢)时重新对齐
+            if ticker.period() != monitor.tick() {
+                ticker = ticker_for(monitor.tick());
+            }
+            let mut reload = false;
+            loop {
+                let resumed = {
+                    let sleep = async {
+                        match (&replay, history.latest()) {
+                            (Some(replay), Some(latest)) => tokio::time::sleep(replay.wait(latest)).await,
+                            _ => {
+                                ticker.tick().await;
+                            }
+                        }
+                    };
+                    tokio::pin!(sleep);
+
+                    let mut resumed = false;
+                    match &mut tui {
+                        Some(tui) => loop {
+                            tokio::select! {
+                                _ = &mut sleep, if !suspended => break,
+                                _ = sigint.recv() => break 'collect,
+                                _ = sigterm.recv() => break 'collect,
+                                _ = sighup.recv() => {
+                                    reload = true;
+                                    break;
+                                }
+                                action = tui.next_event() => match action {
+                                    Action::Quit => break 'collect,
+                                    Action::Redraw => tui.draw(&history)?,
+                                    Action::Kill { process, force } => {
+                                        let status = match monitor.kill(&process, force) {
+                                            Ok(()) => format!("{}: {}", tr("已发送信号"), process.pid),
+                                            Err(reason) => format!("{} {}: {}", reason, process.pid, process.name),
+                                        };
+                                        tui.set_status(status);
+                                        tui.draw(&history)?
+                                    }
+                                    Action::None => {}
+                                },
+                                Some(event) = next_power_event(&mut power_events) => {
+                                    let status = power_event(&event, &mut monitor, &mut suspended, &pipeline, recording.is_none(), &endpoint).await?;
+                                    tui.set_status(status);
+                                    tui.draw(&history)?;
+                                    if event == logind::Event::Resume {
+                                        resumed = true;
+                                        break;
+                                    }
+                                }
+                            }
+                        },
+                        None => loop {
+                            tokio::select! {
+                                _ = &mut sleep, if !suspended => break,
+                                _ = sigint.recv() => break 'collect,
+                                _ = sigterm.recv() => break 'collect,
+                                _ = sighup.recv() => {
+                                    reload = true;
+                                    break;
+                                }
+                                Some(event) = next_power_event(&mut power_events) => {
+                                    power_event(&event, &mut monitor, &mut suspended, &pipeline, recording.is_none(), &endpoint).await?;
+                                    if event == logind::Event::Resume {
+                                        resumed = true;
+                                        break;
+                                    }
+                                }
+                            }
+                        },
+                    }
+                    resumed
+                };
+                if !resumed {
+                    break;
+                }
+                // 休眠恢复后从这时起重新计时，等一个完整的间隔再采集，占用率和速率按恢复之后的这段时间计算
+                ticker = ticker_for(monitor.tick());
+            }
+
+            if reload {
+                // 先解析和校验全部配置，有错误时沿用原来的配置，不修改任何设置
+                let result = config::reload(&command).and_then(|matches| {
+                    let transport = build_transport(&matches)?;
+                    let tags = parse_tags(&matches)?;
+                    let net_filter = parse_net_filter(&matches)?;
+                    let notifiers = build_notifiers(&matches, &host)?;
+                    let process_filter = parse_process_filter(&matches)?;
+                    let uids = resolve_users(&process_filter.users)?;
+                    let scripts = load_scripts(&matches)?;
+                    let derived = parse_derived(&matches)?;
+                    check_collector_intervals(&matches, &monitor)?;
+                    Ok((matches, transport, tags, net_filter, notifiers, (process_filter, uids), (scripts, derived)))
+                });
+                let status = match result {
+                    Ok((matches, transport, tags, net_filter, reloaded_notifiers, (process_filter, uids), (scripts, derived))) => {
+                        interval = options::interval(&matches);
+                        monitor.set_process_filter(process_filter, uids);
+                        monitor.set_scripts(scripts);
+                        monitor.set_derived(derived);
+                        monitor.set_tags(tags);
+                        monitor.set_collectors(parse_collectors(&matches));
+                        monitor.set_net_filter(net_filter);
+                        monitor.set_top(*matches.get_one::<usize>("top").unwrap());
+                        monitor.set_intervals(interval, parse_collector_intervals(&matches));
+                        monitor.set_collect_timeout(Some(collect_timeout(&matches, monitor.tick())));
+                        let (limits, max_slowdown, shed) = throttle_settings(&matches);
+                        monitor.set_throttle(limits, max_slowdown, shed);
+                        alerts.configure(parse_alert_rules(&matches));
+                        alert_events = matches.get_flag("alert-events");
+                        maintenance.configure(parse_maintenance(&matches));
+                        let (anomaly_window, anomaly_threshold) = anomaly_settings(&matches);
+                        anomalies.configure(parse_anomaly_metrics(&matches), anomaly_window, anomaly_threshold);
+                        let (aggregate_window, aggregate_metrics) = aggregate_settings(&matches);
+                        aggregator.configure(aggregate_window, aggregate_metrics);
+                        notifiers = reloaded_notifiers;
+                        display_filter = parse_display_filter(&matches);
+                        display_options = parse_display_options(&matches);
+                        if let Some(tui) = &mut tui {
+                            tui.set_filter(display_filter.clone());
+                        }
+                        endpoint = matches.get_one::<String>("endpoint").unwrap().clone();
+                        max_backlog = *matches.get_one::<usize>("max-backlog").unwrap();
+                        let jitter = seconds(&matches, "jitter").unwrap_or(Duration::ZERO);
+                        pipeline.reconfigure(transport, jitter, batch_settings(&matches), (parse_fields(&matches), parse_sink_units(&matches)), breaker_settings(&matches), endpoint.clone()).await;
+                        info!(%endpoint, interval_ms = interval.as_millis() as u64, "{}", tr("配置已重新加载"));
+                        tr("配置已重新加载").to_string()
+                    }
+                    Err(e) => {
+                        error!(error = %e, "{}", tr("重新加载配置失败"));
+                        format!("{}: {}", tr("重新加载配置失败"), e)
+                    }
+                };
+                if let Some(tui) = &mut tui {
+                    tui.set_status(status);
+                }
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    // 正常退出：先恢复终端，再发送剩余的批量样本和磁盘缓存，最后发送停止事件
+    drop(tui);
+    log::set_terminal_ui(false);
+    wait_notifications(&mut notifications).await;
+    if recording.is_none() {
+        if let Some(stats) = aggregator.take() {
+            if !pipeline.push(stats) {
+                warn!(%endpoint, "{}", tr("发送队列已满，样本被丢弃"));
+            }
+        }
+        let event = AgentEvent {
+            version: SCHEMA_VERSION,
+            event: "stopping",
+            timestamp: Utc::now(),
+            host: monitor.host_name(),
+            instance_id: monitor.instance_id().map(str::to_string),
+            tags: monitor.tags().clone(),
+            alert: None,
+            session: None,
+            change: None,
+            error: outcome.as_ref().err().map(Error::report),
+        };
+        if !pipeline.shutdown(serde_json::to_string(&event)?, SHUTDOWN_TIMEOUT).await {
+            warn!(%endpoint, "{}", tr("退出前发送数据超时"));
+        }
+    }
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, bus.close()).await.is_err() {
+        warn!("{}", tr("退出前发送状态变化事件超时"));
+    }
+    if outcome.is_ok() {
+        info!("{}", tr("已停止"));
+    }
+    outcome
+}
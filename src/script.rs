@@ -0,0 +1,66 @@
+// src/script.rs
+use crate::error::Error;
+use crate::i18n::tr;
+use crate::SystemStats;
+use std::path::Path;
+
+/// 编译时是否启用了 `lua` 特性
+pub const AVAILABLE: bool = cfg!(feature = "lua");
+
+/// 用户提供的Lua脚本：每次采集后以样本为参数调用脚本中的 `collect(stats)`，
+/// 返回值以脚本名(文件名去掉扩展名)为键加入样本，返回 `nil` 时不加入
+pub struct Script {
+    name: String,
+    #[cfg(feature = "lua")]
+    lua: mlua::Lua,
+}
+
+impl Script {
+    /// 读取并运行脚本的顶层代码，脚本中没有定义 `collect` 函数时报错
+    #[cfg(feature = "lua")]
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| Error::config(format!("{} {}: {}", tr("无法读取脚本"), path.display(), e)))?;
+        let lua = mlua::Lua::new();
+        lua.load(&source)
+            .set_name(path.display().to_string())
+            .exec()
+            .map_err(|e| Error::config(format!("{} {}: {}", tr("加载脚本失败"), path.display(), e)))?;
+        if !matches!(lua.globals().get::<_, mlua::Value>("collect").map_err(Error::config)?, mlua::Value::Function(_)) {
+            return Err(Error::config(format!("{}: {}", tr("脚本中没有定义 collect 函数"), path.display())));
+        }
+        Ok(Self { name: name(path), lua })
+    }
+
+    #[cfg(not(feature = "lua"))]
+    pub fn load(_path: &Path) -> Result<Self, Error> {
+        Err(Error::config(tr("自定义脚本需要以 --features lua 编译")))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 用一个样本调用 `collect`，返回要加入样本的值
+    #[cfg(feature = "lua")]
+    pub fn run(&self, stats: &SystemStats) -> Result<Option<serde_json::Value>, Error> {
+        use mlua::LuaSerdeExt;
+
+        let collect: mlua::Function = self.lua.globals().get("collect").map_err(Error::runtime)?;
+        let result: mlua::Value = collect.call(self.lua.to_value(stats).map_err(Error::runtime)?).map_err(Error::runtime)?;
+        if result.is_nil() {
+            return Ok(None);
+        }
+        Ok(Some(self.lua.from_value(result).map_err(Error::runtime)?))
+    }
+
+    #[cfg(not(feature = "lua"))]
+    pub fn run(&self, _stats: &SystemStats) -> Result<Option<serde_json::Value>, Error> {
+        Ok(None)
+    }
+}
+
+#[cfg(feature = "lua")]
+fn name(path: &Path) -> String {
+    path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy().into_owned()
+}
@@ -0,0 +1,312 @@
+// src/serve.rs
+use crate::health::Health;
+use crate::history::History;
+use crate::i18n::tr;
+use crate::{parse_duration, prometheus, SystemStats};
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+/// 读取请求头和写出响应的超时时间，以及请求头的最大长度
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// `GET /events` 的订阅者跟不上时最多积压的样本数，超过时跳过最早的样本
+const EVENTS_BACKLOG: usize = 16;
+
+/// 采集循环还没处理完时最多排队的控制命令
+const CONTROL_BACKLOG: usize = 8;
+
+/// 内嵌的仪表盘页面，`{{...}}` 在响应时替换为当前语言的文字
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+/// 通过 `POST /control/...` 发给采集循环的命令
+#[derive(Debug)]
+pub enum Control {
+    /// 修改默认的采集间隔
+    Interval(Duration),
+    /// 启用和关闭采集项
+    Collectors { enable: Vec<String>, disable: Vec<String> },
+    /// 立即采集一个样本
+    Snapshot,
+    /// 立即轮转日志文件
+    RotateLogs,
+}
+
+/// 采集循环对控制命令的回复：成功时为响应的JSON，失败时为错误信息
+pub type Reply = oneshot::Sender<Result<String, String>>;
+
+/// HTTP服务的状态，采集循环通过 [`Server::publish`] 写入，各请求读取
+pub struct Server {
+    /// 最新样本的JSON
+    latest: RwLock<Option<String>>,
+    /// 最近一段时间的样本
+    recent: RwLock<History>,
+    /// 新样本的JSON，推送给 `GET /events` 的订阅者
+    events: broadcast::Sender<Arc<str>>,
+    /// 控制命令，由采集循环接收
+    controls: mpsc::Sender<(Control, Reply)>,
+    health: Health,
+    token: Option<String>,
+    host: String,
+}
+
+impl Server {
+    /// 设置了 `token` 时除 `/healthz` 和 `/readyz` 外都需要 `Authorization: Bearer <token>` 或查询参数 `token=<token>`，
+    /// 没有设置时不接受控制命令；返回的接收端由采集循环读取控制命令
+    pub fn new(history: History, token: Option<String>, host: String) -> (Arc<Self>, mpsc::Receiver<(Control, Reply)>) {
+        let (events, _) = broadcast::channel(EVENTS_BACKLOG);
+        let (controls, receiver) = mpsc::channel(CONTROL_BACKLOG);
+        let server = Self {
+            latest: RwLock::new(None),
+            recent: RwLock::new(history),
+            events,
+            controls,
+            health: Health::default(),
+            token,
+            host,
+        };
+        (Arc::new(server), receiver)
+    }
+
+    /// 采集循环每得到一个样本调用一次
+    pub fn publish(&self, stats: SystemStats) -> serde_json::Result<()> {
+        let json = serde_json::to_string(&stats)?;
+        let _ = self.events.send(Arc::from(json.as_str()));
+        *self.latest.write().unwrap() = Some(json);
+        self.recent.write().unwrap().push(stats);
+        Ok(())
+    }
+
+    /// `GET /healthz` 和 `GET /readyz` 反映的状态，由采集循环更新
+    pub fn health(&self) -> &Health {
+        &self.health
+    }
+}
+
+/// 拉取模式的HTTP服务：`GET /stats` 返回最新的样本，`GET /history?since=...` 返回最近的样本，
+/// `GET /metrics` 按Prometheus文本格式返回最新的样本，`GET /events` 以Server-Sent Events推送之后的每个样本，
+/// `GET /` 是显示实时曲线的仪表盘，`GET /healthz` 和 `GET /readyz` 见 [`Health`]，`POST /control/...` 见 [`Control`]
+pub async fn run(listener: TcpListener, server: Arc<Server>) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let server = server.clone();
+        tokio::spawn(async move {
+            let _ = handle(stream, server).await;
+        });
+    }
+}
+
+/// 请求头中的Bearer令牌或查询参数 `token` 是否与 `token` 相同，比较时间与令牌内容无关；
+/// 浏览器的 `EventSource` 不能设置请求头，仪表盘用查询参数
+pub(crate) fn authorized(request: &str, query: &str, token: &str) -> bool {
+    let bearer = header(request, "authorization").and_then(|value| value.strip_prefix("Bearer "));
+    let Some(provided) = bearer.or_else(|| param(query, "token")) else {
+        return false;
+    };
+    crate::crypto::constant_eq(provided.trim().as_bytes(), token.as_bytes())
+}
+
+/// `since` 可以是时间(RFC 3339，例如 `2024-05-01T12:00:00Z`)或时长(例如 `10m`，表示最近10分钟)，省略时返回全部
+fn parse_since(query: &str) -> Result<Option<DateTime<Utc>>, ()> {
+    let Some(value) = param(query, "since") else {
+        return Ok(None);
+    };
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(Some(time.with_timezone(&Utc)));
+    }
+    let ago = parse_duration(value).and_then(|d| chrono::Duration::from_std(d).ok()).ok_or(())?;
+    Ok(Some(Utc::now() - ago))
+}
+
+/// 按时间顺序的样本数组
+fn history(server: &Server, query: &str) -> (&'static str, String) {
+    let Ok(since) = parse_since(query) else {
+        return ("400 Bad Request", String::new());
+    };
+    let recent = server.recent.read().unwrap();
+    let samples: Vec<_> = match since {
+        Some(since) => recent.since(since).collect(),
+        None => recent.samples().collect(),
+    };
+    match serde_json::to_string(&samples) {
+        Ok(body) => ("200 OK", body),
+        Err(_) => ("500 Internal Server Error", String::new()),
+    }
+}
+
+/// 最新样本的Prometheus文本
+fn metrics(server: &Server) -> (&'static str, String) {
+    match server.recent.read().unwrap().latest() {
+        Some(stats) => ("200 OK", prometheus::render(stats)),
+        None => ("503 Service Unavailable", String::new()),
+    }
+}
+
+/// 查询参数的值
+fn param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+}
+
+/// `POST /control/interval?value=5s`、`POST /control/collectors?enable=net&disable=proc,disk`、
+/// `POST /control/snapshot`、`POST /control/rotate-logs`，交给采集循环执行并等待结果
+async fn control(server: &Server, command: &str, query: &str) -> (&'static str, String) {
+    let error = |message: String| serde_json::json!({ "error": message }).to_string();
+    if server.token.is_none() {
+        return ("403 Forbidden", error(tr("控制接口需要设置 --auth-token").to_string()));
+    }
+    let names = |name| -> Vec<String> {
+        param(query, name).unwrap_or("").split(',').filter(|n| !n.is_empty()).map(str::to_string).collect()
+    };
+    let control = match command {
+        "interval" => match param(query, "value").and_then(parse_duration) {
+            Some(interval) => Control::Interval(interval),
+            None => return ("400 Bad Request", error(format!("{}: {}", tr("无效的时长"), param(query, "value").unwrap_or("")))),
+        },
+        "collectors" => Control::Collectors { enable: names("enable"), disable: names("disable") },
+        "snapshot" => Control::Snapshot,
+        "rotate-logs" => Control::RotateLogs,
+        _ => return ("404 Not Found", String::new()),
+    };
+    let (reply, result) = oneshot::channel();
+    if server.controls.send((control, reply)).await.is_err() {
+        return ("503 Service Unavailable", String::new());
+    }
+    match result.await {
+        Ok(Ok(body)) => ("200 OK", body),
+        Ok(Err(message)) => ("400 Bad Request", error(message)),
+        Err(_) => ("503 Service Unavailable", String::new()),
+    }
+}
+
+/// 替换了主机名和当前语言文字的仪表盘页面
+fn dashboard(server: &Server) -> (&'static str, String) {
+    let host = server.host.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let page = [
+        ("{{host}}", host.as_str()),
+        ("{{cpu}}", tr("平均CPU使用率")),
+        ("{{mem}}", tr("内存")),
+        ("{{net}}", tr("网络接口")),
+        ("{{disk}}", tr("磁盘")),
+        ("{{disconnected}}", tr("连接已断开，正在重连")),
+    ]
+    .iter()
+    .fold(DASHBOARD.to_string(), |page, (placeholder, text)| page.replace(placeholder, text));
+    ("200 OK", page)
+}
+
+/// 保持连接，把之后的每个样本作为一个 `data:` 事件写出，直到客户端断开
+async fn events(mut stream: TcpStream, mut events: broadcast::Receiver<Arc<str>>) -> std::io::Result<()> {
+    let header = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n";
+    stream.write_all(header.as_bytes()).await?;
+    loop {
+        match events.recv().await {
+            Ok(json) => stream.write_all(format!("data: {}\n\n", json).as_bytes()).await?,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return stream.shutdown().await,
+        }
+    }
+}
+
+/// 读取请求头，以及按 `Content-Length` 读取请求体；请求体超过 `max_body` 字节时不读取，返回 `None`
+pub(crate) async fn read_request(stream: &mut TcpStream, max_body: usize) -> std::io::Result<(String, Option<Vec<u8>>)> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    let end = loop {
+        if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if request.len() >= MAX_REQUEST_BYTES {
+            break request.len();
+        }
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break request.len();
+        }
+        request.extend_from_slice(&buf[..n]);
+    };
+    let mut body = request.split_off(end);
+    let head = String::from_utf8_lossy(&request).into_owned();
+    let length = header(&head, "content-length").and_then(|v| v.parse::<usize>().ok()).unwrap_or(0);
+    if length > max_body {
+        return Ok((head, None));
+    }
+    while body.len() < length {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&buf[..n]);
+    }
+    body.truncate(length);
+    Ok((head, Some(body)))
+}
+
+/// 请求头的值，名称不区分大小写
+pub(crate) fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// 写出响应并关闭连接，`denied` 时带上 `WWW-Authenticate`
+pub(crate) async fn respond(mut stream: TcpStream, status: &str, content_type: &str, body: &str, denied: bool) -> std::io::Result<()> {
+    let challenge = if denied { "WWW-Authenticate: Bearer\r\n" } else { "" };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        challenge,
+        body.len(),
+        body
+    );
+    let write = async {
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    };
+    tokio::time::timeout(REQUEST_TIMEOUT, write).await.unwrap_or(Ok(()))
+}
+
+async fn handle(mut stream: TcpStream, server: Arc<Server>) -> std::io::Result<()> {
+    let Ok(request) = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream, 0)).await else {
+        return Ok(());
+    };
+    let (request, _) = request?;
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+
+    let denied = !matches!(path, "/healthz" | "/readyz") && server.token.as_deref().is_some_and(|token| !authorized(&request, query, token));
+    if (method, path) == ("GET", "/events") && !denied {
+        // 只推送之后的样本，之前的从 `/history` 取
+        return events(stream, server.events.subscribe()).await;
+    }
+
+    let content_type = match path {
+        "/" => "text/html; charset=utf-8",
+        "/metrics" => "text/plain; version=0.0.4",
+        "/healthz" | "/readyz" => "text/plain",
+        _ => "application/json",
+    };
+    let latest = server.latest.read().map(|l| l.clone()).unwrap_or(None);
+    let (status, body) = match (method, path, latest) {
+        _ if denied => ("401 Unauthorized", String::new()),
+        ("GET", "/stats", Some(body)) => ("200 OK", body),
+        ("GET", "/stats", None) => ("503 Service Unavailable", String::new()),
+        ("GET", "/history", _) => history(&server, query),
+        ("GET", "/metrics", _) => metrics(&server),
+        ("GET", "/", _) => dashboard(&server),
+        ("GET", "/healthz" | "/readyz", _) => server.health.response(path).unwrap(),
+        ("POST", path, _) if path.starts_with("/control/") => control(&server, &path["/control/".len()..], query).await,
+        ("GET", _, _) => ("404 Not Found", String::new()),
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+    respond(stream, status, content_type, &body, denied).await
+}
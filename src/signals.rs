@@ -0,0 +1,75 @@
+// src/signals.rs
+use std::io;
+
+/// 要求退出或重新加载配置的信号，在循环之前创建，采集或发送期间收到的信号也不会丢失
+pub enum Signal {
+    #[cfg(unix)]
+    Unix(tokio::signal::unix::Signal),
+    #[cfg(windows)]
+    Interrupt(tokio::signal::windows::CtrlC),
+    /// 关闭控制台窗口或系统关机
+    #[cfg(windows)]
+    Close(tokio::signal::windows::CtrlClose, tokio::signal::windows::CtrlShutdown),
+    /// 当前平台上没有对应的信号
+    #[cfg(windows)]
+    Never,
+}
+
+impl Signal {
+    pub async fn recv(&mut self) -> Option<()> {
+        match self {
+            #[cfg(unix)]
+            Signal::Unix(signal) => signal.recv().await,
+            #[cfg(windows)]
+            Signal::Interrupt(signal) => signal.recv().await,
+            #[cfg(windows)]
+            Signal::Close(close, shutdown) => tokio::select! {
+                received = close.recv() => received,
+                received = shutdown.recv() => received,
+            },
+            #[cfg(windows)]
+            Signal::Never => std::future::pending().await,
+        }
+    }
+}
+
+/// 按下 Ctrl+C：Unix上为 SIGINT
+#[cfg(unix)]
+pub fn interrupt() -> io::Result<Signal> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    signal(SignalKind::interrupt()).map(Signal::Unix)
+}
+
+#[cfg(windows)]
+pub fn interrupt() -> io::Result<Signal> {
+    tokio::signal::windows::ctrl_c().map(Signal::Interrupt)
+}
+
+/// 要求正常退出：Unix上为 SIGTERM，Windows上为关闭控制台窗口或系统关机
+#[cfg(unix)]
+pub fn terminate() -> io::Result<Signal> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    signal(SignalKind::terminate()).map(Signal::Unix)
+}
+
+#[cfg(windows)]
+pub fn terminate() -> io::Result<Signal> {
+    use tokio::signal::windows::{ctrl_close, ctrl_shutdown};
+
+    Ok(Signal::Close(ctrl_close()?, ctrl_shutdown()?))
+}
+
+/// 要求重新加载配置：Unix上为 SIGHUP，Windows上没有对应的信号，需要重启
+#[cfg(unix)]
+pub fn hangup() -> io::Result<Signal> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    signal(SignalKind::hangup()).map(Signal::Unix)
+}
+
+#[cfg(windows)]
+pub fn hangup() -> io::Result<Signal> {
+    Ok(Signal::Never)
+}
@@ -0,0 +1,225 @@
+// src/spool.rs
+use crate::crypto::Cipher;
+use crate::i18n::tr;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const SEGMENT_MAX_BYTES: u64 = 1024 * 1024;
+const SEGMENT_EXT: &str = "jsonl";
+const STATE_FILE: &str = "state.json";
+
+/// 跨重启保存的发送状态
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SpoolState {
+    /// 已产生的最大样本序号
+    pub seq: u64,
+    /// 接收端已确认的最大样本序号
+    pub acked: u64,
+}
+
+/// 缓存已满时的原因：达到容量上限(开始丢弃最旧的样本)，或者所在磁盘的剩余空间不足一个分段；`bytes` 为缓存当前的大小
+pub fn full(dir: &Path, bytes: u64, max_bytes: u64) -> Option<String> {
+    if bytes >= max_bytes {
+        return Some(tr("缓存已达到容量上限，正在丢弃旧样本").to_string());
+    }
+    match available(dir) {
+        Ok(available) if available < SEGMENT_MAX_BYTES => Some(tr("缓存所在的磁盘空间不足").to_string()),
+        Ok(_) => None,
+        Err(e) => Some(format!("{} {}: {}", tr("无法读取缓存目录"), dir.display(), e)),
+    }
+}
+
+/// 目录所在文件系统中非特权用户可用的字节数
+#[cfg(unix)]
+fn available(dir: &Path) -> io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// 目录所在磁盘中当前用户可用的字节数
+#[cfg(windows)]
+fn available(dir: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let path: Vec<u16> = dir.as_os_str().encode_wide().chain([0]).collect();
+    let mut available = 0;
+    if unsafe { GetDiskFreeSpaceExW(path.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut()) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+/// 发送失败时的磁盘缓存，按顺序存放为若干JSONL分段文件；设置了密钥时每行加密存放
+pub struct Spool {
+    dir: PathBuf,
+    max_bytes: u64,
+    next_id: u64,
+    cipher: Option<Cipher>,
+}
+
+impl Spool {
+    pub fn open(dir: &Path, max_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let mut spool = Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            next_id: 0,
+            cipher: None,
+        };
+        spool.next_id = spool.segments()?
+            .last()
+            .and_then(|path| Self::segment_id(path))
+            .map_or(0, |id| id + 1);
+
+        Ok(spool)
+    }
+
+    /// 之后写入的样本用 `cipher` 加密；已有的明文样本照常读取和发送
+    pub fn cipher(mut self, cipher: Option<Cipher>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    pub fn load_state(&self) -> SpoolState {
+        fs::read_to_string(self.dir.join(STATE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save_state(&self, state: &SpoolState) -> io::Result<()> {
+        let tmp = self.dir.join(format!("{}.tmp", STATE_FILE));
+        fs::write(&tmp, serde_json::to_string(state)?)?;
+        fs::rename(tmp, self.dir.join(STATE_FILE))
+    }
+
+    fn segment_id(path: &Path) -> Option<u64> {
+        path.file_stem()?.to_str()?.parse().ok()
+    }
+
+    fn segments(&self) -> io::Result<Vec<PathBuf>> {
+        let mut segments: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == SEGMENT_EXT))
+            .filter(|path| Self::segment_id(path).is_some())
+            .collect();
+        segments.sort_by_key(|path| Self::segment_id(path));
+        Ok(segments)
+    }
+
+    /// 全部分段文件的总字节数
+    pub fn size(&self) -> io::Result<u64> {
+        let mut total = 0;
+        for segment in self.segments()? {
+            total += fs::metadata(segment)?.len();
+        }
+        Ok(total)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments().map(|s| s.is_empty()).unwrap_or(true)
+    }
+
+    /// 按顺序读取缓存的全部请求体，不删除；有无法解密的样本时返回错误
+    pub fn bodies(&self) -> io::Result<Vec<String>> {
+        let mut bodies = Vec::new();
+        for segment in self.segments()? {
+            let content = fs::read_to_string(&segment)?;
+            for line in content.lines().filter(|l| !l.is_empty()) {
+                bodies.push(self.decode(line)?);
+            }
+        }
+        Ok(bodies)
+    }
+
+    /// 追加一条样本，超过容量上限时丢弃最旧的分段
+    pub fn push(&mut self, line: &str) -> io::Result<()> {
+        let path = match self.segments()?.pop() {
+            Some(last) if fs::metadata(&last)?.len() < SEGMENT_MAX_BYTES => last,
+            _ => {
+                let path = self.dir.join(format!("{:020}.{}", self.next_id, SEGMENT_EXT));
+                self.next_id += 1;
+                path
+            }
+        };
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        match &self.cipher {
+            Some(cipher) => writeln!(file, "{}", cipher.seal(line))?,
+            None => writeln!(file, "{}", line)?,
+        }
+
+        let mut size = self.size()?;
+        for segment in self.segments()? {
+            if size <= self.max_bytes || segment == path {
+                break;
+            }
+            size -= fs::metadata(&segment)?.len();
+            fs::remove_file(&segment)?;
+            tracing::warn!(segment = %segment.display(), "{}", tr("缓存已满，丢弃"));
+        }
+
+        Ok(())
+    }
+
+    /// 按顺序发送缓存的样本，遇到失败时保留剩余部分并返回 false；
+    /// 遇到无法解密的样本时停止发送并返回错误，这个样本和之后的样本都保留在缓存中
+    pub async fn drain<F, Fut>(&mut self, mut send: F) -> io::Result<bool>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: std::future::Future<Output = bool>,
+    {
+        for segment in self.segments()? {
+            let content = fs::read_to_string(&segment)?;
+            let lines: Vec<&str> = content.lines().filter(|l| !l.is_empty()).collect();
+
+            for (i, line) in lines.iter().enumerate() {
+                let body = match self.decode(line) {
+                    Ok(body) => body,
+                    Err(e) => {
+                        Self::rewrite(&segment, &lines[i..])?;
+                        return Err(e);
+                    }
+                };
+                if !send(body).await {
+                    Self::rewrite(&segment, &lines[i..])?;
+                    return Ok(false);
+                }
+            }
+
+            fs::remove_file(&segment)?;
+        }
+
+        Ok(true)
+    }
+
+    /// 只保留分段中还没有发送的行：先写入临时文件再替换，中途退出时分段仍然完整
+    fn rewrite(segment: &Path, lines: &[&str]) -> io::Result<()> {
+        let mut rest = lines.join("\n");
+        rest.push('\n');
+        let tmp = segment.with_extension(format!("{}.tmp", SEGMENT_EXT));
+        fs::write(&tmp, rest)?;
+        fs::rename(tmp, segment)
+    }
+
+    /// 缓存中的一行还原为请求体：明文的JSON原样返回，其余的解密；无法解密(没有设置密钥或密钥不对)时返回错误
+    fn decode(&self, line: &str) -> io::Result<String> {
+        if line.starts_with(['{', '[']) {
+            return Ok(line.to_string());
+        }
+        self.cipher.as_ref().and_then(|cipher| cipher.open(line)).ok_or_else(|| {
+            let message = format!("{} {}", tr("无法解密缓存中的样本，请检查 --spool-key"), self.dir.display());
+            io::Error::new(io::ErrorKind::InvalidData, message)
+        })
+    }
+}
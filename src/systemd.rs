@@ -0,0 +1,143 @@
+// src/systemd.rs
+#[cfg(target_os = "linux")]
+use std::os::linux::net::SocketAddrExt;
+#[cfg(unix)]
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// 以 `Type=notify` 运行时通过 `$NOTIFY_SOCKET` 向systemd报告状态，并按 `$WATCHDOG_USEC` 发送看门狗心跳
+pub struct Notifier {
+    socket: Option<Socket>,
+    watchdog: Option<Duration>,
+    last_ping: Option<Instant>,
+    ready: bool,
+}
+
+impl Notifier {
+    /// 不是由systemd启动(没有 `$NOTIFY_SOCKET`)时所有操作都不做任何事
+    pub fn from_env() -> Self {
+        let socket = std::env::var("NOTIFY_SOCKET").ok().and_then(|path| notify_socket(&path));
+        // WATCHDOG_PID 指定了其他进程时心跳不属于本进程
+        let own = std::env::var("WATCHDOG_PID").map_or(true, |pid| pid == std::process::id().to_string());
+        let watchdog = std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse().ok())
+            .filter(|_| own)
+            .map(Duration::from_micros);
+
+        Self { socket, watchdog, last_ping: None, ready: false }
+    }
+
+    pub fn watchdog(&self) -> Option<Duration> {
+        self.watchdog
+    }
+
+    #[cfg(unix)]
+    fn notify(&self, state: &str) {
+        if let Some((socket, addr)) = &self.socket {
+            let _ = socket.send_to_addr(state.as_bytes(), addr);
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn notify(&self, _state: &str) {
+        if let Some(socket) = &self.socket {
+            match *socket {}
+        }
+    }
+
+    /// 完成了一次采集：第一次时报告就绪，之后每隔看门狗超时的一半发送一次心跳
+    pub fn collected(&mut self) {
+        if !self.ready {
+            self.ready = true;
+            self.notify("READY=1");
+        }
+        if let Some(watchdog) = self.watchdog {
+            if self.last_ping.is_none_or(|last| last.elapsed() >= watchdog / 2) {
+                self.last_ping = Some(Instant::now());
+                self.notify("WATCHDOG=1");
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+type Socket = (UnixDatagram, SocketAddr);
+
+/// 没有Unix套接字的平台上不会由systemd启动
+#[cfg(not(unix))]
+type Socket = std::convert::Infallible;
+
+/// `$NOTIFY_SOCKET` 为路径，或以 `@` 开头的抽象套接字名(只有Linux支持)
+#[cfg(unix)]
+fn notify_socket(path: &str) -> Option<Socket> {
+    let addr = match path.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        Some(name) => SocketAddr::from_abstract_name(name.as_bytes()).ok()?,
+        #[cfg(not(target_os = "linux"))]
+        Some(_) => return None,
+        None => SocketAddr::from_pathname(path).ok()?,
+    };
+    Some((UnixDatagram::unbound().ok()?, addr))
+}
+
+#[cfg(not(unix))]
+fn notify_socket(_path: &str) -> Option<Socket> {
+    None
+}
+
+impl Drop for Notifier {
+    fn drop(&mut self) {
+        self.notify("STOPPING=1");
+    }
+}
+
+/// 生成加固的systemd服务文件，看门狗超时取刷新间隔的3倍且不少于30秒
+pub fn unit(exe: &Path, config: Option<&Path>, interval: Duration) -> String {
+    let mut exec_start = format!("{} --no-display", exe.display());
+    if let Some(config) = config {
+        exec_start.push_str(&format!(" --config {}", config.display()));
+    }
+    let watchdog = (interval * 3).as_secs().max(30);
+
+    format!(
+        "[Unit]
+Description=System resource monitor
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+Type=notify
+NotifyAccess=main
+ExecStart={exec_start}
+ExecReload=/bin/kill -HUP $MAINPID
+Restart=on-failure
+RestartSec=5
+WatchdogSec={watchdog}
+DynamicUser=yes
+StateDirectory=system_monitor
+NoNewPrivileges=yes
+ProtectSystem=strict
+ProtectHome=read-only
+PrivateTmp=yes
+PrivateDevices=yes
+ProtectKernelTunables=yes
+ProtectKernelModules=yes
+ProtectControlGroups=yes
+ProtectClock=yes
+ProtectHostname=yes
+RestrictAddressFamilies=AF_UNIX AF_INET AF_INET6 AF_NETLINK
+RestrictNamespaces=yes
+RestrictRealtime=yes
+RestrictSUIDSGID=yes
+LockPersonality=yes
+MemoryDenyWriteExecute=yes
+SystemCallArchitectures=native
+CapabilityBoundingSet=
+
+[Install]
+WantedBy=multi-user.target
+"
+    )
+}
@@ -0,0 +1,365 @@
+// src/talkers.rs
+use crate::collector::{Collector, Context, Section};
+use crate::error::Error;
+use crate::i18n::tr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Instant;
+use sysinfo::{System, Uid};
+
+/// 采集项的名称
+pub const NAME: &str = "talkers";
+
+/// 编译时是否启用了 `ebpf` 特性，只在Linux上可用
+pub const AVAILABLE: bool = cfg!(all(feature = "ebpf", target_os = "linux"));
+
+/// `processes` 和 `ports` 中最多列出的条数
+const MAX_TOP: usize = 10;
+
+/// 按进程和对端端口统计的网络流量(字节/秒)，收发最多的在前
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetTalkers {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub processes: Vec<ProcessTraffic>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<PortTraffic>,
+}
+
+impl NetTalkers {
+    /// 汇总窗口中的各次统计：各进程和端口的速率取平均值，没有出现的样本按0计
+    pub fn merge(samples: &[&NetTalkers]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let count = samples.len() as u64;
+        let mut processes: HashMap<(u32, &str), (u64, u64)> = HashMap::new();
+        let mut ports: HashMap<u16, (u64, u64)> = HashMap::new();
+        for talkers in samples {
+            for process in &talkers.processes {
+                let (rx, tx) = processes.entry((process.pid, &process.name)).or_default();
+                *rx += process.rx;
+                *tx += process.tx;
+            }
+            for port in &talkers.ports {
+                let (rx, tx) = ports.entry(port.port).or_default();
+                *rx += port.rx;
+                *tx += port.tx;
+            }
+        }
+        let mut merged = Self {
+            processes: processes
+                .into_iter()
+                .map(|((pid, name), (rx, tx))| ProcessTraffic { pid, name: name.to_string(), rx: rx / count, tx: tx / count })
+                .collect(),
+            ports: ports.into_iter().map(|(port, (rx, tx))| PortTraffic { port, rx: rx / count, tx: tx / count }).collect(),
+        };
+        merged.sort();
+        Some(merged)
+    }
+
+    /// 收发合计最多的在前，各保留 [`MAX_TOP`] 条
+    fn sort(&mut self) {
+        self.processes.sort_by(|a, b| (b.rx + b.tx).cmp(&(a.rx + a.tx)).then(a.pid.cmp(&b.pid)));
+        self.processes.truncate(MAX_TOP);
+        self.ports.sort_by(|a, b| (b.rx + b.tx).cmp(&(a.rx + a.tx)).then(a.port.cmp(&b.port)));
+        self.ports.truncate(MAX_TOP);
+    }
+}
+
+/// 一个进程的收发速率
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessTraffic {
+    pub pid: u32,
+    /// 内核中的程序名，最多15个字符
+    pub name: String,
+    pub rx: u64,
+    pub tx: u64,
+}
+
+/// 一个对端端口的收发速率，TCP和UDP合计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortTraffic {
+    pub port: u16,
+    pub rx: u64,
+    pub tx: u64,
+}
+
+/// 一个套接字累计的流量，由eBPF程序按套接字cookie记录
+#[cfg_attr(not(all(feature = "ebpf", target_os = "linux")), allow(dead_code))]
+struct Socket {
+    pid: u32,
+    uid: u32,
+    comm: String,
+    /// 对端端口，没有连接的套接字(监听的套接字等)为0
+    port: u16,
+    local_port: u16,
+    rx: u64,
+    tx: u64,
+}
+
+/// 套接字所属的进程
+#[derive(Clone)]
+struct Owner {
+    pid: u32,
+    uid: u32,
+    name: String,
+}
+
+/// 在cgroup v2的根上挂载eBPF程序，统计每个套接字收发的字节数，并记下创建套接字的进程，
+/// 按进程和对端端口给出收发最多的几项。只在Linux上可用，需要以 `--features ebpf` 编译，运行时需要root或 `CAP_BPF` 和 `CAP_NET_ADMIN`；
+/// 无法加载时记录一条警告，样本中没有 `talkers`
+pub struct Talkers {
+    #[cfg(all(feature = "ebpf", target_os = "linux"))]
+    probe: Option<sysmon_bpf::Probe>,
+    /// 上次采集时各套接字的累计字节数(收, 发)
+    last: HashMap<u64, (u64, u64)>,
+    /// 采集时查到的接受的连接和挂载之前创建的套接字所属的进程，查不到的(已经关闭的套接字)为 None
+    owners: HashMap<u64, Option<Owner>>,
+    since: Instant,
+}
+
+impl Talkers {
+    #[cfg(all(feature = "ebpf", target_os = "linux"))]
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self { probe: None, last: HashMap::new(), owners: HashMap::new(), since: Instant::now() })
+    }
+
+    #[cfg(not(all(feature = "ebpf", target_os = "linux")))]
+    pub fn new() -> Result<Self, Error> {
+        Err(Error::config(tr("--net-talkers 需要在Linux上以 --features ebpf 编译")))
+    }
+
+    #[cfg(all(feature = "ebpf", target_os = "linux"))]
+    fn sockets(&self) -> Option<HashMap<u64, Socket>> {
+        let sockets = self.probe.as_ref()?.sockets();
+        Some(
+            sockets
+                .into_iter()
+                .map(|(cookie, socket)| {
+                    let sysmon_bpf::Socket { pid, uid, comm, port, local_port, rx, tx } = socket;
+                    (cookie, Socket { pid, uid, comm, port, local_port, rx, tx })
+                })
+                .collect(),
+        )
+    }
+
+    #[cfg(not(all(feature = "ebpf", target_os = "linux")))]
+    fn sockets(&self) -> Option<HashMap<u64, Socket>> {
+        None
+    }
+
+    #[cfg(all(feature = "ebpf", target_os = "linux"))]
+    fn resolve(cookies: &[u64]) -> HashMap<u64, Option<Owner>> {
+        diag::owners(cookies)
+    }
+
+    #[cfg(not(all(feature = "ebpf", target_os = "linux")))]
+    fn resolve(cookies: &[u64]) -> HashMap<u64, Option<Owner>> {
+        cookies.iter().map(|cookie| (*cookie, None)).collect()
+    }
+}
+
+impl Collector for Talkers {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn init(&mut self, _system: &mut System) {
+        #[cfg(all(feature = "ebpf", target_os = "linux"))]
+        match sysmon_bpf::Probe::attach() {
+            Ok(probe) => {
+                tracing::info!(cgroup = %probe.cgroup(), "{}", tr("已挂载eBPF程序，按进程统计网络流量"));
+                self.probe = Some(probe);
+            }
+            Err(error) => tracing::warn!(error = %describe(&error), "{}", tr("无法加载eBPF程序，不按进程统计网络流量")),
+        }
+        self.since = Instant::now();
+    }
+
+    fn collect(&mut self, ctx: &mut Context) -> Section {
+        let Some(sockets) = self.sockets() else { return Section::Unavailable };
+        let elapsed = std::mem::replace(&mut self.since, Instant::now()).elapsed().as_secs_f64().max(1e-3);
+        let rate = |bytes: u64| (bytes as f64 / elapsed) as u64;
+
+        let mut traffic = Vec::new();
+        let mut listeners = Vec::new();
+        let mut last = HashMap::with_capacity(sockets.len());
+        for (cookie, socket) in sockets {
+            if socket.port == 0 && socket.local_port != 0 {
+                listeners.push((cookie, socket.local_port, socket.pid, socket.uid, socket.comm.clone()));
+            }
+            // 第一次看到的套接字按挂载或上次采集以来的全部流量计
+            let (rx_before, tx_before) = self.last.get(&cookie).copied().unwrap_or_default();
+            let (rx, tx) = (socket.rx.saturating_sub(rx_before), socket.tx.saturating_sub(tx_before));
+            last.insert(cookie, (socket.rx, socket.tx));
+            if rx > 0 || tx > 0 {
+                traffic.push((cookie, socket, rx, tx));
+            }
+        }
+        self.last = last;
+        self.owners.retain(|cookie, _| self.last.contains_key(cookie));
+        let unknown: Vec<u64> = traffic
+            .iter()
+            .filter(|(cookie, socket, ..)| socket.pid == 0 && !self.owners.contains_key(cookie))
+            .map(|(cookie, ..)| *cookie)
+            .collect();
+        if !unknown.is_empty() {
+            self.owners.extend(Self::resolve(&unknown));
+        }
+        // 查询之前已经关闭的接受的连接归属于在同一个端口上监听的进程
+        let listeners: HashMap<u16, Owner> = listeners
+            .into_iter()
+            .filter_map(|(cookie, port, pid, uid, name)| match pid {
+                0 => Some((port, self.owners.get(&cookie).cloned().flatten()?)),
+                pid => Some((port, Owner { pid, uid, name })),
+            })
+            .collect();
+
+        let mut processes: HashMap<(u32, String), (u64, u64)> = HashMap::new();
+        let mut ports: HashMap<u16, (u64, u64)> = HashMap::new();
+        for (cookie, socket, rx, tx) in traffic {
+            let owner = match socket.pid {
+                0 => self.owners.get(&cookie).cloned().flatten().or_else(|| listeners.get(&socket.local_port).cloned()),
+                pid => Some(Owner { pid, uid: socket.uid, name: socket.comm }),
+            };
+            let name = owner.as_ref().map_or("", |owner| owner.name.as_str());
+            let user = owner.as_ref().and_then(|owner| owner.uid.to_string().parse::<Uid>().ok());
+            if !ctx.process_filter.name.matches(name)
+                || (!ctx.process_uids.is_empty() && !user.as_ref().is_some_and(|uid| ctx.process_uids.contains(uid)))
+            {
+                continue;
+            }
+            // 仍然查不到所属进程的套接字只计入端口
+            if let Some(owner) = owner {
+                let (total_rx, total_tx) = processes.entry((owner.pid, owner.name)).or_default();
+                *total_rx += rx;
+                *total_tx += tx;
+            }
+            if socket.port != 0 {
+                let (total_rx, total_tx) = ports.entry(socket.port).or_default();
+                *total_rx += rx;
+                *total_tx += tx;
+            }
+        }
+
+        let mut talkers = NetTalkers {
+            processes: processes
+                .into_iter()
+                .map(|((pid, name), (rx, tx))| ProcessTraffic { pid, name, rx: rate(rx), tx: rate(tx) })
+                .collect(),
+            ports: ports.into_iter().map(|(port, (rx, tx))| PortTraffic { port, rx: rate(rx), tx: rate(tx) }).collect(),
+        };
+        talkers.sort();
+        Section::Talkers(talkers)
+    }
+}
+
+/// 翻译无法挂载的原因
+#[cfg(all(feature = "ebpf", target_os = "linux"))]
+fn describe(error: &sysmon_bpf::Error) -> String {
+    match error {
+        sysmon_bpf::Error::Io(error) => error.to_string(),
+        sysmon_bpf::Error::Rejected { program, error, reason } => format!("{} {}: {} {}", tr("eBPF程序被拒绝"), program, error, reason),
+        sysmon_bpf::Error::NoCgroup2 => tr("没有挂载cgroup v2").to_string(),
+    }
+}
+
+/// 通过sock_diag(inet_diag)查询套接字所属的进程；eBPF程序本身在 `sysmon-bpf` 中
+#[cfg(all(feature = "ebpf", target_os = "linux"))]
+mod diag {
+    use super::Owner;
+    use crate::churn::netlink;
+    use crate::i18n::tr;
+    use socket2::{Domain, Protocol, Socket, Type};
+    use std::collections::HashMap;
+    use std::io::{self, Read};
+
+    /// 通过sock_diag取得套接字的inode和uid，再在 `/proc/*/fd` 中找到打开它的进程，与 `ss -p` 相同；
+    /// 查不到的套接字为 None
+    pub(super) fn owners(cookies: &[u64]) -> HashMap<u64, Option<Owner>> {
+        let mut owners: HashMap<u64, Option<Owner>> = cookies.iter().map(|cookie| (*cookie, None)).collect();
+        let mut inodes = HashMap::new();
+        for family in [libc::AF_INET, libc::AF_INET6] {
+            for protocol in [libc::IPPROTO_TCP, libc::IPPROTO_UDP] {
+                if let Err(error) = dump(family as u8, protocol as u8, &owners, &mut inodes) {
+                    tracing::debug!(%error, "{}", tr("无法查询套接字"));
+                }
+            }
+        }
+        if inodes.is_empty() {
+            return owners;
+        }
+
+        let Ok(processes) = std::fs::read_dir("/proc") else { return owners };
+        for process in processes.filter_map(Result::ok) {
+            let Some(pid) = process.file_name().to_str().and_then(|pid| pid.parse::<u32>().ok()) else { continue };
+            let Ok(fds) = std::fs::read_dir(process.path().join("fd")) else { continue };
+            for fd in fds.filter_map(Result::ok) {
+                let Ok(link) = std::fs::read_link(fd.path()) else { continue };
+                let Some(inode) = link.to_str().and_then(|link| link.strip_prefix("socket:[")?.strip_suffix(']')?.parse::<u32>().ok())
+                else {
+                    continue;
+                };
+                let Some((cookie, uid)) = inodes.remove(&inode) else { continue };
+                let name = std::fs::read_to_string(process.path().join("comm")).unwrap_or_default().trim_end().to_string();
+                owners.insert(cookie, Some(Owner { pid, uid, name }));
+            }
+            if inodes.is_empty() {
+                break;
+            }
+        }
+        owners
+    }
+
+    const SOCK_DIAG_BY_FAMILY: u16 = 20;
+    const NLM_F_REQUEST: u16 = 1;
+    const NLM_F_DUMP: u16 = 0x300;
+    const NLMSG_ERROR: u16 = 2;
+    const NLMSG_DONE: u16 = 3;
+
+    /// `struct inet_diag_msg` 中的字段
+    const COOKIE: usize = 44;
+    const UID: usize = 64;
+    const INODE: usize = 68;
+
+    /// inet_diag：按地址族和协议列出全部套接字，把 `wanted` 中的套接字的inode和uid写入 `inodes`，以inode为键
+    fn dump<T>(family: u8, protocol: u8, wanted: &HashMap<u64, T>, inodes: &mut HashMap<u32, (u64, u32)>) -> io::Result<()> {
+        let socket = Socket::new(Domain::from(libc::AF_NETLINK), Type::RAW, Some(Protocol::from(libc::NETLINK_SOCK_DIAG)))?;
+        socket.set_read_timeout(Some(std::time::Duration::from_secs(1)))?;
+
+        // nlmsghdr 和 inet_diag_req_v2：全部状态，sockid留空
+        let mut request = Vec::with_capacity(72);
+        request.extend_from_slice(&72u32.to_ne_bytes());
+        request.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+        request.extend_from_slice(&(NLM_F_REQUEST | NLM_F_DUMP).to_ne_bytes());
+        request.extend_from_slice(&1u32.to_ne_bytes());
+        request.extend_from_slice(&0u32.to_ne_bytes());
+        request.extend_from_slice(&[family, protocol, 0, 0]);
+        request.extend_from_slice(&u32::MAX.to_ne_bytes());
+        request.resize(72, 0);
+        socket.send(&request)?;
+
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = (&socket).read(&mut buf)?;
+            for (kind, payload) in netlink::messages(&buf[..n]) {
+                match kind {
+                    NLMSG_DONE => return Ok(()),
+                    NLMSG_ERROR => return netlink::error(kind, payload),
+                    _ => {}
+                }
+                let field = |at: usize| Some(u32::from_ne_bytes(payload.get(at..at + 4)?.try_into().ok()?));
+                let (Some(low), Some(high), Some(uid), Some(inode)) = (field(COOKIE), field(COOKIE + 4), field(UID), field(INODE)) else {
+                    continue;
+                };
+                let cookie = low as u64 | (high as u64) << 32;
+                if wanted.contains_key(&cookie) && inode != 0 {
+                    inodes.insert(inode, (cookie, uid));
+                }
+            }
+            if n == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,672 @@
+// src/template.rs
+use crate::i18n::tr;
+use serde_json::Value;
+
+/// Handlebars语法的一个子集，把样本渲染成任意的文本格式(自定义的JSON、XML、旧系统的行协议)。支持的写法：
+///
+/// - `{{path}}` 输出字段并转义 `&<>"'`，`{{{path}}}` 原样输出；没有的字段和 null 输出为空，对象和数组输出为JSON
+/// - 路径：`mem.used`、`cpu.0`、`disk.[/].used`(`[...]` 中可以有 `.`、`/` 和空格)、`this`、`../`、`@root.`，
+///   以及 `#each` 中的 `@key`、`@index`、`@first`、`@last`
+/// - 块：`{{#if}}`、`{{#unless}}`、`{{#each}}`(对象或数组)、`{{#with}}`，都可以带一个 `{{else}}`；
+///   false、null、0、空字符串和空数组为假，空对象为真
+/// - 助手 `json`、`unix`、`round`、`percent`，参数为路径或字面量(字符串、数字、`true`、`false`、`null`)
+/// - 注释 `{{! ... }}` 和 `{{!-- ... --}}`，`{{~`、`~}}` 去掉空白，单独占一行的块标签和注释整行不输出
+///
+/// 不支持：局部模板(`{{> name}}`)、自定义助手、子表达式(`(helper ...)`)、`key=value` 参数、`{{else if}}`、
+/// 块参数(`as |item|`)和 `{{{{raw}}}}` 块；不转义 `` ` `` 和 `=`，输出用在HTML属性中时需要加引号
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Output { expr: Expr, escape: bool },
+    Block { kind: Block, expr: Expr, body: Vec<Node>, otherwise: Vec<Node> },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Block {
+    If,
+    Unless,
+    Each,
+    With,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Path(Path),
+    Literal(Value),
+    Helper(Helper, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Helper {
+    /// `json VALUE`：JSON编码，字符串带引号
+    Json,
+    /// `unix TIMESTAMP`：RFC 3339时间转成Unix时间戳(秒)
+    Unix,
+    /// `round VALUE [DIGITS]`：保留DIGITS位小数，默认取整
+    Round,
+    /// `percent PART TOTAL [DIGITS]`：PART占TOTAL的百分比，默认保留1位小数
+    Percent,
+}
+
+impl Helper {
+    fn parse(name: &str) -> Option<(Self, std::ops::RangeInclusive<usize>)> {
+        match name {
+            "json" => Some((Helper::Json, 1..=1)),
+            "unix" => Some((Helper::Unix, 1..=1)),
+            "round" => Some((Helper::Round, 1..=2)),
+            "percent" => Some((Helper::Percent, 2..=3)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Path {
+    /// 先上溯 `up` 层(`../`)再依次取字段；`root` 时从样本开始(`@root.`)
+    Field { root: bool, up: usize, segments: Vec<String> },
+    /// `@key`、`@index`、`@first`、`@last`，取最近的 `#each`
+    Data(String),
+}
+
+struct Tag {
+    content: String,
+    /// `{{{...}}}`
+    raw: bool,
+    /// `{{~` 和 `~}}`：去掉标签前面或后面的空白
+    trim_before: bool,
+    trim_after: bool,
+    line: usize,
+}
+
+enum Token {
+    Text(String),
+    Tag(Tag),
+}
+
+impl Tag {
+    /// 块标签、`{{else}}` 和注释单独占一行时，整行(包括换行符)都不输出
+    fn standalone(&self) -> bool {
+        !self.raw && (self.content.starts_with(['#', '/', '!']) || self.content == "else")
+    }
+}
+
+enum End {
+    Eof,
+    Else(usize),
+    Close(String, usize),
+}
+
+fn error(line: usize, message: impl std::fmt::Display) -> String {
+    format!("{} {}: {}", tr("行"), line, message)
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut tokens = tokenize(source)?;
+        trim_whitespace(&mut tokens);
+        let mut tokens = tokens.into_iter();
+        match parse_nodes(&mut tokens)? {
+            (nodes, End::Eof) => Ok(Self { nodes }),
+            (_, End::Else(line)) => Err(error(line, tr("多余的 {{else}}"))),
+            (_, End::Close(name, line)) => Err(error(line, format!("{}: {{{{/{}}}}}", tr("多余的结束标签"), name))),
+        }
+    }
+
+    pub fn render(&self, sample: &Value) -> String {
+        let mut out = String::new();
+        let mut frames = vec![Frame::new(sample.clone())];
+        render(&self.nodes, &mut frames, &mut out);
+        out
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        line += rest[..start].matches('\n').count();
+        let after = &rest[start + 2..];
+        let (raw, after) = match after.strip_prefix('{') {
+            Some(after) => (true, after),
+            None => (false, after),
+        };
+        let close = if raw { "}}}" } else { "}}" };
+        // `{{!-- ... --}}` 中可以出现 `}}`
+        let end = if !raw && after.trim_start_matches('~').starts_with("!--") {
+            after.find("--}}").map(|i| i + 2)
+        } else {
+            after.find(close)
+        };
+        let end = end.ok_or_else(|| error(line, tr("标签没有结束")))?;
+        let inner = &after[..end];
+        let (trim_before, inner) = inner.strip_prefix('~').map_or((false, inner), |inner| (true, inner));
+        let (trim_after, inner) = inner.strip_suffix('~').map_or((false, inner), |inner| (true, inner));
+        tokens.push(Token::Tag(Tag { content: inner.trim().to_string(), raw, trim_before, trim_after, line }));
+        line += inner.matches('\n').count();
+        rest = &after[end + close.len()..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+/// 去掉单独占一行的块标签所在的行，以及 `~` 标记的空白
+fn trim_whitespace(tokens: &mut [Token]) {
+    fn text(token: Option<&Token>) -> Option<&str> {
+        match token {
+            Some(Token::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+    // 先按原文判断，再统一修改，相邻的两个块标签共用中间的换行
+    let standalone: Vec<usize> = (0..tokens.len())
+        .filter(|&i| matches!(&tokens[i], Token::Tag(tag) if tag.standalone()))
+        .filter(|&i| {
+            let before = match i.checked_sub(1) {
+                None => true,
+                Some(j) => text(tokens.get(j)).is_some_and(|text| {
+                    let tail = text.rsplit_once('\n').map_or(text, |(_, tail)| tail);
+                    tail.trim_matches([' ', '\t']).is_empty() && (j == 0 || text.contains('\n'))
+                }),
+            };
+            let after = match tokens.get(i + 1) {
+                None => true,
+                Some(token) => text(Some(token)).is_some_and(|text| {
+                    let head = text.split_once('\n').map_or(text, |(head, _)| head);
+                    head.trim_matches([' ', '\t', '\r']).is_empty() && (text.contains('\n') || i + 2 == tokens.len())
+                }),
+            };
+            before && after
+        })
+        .collect();
+    for i in standalone {
+        if let Some(Token::Text(text)) = i.checked_sub(1).map(|j| &mut tokens[j]) {
+            text.truncate(text.rfind('\n').map_or(0, |n| n + 1));
+        }
+        if let Some(Token::Text(text)) = tokens.get_mut(i + 1) {
+            text.drain(..text.find('\n').map_or(text.len(), |n| n + 1));
+        }
+    }
+
+    for i in 0..tokens.len() {
+        let Token::Tag(tag) = &tokens[i] else { continue };
+        let (trim_before, trim_after) = (tag.trim_before, tag.trim_after);
+        if let Some(Token::Text(text)) = i.checked_sub(1).filter(|_| trim_before).map(|j| &mut tokens[j]) {
+            text.truncate(text.trim_end().len());
+        }
+        if let Some(Token::Text(text)) = tokens.get_mut(i + 1).filter(|_| trim_after) {
+            text.drain(..text.len() - text.trim_start().len());
+        }
+    }
+}
+
+fn parse_nodes(tokens: &mut impl Iterator<Item = Token>) -> Result<(Vec<Node>, End), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                if !text.is_empty() {
+                    nodes.push(Node::Text(text));
+                }
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+        let content = tag.content.as_str();
+        if tag.raw {
+            nodes.push(Node::Output { expr: parse_expr(content, tag.line)?, escape: false });
+        } else if content.starts_with('!') {
+            continue;
+        } else if content == "else" {
+            return Ok((nodes, End::Else(tag.line)));
+        } else if let Some(name) = content.strip_prefix('/') {
+            return Ok((nodes, End::Close(name.trim().to_string(), tag.line)));
+        } else if let Some(open) = content.strip_prefix('#') {
+            let (name, args) = open.split_once(char::is_whitespace).unwrap_or((open, ""));
+            let kind = match name {
+                "if" => Block::If,
+                "unless" => Block::Unless,
+                "each" => Block::Each,
+                "with" => Block::With,
+                _ => return Err(error(tag.line, format!("{}: {}", tr("未知的块"), name))),
+            };
+            let expr = parse_expr(args, tag.line)?;
+            let (body, end) = parse_nodes(tokens)?;
+            let (otherwise, end) = match end {
+                End::Else(_) => parse_nodes(tokens)?,
+                end => (Vec::new(), end),
+            };
+            match end {
+                End::Close(close, _) if close == name => {}
+                End::Close(_, line) | End::Else(line) => {
+                    return Err(error(line, format!("{}: {{{{#{}}}}}", tr("结束标签与块不匹配"), name)))
+                }
+                End::Eof => return Err(error(tag.line, format!("{}: {{{{#{}}}}}", tr("块没有结束"), name))),
+            }
+            nodes.push(Node::Block { kind, expr, body, otherwise });
+        } else {
+            nodes.push(Node::Output { expr: parse_expr(content, tag.line)?, escape: true });
+        }
+    }
+    Ok((nodes, End::Eof))
+}
+
+/// 按空白拆分表达式，引号中的字符串和 `[...]` 中的字段名可以包含空白
+fn split_words(expr: &str) -> Option<Vec<&str>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut closing = None;
+    for (i, c) in expr.char_indices() {
+        match closing {
+            Some(end) if c == end => closing = None,
+            Some(_) => {}
+            None if c.is_whitespace() => {
+                if let Some(s) = start.take() {
+                    words.push(&expr[s..i]);
+                }
+            }
+            None => {
+                start.get_or_insert(i);
+                closing = match c {
+                    '"' => Some('"'),
+                    '[' => Some(']'),
+                    _ => None,
+                };
+            }
+        }
+    }
+    if closing.is_some() {
+        return None;
+    }
+    if let Some(s) = start {
+        words.push(&expr[s..]);
+    }
+    Some(words)
+}
+
+fn parse_expr(expr: &str, line: usize) -> Result<Expr, String> {
+    let invalid = || error(line, format!("{}: {}", tr("无效的表达式"), expr));
+    let words = split_words(expr).ok_or_else(invalid)?;
+    match words.as_slice() {
+        [] => Err(invalid()),
+        [word] => parse_operand(word).ok_or_else(invalid),
+        [name, args @ ..] => {
+            let (helper, arity) = Helper::parse(name).ok_or_else(|| error(line, format!("{}: {}", tr("未知的助手"), name)))?;
+            if !arity.contains(&args.len()) {
+                return Err(error(line, format!("{}: {}", tr("助手的参数个数不对"), expr)));
+            }
+            let args = args.iter().map(|arg| parse_operand(arg)).collect::<Option<Vec<_>>>().ok_or_else(invalid)?;
+            Ok(Expr::Helper(helper, args))
+        }
+    }
+}
+
+fn parse_operand(word: &str) -> Option<Expr> {
+    if let Some(string) = word.strip_prefix('"') {
+        return Some(Expr::Literal(Value::from(string.strip_suffix('"')?)));
+    }
+    match word {
+        "true" => return Some(Expr::Literal(Value::Bool(true))),
+        "false" => return Some(Expr::Literal(Value::Bool(false))),
+        "null" => return Some(Expr::Literal(Value::Null)),
+        _ => {}
+    }
+    if word.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+        return serde_json::from_str::<serde_json::Number>(word).ok().map(|n| Expr::Literal(Value::Number(n)));
+    }
+    parse_path(word).map(Expr::Path)
+}
+
+/// `mem.used`、`cpu.0`、`disk.[/].used`、`this`、`../name`、`@root.tags.host`、`@key`
+fn parse_path(path: &str) -> Option<Path> {
+    if let Some(name) = path.strip_prefix('@') {
+        if matches!(name, "key" | "index" | "first" | "last") {
+            return Some(Path::Data(name.to_string()));
+        }
+        let rest = name.strip_prefix("root")?;
+        let rest = if rest.is_empty() { rest } else { rest.strip_prefix('.')? };
+        return Some(Path::Field { root: true, up: 0, segments: parse_segments(rest)? });
+    }
+    let mut rest = path;
+    let mut up = 0;
+    while let Some(after) = rest.strip_prefix("../") {
+        up += 1;
+        rest = after;
+    }
+    let rest = rest.strip_prefix("./").unwrap_or(rest);
+    let rest = match rest {
+        "this" | "." => "",
+        rest => rest.strip_prefix("this.").unwrap_or(rest),
+    };
+    Some(Path::Field { root: false, up, segments: parse_segments(rest)? })
+}
+
+fn parse_segments(path: &str) -> Option<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        let (segment, after, literal) = match rest.strip_prefix('[') {
+            Some(literal) => literal.split_once(']').map(|(segment, after)| (segment, after, true))?,
+            None => {
+                let (segment, after) = rest.split_at(rest.find(['.', '[']).unwrap_or(rest.len()));
+                (segment, after, false)
+            }
+        };
+        // 不支持的写法(局部模板、子表达式、`key=value` 参数、块参数)在这里报错，不当作字段名
+        if segment.is_empty() || (!literal && segment.contains(['>', '(', ')', '=', '|', '{', '}'])) {
+            return None;
+        }
+        segments.push(segment.to_string());
+        rest = match after.strip_prefix('.') {
+            Some("") => return None,
+            Some(after) => after,
+            None if after.is_empty() || after.starts_with('[') => after,
+            None => return None,
+        };
+    }
+    Some(segments)
+}
+
+/// 一层上下文：样本、`#each` 的元素或 `#with` 的对象
+struct Frame {
+    value: Value,
+    /// `#each` 中的 `@key`(对象)或 `@index`(数组)
+    key: Option<Value>,
+    index: usize,
+    first: bool,
+    last: bool,
+}
+
+impl Frame {
+    fn new(value: Value) -> Self {
+        Self { value, key: None, index: 0, first: false, last: false }
+    }
+}
+
+fn render(nodes: &[Node], frames: &mut Vec<Frame>, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Output { expr, escape } => {
+                let text = display(&evaluate(expr, frames));
+                if *escape {
+                    escape_xml(&text, out);
+                } else {
+                    out.push_str(&text);
+                }
+            }
+            Node::Block { kind, expr, body, otherwise } => {
+                let value = evaluate(expr, frames);
+                match kind {
+                    Block::If | Block::Unless => {
+                        let chosen = if truthy(&value) == matches!(kind, Block::If) { body } else { otherwise };
+                        render(chosen, frames, out);
+                    }
+                    Block::With if truthy(&value) => {
+                        frames.push(Frame::new(value));
+                        render(body, frames, out);
+                        frames.pop();
+                    }
+                    Block::Each => {
+                        let items: Vec<(Value, Value)> = match value {
+                            Value::Array(items) => items.into_iter().enumerate().map(|(i, item)| (Value::from(i), item)).collect(),
+                            Value::Object(items) => items.into_iter().map(|(key, item)| (Value::from(key), item)).collect(),
+                            _ => Vec::new(),
+                        };
+                        if items.is_empty() {
+                            render(otherwise, frames, out);
+                        }
+                        let count = items.len();
+                        for (index, (key, item)) in items.into_iter().enumerate() {
+                            frames.push(Frame { value: item, key: Some(key), index, first: index == 0, last: index + 1 == count });
+                            render(body, frames, out);
+                            frames.pop();
+                        }
+                    }
+                    Block::With => render(otherwise, frames, out),
+                }
+            }
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, frames: &[Frame]) -> Value {
+    match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Path(Path::Data(name)) => {
+            let Some(frame) = frames.iter().rev().find(|frame| frame.key.is_some()) else {
+                return Value::Null;
+            };
+            match name.as_str() {
+                "key" => frame.key.clone().unwrap_or_default(),
+                "index" => Value::from(frame.index),
+                "first" => Value::Bool(frame.first),
+                _ => Value::Bool(frame.last),
+            }
+        }
+        Expr::Path(Path::Field { root, up, segments }) => {
+            let frame = if *root { frames.first() } else { frames.len().checked_sub(up + 1).and_then(|i| frames.get(i)) };
+            let mut value = frame.map(|frame| &frame.value);
+            for segment in segments {
+                value = value.and_then(|value| match value {
+                    Value::Object(object) => object.get(segment),
+                    Value::Array(array) => array.get(segment.parse::<usize>().ok()?),
+                    _ => None,
+                });
+            }
+            value.cloned().unwrap_or_default()
+        }
+        Expr::Helper(helper, args) => {
+            let args: Vec<Value> = args.iter().map(|arg| evaluate(arg, frames)).collect();
+            let number = |i: usize| args.get(i).and_then(Value::as_f64);
+            let digits = |i: usize, default: u64| args.get(i).and_then(Value::as_u64).unwrap_or(default).min(9) as usize;
+            match helper {
+                Helper::Json => Value::from(args[0].to_string()),
+                Helper::Unix => match &args[0] {
+                    Value::String(time) => chrono::DateTime::parse_from_rfc3339(time).map_or(Value::Null, |t| Value::from(t.timestamp())),
+                    value if value.is_number() => value.clone(),
+                    _ => Value::Null,
+                },
+                Helper::Round => number(0).map_or(Value::Null, |n| round(n, digits(1, 0))),
+                Helper::Percent => match (number(0), number(1)) {
+                    (Some(part), Some(total)) if total != 0.0 => round(100.0 * part / total, digits(2, 1)),
+                    _ => Value::Null,
+                },
+            }
+        }
+    }
+}
+
+/// 保留 `digits` 位小数，为0时得到整数
+fn round(value: f64, digits: usize) -> Value {
+    if digits == 0 {
+        Value::from(value.round() as i64)
+    } else {
+        Value::from(format!("{:.*}", digits, value))
+    }
+}
+
+/// 与Handlebars相同，false、null、0、空字符串和空数组为假
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+/// 字符串原样输出，null 为空，对象和数组输出为JSON
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn escape_xml(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#x27;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(source: &str, sample: Value) -> String {
+        Template::parse(source).unwrap().render(&sample)
+    }
+
+    #[test]
+    fn escapes_output_unless_triple_braced() {
+        let sample = json!({ "name": "<a href=\"x\">Tom & 'Jerry'</a>" });
+        assert_eq!(render("{{name}}", sample.clone()), "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#x27;Jerry&#x27;&lt;/a&gt;");
+        assert_eq!(render("{{{name}}}", sample), "<a href=\"x\">Tom & 'Jerry'</a>");
+    }
+
+    #[test]
+    fn outputs_missing_fields_as_empty_and_containers_as_json() {
+        let sample = json!({ "tags": { "host": "a" }, "cpu": [1, 2], "swap": null });
+        assert_eq!(render("[{{missing}}][{{swap}}][{{cpu.1}}]", sample.clone()), "[][][2]");
+        assert_eq!(render("{{{tags}}} {{{cpu}}}", sample), r#"{"host":"a"} [1,2]"#);
+    }
+
+    #[test]
+    fn resolves_bracketed_segments() {
+        let sample = json!({ "disk": { "/": { "used": 5 }, "/mnt/a b": { "used": 7 } } });
+        assert_eq!(render("{{disk.[/].used}} {{disk.[/mnt/a b].used}}", sample), "5 7");
+    }
+
+    #[test]
+    fn iterates_arrays_with_data_variables() {
+        let sample = json!({ "cpu": [10, 20, 30] });
+        let source = "{{#each cpu}}{{@index}}={{this}}{{#if @first}}(first){{/if}}{{#if @last}}(last){{/if}};{{/each}}";
+        assert_eq!(render(source, sample), "0=10(first);1=20;2=30(last);");
+    }
+
+    #[test]
+    fn iterates_objects_by_key_and_reaches_outer_scopes() {
+        let sample = json!({ "host": "a", "net": { "eth0": { "rx": 1 }, "lo": { "rx": 2 } } });
+        let source = "{{#each net}}{{@key}}:{{rx}}@{{../host}}/{{@root.host}} {{/each}}";
+        assert_eq!(render(source, sample), "eth0:1@a/a lo:2@a/a ");
+    }
+
+    #[test]
+    fn renders_else_for_empty_each() {
+        assert_eq!(render("{{#each items}}x{{else}}none{{/each}}", json!({ "items": [] })), "none");
+        assert_eq!(render("{{#each items}}x{{else}}none{{/each}}", json!({})), "none");
+    }
+
+    #[test]
+    fn if_and_unless_follow_handlebars_truthiness() {
+        let source = "{{#if v}}T{{else}}F{{/if}}";
+        for (value, expected) in [
+            (json!(false), "F"),
+            (json!(null), "F"),
+            (json!(0), "F"),
+            (json!(0.0), "F"),
+            (json!(""), "F"),
+            (json!([]), "F"),
+            (json!({}), "T"),
+            (json!(0.5), "T"),
+            (json!("0"), "T"),
+            (json!([0]), "T"),
+        ] {
+            assert_eq!(render(source, json!({ "v": value.clone() })), expected, "{}", value);
+        }
+        assert_eq!(render("{{#unless v}}U{{else}}E{{/unless}}", json!({ "v": 0 })), "U");
+        assert_eq!(render("{{#unless v}}U{{else}}E{{/unless}}", json!({ "v": 1 })), "E");
+    }
+
+    #[test]
+    fn with_changes_scope_and_falls_back_to_else() {
+        let sample = json!({ "mem": { "used": 3, "total": 4 }, "host": "a" });
+        assert_eq!(render("{{#with mem}}{{used}}/{{total}} {{../host}}{{/with}}", sample.clone()), "3/4 a");
+        assert_eq!(render("{{#with swap}}{{used}}{{else}}no swap{{/with}}", sample), "no swap");
+    }
+
+    #[test]
+    fn nested_each_data_variables_refer_to_the_innermost_loop() {
+        let sample = json!({ "a": [[1, 2], [3]] });
+        let source = "{{#each a}}{{#each this}}{{@index}}{{/each}}|{{/each}}";
+        assert_eq!(render(source, sample), "01|0|");
+    }
+
+    #[test]
+    fn evaluates_helpers() {
+        let sample = json!({
+            "tags": { "host": "a\"b" },
+            "timestamp": "2024-01-02T03:04:05Z",
+            "cpu": [12.345],
+            "mem": { "used": 1, "total": 3 },
+        });
+        assert_eq!(render("{{{json tags.host}}}", sample.clone()), r#""a\"b""#);
+        assert_eq!(render("{{json tags.host}}", sample.clone()), "&quot;a\\&quot;b&quot;");
+        assert_eq!(render("{{unix timestamp}}", sample.clone()), "1704164645");
+        assert_eq!(render("{{round cpu.0}} {{round cpu.0 2}}", sample.clone()), "12 12.35");
+        assert_eq!(render("{{percent mem.used mem.total}} {{percent mem.used mem.total 0}}", sample.clone()), "33.3 33");
+        assert_eq!(render("[{{percent mem.used 0}}][{{round \"x\"}}][{{unix \"bad\"}}]", sample), "[][][]");
+    }
+
+    #[test]
+    fn removes_standalone_block_lines() {
+        let source = "start\n{{#each disk}}\n  {{@key}}={{used}}\n{{/each}}\n{{! comment }}\nend\n";
+        let sample = json!({ "disk": { "/": { "used": 1 }, "/home": { "used": 2 } } });
+        assert_eq!(render(source, sample), "start\n  /=1\n  /home=2\nend\n");
+    }
+
+    #[test]
+    fn keeps_inline_block_tags_and_trims_with_tilde() {
+        assert_eq!(render("a {{#if v}}b{{/if}} c", json!({ "v": true })), "a b c");
+        assert_eq!(render("a   {{~v~}}   \n c", json!({ "v": 1 })), "a1c");
+        assert_eq!(render("x{{!-- has }} inside --}}y", json!({})), "xy");
+    }
+
+    #[test]
+    fn parses_literals() {
+        assert_eq!(render("{{round 2.6}} {{json \"a b\"}} {{json null}} {{json true}}", json!({})), "3 &quot;a b&quot; null true");
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let error = |source: &str| Template::parse(source).unwrap_err();
+        assert!(error("a\n{{#if v}}\nb").starts_with(&format!("{} 2:", tr("行"))));
+        assert!(error("{{#if v}}{{/each}}").contains(tr("结束标签与块不匹配")));
+        assert!(error("{{/if}}").contains(tr("多余的结束标签")));
+        assert!(error("{{else}}").contains(tr("多余的 {{else}}")));
+        assert!(error("x\n\n{{v").starts_with(&format!("{} 3:", tr("行"))));
+        assert!(error("{{#each}}{{/each}}").contains(tr("无效的表达式")));
+        assert!(error("{{#each a as |item|}}{{/each}}").contains(tr("未知的助手")));
+        assert!(error("{{#partial a}}{{/partial}}").contains(tr("未知的块")));
+        assert!(error("{{round a b c}}").contains(tr("助手的参数个数不对")));
+    }
+
+    #[test]
+    fn rejects_unsupported_syntax() {
+        for source in ["{{> header}}", "{{>header}}", "{{round (json a)}}", "{{round a digits=2}}", "{{else if a}}", "{{a|b}}"] {
+            assert!(Template::parse(source).is_err(), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// src/template_sink.rs
+use crate::breaker::{Breaker, SinkHealth};
+use crate::error::Error;
+use crate::i18n::tr;
+use crate::template::Template;
+use reqwest::header::CONTENT_TYPE;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// 还没发送完时最多排队的批次，超过时丢弃新的批次
+const QUEUE_SIZE: usize = 16;
+
+enum Target {
+    /// `http://...` 或 `https://...`：每个样本 POST 一次
+    Http(String),
+    /// `file:PATH`：逐个追加到文件，每次重新打开，文件被轮转后写到新文件
+    File(PathBuf),
+    /// `tcp://HOST:PORT`：保持连接逐个写入，出错后下一批重新连接
+    Tcp(String),
+    /// `udp://HOST:PORT`：每个样本一个数据报
+    Udp(String),
+}
+
+impl Target {
+    fn parse(value: &str) -> Option<Self> {
+        let target = if value.starts_with("http://") || value.starts_with("https://") {
+            Target::Http(value.to_string())
+        } else if let Some(path) = value.strip_prefix("file:") {
+            Target::File(PathBuf::from(path))
+        } else if let Some(address) = value.strip_prefix("tcp://") {
+            Target::Tcp(address.to_string())
+        } else if let Some(address) = value.strip_prefix("udp://") {
+            Target::Udp(address.to_string())
+        } else {
+            return None;
+        };
+        match &target {
+            Target::File(path) if path.as_os_str().is_empty() => None,
+            Target::Tcp(address) | Target::Udp(address) if !address.contains(':') => None,
+            _ => Some(target),
+        }
+    }
+}
+
+/// `--template-sink TEMPLATE=TARGET` 拆成模板文件和发送目标
+fn parse(value: &str) -> Option<(&str, Target)> {
+    let (path, target) = value.split_once('=')?;
+    Some((path, Target::parse(target)?)).filter(|(path, _)| !path.is_empty())
+}
+
+/// `--template-sink` 的校验
+pub fn template_sink_arg(value: &str) -> Result<String, String> {
+    match parse(value) {
+        Some(_) => Ok(value.to_string()),
+        None => Err(format!("{}: {}", tr("无效的模板输出"), value)),
+    }
+}
+
+/// 渲染结果的去向
+struct Output {
+    target: Target,
+    client: reqwest::Client,
+    content_type: &'static str,
+    timeout: Duration,
+    stream: Option<TcpStream>,
+}
+
+impl Output {
+    async fn write(&mut self, mut text: String) -> Result<(), String> {
+        let timeout = |e: tokio::time::error::Elapsed| e.to_string();
+        match &self.target {
+            Target::Http(url) => {
+                let response = self.client
+                    .post(url)
+                    .header(CONTENT_TYPE, self.content_type)
+                    .body(text)
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if !response.status().is_success() {
+                    return Err(format!("HTTP {}", response.status()));
+                }
+            }
+            Target::File(path) => {
+                if !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                let mut file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+                file.write_all(text.as_bytes()).await.map_err(|e| format!("{}: {}", path.display(), e))?;
+            }
+            Target::Tcp(address) => {
+                if !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                let mut stream = match self.stream.take() {
+                    Some(stream) => stream,
+                    None => tokio::time::timeout(self.timeout, TcpStream::connect(address))
+                        .await
+                        .map_err(timeout)?
+                        .map_err(|e| format!("{}: {}", address, e))?,
+                };
+                // 写入失败时连接丢弃，下次重新连接
+                tokio::time::timeout(self.timeout, stream.write_all(text.as_bytes()))
+                    .await
+                    .map_err(timeout)?
+                    .map_err(|e| format!("{}: {}", address, e))?;
+                self.stream = Some(stream);
+            }
+            Target::Udp(address) => {
+                let addr = tokio::net::lookup_host(address.as_str())
+                    .await
+                    .ok()
+                    .and_then(|mut addrs| addrs.next())
+                    .ok_or_else(|| format!("{}: {}", tr("无法解析主机名"), address))?;
+                let local = if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+                let socket = UdpSocket::bind(local).await.map_err(|e| e.to_string())?;
+                socket.send_to(text.as_bytes(), addr).await.map_err(|e| format!("{}: {}", address, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 模板输出：发往端点的每个样本再按用户的模板渲染成文本(自定义的JSON、XML或旧系统的行协议)，发送到HTTP、文件或TCP/UDP；
+/// 在自己的任务中发送，目标慢或出错不影响发往端点的数据。渲染结果只有空白时不发送
+pub struct TemplateSink {
+    name: String,
+    bodies: mpsc::Sender<String>,
+    task: JoinHandle<()>,
+    /// 连续发送失败后暂停，由发送任务更新
+    breaker: Arc<Mutex<Breaker>>,
+}
+
+impl TemplateSink {
+    /// 读取并解析模板，模板有错时报错；HTTP目标使用 `client`，TCP目标的连接和写入最多等待 `timeout`
+    pub fn load(
+        value: &str,
+        client: reqwest::Client,
+        timeout: Duration,
+        (threshold, cooldown): (u32, Duration),
+    ) -> Result<Self, Error> {
+        let (path, target) = parse(value).ok_or_else(|| Error::config(format!("{}: {}", tr("无效的模板输出"), value)))?;
+        let path = Path::new(path);
+        let error = |e: String| Error::config(format!("{} {}: {}", tr("无效的模板"), path.display(), e));
+        let source = std::fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        let template = Template::parse(&source).map_err(error)?;
+
+        let name = path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy().into_owned();
+        let content_type = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => "application/json",
+            Some("xml") => "application/xml",
+            _ => "text/plain; charset=utf-8",
+        };
+        let mut output = Output { target, client, content_type, timeout, stream: None };
+        let breaker = Arc::new(Mutex::new(Breaker::new(format!("template:{}", name), threshold, cooldown)));
+        let state = breaker.clone();
+        let sink = name.clone();
+        let (bodies, mut receiver) = mpsc::channel::<String>(QUEUE_SIZE);
+        let task = tokio::spawn(async move {
+            while let Some(body) = receiver.recv().await {
+                if !state.lock().unwrap().allow() {
+                    tracing::debug!(%sink, "{}", tr("模板输出断路，批次被丢弃"));
+                    continue;
+                }
+                let samples = match serde_json::from_str(&body) {
+                    Ok(Value::Array(samples)) => samples,
+                    Ok(sample) => vec![sample],
+                    Err(_) => continue,
+                };
+                let mut result = Ok(());
+                for sample in &samples {
+                    let text = template.render(sample);
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    result = output.write(text).await;
+                    if result.is_err() {
+                        break;
+                    }
+                }
+                match result {
+                    Ok(()) => {
+                        tracing::debug!(%sink, samples = samples.len(), "{}", tr("模板输出已发送"));
+                        state.lock().unwrap().success();
+                    }
+                    Err(error) => {
+                        tracing::warn!(%sink, %error, "{}", tr("模板输出失败"));
+                        state.lock().unwrap().failure(error);
+                    }
+                }
+            }
+        });
+        Ok(Self { name, bodies, task, breaker })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 模板输出的断路器状态
+    pub fn health(&self) -> SinkHealth {
+        self.breaker.lock().unwrap().health()
+    }
+
+    /// 交给发送任务，还在发送之前的批次且队列已满时丢弃并返回 false
+    pub fn export(&self, body: String) -> bool {
+        !matches!(self.bodies.try_send(body), Err(mpsc::error::TrySendError::Full(_)))
+    }
+
+    /// 等已排队的批次发送完后结束，退出前调用
+    pub async fn close(self) {
+        drop(self.bodies);
+        let _ = self.task.await;
+    }
+}
@@ -0,0 +1,112 @@
+// src/throttle.rs
+use crate::i18n::tr;
+
+/// 连续这么多个样本都低于限制的80%才恢复一级，避免在限制附近来回切换
+const RECOVER_SAMPLES: u32 = 3;
+
+/// 低于限制的这个比例才算恢复
+const RECOVER_RATIO: f64 = 0.8;
+
+/// 触发放慢采集的限制，都不设置时不放慢
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Limits {
+    /// 本程序自身的CPU占用率(%)
+    pub agent_cpu: Option<f64>,
+    /// 本程序自身的常驻内存(字节)
+    pub agent_rss: Option<f64>,
+    /// 主机1分钟平均负载除以CPU核数
+    pub load: Option<f64>,
+}
+
+/// 自身CPU占用、内存或主机负载超过限制时逐级放慢采集(间隔加倍)，放慢到 `max_slowdown` 倍仍然超过时
+/// 暂停 `shed` 中的采集项；持续低于限制后按相反的顺序逐级恢复
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    limits: Limits,
+    max_slowdown: u32,
+    shed: Vec<String>,
+    slowdown: u32,
+    shedding: bool,
+    calm: u32,
+}
+
+/// `--max-agent-cpu`、`--max-rss` 和 `--max-load` 的校验，必须是正数
+pub fn limit_arg(value: &str) -> Result<String, String> {
+    match value.parse::<f64>() {
+        Ok(limit) if limit > 0.0 && limit.is_finite() => Ok(value.to_string()),
+        _ => Err(format!("{}: {}", tr("无效的限制"), value)),
+    }
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new(Limits::default(), 1, Vec::new())
+    }
+}
+
+impl Throttle {
+    pub fn new(limits: Limits, max_slowdown: u32, shed: Vec<String>) -> Self {
+        Self { limits, max_slowdown: max_slowdown.max(1), shed, slowdown: 1, shedding: false, calm: 0 }
+    }
+
+    /// 重新加载配置时更新设置，当前的放慢程度不超过新的上限
+    pub fn configure(&mut self, limits: Limits, max_slowdown: u32, shed: Vec<String>) {
+        self.limits = limits;
+        self.max_slowdown = max_slowdown.max(1);
+        self.slowdown = self.slowdown.min(self.max_slowdown);
+        self.shedding &= !shed.is_empty();
+        self.shed = shed;
+    }
+
+    /// 采集间隔当前放慢的倍数
+    pub fn slowdown(&self) -> u32 {
+        self.slowdown
+    }
+
+    /// 当前暂停的采集项
+    pub fn shed(&self) -> &[String] {
+        if self.shedding { &self.shed } else { &[] }
+    }
+
+    /// 采集项是否因为过载暂停
+    pub fn sheds(&self, name: &str) -> bool {
+        self.shedding && self.shed.iter().any(|shed| shed == name)
+    }
+
+    /// 用一次采集后自身的CPU占用率、常驻内存和主机负载更新状态，放慢程度或暂停的采集项变化时返回 true
+    pub fn evaluate(&mut self, agent_cpu: f64, agent_rss: f64, load: f64) -> bool {
+        let ratio = [(self.limits.agent_cpu, agent_cpu), (self.limits.agent_rss, agent_rss), (self.limits.load, load)]
+            .into_iter()
+            .filter_map(|(limit, value)| Some(value / limit?))
+            .fold(0.0, f64::max);
+
+        if ratio > 1.0 {
+            self.calm = 0;
+            if self.slowdown < self.max_slowdown {
+                self.slowdown = (self.slowdown * 2).min(self.max_slowdown);
+                return true;
+            }
+            if !self.shedding && !self.shed.is_empty() {
+                self.shedding = true;
+                return true;
+            }
+            return false;
+        }
+
+        if ratio >= RECOVER_RATIO || (self.slowdown == 1 && !self.shedding) {
+            self.calm = 0;
+            return false;
+        }
+        self.calm += 1;
+        if self.calm < RECOVER_SAMPLES {
+            return false;
+        }
+        self.calm = 0;
+        if self.shedding {
+            self.shedding = false;
+        } else {
+            self.slowdown /= 2;
+        }
+        true
+    }
+}
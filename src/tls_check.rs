@@ -0,0 +1,254 @@
+// src/tls_check.rs
+use crate::collector::{Collector, Context, Section};
+use crate::i18n::tr;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// 采集项的名称
+pub const NAME: &str = "tls";
+
+/// 没有用 `--collector-interval tls=...` 设置时的检查间隔，证书的有效期以天计，不需要每个间隔都握手
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 一个端点在一次检查中的结果，按证书链中最先过期的证书给出
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TlsCheckStats {
+    /// 距离过期的天数，已经过期时为负数；连接或握手失败时为 `null`
+    pub days_left: Option<f64>,
+    pub not_after: Option<DateTime<Utc>>,
+    /// 最先过期的证书的CN
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// 服务器发送的证书数，以 native-tls 编译时只能取得服务器证书本身，总是为1
+    pub certificates: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `--tls-check HOST[:PORT]`，端口默认为443；IPv6地址写成 `[::1]:443`
+#[derive(Debug, Clone)]
+struct Check {
+    host: String,
+    port: u16,
+}
+
+impl Check {
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (host, port) = match value.strip_prefix('[') {
+            Some(rest) => match rest.split_once(']')? {
+                (host, "") => (host, None),
+                (host, port) => (host, Some(port.strip_prefix(':')?)),
+            },
+            None => match value.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (value, None),
+            },
+        };
+        let port = match port {
+            Some(port) => port.parse().ok()?,
+            None => 443,
+        };
+        (!host.is_empty() && !host.contains(['/', ' '])).then(|| Self { host: host.to_string(), port })
+    }
+
+    fn run(&self, timeout: Duration) -> TlsCheckStats {
+        let chain = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .and_then(|mut addrs| addrs.next().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, tr("无法解析主机名"))))
+            .and_then(|addr| peer_chain(&self.host, addr, timeout));
+        let chain = match chain {
+            Ok(chain) if !chain.is_empty() => chain,
+            Ok(_) => return TlsCheckStats { error: Some(tr("服务器没有发送证书").to_string()), ..Default::default() },
+            Err(e) => return TlsCheckStats { error: Some(e.to_string()), ..Default::default() },
+        };
+        let certificates = chain.len();
+        let Some((not_after, subject)) = chain.iter().filter_map(|der| parse_certificate(der)).min_by_key(|(not_after, _)| *not_after)
+        else {
+            return TlsCheckStats { certificates, error: Some(tr("无法解析证书").to_string()), ..Default::default() };
+        };
+        let days_left = (not_after - Utc::now()).num_seconds() as f64 / 86400.0;
+        TlsCheckStats {
+            days_left: Some((days_left * 100.0).round() / 100.0),
+            not_after: Some(not_after),
+            subject,
+            certificates,
+            error: None,
+        }
+    }
+}
+
+/// `--tls-check` 的校验
+pub fn tls_check_arg(value: &str) -> Result<String, String> {
+    Check::parse(value)
+        .map(|_| value.trim().to_string())
+        .ok_or_else(|| format!("{}: {}", tr("无效的TLS检查"), value))
+}
+
+/// 每次检查与各端点完成一次TLS握手，读取服务器发送的证书的有效期；不验证证书，已经过期或不受信任的证书同样给出结果。
+/// 各端点同时检查，每个最多等待 `timeout`
+pub struct TlsChecks {
+    checks: Vec<(String, Check)>,
+    timeout: Duration,
+}
+
+impl TlsChecks {
+    /// `values` 为 `--tls-check` 的值，样本中以它为键
+    pub fn new(values: &[String], timeout: Duration) -> Self {
+        let checks = values
+            .iter()
+            .filter_map(|value| Some((value.trim().to_string(), Check::parse(value)?)))
+            .collect();
+        Self { checks, timeout }
+    }
+}
+
+impl Collector for TlsChecks {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn collect(&mut self, _ctx: &mut Context) -> Section {
+        let timeout = self.timeout;
+        let results = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .checks
+                .iter()
+                .map(|(key, check)| (key, scope.spawn(move || check.run(timeout))))
+                .collect();
+            handles
+                .into_iter()
+                .map(|(key, handle)| (key.clone(), handle.join().unwrap_or_default()))
+                .collect()
+        });
+        Section::Tls(results)
+    }
+}
+
+fn connect(addr: SocketAddr, timeout: Duration) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok(stream)
+}
+
+/// 握手并取得服务器发送的整个证书链(DER)
+#[cfg(feature = "rustls")]
+fn peer_chain(host: &str, addr: SocketAddr, timeout: Duration) -> io::Result<Vec<Vec<u8>>> {
+    use rustls::client::{ServerCertVerified, ServerCertVerifier, ServerName};
+    use rustls::{Certificate, ClientConfig, ClientConnection};
+    use std::sync::Arc;
+
+    /// 只读取证书，不验证
+    struct AcceptAny;
+
+    impl ServerCertVerifier for AcceptAny {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &Certificate,
+            _intermediates: &[Certificate],
+            _server_name: &ServerName,
+            _scts: &mut dyn Iterator<Item = &[u8]>,
+            _ocsp_response: &[u8],
+            _now: std::time::SystemTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+    }
+
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(AcceptAny))
+        .with_no_client_auth();
+    let name = ServerName::try_from(host).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut conn = ClientConnection::new(Arc::new(config), name).map_err(io::Error::other)?;
+    let mut stream = connect(addr, timeout)?;
+    while conn.is_handshaking() {
+        conn.complete_io(&mut stream)?;
+    }
+    Ok(conn.peer_certificates().unwrap_or_default().iter().map(|cert| cert.0.clone()).collect())
+}
+
+/// native-tls 只能取得服务器证书本身，中间证书不在其中
+#[cfg(not(feature = "rustls"))]
+fn peer_chain(host: &str, addr: SocketAddr, timeout: Duration) -> io::Result<Vec<Vec<u8>>> {
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(io::Error::other)?;
+    let stream = connector.connect(host, connect(addr, timeout)?).map_err(|e| match e {
+        native_tls::HandshakeError::Failure(e) => io::Error::other(e),
+        native_tls::HandshakeError::WouldBlock(_) => io::Error::new(io::ErrorKind::TimedOut, tr("握手超时")),
+    })?;
+    let cert = stream.peer_certificate().map_err(io::Error::other)?;
+    Ok(cert.and_then(|cert| cert.to_der().ok()).into_iter().collect())
+}
+
+/// 从DER编码的X.509证书中取出 `notAfter` 和主题的CN
+fn parse_certificate(der: &[u8]) -> Option<(DateTime<Utc>, Option<String>)> {
+    let (_, certificate, _) = tlv(der)?;
+    let (_, tbs, _) = tlv(certificate)?;
+    let mut rest = tbs;
+    // 版本是可选的 [0]
+    if rest.first() == Some(&0xa0) {
+        rest = tlv(rest)?.2;
+    }
+    // 序列号、签名算法、颁发者
+    for _ in 0..3 {
+        rest = tlv(rest)?.2;
+    }
+    let (_, validity, rest) = tlv(rest)?;
+    let (_, _, validity) = tlv(validity)?;
+    let (tag, not_after, _) = tlv(validity)?;
+    let not_after = parse_time(tag, not_after)?;
+    let (_, subject, _) = tlv(rest)?;
+    Some((not_after, common_name(subject)))
+}
+
+/// 读取一个DER元素：(标签, 内容, 之后的数据)
+fn tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, data) = data.split_first()?;
+    let (&first, mut data) = data.split_first()?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 || data.len() < n {
+            return None;
+        }
+        let len = data[..n].iter().fold(0usize, |len, b| len << 8 | *b as usize);
+        data = &data[n..];
+        len
+    };
+    (data.len() >= len).then(|| (tag, &data[..len], &data[len..]))
+}
+
+/// UTCTime(`YYMMDDHHMMSSZ`，年份50以下为20xx)或GeneralizedTime(`YYYYMMDDHHMMSSZ`)
+fn parse_time(tag: u8, value: &[u8]) -> Option<DateTime<Utc>> {
+    let value = std::str::from_utf8(value).ok()?.strip_suffix('Z')?;
+    let value = match tag {
+        0x17 => format!("{}{}", if value.get(..2)? < "50" { "20" } else { "19" }, value),
+        0x18 => value.to_string(),
+        _ => return None,
+    };
+    Some(NaiveDateTime::parse_from_str(&value, "%Y%m%d%H%M%S").ok()?.and_utc())
+}
+
+/// 主题中 commonName(2.5.4.3) 的值
+fn common_name(mut name: &[u8]) -> Option<String> {
+    const CN: [u8; 3] = [0x55, 0x04, 0x03];
+    while !name.is_empty() {
+        let (_, set, rest) = tlv(name)?;
+        name = rest;
+        let (_, attribute, _) = tlv(set)?;
+        let (_, oid, value) = tlv(attribute)?;
+        if oid == CN {
+            return Some(String::from_utf8_lossy(tlv(value)?.1).into_owned());
+        }
+    }
+    None
+}
@@ -0,0 +1,147 @@
+// src/token.rs
+use crate::i18n::tr;
+use reqwest::header::ACCEPT;
+use reqwest::Client;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// 令牌过期前多久刷新，不超过有效期的十分之一
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// 令牌端点没有给出 `expires_in` 时按这个有效期缓存
+const DEFAULT_LIFETIME: Duration = Duration::from_secs(3600);
+
+/// OAuth2客户端凭据模式(RFC 6749 4.4)的设置
+pub struct ClientCredentials {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// 以空格分隔的权限范围
+    pub scope: Option<String>,
+    /// 附加到令牌请求中的参数，例如 `audience`
+    pub params: Vec<(String, String)>,
+    /// 客户端ID和密钥放在请求体中(`client_secret_post`)，否则用Basic认证头(`client_secret_basic`)
+    pub post_credentials: bool,
+}
+
+/// 令牌的来源
+pub enum TokenSource {
+    ClientCredentials(ClientCredentials),
+    /// 由其他程序定期更新的令牌文件，修改后重新读取
+    File(PathBuf),
+}
+
+struct Cached {
+    token: String,
+    /// 令牌端点的令牌在这之后刷新
+    refresh_at: Option<Instant>,
+    /// 令牌文件读取时的修改时间
+    modified: Option<SystemTime>,
+}
+
+/// 获取并缓存令牌：过期前刷新，被端点拒绝时由 [`TokenProvider::invalidate`] 丢弃后重新获取
+pub struct TokenProvider {
+    source: TokenSource,
+    cached: Mutex<Option<Cached>>,
+}
+
+impl TokenProvider {
+    pub fn new(source: TokenSource) -> Self {
+        Self { source, cached: Mutex::new(None) }
+    }
+
+    /// 当前有效的令牌；同时发送的请求共用一次获取
+    pub async fn token(&self, client: &Client) -> Result<String, String> {
+        let mut cached = self.cached.lock().await;
+        match &self.source {
+            TokenSource::ClientCredentials(credentials) => {
+                if let Some(token) = cached.as_ref().filter(|c| c.refresh_at.is_some_and(|at| Instant::now() < at)) {
+                    return Ok(token.token.clone());
+                }
+                let (token, lifetime) = fetch(client, credentials).await?;
+                let margin = REFRESH_MARGIN.min(lifetime / 10);
+                tracing::debug!(url = %credentials.token_url, expires_in = lifetime.as_secs(), "{}", tr("已获取令牌"));
+                *cached = Some(Cached { token: token.clone(), refresh_at: Some(Instant::now() + lifetime - margin), modified: None });
+                Ok(token)
+            }
+            TokenSource::File(path) => {
+                let modified = tokio::fs::metadata(path).await.and_then(|m| m.modified()).ok();
+                if let Some(token) = cached.as_ref().filter(|c| c.modified.is_some() && c.modified == modified) {
+                    return Ok(token.token.clone());
+                }
+                let token = tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|e| format!("{} {}: {}", tr("无法读取令牌文件"), path.display(), e))?
+                    .trim()
+                    .to_string();
+                if token.is_empty() {
+                    return Err(format!("{}: {}", tr("令牌文件为空"), path.display()));
+                }
+                *cached = Some(Cached { token: token.clone(), refresh_at: None, modified });
+                Ok(token)
+            }
+        }
+    }
+
+    /// 端点拒绝了令牌(401)，下次重新获取
+    pub async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+}
+
+/// 向令牌端点请求令牌，返回令牌和有效期
+async fn fetch(client: &Client, credentials: &ClientCredentials) -> Result<(String, Duration), String> {
+    let failed = |detail: String| format!("{} {}: {}", tr("获取令牌失败"), credentials.token_url, detail);
+    let mut form = vec![("grant_type", "client_credentials")];
+    if let Some(scope) = &credentials.scope {
+        form.push(("scope", scope));
+    }
+    form.extend(credentials.params.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+    let mut request = client.post(&credentials.token_url).header(ACCEPT, "application/json");
+    if credentials.post_credentials {
+        form.push(("client_id", &credentials.client_id));
+        form.push(("client_secret", &credentials.client_secret));
+    } else {
+        request = request.basic_auth(&credentials.client_id, Some(&credentials.client_secret));
+    }
+
+    let response = request.form(&form).send().await.map_err(|e| failed(e.to_string()))?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or_default();
+    if !status.is_success() {
+        // RFC 6749 5.2：`error` 和可选的 `error_description`
+        let error = [&body["error"], &body["error_description"]].iter().filter_map(|v| v.as_str()).collect::<Vec<_>>().join(": ");
+        return Err(failed(format!("HTTP {} {}", status, error).trim_end().to_string()));
+    }
+    let token = body["access_token"].as_str().filter(|t| !t.is_empty()).ok_or_else(|| failed(tr("响应中没有 access_token").to_string()))?;
+    if let Some(kind) = body["token_type"].as_str().filter(|kind| !kind.eq_ignore_ascii_case("bearer")) {
+        return Err(failed(format!("{}: {}", tr("不支持的令牌类型"), kind)));
+    }
+    // 有的服务端把 `expires_in` 写成字符串
+    let lifetime = match &body["expires_in"] {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    };
+    let lifetime = lifetime.filter(|secs| *secs > 0).map_or(DEFAULT_LIFETIME, Duration::from_secs);
+    Ok((token.to_string(), lifetime))
+}
+
+/// `--oauth-token-url` 的校验
+pub fn token_url_arg(value: &str) -> Result<String, String> {
+    match reqwest::Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(value.to_string()),
+        _ => Err(format!("{}: {}", tr("无效的令牌端点"), value)),
+    }
+}
+
+/// `--oauth-param NAME=VALUE` 的校验
+pub fn oauth_param_arg(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((name, _)) if !name.trim().is_empty() => Ok(value.to_string()),
+        _ => Err(format!("{}: {}", tr("无效的令牌请求参数"), value)),
+    }
+}
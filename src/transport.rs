@@ -0,0 +1,364 @@
+// src/transport.rs
+use crate::crypto::{self, SIGNATURE_HEADER};
+use crate::error::Error;
+use crate::i18n::tr;
+use crate::resolve::Resolver;
+use crate::token::TokenProvider;
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Certificate, Client, Identity, NoProxy, Proxy};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(unix)]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+const UNIX_SCHEME: &str = "unix://";
+
+pub enum Auth {
+    None,
+    Bearer(String),
+    Basic { user: String, password: Option<String> },
+    /// 从令牌端点或令牌文件获取、定期刷新的Bearer令牌
+    Token(TokenProvider),
+}
+
+impl Auth {
+    /// 解析 `user:password` 形式的Basic认证信息
+    pub fn basic(credentials: &str) -> Self {
+        match credentials.split_once(':') {
+            Some((user, password)) => Auth::Basic {
+                user: user.to_string(),
+                password: Some(password.to_string()),
+            },
+            None => Auth::Basic {
+                user: credentials.to_string(),
+                password: None,
+            },
+        }
+    }
+
+    async fn header_value(&self, client: &Client) -> Result<Option<String>, String> {
+        Ok(match self {
+            Auth::None => None,
+            Auth::Bearer(token) => Some(format!("Bearer {}", token)),
+            Auth::Basic { user, password } => {
+                let credentials = format!("{}:{}", user, password.as_deref().unwrap_or(""));
+                let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+                Some(format!("Basic {}", encoded))
+            }
+            Auth::Token(provider) => Some(format!("Bearer {}", provider.token(client).await?)),
+        })
+    }
+}
+
+enum Target {
+    Http(String),
+    /// `unix:///path/to.sock`，以 `POST /` 发送到本地Unix套接字
+    Unix(PathBuf),
+}
+
+/// 限制请求频率，保证相邻两次请求的间隔不小于 1/rate 秒
+struct RateLimiter {
+    spacing: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(rate: f64) -> Self {
+        Self {
+            spacing: Duration::from_secs_f64(1.0 / rate),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn acquire(&self) {
+        let mut next = self.next.lock().await;
+        let slot = (*next).max(Instant::now());
+        *next = slot + self.spacing;
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// 数据发送目标：端点地址、认证信息以及复用的HTTP客户端
+// 请求头和超时在HTTP客户端中设置，这里的只有Unix套接字使用
+#[cfg_attr(not(unix), allow(dead_code))]
+pub struct Transport {
+    target: Target,
+    auth: Auth,
+    headers: HeaderMap,
+    client: Client,
+    resolver: Arc<Resolver>,
+    connect_timeout: Duration,
+    request_timeout: Duration,
+    rate_limiter: Option<RateLimiter>,
+    signing_key: Option<String>,
+}
+
+impl Transport {
+    pub fn builder(endpoint: &str) -> TransportBuilder {
+        TransportBuilder {
+            endpoint: endpoint.to_string(),
+            auth: Auth::None,
+            headers: HeaderMap::new(),
+            root_certs: Vec::new(),
+            identity: None,
+            insecure: false,
+            proxy: None,
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(10),
+            keepalive: Duration::from_secs(90),
+            rate_limit: None,
+            signing_key: None,
+        }
+    }
+
+    /// 发送请求体并返回响应内容，`headers` 为本次请求附加的请求头
+    pub async fn send(&self, body: String, mut headers: HeaderMap) -> Result<String, Error> {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        if let Some(key) = &self.signing_key {
+            headers.insert(SIGNATURE_HEADER, HeaderValue::from_str(&crypto::sign(key, body.as_bytes())).map_err(Error::runtime)?);
+        }
+
+        // 令牌可能在过期之前就被撤销，端点返回401时丢弃缓存的令牌，重新获取后再发送一次
+        let Auth::Token(provider) = &self.auth else { return self.send_to(body, headers).await };
+        match self.send_to(body.clone(), headers.clone()).await {
+            Err(Error::Status { code: 401, .. }) => {}
+            result => return result,
+        }
+        tracing::info!("{}", tr("令牌被拒绝，重新获取"));
+        provider.invalidate().await;
+        self.send_to(body, headers).await
+    }
+
+    async fn send_to(&self, body: String, headers: HeaderMap) -> Result<String, Error> {
+        match &self.target {
+            Target::Http(url) => self.send_http(url, body, headers).await,
+            Target::Unix(path) => self.send_unix(path, body, headers).await,
+        }
+    }
+
+    async fn send_http(&self, url: &str, body: String, headers: HeaderMap) -> Result<String, Error> {
+        let mut request = self.client
+            .post(url)
+            .headers(headers)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body);
+
+        if let Some(auth) = self.auth.header_value(&self.client).await? {
+            request = request.header(reqwest::header::AUTHORIZATION, auth);
+        }
+
+        let response = request.send().await?;
+        tracing::debug!(endpoint = url, status = response.status().as_u16(), "{}", tr("收到响应"));
+        if let (Some(host), Some(addr)) = (response.url().host_str(), response.remote_addr()) {
+            self.resolver.connected(host, addr.ip());
+        }
+
+        if !response.status().is_success() {
+            return Err(Error::Status { code: response.status().as_u16(), text: response.status().to_string() });
+        }
+
+        Ok(response.text().await?)
+    }
+
+    #[cfg(unix)]
+    async fn send_unix(&self, path: &Path, body: String, headers: HeaderMap) -> Result<String, Error> {
+        let mut stream = tokio::time::timeout(self.connect_timeout, UnixStream::connect(path))
+            .await
+            .map_err(|_| format!("{}: {}", tr("连接超时"), path.display()))??;
+
+        let mut request = String::from("POST / HTTP/1.1\r\nHost: localhost\r\n");
+        for (name, value) in self.headers.iter().chain(&headers) {
+            request.push_str(&format!("{}: {}\r\n", name, value.to_str().map_err(Error::runtime)?));
+        }
+        if let Some(auth) = self.auth.header_value(&self.client).await? {
+            request.push_str(&format!("Authorization: {}\r\n", auth));
+        }
+        request.push_str(&format!(
+            "Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        ));
+        request.push_str(&body);
+
+        let exchange = async {
+            stream.write_all(request.as_bytes()).await?;
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await?;
+            Ok::<_, std::io::Error>(response)
+        };
+        let response = tokio::time::timeout(self.request_timeout, exchange)
+            .await
+            .map_err(|_| format!("{}: {}", tr("请求超时"), path.display()))??;
+
+        let response = String::from_utf8_lossy(&response);
+        let status = response
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or(tr("无效的HTTP响应"))?;
+        tracing::debug!(endpoint = %path.display(), status, "{}", tr("收到响应"));
+
+        if !(200..300).contains(&status) {
+            return Err(Error::Status { code: status, text: status.to_string() });
+        }
+
+        let body = response.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+        Ok(body.to_string())
+    }
+
+    #[cfg(not(unix))]
+    async fn send_unix(&self, _path: &Path, _body: String, _headers: HeaderMap) -> Result<String, Error> {
+        Err(tr("Unix套接字端点只能在Unix平台使用").into())
+    }
+}
+
+pub struct TransportBuilder {
+    endpoint: String,
+    auth: Auth,
+    headers: HeaderMap,
+    root_certs: Vec<Certificate>,
+    identity: Option<Identity>,
+    insecure: bool,
+    proxy: Option<Proxy>,
+    connect_timeout: Duration,
+    request_timeout: Duration,
+    keepalive: Duration,
+    rate_limit: Option<f64>,
+    signing_key: Option<String>,
+}
+
+impl TransportBuilder {
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// 添加 `Name: value` 形式的静态请求头
+    pub fn header(mut self, header: &str) -> Result<Self, Error> {
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| Error::config(format!("{}: {}", tr("无效的请求头"), header)))?;
+        self.headers.append(
+            HeaderName::from_bytes(name.trim().as_bytes()).map_err(Error::config)?,
+            HeaderValue::from_str(value.trim()).map_err(Error::config)?,
+        );
+        Ok(self)
+    }
+
+    /// 信任指定PEM文件中的CA证书
+    pub fn ca_cert(mut self, path: &Path) -> Result<Self, Error> {
+        let pem = std::fs::read(path).map_err(|e| Error::config(format!("{}: {}", path.display(), e)))?;
+        self.root_certs.extend(Certificate::from_pem_bundle(&pem).map_err(Error::config)?);
+        Ok(self)
+    }
+
+    /// 使用客户端证书和PKCS#8私钥进行双向TLS认证
+    pub fn client_cert(mut self, cert: &Path, key: &Path) -> Result<Self, Error> {
+        let read = |path: &Path| std::fs::read(path).map_err(|e| Error::config(format!("{}: {}", path.display(), e)));
+        let (cert, key) = (read(cert)?, read(key)?);
+        #[cfg(feature = "native-tls")]
+        let identity = Identity::from_pkcs8_pem(&cert, &key).map_err(Error::config)?;
+        // rustls从同一个PEM中读取证书和私钥
+        #[cfg(not(feature = "native-tls"))]
+        let identity = Identity::from_pem(&[cert, key].join(&b"\n"[..])).map_err(Error::config)?;
+        self.identity = Some(identity);
+        Ok(self)
+    }
+
+    /// 跳过服务器证书校验，仅用于测试环境
+    pub fn insecure(mut self, insecure: bool) -> Self {
+        self.insecure = insecure;
+        self
+    }
+
+    /// 通过指定的HTTP或SOCKS5代理发送，未指定时使用 HTTP_PROXY/HTTPS_PROXY/NO_PROXY 环境变量
+    pub fn proxy(mut self, url: &str, no_proxy: Option<&str>) -> reqwest::Result<Self> {
+        let no_proxy = match no_proxy {
+            Some(list) => NoProxy::from_string(list),
+            None => NoProxy::from_env(),
+        };
+        self.proxy = Some(Proxy::all(url)?.no_proxy(no_proxy));
+        Ok(self)
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// 空闲连接的保持时间，为0时每次请求都新建连接
+    pub fn keepalive(mut self, keepalive: Duration) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+
+    /// 每秒最多发送的请求数，包括发送缓存数据的请求
+    pub fn rate_limit(mut self, rate: Option<f64>) -> Self {
+        self.rate_limit = rate.filter(|rate| *rate > 0.0);
+        self
+    }
+
+    /// 用这个密钥对每个请求体签名，签名放在 `X-Sysmon-Signature` 请求头中
+    pub fn signing_key(mut self, key: Option<String>) -> Self {
+        self.signing_key = key;
+        self
+    }
+
+    pub fn build(self) -> reqwest::Result<Transport> {
+        let resolver = Resolver::new();
+        let mut builder = Client::builder()
+            .dns_resolver(resolver.clone())
+            .default_headers(self.headers.clone())
+            .danger_accept_invalid_certs(self.insecure)
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.request_timeout);
+
+        builder = if self.keepalive.is_zero() {
+            builder.pool_max_idle_per_host(0)
+        } else {
+            builder
+                .pool_idle_timeout(self.keepalive)
+                .tcp_keepalive(self.keepalive)
+        };
+
+        if let Some(proxy) = self.proxy {
+            builder = builder.proxy(proxy);
+        }
+        for cert in self.root_certs {
+            builder = builder.add_root_certificate(cert);
+        }
+        if let Some(identity) = self.identity {
+            builder = builder.identity(identity);
+        }
+
+        let target = match self.endpoint.strip_prefix(UNIX_SCHEME) {
+            Some(path) => Target::Unix(PathBuf::from(path)),
+            None => Target::Http(self.endpoint),
+        };
+
+        Ok(Transport {
+            target,
+            auth: self.auth,
+            headers: self.headers,
+            client: builder.build()?,
+            resolver,
+            connect_timeout: self.connect_timeout,
+            request_timeout: self.request_timeout,
+            rate_limiter: self.rate_limit.map(RateLimiter::new),
+            signing_key: self.signing_key,
+        })
+    }
+}
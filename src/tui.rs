@@ -0,0 +1,599 @@
+// src/tui.rs
+use crate::crash;
+use crate::display::{format_bytes, format_optional_rate, format_rate};
+use crate::filter::DisplayFilter;
+use crate::history::{sparkline, History};
+use crate::i18n::tr;
+use crate::{DiskStats, ProcessInfo, SystemStats};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::cursor::Show;
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use futures::StreamExt;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::widgets::{
+    Axis, Block, Chart, Clear, Dataset, Gauge, GraphType, LineGauge, Paragraph, Row, Sparkline, Table, TableState,
+};
+use ratatui::{Frame, Terminal};
+use std::io::{self, Stdout};
+
+pub enum Action {
+    Quit,
+    Redraw,
+    /// 向确认时看到的进程发送 SIGTERM，`force` 时发送 SIGKILL
+    Kill { process: ProcessInfo, force: bool },
+    None,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pane {
+    Cpu,
+    Network,
+    Processes,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SortKey {
+    Cpu,
+    Mem,
+    Pid,
+    Name,
+}
+
+struct KillPrompt {
+    process: ProcessInfo,
+    force: bool,
+}
+
+/// 基于ratatui的终端界面：CPU、内存、网络和进程面板，Tab切换面板，方向键滚动
+pub struct Tui {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+    events: EventStream,
+    filter: DisplayFilter,
+    /// 用盲文点阵绘制高分辨率的CPU和网络历史曲线
+    braille: bool,
+    focus: Pane,
+    cpu_scroll: usize,
+    net_scroll: usize,
+    status: String,
+    header: String,
+    processes: Vec<ProcessInfo>,
+    sort: SortKey,
+    selected: Option<u32>,
+    proc_table: TableState,
+    prompt: Option<KillPrompt>,
+}
+
+impl Tui {
+    pub fn start(filter: DisplayFilter, braille: bool) -> io::Result<Self> {
+        // panic时先恢复终端，否则错误信息会被备用屏幕吞掉；采集项panic后会重新初始化，界面继续运行
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !crash::supervised() {
+                restore();
+            }
+            hook(info);
+        }));
+
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        terminal.hide_cursor()?;
+        terminal.clear()?;
+
+        Ok(Self {
+            terminal,
+            events: EventStream::new(),
+            filter,
+            braille,
+            focus: Pane::Cpu,
+            cpu_scroll: 0,
+            net_scroll: 0,
+            status: String::new(),
+            header: String::new(),
+            processes: Vec::new(),
+            sort: SortKey::Cpu,
+            selected: None,
+            proc_table: TableState::default(),
+            prompt: None,
+        })
+    }
+
+    /// 等待下一个键盘或终端事件
+    pub async fn next_event(&mut self) -> Action {
+        match self.events.next().await {
+            Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => self.handle_key(key),
+            Some(Ok(Event::Resize(_, _))) => Action::Redraw,
+            Some(Ok(_)) => Action::None,
+            Some(Err(_)) | None => Action::Quit,
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if let Some(prompt) = self.prompt.take() {
+            return match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => Action::Kill {
+                    process: prompt.process,
+                    force: prompt.force,
+                },
+                _ => Action::Redraw,
+            };
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Action::Quit,
+            KeyCode::Char('g') => self.braille = !self.braille,
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Pane::Cpu => Pane::Network,
+                    Pane::Network => Pane::Processes,
+                    Pane::Processes => Pane::Cpu,
+                };
+            }
+            KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Pane::Cpu => Pane::Processes,
+                    Pane::Network => Pane::Cpu,
+                    Pane::Processes => Pane::Network,
+                };
+            }
+            _ if self.focus == Pane::Processes => return self.handle_process_key(key),
+            KeyCode::Up | KeyCode::Char('k') => *self.scroll() = self.scroll().saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => *self.scroll() += 1,
+            KeyCode::PageUp => *self.scroll() = self.scroll().saturating_sub(10),
+            KeyCode::PageDown => *self.scroll() += 10,
+            KeyCode::Home => *self.scroll() = 0,
+            _ => return Action::None,
+        }
+
+        Action::Redraw
+    }
+
+    fn scroll(&mut self) -> &mut usize {
+        match self.focus {
+            Pane::Network => &mut self.net_scroll,
+            _ => &mut self.cpu_scroll,
+        }
+    }
+
+    /// 进程列表：↑↓ 选择，c/m/p/n 按CPU/内存/PID/名称排序，t 发送SIGTERM，K 发送SIGKILL
+    fn handle_process_key(&mut self, key: KeyEvent) -> Action {
+        let index = self.selected_index();
+        let last = self.processes.len().saturating_sub(1);
+
+        let index = match key.code {
+            KeyCode::Up | KeyCode::Char('k') => index.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => (index + 1).min(last),
+            KeyCode::PageUp => index.saturating_sub(10),
+            KeyCode::PageDown => (index + 10).min(last),
+            KeyCode::Home => 0,
+            KeyCode::End => last,
+            KeyCode::Char(c @ ('c' | 'm' | 'p' | 'n')) => {
+                self.sort = match c {
+                    'c' => SortKey::Cpu,
+                    'm' => SortKey::Mem,
+                    'p' => SortKey::Pid,
+                    _ => SortKey::Name,
+                };
+                self.sort_processes();
+                return Action::Redraw;
+            }
+            KeyCode::Char(c @ ('t' | 'K')) => {
+                if let Some(process) = self.processes.get(index) {
+                    self.prompt = Some(KillPrompt {
+                        process: process.clone(),
+                        force: c == 'K',
+                    });
+                }
+                return Action::Redraw;
+            }
+            _ => return Action::None,
+        };
+
+        self.selected = self.processes.get(index).map(|p| p.pid);
+        Action::Redraw
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected
+            .and_then(|pid| self.processes.iter().position(|p| p.pid == pid))
+            .unwrap_or(0)
+    }
+
+    fn sort_processes(&mut self) {
+        match self.sort {
+            SortKey::Cpu => self.processes.sort_by(|a, b| b.cpu.total_cmp(&a.cpu)),
+            SortKey::Mem => self.processes.sort_by_key(|p| std::cmp::Reverse(p.mem)),
+            SortKey::Pid => self.processes.sort_by_key(|p| p.pid),
+            SortKey::Name => self.processes.sort_by(|a, b| a.name.cmp(&b.name)),
+        }
+    }
+
+    /// 更新进程列表，保持当前选中的进程
+    pub fn set_processes(&mut self, processes: Vec<ProcessInfo>) {
+        self.processes = processes;
+        self.sort_processes();
+    }
+
+    /// 标题栏右侧显示的最近一次发送状态
+    pub fn set_status(&mut self, status: String) {
+        self.status = status;
+    }
+
+    pub fn set_filter(&mut self, filter: DisplayFilter) {
+        self.filter = filter;
+    }
+
+    /// 标题栏显示的主机和代理状态
+    pub fn set_header(&mut self, header: String) {
+        self.header = header;
+    }
+
+    pub fn draw(&mut self, history: &History) -> io::Result<()> {
+        let Some(stats) = history.latest() else {
+            return Ok(());
+        };
+
+        let selected = self.selected_index();
+        let focus = self.focus;
+        let status = &self.status;
+        let header = &self.header;
+        let cpu_scroll = &mut self.cpu_scroll;
+        let net_scroll = &mut self.net_scroll;
+        let processes = &self.processes;
+        let proc_table = &mut self.proc_table;
+        let prompt = &self.prompt;
+        let filter = &self.filter;
+        let braille = self.braille;
+        let disks: Vec<_> = stats.disk.iter().filter(|(mount, _)| filter.mount.matches(mount)).collect();
+
+        self.terminal.draw(|frame| {
+            let [title, help, top, procs] = Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Percentage(55),
+                Constraint::Min(5),
+            ])
+            .areas(frame.area());
+            let [left, right] = Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(top);
+            let [mem, disk, net] = Layout::vertical([
+                Constraint::Length(9),
+                Constraint::Length(disks.len().min(6) as u16 + 2),
+                Constraint::Min(4),
+            ])
+            .areas(right);
+
+            let reversed = Style::default().add_modifier(Modifier::REVERSED);
+            frame.render_widget(
+                Paragraph::new(format!(" {} — {}", tr("系统资源监控"), header)).style(reversed),
+                title,
+            );
+            frame.render_widget(
+                Paragraph::new(format!(" {}", tr("q 退出, Tab 切换面板, ↑↓ 滚动, g 切换曲线")))
+                    .style(Style::default().add_modifier(Modifier::DIM)),
+                help,
+            );
+            frame.render_widget(
+                Paragraph::new(format!("{} ", status)).style(reversed).right_aligned(),
+                title,
+            );
+            draw_cpu(frame, left, stats, history, braille, cpu_scroll, focus == Pane::Cpu);
+            draw_memory(frame, mem, stats, history);
+            draw_disks(frame, disk, &disks);
+            if braille {
+                let [traffic, net] = Layout::vertical([Constraint::Length(8), Constraint::Min(4)]).areas(net);
+                draw_traffic(frame, traffic, history, filter);
+                draw_network(frame, net, stats, history, filter, net_scroll, focus == Pane::Network);
+            } else {
+                draw_network(frame, net, stats, history, filter, net_scroll, focus == Pane::Network);
+            }
+            proc_table.select(Some(selected));
+            draw_processes(frame, procs, stats, processes, proc_table, focus == Pane::Processes);
+            if let Some(prompt) = prompt {
+                draw_prompt(frame, prompt);
+            }
+        })?;
+
+        Ok(())
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// 退出原始模式和备用屏幕并显示光标，恢复启动前的终端内容
+fn restore() {
+    let _ = terminal::disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+}
+
+fn pane(title: String, focused: bool) -> Block<'static> {
+    let style = if focused {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    Block::bordered().title(title).border_style(style)
+}
+
+fn usage_color(percent: f64) -> Color {
+    if percent >= 90.0 {
+        Color::Red
+    } else if percent >= 70.0 {
+        Color::Yellow
+    } else {
+        Color::Green
+    }
+}
+
+fn ratio(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (used as f64 / total as f64).clamp(0.0, 1.0)
+    }
+}
+
+/// 把历史数值转换为图表坐标，横轴为样本序号
+fn points(values: &[u64]) -> Vec<(f64, f64)> {
+    values.iter().enumerate().map(|(i, &v)| (i as f64, v as f64)).collect()
+}
+
+/// 盲文点阵折线图，纵轴范围为 0 到 `max`
+fn braille_chart<'a>(datasets: Vec<Dataset<'a>>, samples: usize, max: f64) -> Chart<'a> {
+    Chart::new(datasets)
+        .x_axis(Axis::default().bounds([0.0, samples.saturating_sub(1).max(1) as f64]))
+        .y_axis(Axis::default().bounds([0.0, max.max(1.0)]))
+}
+
+fn draw_cpu(
+    frame: &mut Frame,
+    area: Rect,
+    stats: &SystemStats,
+    history: &History,
+    braille: bool,
+    scroll: &mut usize,
+    focused: bool,
+) {
+    let avg = stats.cpu.iter().sum::<f32>() / stats.cpu.len().max(1) as f32;
+    let block = pane(format!(" CPU ({} {}, {} {:.1}%) ", stats.cpu.len(), tr("核心"), tr("平均"), avg), focused);
+    let graph_height = if braille { 8 } else { 3 };
+    let [graph, inner] = Layout::vertical([Constraint::Length(graph_height), Constraint::Min(0)])
+        .areas(block.inner(area));
+    frame.render_widget(block, area);
+
+    let cpu = history.cpu();
+    let style = Style::default().fg(usage_color(avg as f64));
+    if braille {
+        let data = points(&cpu);
+        let dataset = Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(style)
+            .data(&data);
+        frame.render_widget(braille_chart(vec![dataset], cpu.len(), 100.0), graph);
+    } else {
+        frame.render_widget(
+            Sparkline::default()
+                .data(&cpu[cpu.len().saturating_sub(graph.width as usize)..])
+                .max(100)
+                .style(style),
+            graph,
+        );
+    }
+
+    let rows = inner.height as usize;
+    *scroll = (*scroll).min(stats.cpu.len().saturating_sub(rows));
+
+    for (row, (i, usage)) in stats.cpu.iter().enumerate().skip(*scroll).take(rows).enumerate() {
+        let percent = *usage as f64;
+        let gauge = LineGauge::default()
+            .filled_style(Style::default().fg(usage_color(percent)))
+            .label(format!("{} {:<3} {:>5.1}%", tr("核心"), i, percent))
+            .ratio((percent / 100.0).clamp(0.0, 1.0));
+        let line = Rect::new(inner.x, inner.y + row as u16, inner.width, 1);
+        frame.render_widget(gauge, line);
+    }
+}
+
+fn draw_memory(frame: &mut Frame, area: Rect, stats: &SystemStats, history: &History) {
+    let block = pane(format!(" {} ", tr("内存")), false);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [mem, swap, graph] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Length(2),
+        Constraint::Length(3),
+    ])
+    .areas(inner);
+
+    let usage = history.mem();
+    frame.render_widget(
+        Sparkline::default()
+            .data(&usage[usage.len().saturating_sub(graph.width as usize)..])
+            .max(100)
+            .style(Style::default().fg(Color::Cyan)),
+        graph,
+    );
+
+    let (memory, swap_space) = (stats.mem.clone().unwrap_or_default(), stats.swap.clone().unwrap_or_default());
+    for (area, name, used, total, missing) in [
+        (mem, tr("内存"), memory.used, memory.total, "-"),
+        (swap, tr("交换空间"), swap_space.used, swap_space.total, tr("未配置")),
+    ] {
+        let ratio = ratio(used, total);
+        let [label, bar] = Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).areas(area);
+        // 没有配置swap或读不到内存时总量为0
+        let text = if total == 0 {
+            format!("{}: {}", name, missing)
+        } else {
+            format!("{}: {} / {}", name, format_bytes(used), format_bytes(total))
+        };
+        frame.render_widget(Paragraph::new(text), label);
+        frame.render_widget(
+            Gauge::default()
+                .gauge_style(Style::default().fg(usage_color(ratio * 100.0)))
+                .ratio(ratio),
+            bar,
+        );
+    }
+}
+
+fn draw_disks(frame: &mut Frame, area: Rect, disks: &[(&String, &DiskStats)]) {
+    let block = pane(format!(" {} ", tr("磁盘")), false);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    for (row, (mount, disk)) in disks.iter().take(inner.height as usize).enumerate() {
+        let ratio = ratio(disk.used, disk.total);
+        let gauge = LineGauge::default()
+            .filled_style(Style::default().fg(usage_color(ratio * 100.0)))
+            .label(format!("{:<12} {:>10}", mount, format_bytes(disk.total)))
+            .ratio(ratio);
+        let line = Rect::new(inner.x, inner.y + row as u16, inner.width, 1);
+        frame.render_widget(gauge, line);
+    }
+}
+
+fn draw_network(
+    frame: &mut Frame,
+    area: Rect,
+    stats: &SystemStats,
+    history: &History,
+    filter: &DisplayFilter,
+    scroll: &mut usize,
+    focused: bool,
+) {
+    let mut interfaces: Vec<_> = stats.net.iter().filter(|(name, _)| filter.iface.matches(name)).collect();
+    let block = pane(format!(" {} ({}) ", tr("网络接口"), interfaces.len()), focused);
+    let rows_visible = block.inner(area).height.saturating_sub(1) as usize;
+
+    interfaces.sort_by(|a, b| a.0.cmp(b.0));
+    *scroll = (*scroll).min(interfaces.len().saturating_sub(rows_visible));
+
+    let rows = interfaces.iter().skip(*scroll).map(|(name, net)| {
+        Row::new(vec![
+            name.to_string(),
+            format_optional_rate(net.rx),
+            format_optional_rate(net.tx),
+            sparkline(&history.net(name), 16, None),
+        ])
+    });
+
+    let widths = [
+        Constraint::Min(12),
+        Constraint::Length(14),
+        Constraint::Length(14),
+        Constraint::Length(16),
+    ];
+    let table = Table::new(rows, widths)
+        .header(Row::new(vec![tr("接口"), tr("接收"), tr("发送"), tr("趋势")]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(block);
+    frame.render_widget(table, area);
+}
+
+/// 所有显示网卡的接收和发送总速率曲线
+fn draw_traffic(frame: &mut Frame, area: Rect, history: &History, filter: &DisplayFilter) {
+    let block = pane(format!(" {} ", tr("流量")), false);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let (rx, tx) = history.traffic(&filter.iface);
+    let max = rx.iter().chain(&tx).copied().max().unwrap_or(0);
+    let (rx_points, tx_points) = (points(&rx), points(&tx));
+    let datasets = vec![
+        Dataset::default()
+            .name(format!("{} {}", tr("接收"), format_rate(rx.last().copied().unwrap_or(0))))
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Green))
+            .data(&rx_points),
+        Dataset::default()
+            .name(format!("{} {}", tr("发送"), format_rate(tx.last().copied().unwrap_or(0))))
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Magenta))
+            .data(&tx_points),
+    ];
+    frame.render_widget(
+        braille_chart(datasets, rx.len(), max as f64).hidden_legend_constraints((Constraint::Ratio(1, 1), Constraint::Ratio(1, 1))),
+        inner,
+    );
+}
+
+fn draw_processes(
+    frame: &mut Frame,
+    area: Rect,
+    stats: &SystemStats,
+    processes: &[ProcessInfo],
+    state: &mut TableState,
+    focused: bool,
+) {
+    let proc = stats.proc.clone().unwrap_or_default();
+    let title = format!(
+        " {} — {}: {}  {}: {}  {}: {}  {}: {}  (c/m/p/n {}, t/K {}) ",
+        tr("进程"),
+        tr("总计"), proc.total,
+        tr("运行"), proc.running,
+        tr("睡眠"), proc.sleeping,
+        tr("僵尸"), proc.zombie,
+        tr("排序"),
+        tr("结束进程"),
+    );
+
+    let rows = processes.iter().map(|p| {
+        Row::new(vec![
+            p.pid.to_string(),
+            p.name.clone(),
+            format!("{:.1}", p.cpu),
+            format_bytes(p.mem),
+        ])
+    });
+
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Min(16),
+        Constraint::Length(8),
+        Constraint::Length(12),
+    ];
+    let highlight = if focused {
+        Style::default().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::default()
+    };
+    let table = Table::new(rows, widths)
+        .header(Row::new(vec!["PID", tr("名称"), "CPU%", tr("内存")]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .row_highlight_style(highlight)
+        .block(pane(title, focused));
+    frame.render_stateful_widget(table, area, state);
+}
+
+fn draw_prompt(frame: &mut Frame, prompt: &KillPrompt) {
+    let signal = if prompt.force { "SIGKILL" } else { "SIGTERM" };
+    let text = format!(" {} {} → {} ({})? [y/N] ", tr("发送"), signal, prompt.process.pid, prompt.process.name);
+
+    let area = frame.area();
+    let width = (text.chars().count() as u16 + 4).min(area.width);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + area.height / 2 - 1,
+        width,
+        3,
+    );
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(text).block(Block::bordered().border_style(Style::default().fg(Color::Red))),
+        popup,
+    );
+}
@@ -0,0 +1,56 @@
+// src/tui_stub.rs
+//! 没有启用 `tui` 特性时代替 `tui` 模块，文本输出总是按 `--plain` 逐帧刷新，界面不会被创建
+use crate::filter::DisplayFilter;
+use crate::history::History;
+use crate::i18n::tr;
+use crate::ProcessInfo;
+use std::io;
+
+/// 与终端界面的操作相同，不会被构造
+#[allow(dead_code)]
+pub enum Action {
+    Quit,
+    Redraw,
+    Kill { process: ProcessInfo, force: bool },
+    None,
+}
+
+/// 没有取值，持有 `Tui` 的分支都不会执行
+pub enum Tui {}
+
+impl Tui {
+    pub fn start(_filter: DisplayFilter, _braille: bool) -> io::Result<Self> {
+        Err(io::Error::other(tr("终端界面需要以 --features tui 编译")))
+    }
+
+    pub async fn next_event(&mut self) -> Action {
+        match *self {}
+    }
+
+    pub fn set_processes(&mut self, _processes: Vec<ProcessInfo>) {
+        match *self {}
+    }
+
+    pub fn set_status(&mut self, _status: String) {
+        match *self {}
+    }
+
+    pub fn set_filter(&mut self, _filter: DisplayFilter) {
+        match *self {}
+    }
+
+    pub fn set_header(&mut self, _header: String) {
+        match *self {}
+    }
+
+    pub fn draw(&mut self, _history: &History) -> io::Result<()> {
+        match *self {}
+    }
+}
+
+/// 与终端界面一样在退出时释放，调用方不必区分
+impl Drop for Tui {
+    fn drop(&mut self) {
+        match *self {}
+    }
+}
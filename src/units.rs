@@ -0,0 +1,345 @@
+// src/units.rs
+use crate::alert::Metric;
+use crate::i18n::tr;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// 以字节为单位的字段(网卡为字节/秒)，路径的写法与 `--fields` 相同，数组对其中的每个元素生效
+const BYTE_FIELDS: [&str; 20] = [
+    "mem.total",
+    "mem.used",
+    "swap.total",
+    "swap.used",
+    "disk.*.total",
+    "disk.*.used",
+    "net.*.rx",
+    "net.*.tx",
+    "top.mem",
+    "lifecycle.mem",
+    "talkers.processes.rx",
+    "talkers.processes.tx",
+    "talkers.ports.rx",
+    "talkers.ports.tx",
+    "agent.rss",
+    "agent.spool_bytes",
+    "kubernetes.allocatable.memory",
+    "kubernetes.used.memory",
+    "aggregate.bytes.*.rx",
+    "aggregate.bytes.*.tx",
+];
+
+/// 以百分比为单位的字段
+const PERCENT_FIELDS: [&str; 7] =
+    ["cpu", "cpu_window.peak", "top.cpu", "lifecycle.cpu", "churn.cpu", "agent.cpu", "probes.*.loss_percent"];
+
+/// 汇总指标(`aggregate.metrics`)中以字节为单位的，以 `_percent` 结尾的和 `agent.cpu`、`churn.cpu` 为百分比
+const BYTE_METRICS: [&str; 13] = [
+    "mem.used",
+    "mem.free",
+    "mem.total",
+    "swap.used",
+    "swap.free",
+    "swap.total",
+    "disk.used",
+    "disk.free",
+    "disk.total",
+    "net.rx",
+    "net.tx",
+    "agent.rss",
+    "agent.spool_bytes",
+];
+
+/// 字节数的单位
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ByteUnit {
+    B,
+    KB,
+    KiB,
+    MB,
+    MiB,
+    GB,
+    GiB,
+}
+
+impl ByteUnit {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "b" => Some(ByteUnit::B),
+            "kb" => Some(ByteUnit::KB),
+            "kib" => Some(ByteUnit::KiB),
+            "mb" => Some(ByteUnit::MB),
+            "mib" => Some(ByteUnit::MiB),
+            "gb" => Some(ByteUnit::GB),
+            "gib" => Some(ByteUnit::GiB),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> f64 {
+        match self {
+            ByteUnit::B => 1.0,
+            ByteUnit::KB => 1e3,
+            ByteUnit::KiB => 1024.0,
+            ByteUnit::MB => 1e6,
+            ByteUnit::MiB => 1024.0 * 1024.0,
+            ByteUnit::GB => 1e9,
+            ByteUnit::GiB => 1024.0 * 1024.0 * 1024.0,
+        }
+    }
+}
+
+/// 一个输出使用的单位和精度：`bytes=KiB,percent=fraction,precision=2`，未指定的部分保持样本中的原样
+/// (字节、0到100的百分比、不取整)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Conversion {
+    bytes: Option<ByteUnit>,
+    /// 百分比换算为0到1的比例
+    fraction: bool,
+    /// 浮点数保留的小数位数，为0时输出整数
+    precision: Option<usize>,
+}
+
+impl Conversion {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("{}: {}", tr("无效的单位设置"), spec);
+        let mut conversion = Self::default();
+        for part in spec.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(invalid)?;
+            match (key.trim(), value.trim()) {
+                ("bytes", unit) => conversion.bytes = Some(ByteUnit::parse(unit).ok_or_else(invalid)?),
+                ("percent", "percent") => conversion.fraction = false,
+                ("percent", "fraction") => conversion.fraction = true,
+                ("precision", "int") => conversion.precision = Some(0),
+                ("precision", digits) => {
+                    conversion.precision = Some(digits.parse().ok().filter(|digits| *digits <= 15).ok_or_else(invalid)?)
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(conversion)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 换算一个样本
+    pub fn apply(&self, sample: &mut Value) {
+        if let Some(unit) = self.bytes.filter(|unit| *unit != ByteUnit::B) {
+            let size = unit.size();
+            for path in BYTE_FIELDS {
+                scale(sample, &path.split('.').collect::<Vec<_>>(), size);
+            }
+        }
+        if self.fraction {
+            for path in PERCENT_FIELDS {
+                scale(sample, &path.split('.').collect::<Vec<_>>(), 100.0);
+            }
+        }
+        if let Some(Value::Array(summaries)) = sample.pointer_mut("/aggregate/metrics") {
+            for summary in summaries {
+                let Some(metric) = summary.get("metric").and_then(Value::as_str).and_then(Metric::parse) else { continue };
+                let name = format!("{}.{}", metric.section(), metric.field());
+                let divisor = if BYTE_METRICS.contains(&name.as_str()) {
+                    self.bytes.map_or(1.0, ByteUnit::size)
+                } else if self.fraction && (name.ends_with("_percent") || name == "agent.cpu" || name == "churn.cpu") {
+                    100.0
+                } else {
+                    1.0
+                };
+                for field in ["min", "max", "avg", "p95"] {
+                    scale(summary, &[field], divisor);
+                }
+            }
+        }
+        if let Some(digits) = self.precision {
+            round(sample, digits);
+        }
+    }
+
+    /// 换算请求体(单个样本或样本数组)中的每个样本
+    pub fn apply_body(&self, body: &str) -> serde_json::Result<String> {
+        match serde_json::from_str(body)? {
+            Value::Array(mut samples) => {
+                samples.iter_mut().for_each(|sample| self.apply(sample));
+                serde_json::to_string(&samples)
+            }
+            mut sample => {
+                self.apply(&mut sample);
+                serde_json::to_string(&sample)
+            }
+        }
+    }
+}
+
+/// `path` 处的数值除以 `divisor`，`*` 匹配对象的所有字段
+fn scale(value: &mut Value, path: &[&str], divisor: f64) {
+    match (value, path.split_first()) {
+        (Value::Array(items), _) => items.iter_mut().for_each(|item| scale(item, path, divisor)),
+        (Value::Number(number), None) if divisor != 1.0 => {
+            if let Some(scaled) = number.as_f64().and_then(|n| serde_json::Number::from_f64(n / divisor)) {
+                *number = scaled;
+            }
+        }
+        (Value::Object(fields), Some((&"*", rest))) => fields.values_mut().for_each(|field| scale(field, rest, divisor)),
+        (Value::Object(fields), Some((key, rest))) => {
+            if let Some(field) = fields.get_mut(*key) {
+                scale(field, rest, divisor);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 所有浮点数保留 `digits` 位小数，整数不变
+fn round(value: &mut Value, digits: usize) {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            let n = number.as_f64().unwrap_or_default();
+            *value = if digits == 0 {
+                Value::from(n.round() as i64)
+            } else {
+                let factor = 10f64.powi(digits as i32);
+                Value::from((n * factor).round() / factor)
+            };
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| round(item, digits)),
+        Value::Object(fields) => fields.values_mut().for_each(|field| round(field, digits)),
+        _ => {}
+    }
+}
+
+/// `--sink-units`：按输出的名称(`endpoint`、`exporter:名称`、`template:名称`)换算发出的样本，
+/// 没有设置的输出收到原样的样本
+#[derive(Debug, Clone, Default)]
+pub struct SinkUnits(BTreeMap<String, Conversion>);
+
+impl SinkUnits {
+    /// `SINK=SPEC` 的列表，同一个输出设置多次时使用最后一次
+    pub fn new(values: &[String]) -> Result<Self, String> {
+        let mut units = BTreeMap::new();
+        for value in values {
+            let (sink, spec) = value
+                .split_once('=')
+                .filter(|(sink, _)| !sink.trim().is_empty())
+                .ok_or_else(|| format!("{}: {}", tr("无效的单位设置"), value))?;
+            units.insert(sink.trim().to_string(), Conversion::parse(spec)?);
+        }
+        Ok(Self(units))
+    }
+
+    /// 设置了单位的输出
+    pub fn sinks(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// 按 `sink` 的设置换算请求体，没有设置时原样返回
+    pub fn convert(&self, sink: &str, body: &str) -> serde_json::Result<String> {
+        match self.0.get(sink).filter(|conversion| !conversion.is_empty()) {
+            Some(conversion) => conversion.apply_body(body),
+            None => Ok(body.to_string()),
+        }
+    }
+}
+
+/// `--sink-units` 的校验
+pub fn sink_units_arg(value: &str) -> Result<String, String> {
+    SinkUnits::new(&[value.to_string()]).map(|_| value.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_conversions() {
+        assert!(Conversion::parse("").unwrap().is_empty());
+        assert!(Conversion::parse("percent=percent").unwrap().is_empty());
+        let conversion = Conversion::parse(" bytes = KiB , percent=fraction,precision=int ").unwrap();
+        assert_eq!(conversion, Conversion { bytes: Some(ByteUnit::KiB), fraction: true, precision: Some(0) });
+        assert_eq!(Conversion::parse("bytes=gb").unwrap().bytes, Some(ByteUnit::GB));
+        assert_eq!(Conversion::parse("precision=15").unwrap().precision, Some(15));
+        for spec in ["bytes=TB", "precision=16", "precision=-1", "percent=ratio", "units=b", "bytes"] {
+            assert!(Conversion::parse(spec).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn converts_bytes_and_percentages() {
+        let mut sample = json!({
+            "cpu": [50.0, 25.0],
+            "mem": { "total": 2048, "used": 1024 },
+            "disk": { "/": { "total": 4096, "used": 512, "inodes": 100 } },
+            "net": { "eth0": { "rx": 1024, "tx": 0 } },
+            "top": [{ "pid": 1, "cpu": 10.0, "mem": 2048 }],
+            "proc": { "total": 1024 },
+        });
+        Conversion::parse("bytes=KiB,percent=fraction").unwrap().apply(&mut sample);
+        assert_eq!(
+            sample,
+            json!({
+                "cpu": [0.5, 0.25],
+                "mem": { "total": 2.0, "used": 1.0 },
+                "disk": { "/": { "total": 4.0, "used": 0.5, "inodes": 100 } },
+                "net": { "eth0": { "rx": 1.0, "tx": 0.0 } },
+                "top": [{ "pid": 1, "cpu": 0.1, "mem": 2.0 }],
+                "proc": { "total": 1024 },
+            })
+        );
+
+        let mut sample = json!({ "mem": { "total": 2048 } });
+        Conversion::parse("bytes=B").unwrap().apply(&mut sample);
+        assert_eq!(sample, json!({ "mem": { "total": 2048 } }));
+    }
+
+    #[test]
+    fn converts_aggregate_summaries_by_metric() {
+        let mut sample = json!({
+            "aggregate": { "metrics": [
+                { "metric": "mem.used", "min": 1e6, "max": 2e6, "avg": 1.5e6, "p95": 2e6 },
+                { "metric": "cpu.used_percent", "min": 10.0, "max": 50.0, "avg": 20.0, "p95": 40.0 },
+                { "metric": "proc.total", "min": 100, "max": 200, "avg": 150, "p95": 190 },
+                { "metric": "unknown", "min": 1e6 },
+            ] }
+        });
+        Conversion::parse("bytes=MB,percent=fraction").unwrap().apply(&mut sample);
+        let metrics = &sample["aggregate"]["metrics"];
+        assert_eq!(metrics[0], json!({ "metric": "mem.used", "min": 1.0, "max": 2.0, "avg": 1.5, "p95": 2.0 }));
+        assert_eq!(metrics[1], json!({ "metric": "cpu.used_percent", "min": 0.1, "max": 0.5, "avg": 0.2, "p95": 0.4 }));
+        assert_eq!(metrics[2], json!({ "metric": "proc.total", "min": 100, "max": 200, "avg": 150, "p95": 190 }));
+        assert_eq!(metrics[3]["min"], json!(1e6));
+    }
+
+    #[test]
+    fn rounds_floats_only() {
+        let mut sample = json!({ "cpu": [12.3456, 0.5], "mem": { "used": 3 }, "host": "a" });
+        Conversion::parse("precision=2").unwrap().apply(&mut sample);
+        assert_eq!(sample, json!({ "cpu": [12.35, 0.5], "mem": { "used": 3 }, "host": "a" }));
+        Conversion::parse("precision=int").unwrap().apply(&mut sample);
+        assert_eq!(sample, json!({ "cpu": [12, 1], "mem": { "used": 3 }, "host": "a" }));
+    }
+
+    #[test]
+    fn converts_bodies_per_sink() {
+        let units = SinkUnits::new(&[
+            "endpoint=bytes=KB".to_string(),
+            "exporter:influx = precision=int".to_string(),
+            "endpoint=bytes=MB".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(units.sinks().collect::<Vec<_>>(), ["endpoint", "exporter:influx"]);
+        assert_eq!(units.convert("endpoint", r#"{"mem":{"used":2000000}}"#).unwrap(), r#"{"mem":{"used":2.0}}"#);
+        assert_eq!(
+            units.convert("exporter:influx", r#"[{"cpu":[1.4]},{"cpu":[2.6]}]"#).unwrap(),
+            r#"[{"cpu":[1]},{"cpu":[3]}]"#
+        );
+        assert_eq!(units.convert("template:x", "not json").unwrap(), "not json");
+        assert!(units.convert("endpoint", "not json").is_err());
+
+        assert!(SinkUnits::new(&["=bytes=KB".to_string()]).is_err());
+        assert!(SinkUnits::new(&["endpoint".to_string()]).is_err());
+        assert_eq!(sink_units_arg(" endpoint=bytes=KB ").unwrap(), "endpoint=bytes=KB");
+        assert!(sink_units_arg("endpoint=bytes=XB").is_err());
+    }
+}
@@ -0,0 +1,31 @@
+// src/version.rs
+use crate::i18n::tr;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// 构建时的git提交号(12位)，不在git仓库中构建时为 unknown
+pub const COMMIT: &str = env!("SYSMON_COMMIT");
+pub const BUILD_DATE: &str = env!("SYSMON_BUILD_DATE");
+pub const RUSTC: &str = env!("SYSMON_RUSTC");
+/// 启用的cargo特性，逗号分隔
+pub const FEATURES: &str = env!("SYSMON_FEATURES");
+pub const TARGET: &str = env!("SYSMON_TARGET");
+pub const PROFILE: &str = env!("SYSMON_PROFILE");
+
+/// `--version` 的输出
+pub fn short() -> String {
+    format!("{} {} ({})", env!("CARGO_PKG_NAME"), VERSION, COMMIT)
+}
+
+/// `--version --verbose` 的输出，包括全部构建信息
+pub fn long() -> String {
+    let features = if FEATURES.is_empty() { "-" } else { FEATURES };
+    [
+        format!("{} {}", env!("CARGO_PKG_NAME"), VERSION),
+        format!("{}: {}", tr("提交"), COMMIT),
+        format!("{}: {}", tr("构建时间"), BUILD_DATE),
+        format!("{}: {} ({})", tr("目标平台"), TARGET, PROFILE),
+        format!("{}: {}", tr("编译器"), RUSTC),
+        format!("{}: {}", tr("特性"), features),
+    ]
+    .join("\n")
+}
@@ -0,0 +1,119 @@
+// src/webhook.rs
+use crate::alert::{Notification, State};
+use crate::i18n::tr;
+use serde_json::{json, Value};
+use tokio::task::JoinSet;
+
+/// PagerDuty Events API v2 的地址
+const PAGERDUTY_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// 请求体的格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// 告警事件本身，附加主机名和标签
+    Json,
+    /// Slack、Mattermost、Rocket.Chat 等兼容的 `{"text": ...}`
+    Slack,
+    /// Microsoft Teams 的 MessageCard
+    Teams,
+    /// PagerDuty Events API v2，地址部分为路由键
+    PagerDuty,
+}
+
+/// 告警状态变化时通知的webhook，`[FORMAT=]URL`
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    format: Format,
+    url: String,
+    routing_key: Option<String>,
+}
+
+impl Webhook {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("{}: {}", tr("无效的webhook"), value);
+        let (format, target) = match value.split_once('=') {
+            Some(("json", target)) => (Format::Json, target),
+            Some(("slack", target)) => (Format::Slack, target),
+            Some(("teams", target)) => (Format::Teams, target),
+            Some(("pagerduty", key)) => {
+                if key.trim().is_empty() {
+                    return Err(invalid());
+                }
+                return Ok(Self {
+                    format: Format::PagerDuty,
+                    url: PAGERDUTY_URL.to_string(),
+                    routing_key: Some(key.trim().to_string()),
+                });
+            }
+            _ => (Format::Json, value),
+        };
+        let url = reqwest::Url::parse(target.trim()).map_err(|_| invalid())?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid());
+        }
+        Ok(Self { format, url: url.to_string(), routing_key: None })
+    }
+
+    /// 按格式生成请求体
+    fn body(&self, alert: &Notification) -> Value {
+        let event = alert.event;
+        let (title, color) = match event.state {
+            State::Firing => (tr("告警触发"), "d63333"),
+            State::Resolved => (tr("告警解除"), "2eb886"),
+        };
+        match self.format {
+            Format::Json => json!(alert),
+            Format::Slack => json!({ "text": format!("*[{}]* {}", title, alert.summary()) }),
+            Format::Teams => json!({
+                "@type": "MessageCard",
+                "@context": "http://schema.org/extensions",
+                "summary": alert.summary(),
+                "themeColor": color,
+                "title": format!("[{}] {}", title, event.rule),
+                "text": alert.summary(),
+            }),
+            Format::PagerDuty => json!({
+                "routing_key": self.routing_key,
+                "event_action": if event.state == State::Firing { "trigger" } else { "resolve" },
+                "dedup_key": alert.key(),
+                "payload": {
+                    "summary": alert.summary(),
+                    "source": alert.host,
+                    "severity": "critical",
+                    "timestamp": event.timestamp,
+                    "custom_details": alert,
+                },
+            }),
+        }
+    }
+}
+
+/// `--alert-webhook` 的校验
+pub fn webhook_arg(value: &str) -> Result<String, String> {
+    Webhook::parse(value).map(|_| value.to_string())
+}
+
+/// 向全部webhook发送一个告警通知；在后台发送，失败只记录日志，不影响采集
+pub fn notify(
+    client: &reqwest::Client,
+    webhooks: &[Webhook],
+    alert: &Notification,
+    dry_run: bool,
+    tasks: &mut JoinSet<()>,
+) {
+    for webhook in webhooks {
+        let body = webhook.body(alert).to_string();
+        let url = webhook.url.clone();
+        if dry_run {
+            tracing::info!(%url, %body, "{}", tr("试运行，告警通知未发送"));
+            continue;
+        }
+        let request = client.post(&webhook.url).header("Content-Type", "application/json").body(body);
+        tasks.spawn(async move {
+            match request.send().await.and_then(|response| response.error_for_status()) {
+                Ok(response) => tracing::debug!(%url, status = response.status().as_u16(), "{}", tr("告警通知已发送")),
+                Err(e) => tracing::warn!(%url, error = %e, "{}", tr("发送告警通知失败")),
+            }
+        });
+    }
+}
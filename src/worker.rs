@@ -0,0 +1,139 @@
+// src/worker.rs
+use crate::bench;
+use crate::collector::{Collector, Context, Section};
+use crate::crash::{self, Panic};
+use crate::filter::{ProcessFilter, RegexFilter};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::{System, SystemExt, Uid};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// 一次采集请求，带上采集时需要的设置
+#[derive(Clone)]
+pub(crate) struct Request {
+    pub net_filter: RegexFilter,
+    pub process_filter: ProcessFilter,
+    pub process_uids: Vec<Uid>,
+    pub top: usize,
+}
+
+/// 一次采集的结果
+pub(crate) struct Done {
+    pub index: usize,
+    pub section: Section,
+    pub elapsed: Duration,
+    /// 采集线程在这次采集中的(分配次数, 分配字节数)，只在以 `bench` 特性编译时统计
+    pub allocations: (u64, u64),
+    /// 采集项panic，已经重新初始化；这时 `section` 为 [`Section::Unavailable`]
+    pub panic: Option<Panic>,
+}
+
+/// 在单独的线程中运行一个采集项，各采集项同时采集，进程很多时枚举进程不会拖住其余采集项
+pub(crate) struct Worker {
+    name: &'static str,
+    runner: Runner,
+    /// 采集项自己的 `System`，只包含它刷新过的部分
+    system: Arc<Mutex<System>>,
+    /// 已经发出请求、还没有收到结果
+    busy: bool,
+}
+
+enum Runner {
+    Thread(Sender<Request>),
+    /// 不启动线程，发出请求时直接在调用者的线程中采集，用于低占用模式
+    Inline { index: usize, collector: Box<dyn Collector>, results: UnboundedSender<Done> },
+}
+
+impl Worker {
+    /// 启动采集线程，结果连同 `index` 发到 `results`，等待结果的一方不阻塞异步运行时
+    pub fn spawn(index: usize, mut collector: Box<dyn Collector>, results: UnboundedSender<Done>) -> std::io::Result<Self> {
+        let name = collector.name();
+        let system = Arc::new(Mutex::new(System::new()));
+        let (requests, receiver): (Sender<Request>, Receiver<Request>) = mpsc::channel();
+
+        let shared = Arc::clone(&system);
+        std::thread::Builder::new()
+            .name(format!("collect-{}", name))
+            .spawn(move || {
+                collector.init(&mut shared.lock().unwrap());
+                // `Monitor` 释放时请求通道关闭，线程随之退出
+                for request in receiver {
+                    if results.send(run(index, collector.as_mut(), &shared, &request)).is_err() {
+                        break;
+                    }
+                }
+            })?;
+
+        Ok(Self { name, runner: Runner::Thread(requests), system, busy: false })
+    }
+
+    /// 不启动线程的采集项：各采集项依次采集，采集超时不起作用
+    pub fn inline(index: usize, mut collector: Box<dyn Collector>, results: UnboundedSender<Done>) -> Self {
+        let name = collector.name();
+        let system = Arc::new(Mutex::new(System::new()));
+        collector.init(&mut system.lock().unwrap());
+        Self { name, runner: Runner::Inline { index, collector, results }, system, busy: false }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn system(&self) -> &Arc<Mutex<System>> {
+        &self.system
+    }
+
+    pub fn busy(&self) -> bool {
+        self.busy
+    }
+
+    /// 发出一次采集请求，上一次还没有完成时不再发出
+    pub fn start(&mut self, request: Request) -> bool {
+        if self.busy {
+            return false;
+        }
+        self.busy = match &mut self.runner {
+            Runner::Thread(requests) => requests.send(request).is_ok(),
+            Runner::Inline { index, collector, results } => {
+                results.send(run(*index, collector.as_mut(), &self.system, &request)).is_ok()
+            }
+        };
+        self.busy
+    }
+
+    /// 收到结果后调用
+    pub fn finished(&mut self) {
+        self.busy = false;
+    }
+}
+
+/// 用 `system` 采集一次；采集项panic时换用新的 `System` 重新初始化，下次照常采集，不影响其他采集项
+fn run(index: usize, collector: &mut dyn Collector, system: &Mutex<System>, request: &Request) -> Done {
+    let started = Instant::now();
+    let (allocations, bytes) = bench::allocations();
+    let result = crash::supervise(|| {
+        let mut system = system.lock().unwrap();
+        let mut ctx = Context {
+            system: &mut system,
+            net_filter: &request.net_filter,
+            process_filter: &request.process_filter,
+            process_uids: &request.process_uids,
+            top: request.top,
+        };
+        collector.collect(&mut ctx)
+    });
+    let (allocations_after, bytes_after) = bench::allocations();
+    let allocations = (allocations_after - allocations, bytes_after - bytes);
+    match result {
+        Ok(section) => Done { index, section, elapsed: started.elapsed(), allocations, panic: None },
+        Err(panic) => {
+            system.clear_poison();
+            let mut system = system.lock().unwrap();
+            *system = System::new();
+            // 重新初始化时再panic就等下次采集再试
+            let _ = crash::supervise(|| collector.init(&mut system));
+            Done { index, section: Section::Unavailable, elapsed: started.elapsed(), allocations, panic: Some(panic) }
+        }
+    }
+}